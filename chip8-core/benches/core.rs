@@ -0,0 +1,60 @@
+//! `cargo bench -p chip8-core`
+
+use chip8_core::{Emulator, Quirks, Screen};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::path::Path;
+
+/// Loops through ALU, skip and memory instructions without drawing
+const DISPATCH_ROM: [u8; 20] = [
+    0x60, 0x01, // V0 = 1
+    0x70, 0x03, // V0 += 3
+    0x81, 0x04, // V1 += V0
+    0x82, 0x13, // V2 ^= V1
+    0x32, 0x00, // skip if V2 == 0
+    0x83, 0x26, // V3 = V2 >> 1
+    0xA3, 0x00, // I = 0x300
+    0xF3, 0x33, // BCD of V3
+    0xF2, 0x55, // store V0..V2
+    0x12, 0x02, // loop
+];
+
+fn dispatch(c: &mut Criterion) {
+    let mut emulator = Emulator::builder().seed(0).build();
+    emulator.load_rom_bytes(&DISPATCH_ROM).unwrap();
+    c.bench_function("step 1000 instructions", |b| {
+        b.iter(|| emulator.run_cycles(black_box(1000)))
+    });
+}
+
+fn draw_sprite(c: &mut Criterion) {
+    let mut screen = Screen::default();
+    let sprite = [
+        0xFF, 0x81, 0xA5, 0x81, 0xBD, 0x99, 0x81, 0xFF, 0x3C, 0x42, 0x81, 0x81, 0x42, 0x3C, 0x18,
+    ];
+    c.bench_function("draw_sprite 8x15 wrapping", |b| {
+        b.iter(|| screen.draw_sprite(black_box(60), black_box(25), &sprite, false))
+    });
+    c.bench_function("draw_sprite 8x15 clipped", |b| {
+        b.iter(|| screen.draw_sprite(black_box(60), black_box(25), &sprite, true))
+    });
+}
+
+fn frame(c: &mut Criterion) {
+    let rom = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../roms/games/Brix [Andreas Gustafsson, 1990].ch8");
+    let mut emulator = Emulator::builder()
+        .quirks(Quirks::default())
+        .seed(0)
+        .build();
+    // `load_rom` needs the std feature, the benches also run without it
+    let rom = std::fs::read(rom).unwrap();
+    emulator.load_rom_bytes(&rom).unwrap();
+    // Get past the intro so the frame is representative gameplay
+    emulator.run_cycles(3000);
+    c.bench_function("Brix frame at 500Hz", |b| {
+        b.iter(|| emulator.run_for(black_box(1.0 / 60.0)))
+    });
+}
+
+criterion_group!(benches, dispatch, draw_sprite, frame);
+criterion_main!(benches);
@@ -0,0 +1,594 @@
+//! Assembler for Octo (`.8o`) sources, the language of the Octo IDE.
+//!
+//! Covers the chip8 part of the language: labels, `:const`, `:alias`, `:org`,
+//! `:byte`, `:call`, every chip8 instruction, `if ... then`, `if ... begin ... else
+//! ... end`, `loop ... while ... again` and raw bytes. Comparisons other than `==`
+//! and `!=`, macros and the SCHIP/XO-CHIP extensions aren't supported and are
+//! reported as errors.
+//!
+//! Like Octo, programs start at 0x200 and a program defining `main` anywhere but
+//! at the very start gets a jump to it first.
+
+use crate::emulator::PROGRAM_START;
+use crate::instruction::Instruction;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Error returned when a source doesn't assemble.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssembleError {
+    /// 1-based line of the token the error is about
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AssembleError {}
+
+/// ROM assembled from an Octo source with the addresses of its labels.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Assembly {
+    pub rom: Vec<u8>,
+    pub labels: BTreeMap<String, u16>,
+}
+
+impl Assembly {
+    /// Closest label at or before `address`, with how far past it the address is.
+    ///
+    /// ```
+    /// use chip8_core::Assembly;
+    ///
+    /// let assembly = Assembly::new(": main  v0 := 1  : count  v0 += 1  jump count").unwrap();
+    /// assert_eq!(assembly.locate(0x204), Some(("count", 2)));
+    /// assert_eq!(assembly.resolve("count", 2), Some(0x204));
+    /// ```
+    pub fn locate(&self, address: u16) -> Option<(&str, u16)> {
+        self.labels
+            .iter()
+            .filter(|(_, &label)| label <= address)
+            .max_by_key(|(_, &label)| label)
+            .map(|(name, &label)| (name.as_str(), address - label))
+    }
+
+    /// Address `offset` bytes past `label`, the inverse of `locate`
+    pub fn resolve(&self, label: &str, offset: u16) -> Option<u16> {
+        let address = self.labels.get(label)?.checked_add(offset)?;
+        let end = PROGRAM_START as u16 + self.rom.len() as u16;
+        Some(address).filter(|&address| address < end)
+    }
+
+    /// Assembles a source, see `assemble`
+    pub fn new(source: &str) -> Result<Self, AssembleError> {
+        let mut assembler = Assembler::new(source);
+        assembler.run()?;
+        let labels = assembler
+            .labels
+            .iter()
+            .map(|(&name, &address)| (name.into(), address))
+            .collect();
+        Ok(Assembly {
+            rom: assembler.rom,
+            labels,
+        })
+    }
+}
+
+/// Assembles an Octo source into a ROM loaded at 0x200.
+///
+/// ```
+/// use chip8_core::assemble;
+///
+/// let rom = assemble(": main  v0 := 5  loop v0 += -1  if v0 != 0 then again").unwrap();
+/// assert_eq!(rom, [0x60, 0x05, 0x70, 0xFF, 0x30, 0x00, 0x12, 0x02]);
+/// assert!(assemble("v0 := v16").is_err());
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    Assembly::new(source).map(|assembly| assembly.rom)
+}
+
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+// Open control structures, innermost last
+enum Block {
+    /// `if ... begin`, with the jump to patch to the `else` or `end`
+    If { jump: u16 },
+    /// `else`, with the jump over the else part to patch to the `end`
+    Else { jump: u16 },
+    /// `loop`, with the jumps out of it from `while`
+    Loop { start: u16, exits: Vec<u16> },
+}
+
+// Skips making the next instruction run only when a condition holds, or only when it doesn't
+struct Condition {
+    skip_if_true: Instruction,
+    skip_if_false: Instruction,
+}
+
+struct Assembler<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    rom: Vec<u8>,
+    /// Address the next byte goes to
+    here: u16,
+    labels: BTreeMap<&'a str, u16>,
+    constants: BTreeMap<&'a str, i32>,
+    aliases: BTreeMap<&'a str, u8>,
+    /// Addresses of instructions waiting for a label's address in their NNN
+    fixups: Vec<(u16, Token<'a>)>,
+    blocks: Vec<Block>,
+}
+
+impl<'a> Assembler<'a> {
+    fn new(source: &'a str) -> Self {
+        let tokens = source
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let code = line.split('#').next().unwrap_or_default();
+                code.split_whitespace()
+                    .map(move |text| Token { text, line: i + 1 })
+            })
+            .collect();
+        Assembler {
+            tokens,
+            pos: 0,
+            rom: Vec::new(),
+            here: PROGRAM_START as u16,
+            labels: BTreeMap::new(),
+            constants: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), AssembleError> {
+        let defines_main = self
+            .tokens
+            .windows(2)
+            .any(|pair| pair[0].text == ":" && pair[1].text == "main");
+        let starts_with_main =
+            self.tokens.len() >= 2 && self.tokens[0].text == ":" && self.tokens[1].text == "main";
+        if defines_main && !starts_with_main {
+            let token = self.tokens[0];
+            self.fixups.push((
+                self.here,
+                Token {
+                    text: "main",
+                    ..token
+                },
+            ));
+            self.emit(Instruction::Jump { nnn: 0 });
+        }
+
+        while self.pos < self.tokens.len() {
+            self.statement()?;
+        }
+
+        if let Some(block) = self.blocks.last() {
+            let missing = match block {
+                Block::If { .. } | Block::Else { .. } => "end",
+                Block::Loop { .. } => "again",
+            };
+            let line = self.tokens.last().map_or(1, |token| token.line);
+            return Err(AssembleError {
+                line,
+                message: format!("Missing '{}' at the end of the program", missing),
+            });
+        }
+        for (address, token) in core::mem::take(&mut self.fixups) {
+            let target = match self.labels.get(token.text) {
+                Some(&target) => target,
+                None => return Err(error(token, format!("'{}' isn't defined", token.text))),
+            };
+            self.patch(address, target);
+        }
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Token<'a>, AssembleError> {
+        match self.tokens.get(self.pos) {
+            Some(&token) => {
+                self.pos += 1;
+                Ok(token)
+            }
+            None => {
+                let line = self.tokens.last().map_or(1, |token| token.line);
+                Err(AssembleError {
+                    line,
+                    message: "Unexpected end of the program".into(),
+                })
+            }
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        if token.text != text {
+            return Err(error(
+                token,
+                format!("Expected '{}', found '{}'", text, token.text),
+            ));
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), AssembleError> {
+        use Instruction::*;
+
+        let token = self.next()?;
+        if let Some(x) = self.register(token) {
+            return self.register_statement(x);
+        }
+        match token.text {
+            ":" => {
+                let name = self.name()?;
+                if self.labels.insert(name.text, self.here).is_some() {
+                    return Err(error(name, format!("'{}' is already defined", name.text)));
+                }
+            }
+            ":const" => {
+                let name = self.name()?;
+                let value = self.next()?;
+                let value = self.value(value)?;
+                self.constants.insert(name.text, value);
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let register = self.next_register()?;
+                self.aliases.insert(name.text, register);
+            }
+            ":org" => {
+                let address = self.next()?;
+                let address = self.number(address, PROGRAM_START as i32, 0xFFFF)?;
+                self.here = address;
+            }
+            ":byte" => {
+                let value = self.next()?;
+                let value = self.number(value, -0x80, 0xFF)?;
+                self.emit_byte(value as u8);
+            }
+            ":call" => self.address_instruction(Call { nnn: 0 })?,
+            "clear" => self.emit(ClearScreen),
+            "return" | ";" => self.emit(Return),
+            "jump" => self.address_instruction(Jump { nnn: 0 })?,
+            "jump0" => self.address_instruction(JumpOffset { nnn: 0 })?,
+            "native" => self.address_instruction(System { nnn: 0 })?,
+            "bcd" => self.register_instruction(|x| StoreBcd { x })?,
+            "save" => self.register_instruction(|x| StoreRegisters { x })?,
+            "load" => self.register_instruction(|x| LoadRegisters { x })?,
+            "sprite" => {
+                let x = self.next_register()?;
+                let y = self.next_register()?;
+                let n = self.next()?;
+                let n = self.number(n, 0, 0xF)? as u8;
+                self.emit(Draw { x, y, n });
+            }
+            "delay" => {
+                self.expect(":=")?;
+                self.register_instruction(|x| SetDelay { x })?;
+            }
+            "buzzer" => {
+                self.expect(":=")?;
+                self.register_instruction(|x| SetSound { x })?;
+            }
+            "i" => {
+                let op = self.next()?;
+                match op.text {
+                    ":=" if self.peek() == Some("hex") => {
+                        self.pos += 1;
+                        self.register_instruction(|x| LoadFont { x })?;
+                    }
+                    ":=" => self.address_instruction(LoadIndex { nnn: 0 })?,
+                    "+=" => self.register_instruction(|x| AddIndex { x })?,
+                    _ => return Err(error(op, format!("'i {}' isn't an instruction", op.text))),
+                }
+            }
+            "if" => {
+                let condition = self.condition()?;
+                let then = self.next()?;
+                match then.text {
+                    "then" => self.emit(condition.skip_if_false),
+                    "begin" => {
+                        self.emit(condition.skip_if_true);
+                        self.blocks.push(Block::If { jump: self.here });
+                        self.emit(Jump { nnn: 0 });
+                    }
+                    _ => {
+                        let message = format!("Expected 'then' or 'begin', found '{}'", then.text);
+                        return Err(error(then, message));
+                    }
+                }
+            }
+            "else" => match self.blocks.pop() {
+                Some(Block::If { jump }) => {
+                    self.blocks.push(Block::Else { jump: self.here });
+                    self.emit(Jump { nnn: 0 });
+                    self.patch(jump, self.here);
+                }
+                _ => return Err(error(token, "'else' without 'if ... begin'".into())),
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If { jump }) | Some(Block::Else { jump }) => {
+                    self.patch(jump, self.here)
+                }
+                _ => return Err(error(token, "'end' without 'if ... begin'".into())),
+            },
+            "loop" => self.blocks.push(Block::Loop {
+                start: self.here,
+                exits: Vec::new(),
+            }),
+            "while" => {
+                let condition = self.condition()?;
+                self.emit(condition.skip_if_true);
+                let here = self.here;
+                match self.blocks.iter_mut().rev().find_map(|block| match block {
+                    Block::Loop { exits, .. } => Some(exits),
+                    _ => None,
+                }) {
+                    Some(exits) => exits.push(here),
+                    None => return Err(error(token, "'while' outside of a loop".into())),
+                }
+                self.emit(Jump { nnn: 0 });
+            }
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop { start, exits }) => {
+                    self.emit(Jump { nnn: start });
+                    for exit in exits {
+                        self.patch(exit, self.here);
+                    }
+                }
+                _ => return Err(error(token, "'again' without 'loop'".into())),
+            },
+            text if is_number(text) || self.constants.contains_key(text) => {
+                let value = self.number(token, -0x80, 0xFF)?;
+                self.emit_byte(value as u8);
+            }
+            text if text.starts_with(':') || is_keyword(text) => {
+                return Err(error(token, format!("'{}' isn't supported", text)));
+            }
+            // A name on its own calls the subroutine
+            _ => {
+                self.fixups.push((self.here, token));
+                self.emit(Call { nnn: 0 });
+            }
+        }
+        Ok(())
+    }
+
+    // Statement starting with register `x`: `vx := ...`, `vx += ...` and so on
+    fn register_statement(&mut self, x: u8) -> Result<(), AssembleError> {
+        use Instruction::*;
+
+        let op = self.next()?;
+        let operand = self.next()?;
+        if let Some(y) = self.register(operand) {
+            let instruction = match op.text {
+                ":=" => Move { x, y },
+                "|=" => Or { x, y },
+                "&=" => And { x, y },
+                "^=" => Xor { x, y },
+                "+=" => AddRegisters { x, y },
+                "-=" => Subtract { x, y },
+                ">>=" => ShiftRight { x, y },
+                "=-" => SubtractReversed { x, y },
+                "<<=" => ShiftLeft { x, y },
+                _ => return Err(error(op, format!("'{}' doesn't take a register", op.text))),
+            };
+            self.emit(instruction);
+            return Ok(());
+        }
+        let instruction = match (op.text, operand.text) {
+            (":=", "random") => {
+                let mask = self.next()?;
+                let nn = self.number(mask, 0, 0xFF)? as u8;
+                Random { x, nn }
+            }
+            (":=", "delay") => LoadDelay { x },
+            (":=", "key") => WaitForKey { x },
+            (":=", _) => Load {
+                x,
+                nn: self.number(operand, -0x80, 0xFF)? as u8,
+            },
+            ("+=", _) => Add {
+                x,
+                nn: self.number(operand, -0x80, 0xFF)? as u8,
+            },
+            ("-=", _) => Add {
+                x,
+                nn: (self.number(operand, -0x80, 0xFF)? as u8).wrapping_neg(),
+            },
+            _ => {
+                let message = format!("'{}' doesn't take '{}'", op.text, operand.text);
+                return Err(error(op, message));
+            }
+        };
+        self.emit(instruction);
+        Ok(())
+    }
+
+    // `vx == n`, `vx != vy`, `vx key` or `vx -key`
+    fn condition(&mut self) -> Result<Condition, AssembleError> {
+        use Instruction::*;
+
+        let x = self.next_register()?;
+        let op = self.next()?;
+        let (skip_if_true, skip_if_false) = match op.text {
+            "key" => (SkipIfKeyPressed { x }, SkipIfKeyNotPressed { x }),
+            "-key" => (SkipIfKeyNotPressed { x }, SkipIfKeyPressed { x }),
+            "==" | "!=" => {
+                let operand = self.next()?;
+                let (equal, not_equal) = match self.register(operand) {
+                    Some(y) => (
+                        SkipIfRegistersEqual { x, y },
+                        SkipIfRegistersNotEqual { x, y },
+                    ),
+                    None => {
+                        let nn = self.number(operand, -0x80, 0xFF)? as u8;
+                        (SkipIfEqual { x, nn }, SkipIfNotEqual { x, nn })
+                    }
+                };
+                if op.text == "==" {
+                    (equal, not_equal)
+                } else {
+                    (not_equal, equal)
+                }
+            }
+            "<" | ">" | "<=" | ">=" => {
+                let message = format!("'{}' isn't supported, compare with == or !=", op.text);
+                return Err(error(op, message));
+            }
+            _ => return Err(error(op, format!("'{}' isn't a condition", op.text))),
+        };
+        Ok(Condition {
+            skip_if_true,
+            skip_if_false,
+        })
+    }
+
+    fn register_instruction(&mut self, f: fn(u8) -> Instruction) -> Result<(), AssembleError> {
+        let x = self.next_register()?;
+        self.emit(f(x));
+        Ok(())
+    }
+
+    // Instruction with an NNN operand given as a number or a label
+    fn address_instruction(&mut self, instruction: Instruction) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        let opcode = instruction.encode();
+        if is_number(token.text) || self.constants.contains_key(token.text) {
+            let nnn = self.number(token, 0, 0xFFF)?;
+            self.emit_opcode(opcode | nnn);
+        } else {
+            self.fixups.push((self.here, token));
+            self.emit_opcode(opcode);
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).map(|token| token.text)
+    }
+
+    // Label, constant or alias name being defined
+    fn name(&mut self) -> Result<Token<'a>, AssembleError> {
+        let token = self.next()?;
+        if is_number(token.text) || is_keyword(token.text) || self.register(token).is_some() {
+            return Err(error(token, format!("'{}' can't be a name", token.text)));
+        }
+        Ok(token)
+    }
+
+    fn register(&self, token: Token) -> Option<u8> {
+        if let Some(&register) = self.aliases.get(token.text) {
+            return Some(register);
+        }
+        let digit = token.text.strip_prefix(['v', 'V'])?;
+        if digit.len() != 1 {
+            return None;
+        }
+        u8::from_str_radix(digit, 16).ok()
+    }
+
+    fn next_register(&mut self) -> Result<u8, AssembleError> {
+        let token = self.next()?;
+        self.register(token)
+            .ok_or_else(|| error(token, format!("'{}' isn't a register", token.text)))
+    }
+
+    fn value(&self, token: Token) -> Result<i32, AssembleError> {
+        match self.constants.get(token.text) {
+            Some(&value) => Ok(value),
+            None => parse_number(token.text)
+                .ok_or_else(|| error(token, format!("'{}' isn't a number", token.text))),
+        }
+    }
+
+    // Number literal or constant in `min..=max`, negative numbers are two's complement
+    fn number(&self, token: Token, min: i32, max: i32) -> Result<u16, AssembleError> {
+        let value = self.value(token)?;
+        if value < min || value > max {
+            return Err(error(token, format!("{} is out of range", token.text)));
+        }
+        Ok(value as u16)
+    }
+
+    fn emit_byte(&mut self, byte: u8) {
+        let offset = self.here as usize - PROGRAM_START;
+        if self.rom.len() <= offset {
+            self.rom.resize(offset + 1, 0);
+        }
+        self.rom[offset] = byte;
+        self.here = self.here.wrapping_add(1);
+    }
+
+    fn emit_opcode(&mut self, opcode: u16) {
+        for byte in opcode.to_be_bytes().iter() {
+            self.emit_byte(*byte);
+        }
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.emit_opcode(instruction.encode());
+    }
+
+    // Sets the NNN of the instruction at `address`
+    fn patch(&mut self, address: u16, target: u16) {
+        let offset = address as usize - PROGRAM_START;
+        let opcode = u16::from_be_bytes([self.rom[offset], self.rom[offset + 1]]);
+        let patched = (opcode & 0xF000) | (target & 0x0FFF);
+        self.rom[offset..offset + 2].copy_from_slice(&patched.to_be_bytes());
+    }
+}
+
+fn error(token: Token, message: String) -> AssembleError {
+    AssembleError {
+        line: token.line,
+        message,
+    }
+}
+
+fn is_number(text: &str) -> bool {
+    parse_number(text).is_some()
+}
+
+// Decimal, 0x hex or 0b binary, optionally negative
+fn parse_number(text: &str) -> Option<i32> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i32::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i32::from_str_radix(binary, 2).ok()?
+    } else if digits.bytes().all(|b| b.is_ascii_digit()) && !digits.is_empty() {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+// Words of the language that can't be names
+fn is_keyword(text: &str) -> bool {
+    const KEYWORDS: [&str; 25] = [
+        "clear", "return", "jump", "jump0", "native", "bcd", "save", "load", "sprite", "delay",
+        "buzzer", "i", "if", "then", "begin", "else", "end", "loop", "while", "again", "key",
+        "-key", "hex", "random", ";",
+    ];
+    KEYWORDS.contains(&text) || text.starts_with(':')
+}
@@ -0,0 +1,96 @@
+//! Experimental cache of decoded straight-line instruction runs, built with the
+//! `block-cache` feature.
+//!
+//! `Emulator::run_cycles` executes a cached block without fetching and decoding
+//! each opcode. A block ends at the first instruction that can move PC anywhere
+//! but the next instruction, or stall it. Any write to memory a block was decoded
+//! from drops the whole cache, so self-modifying code keeps working.
+
+use crate::instruction::{Instruction, DECODE_TABLE};
+use crate::memory::Memory;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Long blocks only help until the next jump, this keeps decoding of data bounded
+const MAX_BLOCK_LEN: usize = 64;
+
+pub(crate) struct BlockCache {
+    /// Decoded instructions by start address
+    blocks: Vec<Option<Box<[Instruction]>>>,
+    /// Bytes of memory some cached block was decoded from
+    covered: Vec<bool>,
+}
+
+impl BlockCache {
+    pub fn new(memory_size: usize) -> Self {
+        BlockCache {
+            blocks: vec![None; memory_size],
+            covered: vec![false; memory_size],
+        }
+    }
+
+    /// Length of the block starting at `pc`, decoded on first use.
+    /// Zero if `pc` isn't in memory.
+    pub fn block_len(&mut self, pc: u16, memory: &Memory) -> usize {
+        let start = pc as usize;
+        match self.blocks.get(start) {
+            Some(Some(block)) => return block.len(),
+            Some(None) => {}
+            None => return 0,
+        }
+
+        let mut instructions = Vec::new();
+        let mut address = start;
+        while instructions.len() < MAX_BLOCK_LEN && memory.contains(address + 1) {
+            let memory = memory.as_slice();
+            let opcode = u16::from_be_bytes([memory[address], memory[address + 1]]);
+            let instruction = DECODE_TABLE[opcode as usize];
+            instructions.push(instruction);
+            self.covered[address] = true;
+            self.covered[address + 1] = true;
+            if ends_block(instruction) {
+                break;
+            }
+            address += 2;
+        }
+
+        let len = instructions.len();
+        self.blocks[start] = Some(instructions.into_boxed_slice());
+        len
+    }
+
+    /// Instruction `i` of the block at `pc`, `None` once the block has been dropped
+    pub fn instruction(&self, pc: u16, i: usize) -> Option<Instruction> {
+        self.blocks[pc as usize].as_ref().map(|block| block[i])
+    }
+
+    /// Called for every memory write, `address` is already wrapped into memory
+    pub fn invalidate(&mut self, address: usize) {
+        if self.covered[address] {
+            self.blocks.iter_mut().for_each(|block| *block = None);
+            self.covered.iter_mut().for_each(|covered| *covered = false);
+        }
+    }
+}
+
+fn ends_block(instruction: Instruction) -> bool {
+    use Instruction::*;
+    matches!(
+        instruction,
+        Return
+            | Jump { .. }
+            | Call { .. }
+            | JumpOffset { .. }
+            | SkipIfEqual { .. }
+            | SkipIfNotEqual { .. }
+            | SkipIfRegistersEqual { .. }
+            | SkipIfRegistersNotEqual { .. }
+            | SkipIfKeyPressed { .. }
+            | SkipIfKeyNotPressed { .. }
+            | WaitForKey { .. }
+            // Can start waiting for vblank
+            | Draw { .. }
+            | Unknown(_)
+    )
+}
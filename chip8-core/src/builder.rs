@@ -0,0 +1,115 @@
+use crate::emulator::{Emulator, DEFAULT_CLOCK_HZ};
+use crate::memory::{MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+use crate::quirks::Quirks;
+use alloc::boxed::Box;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+/// Configures an `Emulator` before it is created:
+///
+/// ```
+/// use chip8_core::{Emulator, Quirks};
+///
+/// let emulator = Emulator::builder()
+///     .quirks(Quirks::VIP)
+///     .clock_hz(1000.0)
+///     .seed(42)
+///     .build();
+///
+/// assert_eq!(emulator.quirks(), Quirks::VIP);
+/// ```
+#[derive(Clone)]
+pub struct EmulatorBuilder {
+    quirks: Quirks,
+    clock_hz: f32,
+    pub(crate) seed: Option<u64>,
+    memory_size: usize,
+    vip_timing: bool,
+    wait_for_release: bool,
+    halt_on_fault: bool,
+    history_len: usize,
+}
+
+impl Default for EmulatorBuilder {
+    fn default() -> Self {
+        EmulatorBuilder {
+            quirks: Quirks::default(),
+            clock_hz: DEFAULT_CLOCK_HZ,
+            seed: None,
+            memory_size: MEMORY_SIZE,
+            vip_timing: false,
+            wait_for_release: false,
+            halt_on_fault: false,
+            history_len: 0,
+        }
+    }
+}
+
+impl EmulatorBuilder {
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// CPU speed in instructions per second.
+    pub fn clock_hz(mut self, hz: f32) -> Self {
+        assert!(hz > 0.0, "clock speed must be positive");
+        self.clock_hz = hz;
+        self
+    }
+
+    /// Seeds the random generator used by CXNN, so runs are reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// RAM size in bytes: `MEMORY_SIZE` (4KB, the default) or
+    /// `XO_CHIP_MEMORY_SIZE` (64KB) for XO-CHIP programs.
+    pub fn memory_size(mut self, size: usize) -> Self {
+        assert!(
+            size == MEMORY_SIZE || size == XO_CHIP_MEMORY_SIZE,
+            "memory size must be 0x1000 or 0x10000"
+        );
+        self.memory_size = size;
+        self
+    }
+
+    /// Original COSMAC VIP instruction times, see `Emulator::set_vip_timing`.
+    pub fn vip_timing(mut self, on: bool) -> Self {
+        self.vip_timing = on;
+        self
+    }
+
+    /// FX0A finishes when the key is released, see `Emulator::set_wait_for_release`.
+    pub fn wait_for_release(mut self, on: bool) -> Self {
+        self.wait_for_release = on;
+        self
+    }
+
+    /// Program errors halt the emulator, see `Emulator::set_halt_on_fault`.
+    pub fn halt_on_fault(mut self, on: bool) -> Self {
+        self.halt_on_fault = on;
+        self
+    }
+
+    /// Instructions kept for `Emulator::history`, see `Emulator::set_history_len`.
+    pub fn history_len(mut self, len: usize) -> Self {
+        self.history_len = len;
+        self
+    }
+
+    pub fn build(self) -> Emulator {
+        let mut emulator = Emulator::with_memory_size(self.memory_size);
+        emulator.set_quirks(self.quirks);
+        emulator.set_clock_hz(self.clock_hz);
+        emulator.set_vip_timing(self.vip_timing);
+        emulator.set_wait_for_release(self.wait_for_release);
+        emulator.set_halt_on_fault(self.halt_on_fault);
+        emulator.set_history_len(self.history_len);
+        if let Some(seed) = self.seed {
+            emulator.set_rng(Box::new(SmallRng::seed_from_u64(seed)));
+        }
+        emulator
+    }
+}
@@ -0,0 +1,1599 @@
+#[cfg(feature = "block-cache")]
+use crate::block_cache::BlockCache;
+use crate::builder::EmulatorBuilder;
+use crate::events::{CallbackId, EmulatorEvent, EventCallback};
+use crate::frontend::{Audio, Display, Input};
+use crate::idle::{self, Idle};
+use crate::instruction::{Instruction, DECODE_TABLE};
+use crate::keypad::{KeyEvent, Keypad};
+use crate::memory::{Memory, MemoryAccess, MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+use crate::quirks::Quirks;
+use crate::save_state::SaveState;
+use crate::screen::{Screen, SCREEN_SIZE};
+use crate::timing;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
+use rand::rngs::SmallRng;
+use rand::{Rng, RngCore, SeedableRng};
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path};
+
+/// predefined font sprites
+const FONT_DATA: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+/// Address where programs are loaded and execution starts
+pub(crate) const PROGRAM_START: usize = 0x200;
+
+/// Default CPU speed in instructions per second
+pub const DEFAULT_CLOCK_HZ: f32 = 500.0;
+
+/// Rate of the delay and sound timers, also the rate the original hardware refreshed the screen
+pub const TIMER_HZ: f32 = 60.0;
+const TIMER_PERIOD: f32 = 1.0 / TIMER_HZ;
+
+/// Random generator used unless replaced with `Emulator::set_rng`.
+/// Without `std` there is no entropy source, so it starts from a fixed seed.
+fn default_rng() -> SmallRng {
+    #[cfg(feature = "std")]
+    return SmallRng::from_entropy();
+    #[cfg(not(feature = "std"))]
+    return SmallRng::seed_from_u64(0);
+}
+
+/// Error returned when a ROM can't be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    /// ROM file couldn't be read
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// ROM doesn't fit in memory after the program start address
+    TooLarge { size: usize, max: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::TooLarge { size, max } => {
+                write!(f, "ROM is too large: {} bytes (max {})", size, max)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LoadError {}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+// Counters kept while profiling is on
+struct Profile {
+    kinds: [u64; Instruction::KIND_COUNT], // Executed instructions per kind
+    calls: BTreeMap<u16, u64>,             // Executed 2NNN per target
+}
+
+/// chip8 main emulator class. It is basically CPU + keypad, memory, screen etc.
+///
+/// It doesn't need a window or GPU, so it can be driven headlessly:
+///
+/// ```
+/// use chip8_core::Emulator;
+///
+/// let mut emulator = Emulator::new();
+/// // V0 = 0xA; I = font sprite of digit V0; draw it at (V1, V1)
+/// emulator
+///     .load_rom_bytes(&[0x60, 0x0A, 0xF0, 0x29, 0xD1, 0x15])
+///     .unwrap();
+/// emulator.run_cycles(3);
+///
+/// assert_eq!(emulator.registers()[0], 0xA);
+/// assert!(emulator.screen().get_pixel(0, 0));
+/// ```
+pub struct Emulator {
+    halt: Option<HaltReason>,
+    screen: Screen,
+    keypad: Keypad,
+    memory: Memory,
+    code_len: usize,
+    rom: Vec<u8>, // Kept for reset(), the program may overwrite itself in memory
+    stack: Vec<u16>,
+    rs: [u8; 16], // Data registers
+    ri: u16,      // I register
+    pc: u16,
+    rng: Box<dyn RngCore + Send>,
+    delay: u8,
+    sound: u8,
+    total_dt: f32,
+    quirks: Quirks,
+    vblank_wait: bool, // Set by DXYN with the vblank quirk or VIP timing, cleared on the next timer tick
+    clock_hz: f32,
+    cycle_acc: f32, // Fraction of a cycle left over from the last run_for()
+    vip_timing: bool,
+    time_acc: f32, // Seconds run_for() still owes with VIP timing, negative when it ran ahead
+    frame: u64,    // 60Hz timer ticks since the ROM was loaded
+    wait_for_release: bool,
+    halt_on_fault: bool,
+    // Fault of the instruction being executed, turned into a halt once it's done
+    fault: Option<Fault>,
+    key_wait: Option<u8>, // Key pressed during FX0A, waiting for its release
+    instruction_count: u64,
+    stats: EmulatorStats,
+    profile: Option<Box<Profile>>,
+    access: Option<Box<MemoryAccess>>, // Reads, writes and executes per address
+    register_stores: BTreeMap<u16, u8>, // Start address and length of FX55 stores
+    trace_hook: Option<TraceHook>,
+    history: VecDeque<(u16, u16)>, // PC and opcode of the last `history_len` instructions
+    history_len: usize,
+    breakpoints: BTreeSet<u16>,
+    skip_breakpoint: bool, // Set by resume() to execute the instruction at the breakpoint
+    callbacks: Vec<(CallbackId, EventCallback)>,
+    next_callback_id: u32,
+    #[cfg(feature = "block-cache")]
+    block_cache: BlockCache,
+}
+
+/// Called with PC and opcode before each instruction is executed
+pub type TraceHook = Box<dyn FnMut(u16, u16) + Send>;
+
+/// What happened during a single `Emulator::step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepEvent {
+    /// Instruction executed without anything of interest
+    Normal,
+    /// DXYN drew a sprite, `collision` is the value stored in VF
+    DrewSprite { collision: bool },
+    /// FX0A is waiting for a key, PC stays on the instruction
+    WaitingForKey,
+    /// DXYN with the vblank quirk is waiting for the next 60Hz tick
+    WaitingForVBlank,
+    /// Sound started (`true`) or stopped (`false`)
+    Beep(bool),
+    /// Nothing was executed
+    Halted(HaltReason),
+    /// Opcode isn't a known instruction and was skipped
+    IllegalOpcode { pc: u16, opcode: u16 },
+}
+
+/// Counters of what the running program did, see `Emulator::stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EmulatorStats {
+    /// Executed instructions, same as `Emulator::instruction_count` unless reset
+    pub instructions: u64,
+    /// Sprites drawn by DXYN
+    pub draws: u64,
+    /// Draws that turned off a pixel
+    pub collisions: u64,
+    /// FX0A instructions that got a key, each wait counts once
+    pub key_waits: u64,
+    /// Subroutine calls by 2NNN
+    pub calls: u64,
+    /// Random numbers generated by CXNN
+    pub randoms: u64,
+    /// Unknown opcodes that were skipped
+    pub illegal_opcodes: u64,
+    /// First skipped opcode as (pc, opcode)
+    pub first_illegal_opcode: Option<(u16, u16)>,
+    /// Illegal opcodes, PC running out of memory and faults that halted the
+    /// emulator, see `Emulator::set_halt_on_fault`
+    pub faults: u64,
+    /// Seconds of emulated time, runs at the clock speed and not the real one
+    pub emulated_time: f64,
+    /// Times each key went down
+    pub key_presses: [u64; Keypad::KEY_COUNT],
+    /// Times EX9E or EXA1 checked each key, keys a game checks but no manual
+    /// mentions are controls worth trying
+    pub key_queries: [u64; Keypad::KEY_COUNT],
+}
+
+/// Why the emulator doesn't execute instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HaltReason {
+    /// No ROM has been loaded yet
+    NoRom,
+    /// PC points outside of memory
+    PcOutOfBounds(u16),
+    /// PC reached a breakpoint, see `Emulator::resume`
+    Breakpoint(u16),
+    /// The instruction at `pc` faulted, see `Emulator::set_halt_on_fault`
+    Fault { pc: u16, opcode: u16, fault: Fault },
+}
+
+/// Program error that halts the emulator when `Emulator::set_halt_on_fault` is on.
+/// Otherwise the instruction carries on: unknown opcodes are skipped, a return
+/// with an empty stack does nothing, the stack grows and addresses wrap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Opcode isn't a known instruction
+    IllegalOpcode,
+    /// 00EE with nothing on the stack
+    StackUnderflow,
+    /// 2NNN with `STACK_DEPTH` return addresses on the stack already
+    StackOverflow,
+    /// I pointed past the end of memory, the address is the first one outside
+    MemoryOutOfBounds(u16),
+}
+
+impl Fault {
+    pub fn description(self) -> &'static str {
+        match self {
+            Fault::IllegalOpcode => "Illegal opcode",
+            Fault::StackUnderflow => "Return with an empty stack",
+            Fault::StackOverflow => "Call with a full stack",
+            Fault::MemoryOutOfBounds(_) => "Memory access past the end of memory",
+        }
+    }
+}
+
+/// Nested calls the stack holds before a call faults, as many as SUPER-CHIP has
+pub const STACK_DEPTH: usize = 16;
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Emulator {
+            halt: None,
+            screen: Screen::default(),
+            keypad: Keypad::default(),
+            memory: Memory::new(MEMORY_SIZE),
+            code_len: 0,
+            rom: Vec::new(),
+            stack: Vec::new(),
+            rs: [0; 16],
+            ri: 0,
+            pc: 0,
+            rng: Box::new(default_rng()),
+            delay: 0,
+            sound: 0,
+            total_dt: 0.0,
+            quirks: Quirks::default(),
+            vblank_wait: false,
+            clock_hz: DEFAULT_CLOCK_HZ,
+            cycle_acc: 0.0,
+            vip_timing: false,
+            time_acc: 0.0,
+            frame: 0,
+            wait_for_release: false,
+            halt_on_fault: false,
+            fault: None,
+            key_wait: None,
+            instruction_count: 0,
+            stats: EmulatorStats::default(),
+            profile: None,
+            access: None,
+            register_stores: BTreeMap::new(),
+            trace_hook: None,
+            history: VecDeque::new(),
+            history_len: 0,
+            breakpoints: BTreeSet::new(),
+            skip_breakpoint: false,
+            callbacks: Vec::new(),
+            next_callback_id: 0,
+            #[cfg(feature = "block-cache")]
+            block_cache: BlockCache::new(MEMORY_SIZE),
+        }
+    }
+}
+
+impl Emulator {
+    /// Creates a halted emulator with empty memory (apart from the font).
+    pub fn new() -> Self {
+        Self::with_memory_size(MEMORY_SIZE)
+    }
+
+    /// Starts configuring an emulator, see `EmulatorBuilder`.
+    pub fn builder() -> EmulatorBuilder {
+        EmulatorBuilder::default()
+    }
+
+    pub(crate) fn with_memory_size(memory_size: usize) -> Self {
+        let mut e = Emulator {
+            halt: Some(HaltReason::NoRom),
+            pc: PROGRAM_START as u16,
+            memory: Memory::new(memory_size),
+            #[cfg(feature = "block-cache")]
+            block_cache: BlockCache::new(memory_size),
+            ..Default::default()
+        };
+        // Copy Font data into memory
+        e.memory.as_mut_slice()[..FONT_DATA.len()].copy_from_slice(&FONT_DATA[..]);
+
+        e
+    }
+
+    pub fn get_code_range(&self) -> (usize, usize) {
+        (PROGRAM_START, PROGRAM_START + self.code_len)
+    }
+
+    /// ROM as it was loaded, memory in `get_code_range` may differ once the program
+    /// writes to itself.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// // I = 0x204; V0..V1 stored at I, over the ROM's last bytes
+    /// emulator.load_rom_bytes(&[0xA2, 0x04, 0xF1, 0x55, 0xAB, 0xCD]).unwrap();
+    /// emulator.run_cycles(2);
+    /// assert_eq!(emulator.rom(), [0xA2, 0x04, 0xF1, 0x55, 0xAB, 0xCD]);
+    /// assert_eq!(emulator.memory()[0x204..0x206], [0, 0]);
+    /// ```
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    /// Memory where the ROM was loaded, as it is now: patches made while
+    /// debugging and whatever the program wrote there included.
+    ///
+    /// ```
+    /// # use chip8_core::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_rom_bytes(&[0x12, 0x00]).unwrap();
+    /// emulator.load_memory_bytes(0x201, &[0x02]).unwrap();
+    /// assert_eq!(emulator.rom(), [0x12, 0x00]);
+    /// assert_eq!(emulator.rom_in_memory(), [0x12, 0x02]);
+    /// ```
+    pub fn rom_in_memory(&self) -> &[u8] {
+        &self.memory()[PROGRAM_START..PROGRAM_START + self.rom.len()]
+    }
+
+    /// Start and end of the built-in font sprites, see FX29.
+    pub fn get_font_range(&self) -> (usize, usize) {
+        (0, FONT_DATA.len())
+    }
+
+    /// Places FX55 stored registers to since the ROM was loaded, as start address and
+    /// number of bytes. Stores to the same address keep the longest length.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// // I = 0x300; store V0-V2 at I
+    /// emulator.load_rom_bytes(&[0xA3, 0x00, 0xF2, 0x55]).unwrap();
+    /// emulator.run_cycles(2);
+    /// assert_eq!(emulator.register_stores().collect::<Vec<_>>(), [(0x300, 3)]);
+    /// ```
+    pub fn register_stores(&self) -> impl Iterator<Item = (u16, usize)> + '_ {
+        self.register_stores
+            .iter()
+            .map(|(&address, &len)| (address, len as usize))
+    }
+
+    /// Resets the machine and loads ROM from file. Emulator starts running right away.
+    #[cfg(feature = "std")]
+    pub fn load_rom(&mut self, romfile: &Path) -> Result<(), LoadError> {
+        let contents = fs::read(romfile)?;
+        self.load_rom_bytes(&contents)
+    }
+
+    /// Resets the machine and loads ROM from memory. Emulator starts running right away.
+    pub fn load_rom_bytes(&mut self, rom: &[u8]) -> Result<(), LoadError> {
+        let max = self.memory.len() - PROGRAM_START;
+        if rom.len() > max {
+            return Err(LoadError::TooLarge {
+                size: rom.len(),
+                max,
+            });
+        }
+
+        // Reset emulator to initial state, keeping configuration
+        self.clear(self.memory.len());
+
+        // Copy rom in memory
+        self.memory.as_mut_slice()[PROGRAM_START..PROGRAM_START + rom.len()].copy_from_slice(rom);
+        self.code_len = rom.len();
+        self.rom = rom.to_vec();
+        log::debug!("Loaded {} bytes ROM", rom.len());
+
+        self.halt = None;
+        Ok(())
+    }
+
+    /// Switches RAM between `MEMORY_SIZE` (4KB) and `XO_CHIP_MEMORY_SIZE` (64KB).
+    /// Data addresses wrap around at the new size. A loaded ROM restarts in the
+    /// new memory like after `reset`, and the size isn't changed if it doesn't fit.
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, XO_CHIP_MEMORY_SIZE};
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_rom_bytes(&[0x12, 0x00]).unwrap(); // Loop
+    /// emulator.set_memory_size(XO_CHIP_MEMORY_SIZE).unwrap();
+    /// assert_eq!(emulator.memory().len(), 0x10000);
+    /// assert_eq!(emulator.memory()[0x200..0x202], [0x12, 0x00]);
+    /// ```
+    pub fn set_memory_size(&mut self, size: usize) -> Result<(), LoadError> {
+        assert!(
+            size == MEMORY_SIZE || size == XO_CHIP_MEMORY_SIZE,
+            "memory size must be 0x1000 or 0x10000"
+        );
+        if size == self.memory.len() {
+            return Ok(());
+        }
+        let max = size - PROGRAM_START;
+        if self.rom.len() > max {
+            return Err(LoadError::TooLarge {
+                size: self.rom.len(),
+                max,
+            });
+        }
+        let loaded = self.halt != Some(HaltReason::NoRom);
+        let rom = mem::take(&mut self.rom);
+        self.clear(size);
+        if loaded {
+            self.load_rom_bytes(&rom)?;
+        }
+        Ok(())
+    }
+
+    // Halted machine with `size` bytes of empty memory, keeping configuration,
+    // breakpoints and callbacks
+    fn clear(&mut self, size: usize) {
+        let previous = mem::replace(self, Self::with_memory_size(size));
+        self.clock_hz = previous.clock_hz;
+        self.vip_timing = previous.vip_timing;
+        self.wait_for_release = previous.wait_for_release;
+        self.halt_on_fault = previous.halt_on_fault;
+        self.quirks = previous.quirks;
+        self.rng = previous.rng;
+        self.trace_hook = previous.trace_hook;
+        self.history_len = previous.history_len;
+        self.profile = previous.profile;
+        // Access counters follow the memory size
+        self.access = previous.access.map(|access| {
+            if access.reads.len() == size {
+                access
+            } else {
+                Box::new(MemoryAccess::new(size))
+            }
+        });
+        self.breakpoints = previous.breakpoints;
+        self.callbacks = previous.callbacks;
+        self.next_callback_id = previous.next_callback_id;
+    }
+
+    /// Restarts the loaded ROM as if it was just loaded, undoing anything it wrote
+    /// to memory. Settings, breakpoints and callbacks are kept, the random
+    /// generator isn't reseeded. Does nothing until a ROM is loaded.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_rom_bytes(&[0x60, 0x0A]).unwrap(); // V0 = 0x0A
+    /// emulator.run_cycles(1);
+    /// emulator.reset();
+    /// assert_eq!(emulator.registers()[0], 0);
+    /// assert_eq!(emulator.pc(), 0x200);
+    /// ```
+    pub fn reset(&mut self) {
+        if self.halt == Some(HaltReason::NoRom) {
+            return;
+        }
+        let rom = mem::take(&mut self.rom);
+        // Can't fail, the ROM already fit into this memory
+        let _ = self.load_rom_bytes(&rom);
+    }
+
+    /// Captures machine state, see `SaveState`.
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            memory: self.memory.as_slice().to_vec(),
+            screen: *self.screen.rows(),
+            stack: self.stack.clone(),
+            registers: self.rs,
+            index: self.ri,
+            pc: self.pc,
+            delay_timer: self.delay,
+            sound_timer: self.sound,
+            timer_phase: self.total_dt,
+            vblank_wait: self.vblank_wait,
+            quirks: self.quirks,
+            clock_hz: self.clock_hz,
+            instruction_count: self.instruction_count,
+            rom: self.rom.clone(),
+        }
+    }
+
+    /// Restores a state captured by `save_state` and continues running from it.
+    /// Breakpoints, callbacks, the trace hook and the random generator are kept,
+    /// the instruction `history` starts over.
+    /// Panics if the memory size isn't supported, `SaveState::from_bytes` checks it.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_rom_bytes(&[0x70, 0x01, 0x12, 0x00]).unwrap(); // V0 += 1; loop
+    /// emulator.run_cycles(2);
+    /// let state = emulator.save_state();
+    /// emulator.run_cycles(10);
+    /// emulator.load_state(&state);
+    /// assert_eq!(emulator.registers()[0], 1);
+    /// ```
+    pub fn load_state(&mut self, state: &SaveState) {
+        let memory_size = state.memory.len();
+        self.memory = Memory::new(memory_size);
+        self.memory.as_mut_slice().copy_from_slice(&state.memory);
+        if let Some(access) = &mut self.access {
+            if access.reads.len() != memory_size {
+                **access = MemoryAccess::new(memory_size);
+            }
+        }
+        #[cfg(feature = "block-cache")]
+        {
+            self.block_cache = BlockCache::new(memory_size);
+        }
+        self.screen = Screen::from_rows(state.screen);
+        self.stack = state.stack.clone();
+        self.rs = state.registers;
+        self.ri = state.index;
+        self.pc = state.pc;
+        self.delay = state.delay_timer;
+        self.sound = state.sound_timer;
+        self.total_dt = state.timer_phase;
+        self.vblank_wait = state.vblank_wait;
+        self.quirks = state.quirks;
+        self.clock_hz = state.clock_hz;
+        self.cycle_acc = 0.0;
+        self.instruction_count = state.instruction_count;
+        self.code_len = state.rom.len();
+        self.rom = state.rom.clone();
+        self.skip_breakpoint = false;
+        // Instructions before the load didn't lead here
+        self.history.clear();
+        self.halt = None;
+    }
+
+    /// Advances emulation by `dt` seconds of wall time.
+    pub fn update(&mut self, dt: f32) {
+        self.run_for(dt);
+    }
+
+    /// Advances emulation by `dt` seconds, exchanging state with a frontend:
+    /// keys are pulled from `input` first, then screen and sound changes are
+    /// pushed to `display` and `audio`.
+    pub fn run_frame<I, D, A>(&mut self, dt: f32, input: &I, display: &mut D, audio: &mut A)
+    where
+        I: Input,
+        D: Display,
+        A: Audio,
+    {
+        for key in 0..Keypad::KEY_COUNT as u8 {
+            let down = input.is_key_down(key);
+            if down != self.keypad.will_be_pressed(key) {
+                self.push_key_event(KeyEvent {
+                    key,
+                    down,
+                    frame: self.frame,
+                });
+            }
+        }
+
+        self.run_for(dt);
+
+        if self.screen.is_dirty() {
+            display.draw(&self.screen);
+            self.screen.reset_dirty();
+        }
+        audio.set_beep(self.sound > 0);
+    }
+
+    /// Runs as many instructions as fit into `seconds` at the current clock speed.
+    /// Fractional cycles are carried over to the next call.
+    pub fn run_for(&mut self, seconds: f32) {
+        if self.is_halted() {
+            return;
+        }
+        if self.vip_timing {
+            // Instructions take different times, run them until `seconds` are used up
+            self.time_acc += seconds;
+            while self.time_acc > 0.0 {
+                let dt = self.step_time();
+                if let StepEvent::Halted(_) = self.step() {
+                    self.time_acc = 0.0;
+                    break;
+                }
+                self.time_acc -= dt;
+            }
+            return;
+        }
+        self.cycle_acc += seconds * self.clock_hz;
+        let cycles = self.cycle_acc as usize;
+        self.cycle_acc -= cycles as f32;
+        self.run_cycles(cycles);
+    }
+
+    /// Runs exactly `cycles` instructions, advancing timers by the time they take.
+    ///
+    /// With the `block-cache` feature, runs of instructions are decoded once and
+    /// replayed from a cache while no breakpoints or trace hook are set.
+    pub fn run_cycles(&mut self, cycles: usize) {
+        let mut done = 0;
+        while done < cycles {
+            #[cfg(feature = "block-cache")]
+            {
+                let executed = self.run_block(cycles - done);
+                if executed > 0 {
+                    done += executed;
+                    continue;
+                }
+            }
+            if let StepEvent::Halted(_) = self.step() {
+                break;
+            }
+            done += 1;
+        }
+    }
+
+    /// Executes up to `max_cycles` instructions of the cached block at PC, returns
+    /// how many ran. Does nothing when stepping has to check every instruction.
+    #[cfg(feature = "block-cache")]
+    fn run_block(&mut self, max_cycles: usize) -> usize {
+        if self.halt.is_some()
+            || self.vip_timing
+            || self.vblank_wait
+            || self.skip_breakpoint
+            || self.trace_hook.is_some()
+            || self.history_len > 0
+            || !self.breakpoints.is_empty()
+        {
+            return 0;
+        }
+
+        let dt = 1.0 / self.clock_hz;
+        let start = self.pc;
+        let count = self
+            .block_cache
+            .block_len(start, &self.memory)
+            .min(max_cycles);
+        for i in 0..count {
+            // The block overwrote itself, the rest has to be decoded again
+            let instruction = match self.block_cache.instruction(start, i) {
+                Some(instruction) => instruction,
+                None => return i,
+            };
+            // Same as step(), minus the checks that can't fail inside a block
+            let beeping = self.sound > 0;
+            self.update_timer(dt);
+            let pc = self.pc;
+            let event = self.execute(instruction);
+            if self.fault.is_some() {
+                self.halt_on(pc);
+                return i + 1;
+            }
+            self.finish_step(beeping, event);
+        }
+        count
+    }
+
+    /// Executes a single instruction, advancing timers by the time it takes.
+    ///
+    /// Returns what the instruction did. A sound start/stop is reported as
+    /// `Beep` only when the instruction itself had nothing else to report.
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, HaltReason, StepEvent};
+    ///
+    /// let mut emulator = Emulator::new();
+    /// assert_eq!(emulator.step(), StepEvent::Halted(HaltReason::NoRom));
+    ///
+    /// // I = font sprite of digit 0; draw it twice at (V0, V0)
+    /// emulator
+    ///     .load_rom_bytes(&[0xF0, 0x29, 0xD0, 0x05, 0xD0, 0x05])
+    ///     .unwrap();
+    /// assert_eq!(emulator.step(), StepEvent::Normal);
+    /// assert_eq!(emulator.step(), StepEvent::DrewSprite { collision: false });
+    /// assert_eq!(emulator.step(), StepEvent::DrewSprite { collision: true });
+    /// ```
+    pub fn step(&mut self) -> StepEvent {
+        if let Some(reason) = self.halt {
+            return StepEvent::Halted(reason);
+        }
+        let skip_breakpoint = mem::replace(&mut self.skip_breakpoint, false);
+        if !skip_breakpoint && self.breakpoints.contains(&self.pc) {
+            self.halt = Some(HaltReason::Breakpoint(self.pc));
+            self.emit(EmulatorEvent::BreakpointHit(self.pc));
+            return StepEvent::Halted(HaltReason::Breakpoint(self.pc));
+        }
+        let beeping = self.sound > 0;
+        self.update_timer(self.step_time());
+
+        let event = if self.vblank_wait {
+            StepEvent::WaitingForVBlank
+        } else if !self.memory.contains(self.pc as usize + 1) {
+            let reason = HaltReason::PcOutOfBounds(self.pc);
+            self.halt = Some(reason);
+            self.stats.faults += 1;
+            self.emit(EmulatorEvent::Halted(reason));
+            StepEvent::Halted(reason)
+        } else {
+            self.execute_instruction()
+        };
+        self.finish_step(beeping, event)
+    }
+
+    // Time the next step takes, `1 / clock_hz` or with VIP timing what the instruction
+    // took on the VIP
+    fn step_time(&self) -> f32 {
+        if !self.vip_timing {
+            return 1.0 / self.clock_hz;
+        }
+        if self.vblank_wait {
+            // Idles until the display interrupt, a bit past it so the timer ticks
+            return (TIMER_PERIOD - self.total_dt).max(0.0) + 1e-6;
+        }
+        let opcode = u16::from_be_bytes([
+            self.memory.read(self.pc),
+            self.memory.read(self.pc.wrapping_add(1)),
+        ]);
+        timing::vip_time(Instruction::decode(opcode))
+    }
+
+    // Reports sound changes of a step
+    fn finish_step(&mut self, beeping: bool, event: StepEvent) -> StepEvent {
+        let beep = self.sound > 0;
+        if beep != beeping {
+            self.emit(if beep {
+                EmulatorEvent::SoundStarted
+            } else {
+                EmulatorEvent::SoundStopped
+            });
+        }
+        match event {
+            StepEvent::Normal if beep != beeping => StepEvent::Beep(beep),
+            event => event,
+        }
+    }
+
+    /// Continues after a breakpoint, the instruction at the breakpoint is executed
+    /// by the next step. Does nothing if the emulator isn't stopped at a breakpoint.
+    pub fn resume(&mut self) {
+        if let Some(HaltReason::Breakpoint(_)) = self.halt {
+            self.halt = None;
+            self.skip_breakpoint = true;
+        }
+    }
+
+    /// Continues after a fault with the next instruction. The faulting one already
+    /// ran the way it does with `set_halt_on_fault` off. Does nothing unless the
+    /// emulator is halted by a fault.
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, Fault, HaltReason};
+    ///
+    /// let mut emulator = Emulator::builder().halt_on_fault(true).build();
+    /// // Return with an empty stack, then V0 = 1
+    /// emulator.load_rom_bytes(&[0x00, 0xEE, 0x60, 0x01]).unwrap();
+    /// emulator.run_cycles(2);
+    /// assert_eq!(
+    ///     emulator.halt_reason(),
+    ///     Some(HaltReason::Fault { pc: 0x200, opcode: 0x00EE, fault: Fault::StackUnderflow })
+    /// );
+    ///
+    /// emulator.ignore_fault();
+    /// emulator.run_cycles(1);
+    /// assert_eq!(emulator.registers()[0], 1);
+    /// ```
+    pub fn ignore_fault(&mut self) {
+        if let Some(HaltReason::Fault { .. }) = self.halt {
+            self.halt = None;
+        }
+    }
+
+    // Halts on the fault of the instruction that was at `pc`
+    fn halt_on(&mut self, pc: u16) -> StepEvent {
+        let fault = self.fault.take().expect("no fault to halt on");
+        let opcode =
+            u16::from_be_bytes([self.memory.read(pc), self.memory.read(pc.wrapping_add(1))]);
+        let reason = HaltReason::Fault { pc, opcode, fault };
+        self.halt = Some(reason);
+        // Illegal opcodes are counted either way
+        if fault != Fault::IllegalOpcode {
+            self.stats.faults += 1;
+        }
+        self.emit(EmulatorEvent::Halted(reason));
+        StepEvent::Halted(reason)
+    }
+
+    /// Stops execution before the instruction at `address` is executed.
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) {
+        self.breakpoints.remove(&address);
+    }
+
+    /// Breakpoint addresses in ascending order.
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    /// Registers a callback for core events, it's kept until `unsubscribe`
+    /// (also across ROM loads).
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, EmulatorEvent};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let mut emulator = Emulator::new();
+    /// let sink = events.clone();
+    /// emulator.subscribe(Box::new(move |_, event| sink.lock().unwrap().push(event)));
+    ///
+    /// // Clear screen, then loop at 0x202
+    /// emulator.load_rom_bytes(&[0x00, 0xE0, 0x12, 0x02]).unwrap();
+    /// emulator.add_breakpoint(0x202);
+    /// emulator.run_cycles(10);
+    ///
+    /// assert_eq!(
+    ///     *events.lock().unwrap(),
+    ///     [EmulatorEvent::ScreenUpdated, EmulatorEvent::BreakpointHit(0x202)]
+    /// );
+    /// ```
+    pub fn subscribe(&mut self, callback: EventCallback) -> CallbackId {
+        let id = CallbackId(self.next_callback_id);
+        self.next_callback_id += 1;
+        self.callbacks.push((id, callback));
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: CallbackId) {
+        self.callbacks.retain(|(callback_id, _)| *callback_id != id);
+    }
+
+    fn emit(&mut self, event: EmulatorEvent) {
+        if self.callbacks.is_empty() {
+            return;
+        }
+        let mut callbacks = mem::take(&mut self.callbacks);
+        for (_, callback) in callbacks.iter_mut() {
+            callback(self, event);
+        }
+        self.callbacks = callbacks;
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halt.is_some()
+    }
+
+    /// Why the emulator is halted, `None` while running.
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt
+    }
+
+    /// Whether the program at PC is done or waits for a key, so it only looks
+    /// stuck. `None` while it does anything else or is halted.
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, Idle};
+    ///
+    /// let mut emulator = Emulator::new();
+    /// // Clear the screen, then jump to itself
+    /// emulator.load_rom_bytes(&[0x00, 0xE0, 0x12, 0x02]).unwrap();
+    /// assert_eq!(emulator.idle(), None);
+    /// emulator.step();
+    /// assert_eq!(emulator.idle(), Some(Idle::Finished));
+    /// ```
+    pub fn idle(&self) -> Option<Idle> {
+        idle::detect(self)
+    }
+
+    /// Instructions executed since the ROM was loaded, for measuring actual speed.
+    /// Waiting for a key counts, waiting for vblank and halts don't.
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    /// Counters since the ROM was loaded or `reset_stats` was called.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// // I = font sprite of digit 0; draw it at (V0, V0); loop
+    /// emulator
+    ///     .load_rom_bytes(&[0xF0, 0x29, 0xD0, 0x05, 0x12, 0x02])
+    ///     .unwrap();
+    /// emulator.run_cycles(5);
+    /// assert_eq!(emulator.stats().draws, 2);
+    /// assert_eq!(emulator.stats().collisions, 1);
+    /// assert_eq!(emulator.stats().faults, 0);
+    ///
+    /// emulator.reset_stats();
+    /// assert_eq!(emulator.stats().instructions, 0);
+    /// ```
+    pub fn stats(&self) -> &EmulatorStats {
+        &self.stats
+    }
+
+    /// Starts the counters of `stats` over, the machine keeps running.
+    pub fn reset_stats(&mut self) {
+        self.stats = EmulatorStats::default();
+    }
+
+    /// Starts or stops counting executed instructions per kind and calls per
+    /// subroutine, see `instruction_profile` and `call_profile`. Counting starts over
+    /// when enabled again.
+    pub fn set_profiling(&mut self, on: bool) {
+        self.profile = if on {
+            Some(Box::new(Profile {
+                kinds: [0; Instruction::KIND_COUNT],
+                calls: BTreeMap::new(),
+            }))
+        } else {
+            None
+        };
+    }
+
+    /// Executed instructions per `Instruction::kind` since profiling was enabled,
+    /// `None` while it's off.
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, Instruction};
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_profiling(true);
+    /// emulator.load_rom_bytes(&[0x70, 0x01, 0x12, 0x00]).unwrap(); // V0 += 1; loop
+    /// emulator.run_cycles(10);
+    /// let profile = emulator.instruction_profile().unwrap();
+    /// assert_eq!(profile[Instruction::decode(0x7001).kind()], 5);
+    /// ```
+    pub fn instruction_profile(&self) -> Option<&[u64; Instruction::KIND_COUNT]> {
+        self.profile.as_ref().map(|profile| &profile.kinds)
+    }
+
+    /// Times each subroutine was called since profiling was enabled, by address,
+    /// `None` while it's off.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_profiling(true);
+    /// // 200: call 204; 202: jump 200; 204: return
+    /// emulator.load_rom_bytes(&[0x22, 0x04, 0x12, 0x00, 0x00, 0xEE]).unwrap();
+    /// emulator.run_cycles(9);
+    /// assert_eq!(emulator.call_profile().unwrap()[&0x204], 3);
+    /// ```
+    pub fn call_profile(&self) -> Option<&BTreeMap<u16, u64>> {
+        self.profile.as_ref().map(|profile| &profile.calls)
+    }
+
+    /// Starts or stops counting reads, writes and executes of each memory address,
+    /// see `memory_access`. Counting starts over when enabled again.
+    pub fn set_access_tracking(&mut self, on: bool) {
+        self.access = if on {
+            Some(Box::new(MemoryAccess::new(self.memory.len())))
+        } else {
+            None
+        };
+    }
+
+    /// Memory accesses since tracking was enabled, `None` while it's off.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_access_tracking(true);
+    /// // I = 0x300; store V0 at I; loop
+    /// emulator.load_rom_bytes(&[0xA3, 0x00, 0xF0, 0x55, 0x12, 0x04]).unwrap();
+    /// emulator.run_cycles(3);
+    /// let access = emulator.memory_access().unwrap();
+    /// assert_eq!(access.writes[0x300], 1);
+    /// assert_eq!(access.executes[0x202], 1);
+    /// ```
+    pub fn memory_access(&self) -> Option<&MemoryAccess> {
+        self.access.as_deref()
+    }
+
+    /// CPU speed in instructions per second.
+    pub fn clock_hz(&self) -> f32 {
+        self.clock_hz
+    }
+
+    pub fn set_clock_hz(&mut self, hz: f32) {
+        assert!(hz > 0.0, "clock speed must be positive");
+        self.clock_hz = hz;
+    }
+
+    /// Charges every instruction the time it took on the COSMAC VIP instead of
+    /// `1 / clock_hz`, so `run_for` runs programs at their original pace and DXYN
+    /// waits for the next 60Hz tick like the vblank quirk. Clock speed is ignored
+    /// while it's on.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.set_vip_timing(true);
+    /// emulator.load_rom_bytes(&[0x12, 0x00]).unwrap(); // Jump to self
+    /// emulator.run_for(1.0 / 60.0);
+    /// assert!(emulator.instruction_count() < 200);
+    /// ```
+    pub fn set_vip_timing(&mut self, on: bool) {
+        self.vip_timing = on;
+        self.time_acc = 0.0;
+    }
+
+    pub fn vip_timing(&self) -> bool {
+        self.vip_timing
+    }
+
+    /// FX0A waits for the key to be released before storing it, like the COSMAC
+    /// VIP, instead of finishing as soon as a key is down.
+    pub fn set_wait_for_release(&mut self, on: bool) {
+        self.wait_for_release = on;
+        self.key_wait = None;
+    }
+
+    pub fn wait_for_release(&self) -> bool {
+        self.wait_for_release
+    }
+
+    /// Illegal opcodes, returns with an empty stack, calls past `STACK_DEPTH`
+    /// and I pointing past the end of memory halt the emulator, see `Fault`.
+    /// Off by default, programs that get away with these keep running.
+    pub fn set_halt_on_fault(&mut self, on: bool) {
+        self.halt_on_fault = on;
+    }
+
+    pub fn halt_on_fault(&self) -> bool {
+        self.halt_on_fault
+    }
+
+    // Remembers a fault of the current instruction if faults halt
+    fn fault(&mut self, fault: Fault) {
+        if self.halt_on_fault {
+            self.fault.get_or_insert(fault);
+        }
+    }
+
+    /// Replaces the random number generator used by CXNN.
+    pub fn set_rng(&mut self, rng: Box<dyn RngCore + Send>) {
+        self.rng = rng;
+    }
+
+    /// Installs (or removes with `None`) a function observing every executed instruction.
+    pub fn set_trace_hook(&mut self, hook: Option<TraceHook>) {
+        self.trace_hook = hook;
+    }
+
+    /// Keeps PC and opcode of the last `len` executed instructions for `history`,
+    /// 0 (the default) turns it off. With the `block-cache` feature, instructions
+    /// run one by one while it is on.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::builder().history_len(2).build();
+    /// emulator.load_rom_bytes(&[0x60, 0x01, 0x61, 0x02, 0x12, 0x00]).unwrap();
+    /// for _ in 0..3 {
+    ///     emulator.step();
+    /// }
+    /// let history: Vec<_> = emulator.history().collect();
+    /// assert_eq!(history, [(0x202, 0x6102), (0x204, 0x1200)]);
+    /// ```
+    pub fn set_history_len(&mut self, len: usize) {
+        self.history_len = len;
+        while self.history.len() > len {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn history_len(&self) -> usize {
+        self.history_len
+    }
+
+    /// PC and opcode of recently executed instructions, oldest first
+    pub fn history(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.history.iter().copied()
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Changes interpreter behaviour, takes effect from the next instruction.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    /// Presses (`down == true`) or releases a key of the hex keypad (0x0..=0xF).
+    pub fn set_key(&mut self, key: u8, down: bool) {
+        if down && !self.keypad.is_pressed(key) {
+            self.stats.key_presses[key as usize] += 1;
+        }
+        self.keypad.set(key, down);
+    }
+
+    /// Queues a key press or release for `event.frame`, events due already happen
+    /// right away. A key pressed this way stays down for at least one frame even
+    /// if its release follows immediately, so short taps aren't missed.
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, KeyEvent};
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_rom_bytes(&[0x12, 0x00]).unwrap(); // Jump to self
+    /// let frame = emulator.frame();
+    /// emulator.push_key_event(KeyEvent { key: 5, down: true, frame });
+    /// emulator.push_key_event(KeyEvent { key: 5, down: false, frame });
+    /// assert!(emulator.keypad().is_pressed(5));
+    /// emulator.run_for(1.0 / 30.0);
+    /// assert!(!emulator.keypad().is_pressed(5));
+    /// ```
+    pub fn push_key_event(&mut self, event: KeyEvent) {
+        self.keypad.push(event);
+        self.apply_key_events();
+    }
+
+    fn apply_key_events(&mut self) {
+        while let Some(event) = self.keypad.next_due(self.frame) {
+            self.set_key(event.key, event.down);
+        }
+    }
+
+    pub fn keypad(&self) -> &Keypad {
+        &self.keypad
+    }
+
+    /// 60Hz timer ticks since the ROM was loaded, the clock `KeyEvent`s use
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    /// Data registers V0..VF.
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.rs
+    }
+
+    /// I register.
+    pub fn index(&self) -> u16 {
+        self.ri
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.pc
+    }
+
+    /// Moves execution to `pc`, the next step runs the instruction there. Clears a
+    /// halt, so a program stopped by a bad jump can be put back on track.
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+        self.skip_breakpoint = false;
+        if !matches!(self.halt, Some(HaltReason::NoRom)) {
+            self.halt = None;
+        }
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound
+    }
+
+    /// Whole memory, including the font area and loaded program.
+    pub fn memory(&self) -> &[u8] {
+        self.memory.as_slice()
+    }
+
+    /// Overwrites memory starting at `address`, for debuggers restoring a dump or patching
+    /// a running program. Doesn't count as accesses of the program.
+    ///
+    /// ```
+    /// use chip8_core::Emulator;
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_memory_bytes(0x300, &[1, 2, 3]).unwrap();
+    /// assert_eq!(emulator.memory()[0x300..0x303], [1, 2, 3]);
+    /// assert!(emulator.load_memory_bytes(0xFFF, &[1, 2]).is_err());
+    /// ```
+    pub fn load_memory_bytes(&mut self, address: usize, data: &[u8]) -> Result<(), LoadError> {
+        let max = self.memory.len().saturating_sub(address);
+        if data.len() > max {
+            return Err(LoadError::TooLarge {
+                size: data.len(),
+                max,
+            });
+        }
+        self.memory.as_mut_slice()[address..address + data.len()].copy_from_slice(data);
+        #[cfg(feature = "block-cache")]
+        for i in address..address + data.len() {
+            self.block_cache.invalidate(i);
+        }
+        Ok(())
+    }
+
+    /// Return addresses, innermost call last.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
+    }
+
+    /// Replaces the return addresses, to unwind or fix up calls while debugging.
+    ///
+    /// ```
+    /// # use chip8_core::Emulator;
+    /// let mut emulator = Emulator::new();
+    /// // CALL 0x204, the subroutine at 0x204 is a RET
+    /// emulator.load_rom_bytes(&[0x22, 0x04, 0x12, 0x02, 0x00, 0xEE]).unwrap();
+    /// emulator.step();
+    /// assert_eq!(emulator.stack(), &[0x202]);
+    ///
+    /// // Return somewhere else
+    /// emulator.set_stack(&[0x200]);
+    /// emulator.step();
+    /// assert_eq!(emulator.pc(), 0x200);
+    /// ```
+    pub fn set_stack(&mut self, stack: &[u16]) {
+        self.stack = stack.to_vec();
+    }
+
+    /// FNV-1a hash of registers, I, PC, timers, stack, memory and screen.
+    ///
+    /// It only depends on machine state visible to programs (not on clock,
+    /// quirks or host timing) and is the same on every platform, so it can be
+    /// stored in replays and compared between runs, machines or emulators.
+    pub fn state_hash(&self) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+        let mut hash = FNV_OFFSET;
+        let mut feed = |bytes: &[u8]| {
+            for &b in bytes {
+                hash = (hash ^ b as u64).wrapping_mul(FNV_PRIME);
+            }
+        };
+
+        feed(&self.rs);
+        feed(&self.ri.to_be_bytes());
+        feed(&self.pc.to_be_bytes());
+        feed(&[self.delay, self.sound]);
+        for adr in &self.stack {
+            feed(&adr.to_be_bytes());
+        }
+        feed(self.memory.as_slice());
+        for y in 0..SCREEN_SIZE.1 {
+            for x in 0..SCREEN_SIZE.0 {
+                feed(&[self.screen.get_pixel(x, y) as u8]);
+            }
+        }
+        hash
+    }
+
+    fn update_timer(&mut self, dt: f32) {
+        self.total_dt += dt;
+        self.stats.emulated_time += dt as f64;
+        while self.total_dt > TIMER_PERIOD {
+            self.total_dt -= TIMER_PERIOD;
+            self.delay = self.delay.saturating_sub(1);
+            self.sound = self.sound.saturating_sub(1);
+            self.vblank_wait = false;
+            self.frame += 1;
+            self.keypad.new_frame();
+        }
+        self.apply_key_events();
+    }
+
+    fn read_memory(&mut self, address: u16) -> u8 {
+        if !self.memory.contains(address as usize) {
+            self.fault(Fault::MemoryOutOfBounds(address));
+        }
+        if let Some(access) = &mut self.access {
+            let i = self.memory.mask(address);
+            access.reads[i] = access.reads[i].wrapping_add(1);
+        }
+        self.memory.read(address)
+    }
+
+    fn write_memory(&mut self, address: u16, value: u8) {
+        if !self.memory.contains(address as usize) {
+            self.fault(Fault::MemoryOutOfBounds(address));
+        }
+        if let Some(access) = &mut self.access {
+            let i = self.memory.mask(address);
+            access.writes[i] = access.writes[i].wrapping_add(1);
+        }
+        self.memory.write(address, value);
+        #[cfg(feature = "block-cache")]
+        self.block_cache.invalidate(self.memory.mask(address));
+    }
+
+    fn execute_instruction(&mut self) -> StepEvent {
+        let pc = self.pc;
+        let opcode = u16::from_be_bytes([self.memory.read(pc), self.memory.read(pc + 1)]);
+        if let Some(hook) = &mut self.trace_hook {
+            hook(pc, opcode);
+        }
+        if self.history_len > 0 {
+            if self.history.len() == self.history_len {
+                self.history.pop_front();
+            }
+            self.history.push_back((pc, opcode));
+        }
+        let event = self.execute(DECODE_TABLE[opcode as usize]);
+        if self.fault.is_some() {
+            return self.halt_on(pc);
+        }
+        event
+    }
+
+    // Executes an instruction fetched from PC
+    #[inline(always)]
+    fn execute(&mut self, instruction: Instruction) -> StepEvent {
+        use Instruction::*;
+
+        self.pc = self.pc.wrapping_add(2);
+        self.instruction_count = self.instruction_count.wrapping_add(1);
+        self.stats.instructions += 1;
+        if let Some(profile) = &mut self.profile {
+            profile.kinds[instruction.kind()] += 1;
+            if let Call { nnn } = instruction {
+                *profile.calls.entry(nnn).or_insert(0) += 1;
+            }
+        }
+        if let Some(access) = &mut self.access {
+            for offset in 0..2 {
+                let i = self.memory.mask(self.pc.wrapping_sub(2 - offset));
+                access.executes[i] = access.executes[i].wrapping_add(1);
+            }
+        }
+
+        let mut event = StepEvent::Normal;
+        match instruction {
+            ClearScreen => {
+                self.screen.clear();
+                self.emit(EmulatorEvent::ScreenUpdated);
+            }
+            Return => match self.stack.pop() {
+                Some(adr) => self.pc = adr,
+                None => self.fault(Fault::StackUnderflow),
+            },
+            System { .. } => {
+                // Machine code routines aren't supported
+            }
+            Jump { nnn } => {
+                self.pc = nnn;
+            }
+            Call { nnn } => {
+                self.stats.calls += 1;
+                if self.stack.len() >= STACK_DEPTH {
+                    self.fault(Fault::StackOverflow);
+                }
+                self.stack.push(self.pc);
+                self.pc = nnn;
+            }
+            SkipIfEqual { x, nn } => {
+                if self.rs[x as usize] == nn {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            SkipIfNotEqual { x, nn } => {
+                if self.rs[x as usize] != nn {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            SkipIfRegistersEqual { x, y } => {
+                if self.rs[x as usize] == self.rs[y as usize] {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            Load { x, nn } => {
+                self.rs[x as usize] = nn;
+            }
+            Add { x, nn } => {
+                self.rs[x as usize] = self.rs[x as usize].wrapping_add(nn);
+            }
+            Move { x, y } => {
+                self.rs[x as usize] = self.rs[y as usize];
+            }
+            Or { x, y } => {
+                self.rs[x as usize] |= self.rs[y as usize];
+                if self.quirks.logic {
+                    self.rs[0xF] = 0;
+                }
+            }
+            And { x, y } => {
+                self.rs[x as usize] &= self.rs[y as usize];
+                if self.quirks.logic {
+                    self.rs[0xF] = 0;
+                }
+            }
+            Xor { x, y } => {
+                self.rs[x as usize] ^= self.rs[y as usize];
+                if self.quirks.logic {
+                    self.rs[0xF] = 0;
+                }
+            }
+            AddRegisters { x, y } => {
+                let (res, overflow) = self.rs[x as usize].overflowing_add(self.rs[y as usize]);
+                self.rs[0xF] = overflow as u8;
+                self.rs[x as usize] = res;
+            }
+            Subtract { x, y } => {
+                let (res, overflow) = self.rs[x as usize].overflowing_sub(self.rs[y as usize]);
+                self.rs[0xF] = !overflow as u8;
+                self.rs[x as usize] = res;
+            }
+            ShiftRight { x, y } => {
+                let v = if self.quirks.shift {
+                    self.rs[x as usize]
+                } else {
+                    self.rs[y as usize]
+                };
+                self.rs[0xF] = v & 0x1;
+                self.rs[x as usize] = v >> 1;
+            }
+            SubtractReversed { x, y } => {
+                let (res, overflow) = self.rs[y as usize].overflowing_sub(self.rs[x as usize]);
+                self.rs[0xF] = !overflow as u8;
+                self.rs[x as usize] = res;
+            }
+            ShiftLeft { x, y } => {
+                let v = if self.quirks.shift {
+                    self.rs[x as usize]
+                } else {
+                    self.rs[y as usize]
+                };
+                self.rs[0xF] = v >> 7;
+                self.rs[x as usize] = v << 1;
+            }
+            SkipIfRegistersNotEqual { x, y } => {
+                if self.rs[x as usize] != self.rs[y as usize] {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+            LoadIndex { nnn } => {
+                self.ri = nnn;
+            }
+            JumpOffset { nnn } => {
+                let offset = if self.quirks.jump {
+                    self.rs[(nnn >> 8) as usize]
+                } else {
+                    self.rs[0]
+                };
+                self.pc = nnn + offset as u16;
+            }
+            Random { x, nn } => {
+                self.rs[x as usize] = self.rng.gen::<u8>() & nn;
+                self.stats.randoms += 1;
+            }
+            Draw { x, y, n } => {
+                let mut sprite = [0; 15];
+                for (i, row) in sprite[..n as usize].iter_mut().enumerate() {
+                    *row = self.read_memory(self.ri.wrapping_add(i as u16));
+                }
+                let c = self.screen.draw_sprite(
+                    self.rs[x as usize] as usize,
+                    self.rs[y as usize] as usize,
+                    &sprite[..n as usize],
+                    self.quirks.clip,
+                );
+                self.rs[0xF] = c as u8;
+                self.stats.draws += 1;
+                self.stats.collisions += c as u64;
+                // The VIP interpreter always waits for the display before drawing
+                self.vblank_wait = self.quirks.vblank || self.vip_timing;
+                event = StepEvent::DrewSprite { collision: c };
+                self.emit(EmulatorEvent::ScreenUpdated);
+            }
+            SkipIfKeyPressed { x } => {
+                // Only the low nibble picks a key, ROMs can have anything in Vx
+                let key = self.rs[x as usize] & 0xF;
+                if self.keypad.is_pressed(key) {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+                self.stats.key_queries[key as usize] += 1;
+            }
+            SkipIfKeyNotPressed { x } => {
+                let key = self.rs[x as usize] & 0xF;
+                if !self.keypad.is_pressed(key) {
+                    self.pc = self.pc.wrapping_add(2);
+                }
+                self.stats.key_queries[key as usize] += 1;
+            }
+            LoadDelay { x } => {
+                self.rs[x as usize] = self.delay;
+            }
+            WaitForKey { x } => {
+                let key = if self.wait_for_release {
+                    match self.key_wait {
+                        Some(key) if !self.keypad.is_pressed(key) => self.key_wait.take(),
+                        Some(_) => None,
+                        None => {
+                            self.key_wait = self.keypad.get_pressed_key();
+                            None
+                        }
+                    }
+                } else {
+                    self.keypad.get_pressed_key()
+                };
+                if let Some(key) = key {
+                    self.rs[x as usize] = key;
+                    self.stats.key_waits += 1;
+                } else {
+                    self.pc = self.pc.wrapping_sub(2);
+                    event = StepEvent::WaitingForKey;
+                }
+            }
+            SetDelay { x } => {
+                self.delay = self.rs[x as usize];
+            }
+            SetSound { x } => {
+                self.sound = self.rs[x as usize];
+            }
+            AddIndex { x } => {
+                self.ri = self.ri.wrapping_add(self.rs[x as usize] as u16);
+            }
+            LoadFont { x } => {
+                self.ri = self.rs[x as usize] as u16 * 5;
+            }
+            StoreBcd { x } => {
+                let v = self.rs[x as usize];
+                self.write_memory(self.ri, v / 100);
+                self.write_memory(self.ri.wrapping_add(1), (v / 10) % 10);
+                self.write_memory(self.ri.wrapping_add(2), v % 10);
+            }
+            StoreRegisters { x } => {
+                let len = self.register_stores.entry(self.ri).or_insert(0);
+                *len = (*len).max(x + 1);
+                for i in 0..=x {
+                    self.write_memory(self.ri.wrapping_add(i as u16), self.rs[i as usize]);
+                }
+                if !self.quirks.load_store {
+                    self.ri = self.ri.wrapping_add(x as u16 + 1);
+                }
+            }
+            LoadRegisters { x } => {
+                for i in 0..=x {
+                    self.rs[i as usize] = self.read_memory(self.ri.wrapping_add(i as u16));
+                }
+                if !self.quirks.load_store {
+                    self.ri = self.ri.wrapping_add(x as u16 + 1);
+                }
+            }
+            Unknown(opcode) => {
+                let pc = self.pc.wrapping_sub(2);
+                log::warn!("Unknown opcode {:04X} at {:03X}", opcode, pc);
+                self.stats.illegal_opcodes += 1;
+                self.stats.first_illegal_opcode.get_or_insert((pc, opcode));
+                self.stats.faults += 1;
+                self.fault(Fault::IllegalOpcode);
+                event = StepEvent::IllegalOpcode { pc, opcode };
+            }
+        }
+        event
+    }
+}
@@ -0,0 +1,24 @@
+use crate::emulator::{Emulator, HaltReason};
+use alloc::boxed::Box;
+
+/// Core events delivered to callbacks registered with `Emulator::subscribe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmulatorEvent {
+    /// 00E0 or DXYN changed the screen
+    ScreenUpdated,
+    /// Sound timer became non-zero
+    SoundStarted,
+    /// Sound timer ran out
+    SoundStopped,
+    /// Emulator stopped because of an error
+    Halted(HaltReason),
+    /// PC reached a breakpoint, the instruction there isn't executed yet
+    BreakpointHit(u16),
+}
+
+/// Called with the emulator state right after the event happened
+pub type EventCallback = Box<dyn FnMut(&Emulator, EmulatorEvent) + Send>;
+
+/// Identifies a subscribed callback, see `Emulator::unsubscribe`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallbackId(pub(crate) u32);
@@ -0,0 +1,100 @@
+use crate::emulator::PROGRAM_START;
+use crate::flow_graph::FlowGraph;
+
+/// Extension opcodes a ROM needs before it counts as using the extension. Data
+/// right after code can read as opcodes, a single match doesn't mean much.
+const MIN_OPCODES: usize = 2;
+
+/// Instructions at the start of a ROM where a switch to hires means SUPER-CHIP
+const ENTRY_INSTRUCTIONS: usize = 8;
+
+/// CHIP-8 extension an opcode comes from. This emulator runs plain CHIP-8, so
+/// ROMs using these opcodes need another interpreter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Extension {
+    /// SUPER-CHIP 1.1: hires mode, scrolling, big sprites and flag registers
+    SuperChip,
+    /// XO-CHIP: bit planes, audio patterns, long I loads and register ranges
+    XoChip,
+}
+
+impl Extension {
+    /// Extension that defines `opcode`, `None` for CHIP-8 instructions and
+    /// opcodes no extension uses. XO-CHIP also has the SUPER-CHIP opcodes,
+    /// those count as SUPER-CHIP.
+    ///
+    /// ```
+    /// use chip8_core::Extension;
+    /// assert_eq!(Extension::of(0x00FF), Some(Extension::SuperChip)); // hires
+    /// assert_eq!(Extension::of(0xF000), Some(Extension::XoChip)); // i := long NNNN
+    /// assert_eq!(Extension::of(0x00E0), None);
+    /// ```
+    pub fn of(opcode: u16) -> Option<Extension> {
+        let x = (opcode >> 8) & 0xF;
+        match (opcode >> 12, opcode & 0xFF) {
+            // 00CN scroll down, 00FB-00FF scroll, exit, lores, hires
+            (0, 0xC0..=0xCF) | (0, 0xFB..=0xFF) if x == 0 => Some(Extension::SuperChip),
+            // DXY0 draws a 16x16 sprite, nothing on the VIP
+            (0xD, _) if opcode & 0xF == 0 => Some(Extension::SuperChip),
+            // FX30 big font, FX75/FX85 flag registers
+            (0xF, 0x30) | (0xF, 0x75) | (0xF, 0x85) => Some(Extension::SuperChip),
+            // 00DN scroll up
+            (0, 0xD0..=0xDF) if x == 0 => Some(Extension::XoChip),
+            // 5XY2/5XY3 save and load VX..VY
+            (5, _) if matches!(opcode & 0xF, 2 | 3) => Some(Extension::XoChip),
+            // F000 long I, FN01 plane, F002 audio, FX3A pitch
+            (0xF, 0x00) if x == 0 => Some(Extension::XoChip),
+            (0xF, 0x01) | (0xF, 0x3A) => Some(Extension::XoChip),
+            (0xF, 0x02) if x == 0 => Some(Extension::XoChip),
+            _ => None,
+        }
+    }
+
+    /// Guesses the extension a ROM is written for without running it, from the
+    /// extension opcodes in code reachable from the start and whether it switches
+    /// to hires (00FF) right away. `None` means plain CHIP-8 as far as can be told.
+    ///
+    /// ```
+    /// use chip8_core::Extension;
+    /// // hires, clear, jump to itself
+    /// let rom = [0x00, 0xFF, 0x00, 0xE0, 0x12, 0x04];
+    /// assert_eq!(Extension::detect(&rom), Some(Extension::SuperChip));
+    /// assert_eq!(Extension::detect(&[0x00, 0xE0, 0x12, 0x02]), None);
+    /// ```
+    pub fn detect(rom: &[u8]) -> Option<Extension> {
+        let origin = PROGRAM_START as u16;
+        let graph = FlowGraph::build(rom, origin, origin);
+        let opcode_at = |address: u16| {
+            let i = (address - origin) as usize;
+            u16::from_be_bytes([rom[i], rom[i + 1]])
+        };
+        let (mut super_chip, mut xo_chip) = (0, 0);
+        let mut hires_entry = false;
+        for block in graph.blocks() {
+            for address in (block.start..block.end()).step_by(2) {
+                let opcode = opcode_at(address);
+                match Extension::of(opcode) {
+                    Some(Extension::SuperChip) => super_chip += 1,
+                    Some(Extension::XoChip) => xo_chip += 1,
+                    None => {}
+                }
+                let entry = (address - origin) as usize / 2 < ENTRY_INSTRUCTIONS;
+                hires_entry |= entry && opcode == 0x00FF;
+            }
+        }
+        if xo_chip >= MIN_OPCODES {
+            Some(Extension::XoChip)
+        } else if hires_entry || super_chip >= MIN_OPCODES {
+            Some(Extension::SuperChip)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Extension::SuperChip => "SUPER-CHIP",
+            Extension::XoChip => "XO-CHIP",
+        }
+    }
+}
@@ -0,0 +1,394 @@
+//! Static control flow graph of a program, built by following jumps, calls and
+//! skips from the entry point without running anything.
+//!
+//! Only code reachable through direct jumps is found: targets of `BNNN` depend on
+//! registers and aren't followed. Opcodes that aren't instructions are stepped
+//! over like the emulator does, so data right after code may show up as code.
+
+use crate::instruction::Instruction;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// How control gets from one block to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Runs on into the following block, also where a call returns to
+    Next,
+    /// 1NNN jump
+    Jump,
+    /// 2NNN subroutine call
+    Call,
+    /// Taken when a skip instruction skips
+    Skip,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+    pub kind: EdgeKind,
+    pub target: u16,
+}
+
+/// Straight run of instructions, only the last one can go anywhere but the next one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: u16,
+    pub instructions: Vec<Instruction>,
+    /// Where control goes after the block, empty for returns, `BNNN` and the end of code
+    pub edges: Vec<Edge>,
+}
+
+impl BasicBlock {
+    /// Address right after the block
+    pub fn end(&self) -> u16 {
+        self.start + 2 * self.instructions.len() as u16
+    }
+
+    pub fn contains(&self, address: u16) -> bool {
+        (self.start..self.end()).contains(&address)
+    }
+}
+
+/// Basic blocks of a program, sorted by start address.
+///
+/// ```
+/// use chip8_core::{EdgeKind, FlowGraph};
+///
+/// // 200: V0 += 1; 202: skip if V0 == 5; 204: jump 200; 206: jump 206
+/// let memory = [0x70, 0x01, 0x30, 0x05, 0x12, 0x00, 0x12, 0x06];
+/// let graph = FlowGraph::build(&memory, 0x200, 0x200);
+/// let starts: Vec<u16> = graph.blocks().iter().map(|block| block.start).collect();
+/// assert_eq!(starts, [0x200, 0x204, 0x206]);
+/// assert_eq!(graph.blocks()[0].edges[1].kind, EdgeKind::Skip);
+/// assert_eq!(graph.block_at(0x202).unwrap().start, 0x200);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlowGraph {
+    entry: u16,
+    blocks: Vec<BasicBlock>,
+}
+
+/// Sprite data a program draws, see `FlowGraph::sprites`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpriteRef {
+    pub address: u16,
+    /// 8, or 16 for the 16x16 sprites `DXY0` draws in SCHIP
+    pub width: u8,
+    pub height: u8,
+    /// No `DXYN` was found after the `ANNN`, the size is a guess
+    pub guessed: bool,
+}
+
+impl SpriteRef {
+    /// Number of bytes the sprite takes in memory
+    pub fn size(&self) -> usize {
+        self.width as usize / 8 * self.height as usize
+    }
+}
+
+/// What a byte of the program is, see `FlowGraph::byte_kinds`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteKind {
+    /// Part of an instruction reachable from the entry
+    Code,
+    /// Part of a sprite found by `FlowGraph::sprites`
+    Sprite,
+    /// Anything else: tables, text, padding or code only reached through `BNNN`
+    Data,
+}
+
+/// Code starting at the program entry or at a 2NNN target, see `FlowGraph::subroutines`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subroutine {
+    pub entry: u16,
+    /// Subroutines called from this one, sorted by address
+    pub calls: Vec<u16>,
+}
+
+impl FlowGraph {
+    /// Follows the program in `code`, which is loaded at `origin`, from `origin` on.
+    /// Targets outside of `code` show up as edges but aren't followed.
+    pub fn build(code: &[u8], origin: u16, entry: u16) -> Self {
+        let origin = origin as usize;
+        let end = origin + code.len();
+        let decode = |address: usize| {
+            let i = address - origin;
+            Instruction::decode(u16::from_be_bytes([code[i], code[i + 1]]))
+        };
+        let in_code = |address: usize| address >= origin && address + 1 < end;
+
+        // Find reachable instructions and where blocks have to start
+        let mut reachable = vec![false; code.len()];
+        let mut leaders = BTreeSet::new();
+        let mut pending = vec![entry as usize];
+        leaders.insert(entry as usize);
+        while let Some(mut address) = pending.pop() {
+            while in_code(address) && !reachable[address - origin] {
+                reachable[address - origin] = true;
+                let targets = match successors(decode(address), address as u16) {
+                    Flow::Next => {
+                        address += 2;
+                        continue;
+                    }
+                    Flow::End(edges) => edges,
+                };
+                for edge in targets {
+                    let target = edge.target as usize;
+                    leaders.insert(target);
+                    pending.push(target);
+                }
+                break;
+            }
+        }
+
+        // Cut reachable code into blocks at the leaders
+        let mut blocks = Vec::new();
+        for &start in leaders
+            .iter()
+            .filter(|&&a| in_code(a) && reachable[a - origin])
+        {
+            let mut instructions = Vec::new();
+            let mut address = start;
+            let mut edges = Vec::new();
+            loop {
+                let instruction = decode(address);
+                instructions.push(instruction);
+                if let Flow::End(targets) = successors(instruction, address as u16) {
+                    edges = targets;
+                    break;
+                }
+                address += 2;
+                if !in_code(address) || !reachable[address - origin] {
+                    break;
+                }
+                if leaders.contains(&address) {
+                    edges.push(Edge {
+                        kind: EdgeKind::Next,
+                        target: address as u16,
+                    });
+                    break;
+                }
+            }
+            blocks.push(BasicBlock {
+                start: start as u16,
+                instructions,
+                edges,
+            });
+        }
+        FlowGraph { entry, blocks }
+    }
+
+    pub fn entry(&self) -> u16 {
+        self.entry
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    /// Block with the instruction at `address`
+    pub fn block_at(&self, address: u16) -> Option<&BasicBlock> {
+        self.blocks.iter().find(|block| block.contains(address))
+    }
+
+    /// Call graph: the program entry and every call target, sorted by address, with
+    /// the subroutines each of them calls. Code shared through jumps counts for every
+    /// subroutine that reaches it.
+    ///
+    /// ```
+    /// use chip8_core::FlowGraph;
+    ///
+    /// // 200: call 206; 202: call 20A; 204: jump 204; 206: call 20A; 208: return; 20A: return
+    /// let memory = [0x22, 0x06, 0x22, 0x0A, 0x12, 0x04, 0x22, 0x0A, 0x00, 0xEE, 0x00, 0xEE];
+    /// let graph = FlowGraph::build(&memory, 0x200, 0x200);
+    /// let subroutines = graph.subroutines();
+    /// assert_eq!(subroutines.len(), 3);
+    /// assert_eq!(subroutines[0].calls, [0x206, 0x20A]);
+    /// assert_eq!(subroutines[1].calls, [0x20A]);
+    /// assert!(subroutines[2].calls.is_empty());
+    /// ```
+    pub fn subroutines(&self) -> Vec<Subroutine> {
+        let mut entries = BTreeMap::new();
+        entries.insert(self.entry, BTreeSet::new());
+        for edge in self.blocks.iter().flat_map(|block| &block.edges) {
+            if edge.kind == EdgeKind::Call {
+                entries.insert(edge.target, BTreeSet::new());
+            }
+        }
+        for (&entry, calls) in entries.iter_mut() {
+            // Everything reachable without entering a call
+            let mut visited = BTreeSet::new();
+            let mut pending = vec![entry];
+            while let Some(start) = pending.pop() {
+                if !visited.insert(start) {
+                    continue;
+                }
+                let block = match self.blocks.binary_search_by_key(&start, |b| b.start) {
+                    Ok(i) => &self.blocks[i],
+                    Err(_) => continue,
+                };
+                for edge in &block.edges {
+                    if edge.kind == EdgeKind::Call {
+                        calls.insert(edge.target);
+                    } else {
+                        pending.push(edge.target);
+                    }
+                }
+            }
+        }
+        entries
+            .into_iter()
+            .map(|(entry, calls)| Subroutine {
+                entry,
+                calls: calls.into_iter().collect(),
+            })
+            .collect()
+    }
+
+    /// Sprites found by pairing each `ANNN` with a `DXYN` later in the same block,
+    /// sorted by address. A sprite drawn with different heights gets the tallest.
+    ///
+    /// `ANNN` targets that aren't drawn in their block are guessed to be 8 pixels
+    /// wide sprites, running up to the next target or block, at most 15 rows, and
+    /// ending at `code_end`. Targets inside code aren't guessed.
+    ///
+    /// ```
+    /// use chip8_core::FlowGraph;
+    ///
+    /// // 200: I = 208; 202: draw V0 V1 3; 204: I = 20B; 206: jump 206; 208: sprite data
+    /// let memory = [0xA2, 0x08, 0xD0, 0x13, 0xA2, 0x0B, 0x12, 0x06, 0xF0, 0x90, 0xF0, 0x60, 0x60];
+    /// let graph = FlowGraph::build(&memory, 0x200, 0x200);
+    /// let sprites = graph.sprites(0x20D);
+    /// assert_eq!((sprites[0].address, sprites[0].height, sprites[0].guessed), (0x208, 3, false));
+    /// assert_eq!((sprites[1].address, sprites[1].height, sprites[1].guessed), (0x20B, 2, true));
+    /// ```
+    pub fn sprites(&self, code_end: u16) -> Vec<SpriteRef> {
+        let mut drawn: BTreeMap<u16, SpriteRef> = BTreeMap::new();
+        let mut undrawn = BTreeSet::new();
+        for block in &self.blocks {
+            let mut index = None;
+            for &instruction in &block.instructions {
+                match instruction {
+                    Instruction::LoadIndex { nnn } => {
+                        if let Some(address) = index.replace(nnn) {
+                            undrawn.insert(address);
+                        }
+                    }
+                    Instruction::Draw { n, .. } => {
+                        let address = match index {
+                            Some(address) => address,
+                            None => continue,
+                        };
+                        let (width, height) = if n == 0 { (16, 16) } else { (8, n) };
+                        let sprite = drawn.entry(address).or_insert(SpriteRef {
+                            address,
+                            width,
+                            height,
+                            guessed: false,
+                        });
+                        if sprite.size() < width as usize / 8 * height as usize {
+                            sprite.width = width;
+                            sprite.height = height;
+                        }
+                    }
+                    // I no longer points at the start of what ANNN loaded
+                    Instruction::AddIndex { .. } | Instruction::LoadFont { .. } => index = None,
+                    _ => {}
+                }
+            }
+            undrawn.extend(index);
+        }
+
+        // Guessed sprites end where something else starts
+        let mut starts: BTreeSet<u16> = drawn.keys().chain(&undrawn).copied().collect();
+        starts.extend(self.blocks.iter().map(|block| block.start));
+        starts.insert(code_end);
+        for address in undrawn {
+            if drawn.contains_key(&address) || self.block_at(address).is_some() {
+                continue;
+            }
+            let next = starts
+                .range(address + 1..)
+                .next()
+                .copied()
+                .unwrap_or(code_end);
+            let height = next.saturating_sub(address).min(15) as u8;
+            if height > 0 {
+                drawn.insert(
+                    address,
+                    SpriteRef {
+                        address,
+                        width: 8,
+                        height,
+                        guessed: true,
+                    },
+                );
+            }
+        }
+        drawn.into_values().collect()
+    }
+
+    /// Classifies every byte of the `len` bytes loaded at `origin`, before any of
+    /// them ran. Instructions can start at odd addresses, their bytes are code all
+    /// the same. Code wins over sprites overlapping it.
+    ///
+    /// ```
+    /// use chip8_core::{ByteKind, FlowGraph};
+    ///
+    /// // 200: I = 206; 202: draw V0 V0 1; 204: jump 204; 206: sprite; 207: unused
+    /// let memory = [0xA2, 0x06, 0xD0, 0x01, 0x12, 0x04, 0xFF, 0x00];
+    /// let graph = FlowGraph::build(&memory, 0x200, 0x200);
+    /// let kinds = graph.byte_kinds(0x200, memory.len());
+    /// assert_eq!(kinds[..6], [ByteKind::Code; 6]);
+    /// assert_eq!(kinds[6..], [ByteKind::Sprite, ByteKind::Data]);
+    /// ```
+    pub fn byte_kinds(&self, origin: u16, len: usize) -> Vec<ByteKind> {
+        let origin = origin as usize;
+        let mut kinds = vec![ByteKind::Data; len];
+        let mut mark = |start: usize, end: usize, kind: ByteKind| {
+            let start = start.max(origin).min(origin + len);
+            let end = end.max(start).min(origin + len);
+            for byte in &mut kinds[start - origin..end - origin] {
+                if *byte != ByteKind::Code {
+                    *byte = kind;
+                }
+            }
+        };
+        for block in &self.blocks {
+            mark(block.start as usize, block.end() as usize, ByteKind::Code);
+        }
+        for sprite in self.sprites((origin + len).min(u16::MAX as usize) as u16) {
+            let start = sprite.address as usize;
+            mark(start, start + sprite.size(), ByteKind::Sprite);
+        }
+        kinds
+    }
+}
+
+enum Flow {
+    /// Runs on to the next instruction
+    Next,
+    /// Ends a block, with where control can go from there
+    End(Vec<Edge>),
+}
+
+fn successors(instruction: Instruction, address: u16) -> Flow {
+    use Instruction::*;
+    let edge = |kind, target| Edge { kind, target };
+    let next = address.wrapping_add(2);
+    Flow::End(match instruction {
+        Jump { nnn } => vec![edge(EdgeKind::Jump, nnn)],
+        Call { nnn } => vec![edge(EdgeKind::Call, nnn), edge(EdgeKind::Next, next)],
+        SkipIfEqual { .. }
+        | SkipIfNotEqual { .. }
+        | SkipIfRegistersEqual { .. }
+        | SkipIfRegistersNotEqual { .. }
+        | SkipIfKeyPressed { .. }
+        | SkipIfKeyNotPressed { .. } => vec![
+            edge(EdgeKind::Next, next),
+            edge(EdgeKind::Skip, next.wrapping_add(2)),
+        ],
+        Return | JumpOffset { .. } => Vec::new(),
+        _ => return Flow::Next,
+    })
+}
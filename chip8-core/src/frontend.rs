@@ -0,0 +1,20 @@
+use crate::screen::Screen;
+
+/// Receives screen output from the emulator.
+pub trait Display {
+    /// Called with the current screen contents whenever they changed,
+    /// `Screen::dirty_rects` tells which parts did.
+    fn draw(&mut self, screen: &Screen);
+}
+
+/// Provides keypad state to the emulator.
+pub trait Input {
+    /// Returns whether hex key `key` (0x0..=0xF) is currently held down.
+    fn is_key_down(&self, key: u8) -> bool;
+}
+
+/// Receives sound output from the emulator.
+pub trait Audio {
+    /// Called once per frame: `on` is true while the sound timer is active.
+    fn set_beep(&mut self, on: bool);
+}
@@ -0,0 +1,74 @@
+//! Tells a program that is done or waits for a key apart from one that hangs, by
+//! following the code at PC without running it, see `Emulator::idle`.
+
+use crate::emulator::Emulator;
+use crate::instruction::Instruction;
+
+/// Instructions followed from PC before the loop counts as doing real work
+const MAX_LOOP: usize = 8;
+
+/// What a program going around a loop that changes nothing is doing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Idle {
+    /// Jumps back to where it is without doing anything else, like `1NNN` to its
+    /// own address. Only a reset gets it going again.
+    Finished,
+    /// FX0A, or a loop that does nothing but check keys
+    WaitingForKey,
+}
+
+impl Idle {
+    pub fn description(self) -> &'static str {
+        match self {
+            Idle::Finished => "Program finished",
+            Idle::WaitingForKey => "Waiting for key",
+        }
+    }
+}
+
+pub(crate) fn detect(emulator: &Emulator) -> Option<Idle> {
+    if emulator.is_halted() {
+        return None;
+    }
+    let memory = emulator.memory();
+    let fetch = |address: u16| {
+        let i = address as usize;
+        let bytes = memory.get(i..i + 2)?;
+        Some(Instruction::decode(u16::from_be_bytes([
+            bytes[0], bytes[1],
+        ])))
+    };
+    let key_down = |x: u8| {
+        let key = emulator.registers()[x as usize] & 0xF;
+        emulator.keypad().is_pressed(key)
+    };
+
+    let start = emulator.pc();
+    let mut pc = start;
+    let mut checks_keys = false;
+    for _ in 0..MAX_LOOP {
+        // Keys are checked with the current keypad, the loop goes the same way
+        // until one changes
+        pc = match fetch(pc)? {
+            Instruction::WaitForKey { .. } if pc == start => return Some(Idle::WaitingForKey),
+            Instruction::Jump { nnn } => nnn,
+            Instruction::SkipIfKeyPressed { x } => {
+                checks_keys = true;
+                pc.wrapping_add(if key_down(x) { 4 } else { 2 })
+            }
+            Instruction::SkipIfKeyNotPressed { x } => {
+                checks_keys = true;
+                pc.wrapping_add(if key_down(x) { 2 } else { 4 })
+            }
+            _ => return None,
+        };
+        if pc == start {
+            return Some(if checks_keys {
+                Idle::WaitingForKey
+            } else {
+                Idle::Finished
+            });
+        }
+    }
+    None
+}
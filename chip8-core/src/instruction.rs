@@ -0,0 +1,642 @@
+use crate::quirks::Quirks;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Decoded chip8 instruction.
+///
+/// `x` and `y` are register indices (0x0..=0xF), `nn` is an 8-bit immediate
+/// and `nnn` a 12-bit address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    /// 00E0: clear the screen
+    ClearScreen,
+    /// 00EE: return from a subroutine
+    Return,
+    /// 0NNN: call a machine code routine, ignored
+    System { nnn: u16 },
+    /// 1NNN: jump to NNN
+    Jump { nnn: u16 },
+    /// 2NNN: call the subroutine at NNN
+    Call { nnn: u16 },
+    /// 3XNN: skip the next instruction if VX == NN
+    SkipIfEqual { x: u8, nn: u8 },
+    /// 4XNN: skip the next instruction if VX != NN
+    SkipIfNotEqual { x: u8, nn: u8 },
+    /// 5XY0: skip the next instruction if VX == VY
+    SkipIfRegistersEqual { x: u8, y: u8 },
+    /// 6XNN: VX = NN
+    Load { x: u8, nn: u8 },
+    /// 7XNN: VX += NN, VF is untouched
+    Add { x: u8, nn: u8 },
+    /// 8XY0: VX = VY
+    Move { x: u8, y: u8 },
+    /// 8XY1: VX |= VY
+    Or { x: u8, y: u8 },
+    /// 8XY2: VX &= VY
+    And { x: u8, y: u8 },
+    /// 8XY3: VX ^= VY
+    Xor { x: u8, y: u8 },
+    /// 8XY4: VX += VY, VF = carry
+    AddRegisters { x: u8, y: u8 },
+    /// 8XY5: VX -= VY, VF = !borrow
+    Subtract { x: u8, y: u8 },
+    /// 8XY6: VX = VY >> 1 (VX >> 1 with the shift quirk), VF = shifted out bit
+    ShiftRight { x: u8, y: u8 },
+    /// 8XY7: VX = VY - VX, VF = !borrow
+    SubtractReversed { x: u8, y: u8 },
+    /// 8XYE: VX = VY << 1 (VX << 1 with the shift quirk), VF = shifted out bit
+    ShiftLeft { x: u8, y: u8 },
+    /// 9XY0: skip the next instruction if VX != VY
+    SkipIfRegistersNotEqual { x: u8, y: u8 },
+    /// ANNN: I = NNN
+    LoadIndex { nnn: u16 },
+    /// BNNN: jump to NNN + V0 (NNN + VX with the jump quirk, X being the top nibble of NNN)
+    JumpOffset { nnn: u16 },
+    /// CXNN: VX = random & NN
+    Random { x: u8, nn: u8 },
+    /// DXYN: draw N bytes of sprite data from I at VX, VY, VF = collision
+    Draw { x: u8, y: u8, n: u8 },
+    /// EX9E: skip the next instruction if the key in VX is pressed
+    SkipIfKeyPressed { x: u8 },
+    /// EXA1: skip the next instruction if the key in VX is not pressed
+    SkipIfKeyNotPressed { x: u8 },
+    /// FX07: VX = delay timer
+    LoadDelay { x: u8 },
+    /// FX0A: wait for a key press and store it in VX
+    WaitForKey { x: u8 },
+    /// FX15: delay timer = VX
+    SetDelay { x: u8 },
+    /// FX18: sound timer = VX
+    SetSound { x: u8 },
+    /// FX1E: I += VX
+    AddIndex { x: u8 },
+    /// FX29: I = address of the font sprite for the digit in VX
+    LoadFont { x: u8 },
+    /// FX33: store BCD of VX at I, I + 1 and I + 2
+    StoreBcd { x: u8 },
+    /// FX55: store V0..=VX at I, I is advanced unless the load/store quirk is on
+    StoreRegisters { x: u8 },
+    /// FX65: load V0..=VX from I, I is advanced unless the load/store quirk is on
+    LoadRegisters { x: u8 },
+    /// Opcode that isn't a chip8 instruction
+    Unknown(u16),
+}
+
+impl Instruction {
+    /// Decodes a big-endian opcode, never fails: unknown opcodes become `Unknown`.
+    pub const fn decode(opcode: u16) -> Instruction {
+        use Instruction::*;
+
+        let nibbles = (
+            (opcode >> 12) as u8,
+            ((opcode >> 8) & 0xF) as u8,
+            ((opcode >> 4) & 0xF) as u8,
+            (opcode & 0xF) as u8,
+        );
+        let nnn = opcode & 0x0FFF;
+        let nn = (opcode & 0x00FF) as u8;
+        let x = nibbles.1;
+        let y = nibbles.2;
+        let n = nibbles.3;
+
+        match nibbles {
+            (0, 0, 0xE, 0) => ClearScreen,
+            (0, 0, 0xE, 0xE) => Return,
+            (0, _, _, _) => System { nnn },
+            (1, _, _, _) => Jump { nnn },
+            (2, _, _, _) => Call { nnn },
+            (3, _, _, _) => SkipIfEqual { x, nn },
+            (4, _, _, _) => SkipIfNotEqual { x, nn },
+            (5, _, _, 0) => SkipIfRegistersEqual { x, y },
+            (6, _, _, _) => Load { x, nn },
+            (7, _, _, _) => Add { x, nn },
+            (8, _, _, 0) => Move { x, y },
+            (8, _, _, 1) => Or { x, y },
+            (8, _, _, 2) => And { x, y },
+            (8, _, _, 3) => Xor { x, y },
+            (8, _, _, 4) => AddRegisters { x, y },
+            (8, _, _, 5) => Subtract { x, y },
+            (8, _, _, 6) => ShiftRight { x, y },
+            (8, _, _, 7) => SubtractReversed { x, y },
+            (8, _, _, 0xE) => ShiftLeft { x, y },
+            (9, _, _, 0) => SkipIfRegistersNotEqual { x, y },
+            (0xA, _, _, _) => LoadIndex { nnn },
+            (0xB, _, _, _) => JumpOffset { nnn },
+            (0xC, _, _, _) => Random { x, nn },
+            (0xD, _, _, _) => Draw { x, y, n },
+            (0xE, _, 0x9, 0xE) => SkipIfKeyPressed { x },
+            (0xE, _, 0xA, 0x1) => SkipIfKeyNotPressed { x },
+            (0xF, _, 0x0, 0x7) => LoadDelay { x },
+            (0xF, _, 0x0, 0xA) => WaitForKey { x },
+            (0xF, _, 0x1, 0x5) => SetDelay { x },
+            (0xF, _, 0x1, 0x8) => SetSound { x },
+            (0xF, _, 0x1, 0xE) => AddIndex { x },
+            (0xF, _, 0x2, 0x9) => LoadFont { x },
+            (0xF, _, 0x3, 0x3) => StoreBcd { x },
+            (0xF, _, 0x5, 0x5) => StoreRegisters { x },
+            (0xF, _, 0x6, 0x5) => LoadRegisters { x },
+            _ => Unknown(opcode),
+        }
+    }
+
+    /// Opcode of the instruction, the inverse of `decode`. Fields are masked to
+    /// the bits the opcode has room for.
+    ///
+    /// ```
+    /// use chip8_core::Instruction;
+    ///
+    /// assert_eq!(Instruction::Draw { x: 1, y: 2, n: 5 }.encode(), 0xD125);
+    /// assert_eq!(Instruction::decode(0xF265).encode(), 0xF265);
+    /// ```
+    pub fn encode(self) -> u16 {
+        use Instruction::*;
+
+        let xy = |base: u16, x: u8, y: u8| base | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4;
+        let xnn = |base: u16, x: u8, nn: u8| base | (x as u16 & 0xF) << 8 | nn as u16;
+        match self {
+            ClearScreen => 0x00E0,
+            Return => 0x00EE,
+            System { nnn } => nnn & 0xFFF,
+            Jump { nnn } => 0x1000 | nnn & 0xFFF,
+            Call { nnn } => 0x2000 | nnn & 0xFFF,
+            SkipIfEqual { x, nn } => xnn(0x3000, x, nn),
+            SkipIfNotEqual { x, nn } => xnn(0x4000, x, nn),
+            SkipIfRegistersEqual { x, y } => xy(0x5000, x, y),
+            Load { x, nn } => xnn(0x6000, x, nn),
+            Add { x, nn } => xnn(0x7000, x, nn),
+            Move { x, y } => xy(0x8000, x, y),
+            Or { x, y } => xy(0x8001, x, y),
+            And { x, y } => xy(0x8002, x, y),
+            Xor { x, y } => xy(0x8003, x, y),
+            AddRegisters { x, y } => xy(0x8004, x, y),
+            Subtract { x, y } => xy(0x8005, x, y),
+            ShiftRight { x, y } => xy(0x8006, x, y),
+            SubtractReversed { x, y } => xy(0x8007, x, y),
+            ShiftLeft { x, y } => xy(0x800E, x, y),
+            SkipIfRegistersNotEqual { x, y } => xy(0x9000, x, y),
+            LoadIndex { nnn } => 0xA000 | nnn & 0xFFF,
+            JumpOffset { nnn } => 0xB000 | nnn & 0xFFF,
+            Random { x, nn } => xnn(0xC000, x, nn),
+            Draw { x, y, n } => xy(0xD000, x, y) | n as u16 & 0xF,
+            SkipIfKeyPressed { x } => xnn(0xE000, x, 0x9E),
+            SkipIfKeyNotPressed { x } => xnn(0xE000, x, 0xA1),
+            LoadDelay { x } => xnn(0xF000, x, 0x07),
+            WaitForKey { x } => xnn(0xF000, x, 0x0A),
+            SetDelay { x } => xnn(0xF000, x, 0x15),
+            SetSound { x } => xnn(0xF000, x, 0x18),
+            AddIndex { x } => xnn(0xF000, x, 0x1E),
+            LoadFont { x } => xnn(0xF000, x, 0x29),
+            StoreBcd { x } => xnn(0xF000, x, 0x33),
+            StoreRegisters { x } => xnn(0xF000, x, 0x55),
+            LoadRegisters { x } => xnn(0xF000, x, 0x65),
+            Unknown(opcode) => opcode,
+        }
+    }
+
+    /// Number of instruction kinds, one per variant, see `kind`.
+    pub const KIND_COUNT: usize = 36;
+
+    /// Opcode pattern of each kind, indexed by `kind`
+    pub const KIND_PATTERNS: [&'static str; Self::KIND_COUNT] = [
+        "00E0", "00EE", "0NNN", "1NNN", "2NNN", "3XNN", "4XNN", "5XY0", "6XNN", "7XNN", "8XY0",
+        "8XY1", "8XY2", "8XY3", "8XY4", "8XY5", "8XY6", "8XY7", "8XYE", "9XY0", "ANNN", "BNNN",
+        "CXNN", "DXYN", "EX9E", "EXA1", "FX07", "FX0A", "FX15", "FX18", "FX1E", "FX29", "FX33",
+        "FX55", "FX65", "????",
+    ];
+
+    /// Index of the variant in declaration order, for tables kept per instruction kind.
+    ///
+    /// ```
+    /// use chip8_core::Instruction;
+    ///
+    /// let kind = Instruction::decode(0x8124).kind();
+    /// assert_eq!(Instruction::KIND_PATTERNS[kind], "8XY4");
+    /// ```
+    pub const fn kind(self) -> usize {
+        use Instruction::*;
+        match self {
+            ClearScreen => 0,
+            Return => 1,
+            System { .. } => 2,
+            Jump { .. } => 3,
+            Call { .. } => 4,
+            SkipIfEqual { .. } => 5,
+            SkipIfNotEqual { .. } => 6,
+            SkipIfRegistersEqual { .. } => 7,
+            Load { .. } => 8,
+            Add { .. } => 9,
+            Move { .. } => 10,
+            Or { .. } => 11,
+            And { .. } => 12,
+            Xor { .. } => 13,
+            AddRegisters { .. } => 14,
+            Subtract { .. } => 15,
+            ShiftRight { .. } => 16,
+            SubtractReversed { .. } => 17,
+            ShiftLeft { .. } => 18,
+            SkipIfRegistersNotEqual { .. } => 19,
+            LoadIndex { .. } => 20,
+            JumpOffset { .. } => 21,
+            Random { .. } => 22,
+            Draw { .. } => 23,
+            SkipIfKeyPressed { .. } => 24,
+            SkipIfKeyNotPressed { .. } => 25,
+            LoadDelay { .. } => 26,
+            WaitForKey { .. } => 27,
+            SetDelay { .. } => 28,
+            SetSound { .. } => 29,
+            AddIndex { .. } => 30,
+            LoadFont { .. } => 31,
+            StoreBcd { .. } => 32,
+            StoreRegisters { .. } => 33,
+            LoadRegisters { .. } => 34,
+            Unknown(_) => 35,
+        }
+    }
+
+    /// What each kind of instruction does, indexed by `kind`
+    pub const KIND_DESCRIPTIONS: [&'static str; Self::KIND_COUNT] = [
+        "Clear the screen",
+        "Return from a subroutine, PC is popped off the stack",
+        "Call a machine code routine of the original computer, ignored",
+        "Jump to NNN",
+        "Call the subroutine at NNN, PC is pushed onto the stack",
+        "Skip the next instruction if VX == NN",
+        "Skip the next instruction if VX != NN",
+        "Skip the next instruction if VX == VY",
+        "Set VX to NN",
+        "Add NN to VX, VF is not changed on overflow",
+        "Set VX to VY",
+        "Set VX to VX OR VY",
+        "Set VX to VX AND VY",
+        "Set VX to VX XOR VY",
+        "Add VY to VX, VF is set to 1 on carry and 0 otherwise",
+        "Subtract VY from VX, VF is set to 0 on borrow and 1 otherwise",
+        "Shift right by one bit, VF is set to the bit shifted out",
+        "Set VX to VY - VX, VF is set to 0 on borrow and 1 otherwise",
+        "Shift left by one bit, VF is set to the bit shifted out",
+        "Skip the next instruction if VX != VY",
+        "Set I to NNN",
+        "Jump to NNN plus a register",
+        "Set VX to a random number AND NN",
+        "Draw an 8xN sprite from memory at I to VX, VY by XOR-ing it onto the screen, \
+         VF is set to 1 if any pixel was turned off",
+        "Skip the next instruction if the key in VX is pressed",
+        "Skip the next instruction if the key in VX is not pressed",
+        "Set VX to the delay timer",
+        "Wait until a key is pressed, then store it in VX",
+        "Set the delay timer to VX, it counts down at 60Hz",
+        "Set the sound timer to VX, a tone plays while it is above zero",
+        "Add VX to I",
+        "Set I to the font sprite of the hex digit in VX",
+        "Store the decimal digits of VX at I, I + 1 and I + 2",
+        "Store V0 to VX in memory starting at I",
+        "Load V0 to VX from memory starting at I",
+        "Not a chip8 instruction, may belong to an extension like SCHIP or XO-CHIP",
+    ];
+
+    /// Number of bytes starting at I the instruction reads or writes, 0 if it doesn't
+    /// access memory.
+    ///
+    /// ```
+    /// use chip8_core::Instruction;
+    ///
+    /// assert_eq!(Instruction::decode(0xD125).index_bytes(), 5);
+    /// assert_eq!(Instruction::decode(0xF355).index_bytes(), 4);
+    /// assert_eq!(Instruction::decode(0x6123).index_bytes(), 0);
+    /// ```
+    pub fn index_bytes(self) -> usize {
+        use Instruction::*;
+        match self {
+            Draw { n, .. } => n as usize,
+            StoreBcd { .. } => 3,
+            StoreRegisters { x } | LoadRegisters { x } => x as usize + 1,
+            _ => 0,
+        }
+    }
+
+    /// Statement in Octo syntax that assembles back to the same opcode, `None` for
+    /// `Unknown` opcodes. Skips become `if ... then` with the opposite condition.
+    ///
+    /// ```
+    /// use chip8_core::Instruction;
+    ///
+    /// assert_eq!(Instruction::decode(0x3A05).to_octo().unwrap(), "if vA != 0x05 then");
+    /// assert_eq!(Instruction::decode(0xF129).to_octo().unwrap(), "i := hex v1");
+    /// assert_eq!(Instruction::decode(0x5121).to_octo(), None);
+    /// ```
+    pub fn to_octo(self) -> Option<String> {
+        use Instruction::*;
+        Some(match self {
+            ClearScreen => "clear".into(),
+            Return => "return".into(),
+            System { nnn } => format!("native 0x{:03X}", nnn),
+            Jump { nnn } => format!("jump 0x{:03X}", nnn),
+            Call { nnn } => format!(":call 0x{:03X}", nnn),
+            SkipIfEqual { x, nn } => format!("if v{:X} != 0x{:02X} then", x, nn),
+            SkipIfNotEqual { x, nn } => format!("if v{:X} == 0x{:02X} then", x, nn),
+            SkipIfRegistersEqual { x, y } => format!("if v{:X} != v{:X} then", x, y),
+            Load { x, nn } => format!("v{:X} := 0x{:02X}", x, nn),
+            Add { x, nn } => format!("v{:X} += 0x{:02X}", x, nn),
+            Move { x, y } => format!("v{:X} := v{:X}", x, y),
+            Or { x, y } => format!("v{:X} |= v{:X}", x, y),
+            And { x, y } => format!("v{:X} &= v{:X}", x, y),
+            Xor { x, y } => format!("v{:X} ^= v{:X}", x, y),
+            AddRegisters { x, y } => format!("v{:X} += v{:X}", x, y),
+            Subtract { x, y } => format!("v{:X} -= v{:X}", x, y),
+            ShiftRight { x, y } => format!("v{:X} >>= v{:X}", x, y),
+            SubtractReversed { x, y } => format!("v{:X} =- v{:X}", x, y),
+            ShiftLeft { x, y } => format!("v{:X} <<= v{:X}", x, y),
+            SkipIfRegistersNotEqual { x, y } => format!("if v{:X} == v{:X} then", x, y),
+            LoadIndex { nnn } => format!("i := 0x{:03X}", nnn),
+            JumpOffset { nnn } => format!("jump0 0x{:03X}", nnn),
+            Random { x, nn } => format!("v{:X} := random 0x{:02X}", x, nn),
+            Draw { x, y, n } => format!("sprite v{:X} v{:X} {}", x, y, n),
+            SkipIfKeyPressed { x } => format!("if v{:X} -key then", x),
+            SkipIfKeyNotPressed { x } => format!("if v{:X} key then", x),
+            LoadDelay { x } => format!("v{:X} := delay", x),
+            WaitForKey { x } => format!("v{:X} := key", x),
+            SetDelay { x } => format!("delay := v{:X}", x),
+            SetSound { x } => format!("buzzer := v{:X}", x),
+            AddIndex { x } => format!("i += v{:X}", x),
+            LoadFont { x } => format!("i := hex v{:X}", x),
+            StoreBcd { x } => format!("bcd v{:X}", x),
+            StoreRegisters { x } => format!("save v{:X}", x),
+            LoadRegisters { x } => format!("load v{:X}", x),
+            Unknown(_) => return None,
+        })
+    }
+
+    /// Plain English sentence about what this instruction does with its operands, for
+    /// people learning chip8. Quirks aren't taken into account, see `quirk_notes`.
+    ///
+    /// ```
+    /// use chip8_core::Instruction;
+    ///
+    /// assert_eq!(Instruction::decode(0x6A05).explain(), "Put the number 5 (0x05) into register VA.");
+    /// assert_eq!(
+    ///     Instruction::decode(0x3105).explain(),
+    ///     "Compare V1 with 5 (0x05). If they're equal, skip the next instruction."
+    /// );
+    /// ```
+    pub fn explain(self) -> String {
+        use Instruction::*;
+        let number = |nn: u8| format!("{} (0x{:02X})", nn, nn);
+        match self {
+            ClearScreen => "Turn off every pixel on the screen.".into(),
+            Return => "Return from the current subroutine: take the address on top of the \
+                       stack and continue there."
+                .into(),
+            System { nnn } => format!(
+                "Call machine code at 0x{:03X}. Only the original computers could run it, \
+                 emulators ignore this instruction.",
+                nnn
+            ),
+            Jump { nnn } => format!("Continue the program at address 0x{:03X}.", nnn),
+            Call { nnn } => format!(
+                "Call the subroutine at 0x{:03X}: remember the address of the next \
+                 instruction on the stack, then continue at 0x{:03X}.",
+                nnn, nnn
+            ),
+            SkipIfEqual { x, nn } => format!(
+                "Compare V{:X} with {}. If they're equal, skip the next instruction.",
+                x,
+                number(nn)
+            ),
+            SkipIfNotEqual { x, nn } => format!(
+                "Compare V{:X} with {}. If they're different, skip the next instruction.",
+                x,
+                number(nn)
+            ),
+            SkipIfRegistersEqual { x, y } => format!(
+                "Compare V{:X} with V{:X}. If they're equal, skip the next instruction.",
+                x, y
+            ),
+            Load { x, nn } => format!("Put the number {} into register V{:X}.", number(nn), x),
+            Add { x, nn } => format!(
+                "Add {} to V{:X}. Past 255 it wraps around to 0, VF isn't changed.",
+                number(nn),
+                x
+            ),
+            Move { x, y } => format!("Copy the value of V{:X} into V{:X}.", y, x),
+            Or { x, y } => format!(
+                "Combine V{:X} and V{:X} bit by bit with OR: a bit is 1 if it's 1 in \
+                 either. The result goes into V{:X}.",
+                x, y, x
+            ),
+            And { x, y } => format!(
+                "Combine V{:X} and V{:X} bit by bit with AND: a bit is 1 only if it's 1 \
+                 in both. The result goes into V{:X}.",
+                x, y, x
+            ),
+            Xor { x, y } => format!(
+                "Combine V{:X} and V{:X} bit by bit with XOR: a bit is 1 if it's 1 in \
+                 exactly one of them. The result goes into V{:X}.",
+                x, y, x
+            ),
+            AddRegisters { x, y } => format!(
+                "Add V{:X} to V{:X}. If the sum doesn't fit in a byte, VF becomes 1 (the \
+                 carry), otherwise 0.",
+                y, x
+            ),
+            Subtract { x, y } => format!(
+                "Subtract V{:X} from V{:X}. VF becomes 0 if V{:X} was bigger (a borrow), \
+                 otherwise 1.",
+                y, x, y
+            ),
+            ShiftRight { x, y } => format!(
+                "Shift V{:X} one bit to the right into V{:X}, halving it. The bit that \
+                 falls off goes into VF.",
+                y, x
+            ),
+            SubtractReversed { x, y } => format!(
+                "Put V{:X} minus V{:X} into V{:X}. VF becomes 0 if V{:X} was bigger (a \
+                 borrow), otherwise 1.",
+                y, x, x, x
+            ),
+            ShiftLeft { x, y } => format!(
+                "Shift V{:X} one bit to the left into V{:X}, doubling it. The bit that \
+                 falls off goes into VF.",
+                y, x
+            ),
+            SkipIfRegistersNotEqual { x, y } => format!(
+                "Compare V{:X} with V{:X}. If they're different, skip the next instruction.",
+                x, y
+            ),
+            LoadIndex { nnn } => format!(
+                "Point the I register at memory address 0x{:03X}, usually sprite or other data.",
+                nnn
+            ),
+            JumpOffset { nnn } => format!(
+                "Continue the program at 0x{:03X} plus the value of V0, a way to pick one \
+                 of several places to go.",
+                nnn
+            ),
+            Random { x, nn } => format!(
+                "Put a random number into V{:X}, keeping only the bits that are set in {}.",
+                x,
+                number(nn)
+            ),
+            Draw { x, y, n } => format!(
+                "Draw a sprite {} rows tall from memory at I, at the position in V{:X} \
+                 and V{:X}. Pixels are flipped (XOR), and VF becomes 1 if any pixel was \
+                 turned off, which is how games notice collisions.",
+                n, x, y
+            ),
+            SkipIfKeyPressed { x } => format!(
+                "If the key whose number is in V{:X} is held down, skip the next instruction.",
+                x
+            ),
+            SkipIfKeyNotPressed { x } => format!(
+                "If the key whose number is in V{:X} isn't held down, skip the next \
+                 instruction.",
+                x
+            ),
+            LoadDelay { x } => format!("Copy the delay timer into V{:X}.", x),
+            WaitForKey { x } => format!(
+                "Stop until a key is pressed, then put its number into V{:X}.",
+                x
+            ),
+            SetDelay { x } => format!(
+                "Set the delay timer to V{:X}. It counts down by one 60 times a second.",
+                x
+            ),
+            SetSound { x } => format!(
+                "Set the sound timer to V{:X}. A tone plays until it counts down to 0.",
+                x
+            ),
+            AddIndex { x } => format!("Add V{:X} to the I register.", x),
+            LoadFont { x } => format!(
+                "Point I at the built-in font sprite for the hex digit in V{:X}.",
+                x
+            ),
+            StoreBcd { x } => format!(
+                "Write the hundreds, tens and ones digits of V{:X} to memory at I, I + 1 \
+                 and I + 2, handy for showing scores.",
+                x
+            ),
+            StoreRegisters { x } => format!(
+                "Copy registers V0 to V{:X} into memory, starting at the address in I.",
+                x
+            ),
+            LoadRegisters { x } => format!(
+                "Copy memory starting at the address in I into registers V0 to V{:X}.",
+                x
+            ),
+            Unknown(opcode) => format!(
+                "0x{:04X} isn't a chip8 instruction. It may be data, or an instruction \
+                 of an extension like SCHIP, and does nothing here.",
+                opcode
+            ),
+        }
+    }
+
+    /// How the given quirks change what the instruction does, empty if they don't.
+    ///
+    /// ```
+    /// use chip8_core::{Instruction, Quirks};
+    ///
+    /// let shift = Instruction::decode(0x8126);
+    /// assert_eq!(shift.quirk_notes(Quirks::VIP), ["shift quirk off: VY is shifted into VX"]);
+    /// assert!(Instruction::decode(0x6123).quirk_notes(Quirks::VIP).is_empty());
+    /// ```
+    pub fn quirk_notes(self, quirks: Quirks) -> Vec<&'static str> {
+        use Instruction::*;
+        let pick = |on: bool, when_on, when_off| if on { when_on } else { when_off };
+        match self {
+            ShiftRight { .. } | ShiftLeft { .. } => vec![pick(
+                quirks.shift,
+                "shift quirk on: VX is shifted in place, VY is ignored",
+                "shift quirk off: VY is shifted into VX",
+            )],
+            Or { .. } | And { .. } | Xor { .. } => vec![pick(
+                quirks.logic,
+                "logic quirk on: VF is reset to 0",
+                "logic quirk off: VF is not changed",
+            )],
+            JumpOffset { .. } => vec![pick(
+                quirks.jump,
+                "jump quirk on: jumps to NNN + VX, X being the top nibble of NNN",
+                "jump quirk off: jumps to NNN + V0",
+            )],
+            StoreRegisters { .. } | LoadRegisters { .. } => vec![pick(
+                quirks.load_store,
+                "load/store quirk on: I is not changed",
+                "load/store quirk off: I ends up at I + X + 1",
+            )],
+            Draw { .. } => vec![
+                pick(
+                    quirks.clip,
+                    "clip quirk on: pixels past the screen edges are cut off",
+                    "clip quirk off: pixels past the screen edges wrap around",
+                ),
+                pick(
+                    quirks.vblank,
+                    "vblank quirk on: waits for the next 60Hz tick before drawing",
+                    "vblank quirk off: draws right away",
+                ),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Disassembles into the common (Cowgod's reference) mnemonics, e.g. `LD V1, 0x2A`
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use Instruction::*;
+        match *self {
+            ClearScreen => write!(f, "CLS"),
+            Return => write!(f, "RET"),
+            System { nnn } => write!(f, "SYS 0x{:03X}", nnn),
+            Jump { nnn } => write!(f, "JP 0x{:03X}", nnn),
+            Call { nnn } => write!(f, "CALL 0x{:03X}", nnn),
+            SkipIfEqual { x, nn } => write!(f, "SE V{:X}, 0x{:02X}", x, nn),
+            SkipIfNotEqual { x, nn } => write!(f, "SNE V{:X}, 0x{:02X}", x, nn),
+            SkipIfRegistersEqual { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            Load { x, nn } => write!(f, "LD V{:X}, 0x{:02X}", x, nn),
+            Add { x, nn } => write!(f, "ADD V{:X}, 0x{:02X}", x, nn),
+            Move { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            AddRegisters { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Subtract { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            ShiftRight { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            SubtractReversed { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            ShiftLeft { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            SkipIfRegistersNotEqual { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            LoadIndex { nnn } => write!(f, "LD I, 0x{:03X}", nnn),
+            JumpOffset { nnn } => write!(f, "JP V0, 0x{:03X}", nnn),
+            Random { x, nn } => write!(f, "RND V{:X}, 0x{:02X}", x, nn),
+            Draw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            SkipIfKeyPressed { x } => write!(f, "SKP V{:X}", x),
+            SkipIfKeyNotPressed { x } => write!(f, "SKNP V{:X}", x),
+            LoadDelay { x } => write!(f, "LD V{:X}, DT", x),
+            WaitForKey { x } => write!(f, "LD V{:X}, K", x),
+            SetDelay { x } => write!(f, "LD DT, V{:X}", x),
+            SetSound { x } => write!(f, "LD ST, V{:X}", x),
+            AddIndex { x } => write!(f, "ADD I, V{:X}", x),
+            LoadFont { x } => write!(f, "LD F, V{:X}", x),
+            StoreBcd { x } => write!(f, "LD B, V{:X}", x),
+            StoreRegisters { x } => write!(f, "LD [I], V{:X}", x),
+            LoadRegisters { x } => write!(f, "LD V{:X}, [I]", x),
+            Unknown(opcode) => write!(f, "DW 0x{:04X}", opcode),
+        }
+    }
+}
+
+/// Every opcode decoded at compile time, so executing an instruction is a
+/// lookup instead of matching nibbles
+pub(crate) static DECODE_TABLE: [Instruction; 0x10000] = {
+    let mut table = [Instruction::Unknown(0); 0x10000];
+    let mut opcode = 0;
+    while opcode < table.len() {
+        table[opcode] = Instruction::decode(opcode as u16);
+        opcode += 1;
+    }
+    table
+};
@@ -0,0 +1,80 @@
+use alloc::collections::VecDeque;
+
+/// Key going down or up on a 60Hz frame, see `Emulator::push_key_event`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// Hex key, 0x0..=0xF
+    pub key: u8,
+    pub down: bool,
+    /// `Emulator::frame` the event happens on, earlier frames mean right away
+    pub frame: u64,
+}
+
+/// chip8 keypad state
+#[derive(Default)]
+pub struct Keypad {
+    keys: [bool; Self::KEY_COUNT],
+    /// Events waiting for their frame, in the order they were pushed
+    queue: VecDeque<KeyEvent>,
+    /// Keys pressed from the queue on the current frame, one bit per key. Their
+    /// release waits for the next frame so the program gets to see them down.
+    held: u16,
+}
+
+impl Keypad {
+    /// chip8 has 16 keys keypad
+    pub const KEY_COUNT: usize = 16;
+
+    pub fn is_pressed(&self, key: u8) -> bool {
+        self.keys[key as usize]
+    }
+
+    pub fn set(&mut self, index: u8, down: bool) {
+        log::trace!("Key {:X} {}", index, if down { "down" } else { "up" });
+        self.keys[index as usize] = down;
+    }
+
+    /// Events that didn't happen yet, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &KeyEvent> {
+        self.queue.iter()
+    }
+
+    /// Whether `key` ends up down once the queued events have happened
+    pub fn will_be_pressed(&self, key: u8) -> bool {
+        self.queue
+            .iter()
+            .rev()
+            .find(|event| event.key == key)
+            .map_or(self.is_pressed(key), |event| event.down)
+    }
+
+    pub(crate) fn push(&mut self, event: KeyEvent) {
+        self.queue.push_back(event);
+    }
+
+    /// Takes the next event due on `frame`. Events stay in order, so one that
+    /// has to wait holds back the ones after it.
+    pub(crate) fn next_due(&mut self, frame: u64) -> Option<KeyEvent> {
+        let event = *self.queue.front()?;
+        if event.frame > frame || (!event.down && self.held & (1 << event.key) != 0) {
+            return None;
+        }
+        if event.down {
+            self.held |= 1 << event.key;
+        }
+        self.queue.pop_front()
+    }
+
+    pub(crate) fn new_frame(&mut self) {
+        self.held = 0;
+    }
+
+    pub(crate) fn get_pressed_key(&self) -> Option<u8> {
+        for i in 0..self.keys.len() {
+            if self.is_pressed(i as u8) {
+                return Some(i as u8);
+            }
+        }
+        None
+    }
+}
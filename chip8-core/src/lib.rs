@@ -0,0 +1,54 @@
+//! chip8 emulator core: CPU, memory, screen and keypad.
+//!
+//! This crate has no windowing or GPU dependencies, so it can be used by any
+//! frontend (or directly from tests).
+//!
+//! With default features disabled it is `no_std` (only `alloc` is required),
+//! loading ROMs from files and seeding the random generator from OS entropy
+//! need the `std` feature. The `serde` feature makes configuration types
+//! serializable. The experimental `block-cache` feature caches decoded runs of
+//! instructions, see `Emulator::run_cycles`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod assembler;
+#[cfg(feature = "block-cache")]
+mod block_cache;
+mod builder;
+mod emulator;
+mod events;
+mod extension;
+mod flow_graph;
+mod frontend;
+mod idle;
+mod instruction;
+mod keypad;
+mod lockstep;
+mod memory;
+mod quirks;
+mod save_state;
+mod screen;
+mod test_roms;
+mod timing;
+
+pub use assembler::{assemble, AssembleError, Assembly};
+pub use builder::EmulatorBuilder;
+pub use emulator::{
+    Emulator, EmulatorStats, Fault, HaltReason, LoadError, StepEvent, TraceHook, DEFAULT_CLOCK_HZ,
+    STACK_DEPTH, TIMER_HZ,
+};
+pub use events::{CallbackId, EmulatorEvent, EventCallback};
+pub use extension::Extension;
+pub use flow_graph::{BasicBlock, ByteKind, Edge, EdgeKind, FlowGraph, SpriteRef, Subroutine};
+pub use frontend::{Audio, Display, Input};
+pub use idle::Idle;
+pub use instruction::Instruction;
+pub use keypad::{KeyEvent, Keypad};
+pub use lockstep::{Divergence, Lockstep};
+pub use memory::{MemoryAccess, MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+pub use quirks::Quirks;
+pub use save_state::{SaveState, StateError};
+pub use screen::{DirtyRect, Screen, SCREEN_SIZE};
+pub use test_roms::{TestReport, TestRom, TEST_ROMS};
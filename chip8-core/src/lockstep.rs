@@ -0,0 +1,118 @@
+//! Runs one program on two emulators with different quirks, an instruction at a
+//! time on each, and stops where their states first differ. The instruction found
+//! is usually where the ROM starts depending on one of the quirks.
+
+use crate::builder::EmulatorBuilder;
+use crate::emulator::{Emulator, HaltReason, LoadError};
+use crate::quirks::Quirks;
+
+/// Where two lockstep emulators stopped being the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// Instructions executed before the states differed, counting this one
+    pub step: u64,
+    /// Address of the instruction after which the states differ
+    pub address: u16,
+    pub opcode: u16,
+}
+
+/// Two emulators fed the same ROM, random numbers and keys, see `Lockstep::run`.
+///
+/// ```
+/// use chip8_core::{Emulator, Lockstep, Quirks};
+///
+/// // V0 = 3, V1 = 8, 8016 shifts V1 into V0 without the shift quirk
+/// let rom = [0x60, 0x03, 0x61, 0x08, 0x80, 0x16];
+/// let quirks = [Quirks { shift: true, ..Quirks::OCTO }, Quirks::OCTO];
+/// let mut lockstep = Lockstep::new(Emulator::builder(), quirks, &rom).unwrap();
+///
+/// let divergence = lockstep.run(100).unwrap();
+/// assert_eq!(divergence.address, 0x204);
+/// assert_eq!(divergence.opcode, 0x8016);
+/// ```
+pub struct Lockstep {
+    emulators: [Emulator; 2],
+    steps: u64,
+    divergence: Option<Divergence>,
+}
+
+impl Lockstep {
+    /// Builds both emulators from `builder` with their own quirks and loads `rom`.
+    /// Without a seed set on the builder both random generators get seed 0.
+    pub fn new(
+        builder: EmulatorBuilder,
+        quirks: [Quirks; 2],
+        rom: &[u8],
+    ) -> Result<Self, LoadError> {
+        let seed = builder.seed.unwrap_or(0);
+        let build = |quirks| -> Result<Emulator, LoadError> {
+            let mut emulator = builder.clone().quirks(quirks).seed(seed).build();
+            emulator.load_rom_bytes(rom)?;
+            Ok(emulator)
+        };
+        Ok(Lockstep {
+            emulators: [build(quirks[0])?, build(quirks[1])?],
+            steps: 0,
+            divergence: None,
+        })
+    }
+
+    /// The emulator for `quirks[0]` and the one for `quirks[1]`
+    pub fn emulators(&self) -> &[Emulator; 2] {
+        &self.emulators
+    }
+
+    pub fn set_key(&mut self, key: u8, down: bool) {
+        for emulator in &mut self.emulators {
+            emulator.set_key(key, down);
+        }
+    }
+
+    /// Instructions executed so far by each emulator
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn divergence(&self) -> Option<Divergence> {
+        self.divergence
+    }
+
+    /// Why both emulators stopped, they halt the same way until they diverge
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.emulators[0].halt_reason()
+    }
+
+    /// Steps both emulators up to `steps` times, comparing their state hashes after
+    /// every instruction. Stops at the first difference, which is kept, later calls
+    /// do nothing once the emulators differ or halted.
+    pub fn run(&mut self, steps: usize) -> Option<Divergence> {
+        if self.divergence.is_some() {
+            return self.divergence;
+        }
+        for _ in 0..steps {
+            if self.emulators[0].is_halted() {
+                break;
+            }
+            let address = self.emulators[0].pc();
+            let memory = self.emulators[0].memory();
+            let opcode = match memory.get(address as usize..address as usize + 2) {
+                Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]),
+                None => 0,
+            };
+            for emulator in &mut self.emulators {
+                emulator.step();
+            }
+            self.steps += 1;
+            let [a, b] = &self.emulators;
+            if a.state_hash() != b.state_hash() || a.halt_reason() != b.halt_reason() {
+                self.divergence = Some(Divergence {
+                    step: self.steps,
+                    address,
+                    opcode,
+                });
+                break;
+            }
+        }
+        self.divergence
+    }
+}
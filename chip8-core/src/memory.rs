@@ -0,0 +1,94 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+/// Standard chip8 RAM size
+pub const MEMORY_SIZE: usize = 0x1000;
+
+/// XO-CHIP RAM size
+pub const XO_CHIP_MEMORY_SIZE: usize = 0x10000;
+
+/// Emulator RAM. Data accesses wrap around at the end of memory, like
+/// address lines that aren't connected.
+// Standard memory is kept inline on purpose, it's what almost every ROM uses
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Memory {
+    Standard([u8; MEMORY_SIZE]),
+    XoChip(Box<[u8; XO_CHIP_MEMORY_SIZE]>),
+}
+
+impl Memory {
+    /// `size` has to be `MEMORY_SIZE` or `XO_CHIP_MEMORY_SIZE`
+    pub fn new(size: usize) -> Self {
+        match size {
+            MEMORY_SIZE => Memory::Standard([0; MEMORY_SIZE]),
+            XO_CHIP_MEMORY_SIZE => {
+                // Built on the heap, 64KB would be a lot for the stack
+                let data = vec![0; XO_CHIP_MEMORY_SIZE].into_boxed_slice();
+                Memory::XoChip(data.try_into().unwrap())
+            }
+            _ => panic!("unsupported memory size {:#X}", size),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// True if `address` is inside memory, without wrapping
+    pub fn contains(&self, address: usize) -> bool {
+        address < self.len()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        match self {
+            Memory::Standard(data) => data,
+            Memory::XoChip(data) => &data[..],
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        match self {
+            Memory::Standard(data) => data,
+            Memory::XoChip(data) => &mut data[..],
+        }
+    }
+
+    /// Sizes are powers of two, so wrapping is a mask
+    pub fn mask(&self, address: u16) -> usize {
+        address as usize & (self.len() - 1)
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        self.as_slice()[self.mask(address)]
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        let address = self.mask(address);
+        self.as_mut_slice()[address] = value;
+    }
+}
+
+/// How many times each memory address was read, written and executed, see
+/// `Emulator::set_access_tracking`. Counters wrap around, so take differences
+/// between two snapshots with `wrapping_sub`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    /// Data reads by DXYN and FX65
+    pub reads: Vec<u32>,
+    /// Writes by FX33 and FX55
+    pub writes: Vec<u32>,
+    /// Instruction fetches, both bytes of an opcode count
+    pub executes: Vec<u32>,
+}
+
+impl MemoryAccess {
+    pub(crate) fn new(size: usize) -> Self {
+        MemoryAccess {
+            reads: vec![0; size],
+            writes: vec![0; size],
+            executes: vec![0; size],
+        }
+    }
+}
@@ -0,0 +1,78 @@
+/// Behaviour differences between chip8 interpreters that ROMs may depend on.
+/// Flags follow the quirk names used by Octo.
+///
+/// `Quirks::default()` matches the behaviour this emulator always had.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Quirks {
+    /// 8XY6/8XYE shift VX in place instead of storing shifted VY into VX
+    pub shift: bool,
+    /// FX55/FX65 leave I unchanged instead of incrementing it by X + 1
+    pub load_store: bool,
+    /// BNNN jumps to NNN + VX (X is the high nibble of NNN) instead of NNN + V0
+    pub jump: bool,
+    /// 8XY1/8XY2/8XY3 reset VF to 0
+    pub logic: bool,
+    /// Sprites are clipped at the screen edges instead of wrapping around
+    pub clip: bool,
+    /// DXYN waits for the next 60Hz tick, so at most one sprite is drawn per frame
+    pub vblank: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks {
+            shift: true,
+            load_store: false,
+            jump: false,
+            logic: false,
+            clip: false,
+            vblank: false,
+        }
+    }
+}
+
+impl Quirks {
+    /// Original COSMAC VIP interpreter
+    pub const VIP: Quirks = Quirks {
+        shift: false,
+        load_store: false,
+        jump: false,
+        logic: true,
+        clip: true,
+        vblank: true,
+    };
+
+    /// SUPER-CHIP 1.1 on HP48 calculators
+    pub const SCHIP: Quirks = Quirks {
+        shift: true,
+        load_store: true,
+        jump: true,
+        logic: false,
+        clip: true,
+        vblank: false,
+    };
+
+    /// Octo defaults (all quirks off)
+    pub const OCTO: Quirks = Quirks {
+        shift: false,
+        load_store: false,
+        jump: false,
+        logic: false,
+        clip: false,
+        vblank: false,
+    };
+
+    /// Names accepted by `Quirks::preset`
+    pub const PRESET_NAMES: [&'static str; 4] = ["default", "vip", "schip", "octo"];
+
+    /// Looks up a preset by name (case insensitive)
+    pub fn preset(name: &str) -> Option<Quirks> {
+        let presets = [Quirks::default(), Quirks::VIP, Quirks::SCHIP, Quirks::OCTO];
+        Self::PRESET_NAMES
+            .iter()
+            .position(|preset| preset.eq_ignore_ascii_case(name))
+            .map(|i| presets[i])
+    }
+}
@@ -0,0 +1,228 @@
+use crate::emulator::{PROGRAM_START, STACK_DEPTH};
+use crate::memory::{MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+use crate::quirks::Quirks;
+use crate::screen::SCREEN_SIZE;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+
+/// Start of every encoded state
+const MAGIC: &[u8; 4] = b"C8ST";
+
+/// Bumped whenever the encoding changes, older states are rejected
+const VERSION: u8 = 1;
+
+/// Snapshot of everything a running program can observe, taken with
+/// `Emulator::save_state` and restored with `Emulator::load_state`.
+///
+/// The random generator isn't part of it, so CXNN may produce different
+/// numbers after a state is loaded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveState {
+    /// Whole RAM, `MEMORY_SIZE` or `XO_CHIP_MEMORY_SIZE` bytes
+    pub memory: Vec<u8>,
+    /// Screen pixel rows, bit 63 of each row is its leftmost pixel
+    pub screen: [u64; SCREEN_SIZE.1],
+    pub stack: Vec<u16>,
+    pub registers: [u8; 16],
+    pub index: u16,
+    pub pc: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    /// Time since the last 60Hz timer tick
+    pub timer_phase: f32,
+    /// DXYN is waiting for the next timer tick (vblank quirk)
+    pub vblank_wait: bool,
+    pub quirks: Quirks,
+    pub clock_hz: f32,
+    pub instruction_count: u64,
+    /// ROM as it was loaded, used by `Emulator::reset`
+    pub rom: Vec<u8>,
+}
+
+/// Why `SaveState::from_bytes` rejected its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// Data doesn't start with the save state header
+    NotAState,
+    /// State was written by a different version of the encoding
+    UnsupportedVersion(u8),
+    /// Data ends before the state does
+    Truncated,
+    /// Memory size isn't one the emulator supports
+    BadMemorySize(usize),
+    /// ROM of this many bytes doesn't fit in the state's memory
+    RomTooLarge(usize),
+    /// Stack holds more return addresses than `STACK_DEPTH`
+    StackTooDeep(usize),
+    /// Clock speed isn't a positive number
+    BadClockSpeed,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NotAState => write!(f, "not a chip8 save state"),
+            StateError::UnsupportedVersion(v) => write!(f, "unsupported save state version {}", v),
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::BadMemorySize(size) => write!(f, "unsupported memory size {:#X}", size),
+            StateError::RomTooLarge(size) => {
+                write!(f, "ROM of {} bytes doesn't fit in memory", size)
+            }
+            StateError::StackTooDeep(depth) => write!(f, "stack is {} calls deep", depth),
+            StateError::BadClockSpeed => write!(f, "clock speed isn't a positive number"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StateError {}
+
+impl SaveState {
+    /// Encodes the state in a compact binary format, see `from_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.memory.len() + self.rom.len() + 512);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        put_bytes(&mut out, &self.memory);
+        for row in self.screen.iter() {
+            out.extend_from_slice(&row.to_le_bytes());
+        }
+        out.extend_from_slice(&(self.stack.len() as u32).to_le_bytes());
+        for adr in &self.stack {
+            out.extend_from_slice(&adr.to_le_bytes());
+        }
+        out.extend_from_slice(&self.registers);
+        out.extend_from_slice(&self.index.to_le_bytes());
+        out.extend_from_slice(&self.pc.to_le_bytes());
+        out.push(self.delay_timer);
+        out.push(self.sound_timer);
+        out.extend_from_slice(&self.timer_phase.to_le_bytes());
+        out.push(self.vblank_wait as u8);
+        out.push(quirk_bits(self.quirks));
+        out.extend_from_slice(&self.clock_hz.to_le_bytes());
+        out.extend_from_slice(&self.instruction_count.to_le_bytes());
+        put_bytes(&mut out, &self.rom);
+        out
+    }
+
+    /// Decodes a state written by `to_bytes`.
+    ///
+    /// ```
+    /// use chip8_core::{Emulator, SaveState};
+    ///
+    /// let mut emulator = Emulator::new();
+    /// emulator.load_rom_bytes(&[0x60, 0x0A]).unwrap();
+    /// emulator.run_cycles(1);
+    ///
+    /// let state = emulator.save_state();
+    /// assert_eq!(SaveState::from_bytes(&state.to_bytes()), Ok(state));
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Result<SaveState, StateError> {
+        let mut r = Reader(data);
+        if r.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+            return Err(StateError::NotAState);
+        }
+        let version = r.u8()?;
+        if version != VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let memory = r.bytes()?.to_vec();
+        if memory.len() != MEMORY_SIZE && memory.len() != XO_CHIP_MEMORY_SIZE {
+            return Err(StateError::BadMemorySize(memory.len()));
+        }
+        let mut screen = [0; SCREEN_SIZE.1];
+        for row in screen.iter_mut() {
+            *row = u64::from_le_bytes(r.array()?);
+        }
+        let stack_len = r.u32()? as usize;
+        if stack_len > STACK_DEPTH {
+            return Err(StateError::StackTooDeep(stack_len));
+        }
+        let mut stack = Vec::new();
+        for _ in 0..stack_len {
+            stack.push(u16::from_le_bytes(r.array()?));
+        }
+        let state = SaveState {
+            memory,
+            screen,
+            stack,
+            registers: r.array()?,
+            index: u16::from_le_bytes(r.array()?),
+            pc: u16::from_le_bytes(r.array()?),
+            delay_timer: r.u8()?,
+            sound_timer: r.u8()?,
+            timer_phase: f32::from_le_bytes(r.array()?),
+            vblank_wait: r.u8()? != 0,
+            quirks: quirks_from_bits(r.u8()?),
+            clock_hz: f32::from_le_bytes(r.array()?),
+            instruction_count: u64::from_le_bytes(r.array()?),
+            rom: r.bytes()?.to_vec(),
+        };
+        // The emulator relies on these when it loads the state
+        if !(state.clock_hz > 0.0 && state.clock_hz.is_finite()) {
+            return Err(StateError::BadClockSpeed);
+        }
+        if state.rom.len() > state.memory.len() - PROGRAM_START {
+            return Err(StateError::RomTooLarge(state.rom.len()));
+        }
+        Ok(state)
+    }
+}
+
+// Length-prefixed byte string
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn quirk_bits(q: Quirks) -> u8 {
+    [q.shift, q.load_store, q.jump, q.logic, q.clip, q.vblank]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (i, &on)| bits | (on as u8) << i)
+}
+
+fn quirks_from_bits(bits: u8) -> Quirks {
+    let bit = |i: u8| bits & (1 << i) != 0;
+    Quirks {
+        shift: bit(0),
+        load_store: bit(1),
+        jump: bit(2),
+        logic: bit(3),
+        clip: bit(4),
+        vblank: bit(5),
+    }
+}
+
+// Reads fields off the front of the encoded state
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.0.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], StateError> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
@@ -0,0 +1,162 @@
+use alloc::vec::Vec;
+use core::iter;
+
+/// chip8 original screen size
+pub const SCREEN_SIZE: (usize, usize) = (64, 32);
+
+/// Part of the screen that changed, in pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Screen buffer, one bit per pixel.
+#[derive(Clone)]
+pub struct Screen {
+    /// One row per entry, the most significant bit is the leftmost pixel
+    rows: [u64; SCREEN_SIZE.1],
+    /// Pixels changed since `reset_dirty`, laid out like `rows`
+    changed: [u64; SCREEN_SIZE.1],
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self::from_rows([0; SCREEN_SIZE.1])
+    }
+}
+
+impl Screen {
+    /// Whole screen starts out dirty
+    pub(crate) fn from_rows(rows: [u64; SCREEN_SIZE.1]) -> Self {
+        Screen {
+            rows,
+            changed: [u64::MAX; SCREEN_SIZE.1],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn reset_dirty(&mut self) {
+        self.changed = [0; SCREEN_SIZE.1];
+    }
+    pub fn is_dirty(&self) -> bool {
+        self.changed.iter().any(|&changed| changed != 0)
+    }
+
+    /// Regions changed since `reset_dirty`, one per run of changed rows spanning
+    /// the changed columns of those rows. Frontends can upload just these.
+    ///
+    /// ```
+    /// use chip8_core::{DirtyRect, Screen};
+    ///
+    /// let mut screen = Screen::default();
+    /// screen.reset_dirty();
+    /// screen.draw_sprite(10, 4, &[0xF0, 0x90], false);
+    /// let rect = DirtyRect { x: 10, y: 4, width: 4, height: 2 };
+    /// assert_eq!(screen.dirty_rects(), [rect]);
+    /// ```
+    pub fn dirty_rects(&self) -> Vec<DirtyRect> {
+        let mut rects = Vec::new();
+        // First row of the current run and its changed columns
+        let mut run: Option<(usize, u64)> = None;
+        // A blank row past the end closes the last run
+        for (y, &changed) in self.changed.iter().chain(iter::once(&0)).enumerate() {
+            if changed != 0 {
+                let (start, columns) = run.unwrap_or((y, 0));
+                run = Some((start, columns | changed));
+            } else if let Some((start, columns)) = run.take() {
+                let x = columns.leading_zeros() as usize;
+                rects.push(DirtyRect {
+                    x,
+                    y: start,
+                    width: SCREEN_SIZE.0 - x - columns.trailing_zeros() as usize,
+                    height: y - start,
+                });
+            }
+        }
+        rects
+    }
+
+    fn bit(x: usize) -> u64 {
+        1 << (SCREEN_SIZE.0 - 1 - x)
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, v: bool) {
+        if self.get_pixel(x, y) != v {
+            self.rows[y] ^= Self::bit(x);
+            self.changed[y] |= Self::bit(x);
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> bool {
+        self.rows[y] & Self::bit(x) != 0
+    }
+
+    /// Pixel rows, bit 63 of each row is its leftmost pixel
+    pub fn rows(&self) -> &[u64; SCREEN_SIZE.1] {
+        &self.rows
+    }
+
+    /// Width and height in pixels
+    pub fn resolution(&self) -> (usize, usize) {
+        SCREEN_SIZE
+    }
+
+    /// Whole framebuffer: one `u64` per row from the top, bit 63 of each row
+    /// is its leftmost pixel and a set bit is a lit pixel.
+    pub fn as_slice(&self) -> &[u64] {
+        &self.rows
+    }
+
+    /// Pixels row by row from the top, each row left to right.
+    ///
+    /// ```
+    /// use chip8_core::Screen;
+    ///
+    /// let mut screen = Screen::default();
+    /// screen.set_pixel(1, 0, true);
+    /// let bytes: Vec<u8> = screen.iter_rows().flatten().map(|on| on as u8).collect();
+    /// assert_eq!(bytes.len(), 64 * 32);
+    /// assert_eq!(bytes[..3], [0, 1, 0]);
+    /// ```
+    pub fn iter_rows(&self) -> impl Iterator<Item = impl Iterator<Item = bool>> + '_ {
+        self.rows
+            .iter()
+            .map(|&row| (0..SCREEN_SIZE.0).map(move |x| row & Self::bit(x) != 0))
+    }
+
+    /// XORs sprite rows onto the screen at (x, y), returns true if any pixel was turned off.
+    /// With `clip` set, pixels past the screen edges are dropped instead of wrapping around
+    /// (the start position itself always wraps).
+    pub fn draw_sprite(&mut self, x: usize, y: usize, sprite: &[u8], clip: bool) -> bool {
+        let (x, y) = (x % SCREEN_SIZE.0, y % SCREEN_SIZE.1);
+        let mut collision = false;
+        for (j, &row) in sprite.iter().enumerate() {
+            let mut yj = y + j;
+            if yj >= SCREEN_SIZE.1 {
+                if clip {
+                    break;
+                }
+                yj %= SCREEN_SIZE.1;
+            }
+            // Sprite row in the leftmost 8 bits, moved to x
+            let row = (row as u64) << (SCREEN_SIZE.0 - 8);
+            let mask = if clip {
+                row >> x
+            } else {
+                row.rotate_right(x as u32)
+            };
+            if mask != 0 {
+                collision |= self.rows[yj] & mask != 0;
+                self.rows[yj] ^= mask;
+                self.changed[yj] |= mask;
+            }
+        }
+        collision
+    }
+}
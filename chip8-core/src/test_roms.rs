@@ -0,0 +1,117 @@
+//! Pass/fail detection for community test ROMs.
+//!
+//! These ROMs draw a result glyph next to each tested opcode, so a run is
+//! judged by the sprites it draws. The ROMs aren't distributed with the
+//! emulator, frontends look them up by `TestRom::file` name.
+
+use crate::emulator::{Emulator, StepEvent};
+use crate::instruction::Instruction;
+use crate::quirks::Quirks;
+
+/// Test ROM with a known way to tell passed and failed checks apart
+pub struct TestRom {
+    pub name: &'static str,
+    /// File name the ROM is usually distributed under
+    pub file: &'static str,
+    pub quirks: Quirks,
+    /// Upper bound on instructions needed to draw all results
+    pub cycles: usize,
+    /// Sprite drawn for each passed check
+    pub pass_glyph: &'static [u8],
+    /// Sprite drawn for each failed check
+    pub fail_glyph: &'static [u8],
+}
+
+/// "OK" and "NO" labels of corax89's test ROM
+const CORAX_OK: &[u8] = &[0xEA, 0xAC, 0xAA, 0xEA];
+const CORAX_NO: &[u8] = &[0xCE, 0xAA, 0xAA, 0xAE];
+
+/// Check mark and cross of Timendus' test suite
+const TIMENDUS_OK: &[u8] = &[0x00, 0x01, 0x03, 0x06, 0xCC, 0x78, 0x30];
+const TIMENDUS_ERR: &[u8] = &[0x00, 0x84, 0x48, 0x30, 0x30, 0x48, 0x84];
+
+pub const TEST_ROMS: [TestRom; 3] = [
+    TestRom {
+        name: "corax89 opcode test",
+        file: "test_opcode.ch8",
+        quirks: Quirks::OCTO,
+        cycles: 10_000,
+        pass_glyph: CORAX_OK,
+        fail_glyph: CORAX_NO,
+    },
+    TestRom {
+        name: "Timendus corax+",
+        file: "3-corax+.ch8",
+        quirks: Quirks::OCTO,
+        cycles: 10_000,
+        pass_glyph: TIMENDUS_OK,
+        fail_glyph: TIMENDUS_ERR,
+    },
+    TestRom {
+        name: "Timendus flags",
+        file: "4-flags.ch8",
+        quirks: Quirks::OCTO,
+        cycles: 10_000,
+        pass_glyph: TIMENDUS_OK,
+        fail_glyph: TIMENDUS_ERR,
+    },
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TestReport {
+    pub passed: usize,
+    pub failed: usize,
+    /// The ROM reached its final idle loop before running out of cycles
+    pub finished: bool,
+}
+
+impl TestReport {
+    /// All checks ran and passed
+    pub fn is_success(&self) -> bool {
+        self.finished && self.passed > 0 && self.failed == 0
+    }
+}
+
+impl TestRom {
+    /// Runs the ROM until it parks in a jump-to-self loop or `cycles` run out,
+    /// counting result glyphs it draws.
+    pub fn run(&self, rom: &[u8]) -> TestReport {
+        let mut report = TestReport {
+            passed: 0,
+            failed: 0,
+            finished: false,
+        };
+        let mut emulator = Emulator::builder().quirks(self.quirks).seed(0).build();
+        if emulator.load_rom_bytes(rom).is_err() {
+            return report;
+        }
+
+        for _ in 0..self.cycles {
+            let pc = emulator.pc() as usize;
+            let memory = emulator.memory();
+            if pc + 1 >= memory.len() {
+                break;
+            }
+            match Instruction::decode(u16::from_be_bytes([memory[pc], memory[pc + 1]])) {
+                Instruction::Jump { nnn } if nnn as usize == pc => {
+                    report.finished = true;
+                    break;
+                }
+                Instruction::Draw { n, .. } => {
+                    let start = emulator.index() as usize;
+                    let sprite = memory.get(start..start + n as usize).unwrap_or(&[]);
+                    if sprite == self.pass_glyph {
+                        report.passed += 1;
+                    } else if sprite == self.fail_glyph {
+                        report.failed += 1;
+                    }
+                }
+                _ => {}
+            }
+            if let StepEvent::Halted(_) = emulator.step() {
+                break;
+            }
+        }
+        report
+    }
+}
@@ -0,0 +1,54 @@
+//! Instruction times of the original COSMAC VIP interpreter, used when
+//! `Emulator::set_vip_timing` is on. Times are averages in microseconds and
+//! include the cycles the display steals from the CPU, so they are close to
+//! what a program sees but not cycle exact.
+
+use crate::instruction::Instruction;
+
+/// Sprite drawing setup, then every row is shifted into place and XORed on screen
+const DRAW_BASE: u32 = 68;
+const DRAW_ROW: u32 = 227;
+
+/// FX55/FX65 loop over the registers
+const REGISTERS_BASE: u32 = 77;
+const REGISTERS_EACH: u32 = 64;
+
+/// Seconds the VIP took to execute `instruction`. DXYN also waits for the next
+/// display interrupt, that wait isn't included.
+pub(crate) fn vip_time(instruction: Instruction) -> f32 {
+    use Instruction::*;
+    let micros = match instruction {
+        ClearScreen => 109,
+        Return | Jump { .. } | Call { .. } | JumpOffset { .. } => 105,
+        // Machine code routines, their time is unknown
+        System { .. } | Unknown(_) => 105,
+        SkipIfEqual { .. } | SkipIfNotEqual { .. } => 55,
+        SkipIfRegistersEqual { .. } | SkipIfRegistersNotEqual { .. } => 73,
+        Load { .. } => 27,
+        Add { .. } => 45,
+        Move { .. }
+        | Or { .. }
+        | And { .. }
+        | Xor { .. }
+        | AddRegisters { .. }
+        | Subtract { .. }
+        | ShiftRight { .. }
+        | SubtractReversed { .. }
+        | ShiftLeft { .. } => 200,
+        LoadIndex { .. } => 55,
+        Random { .. } => 164,
+        // DXY0 draws nothing on the VIP
+        Draw { n, .. } => DRAW_BASE + DRAW_ROW * n as u32,
+        SkipIfKeyPressed { .. } | SkipIfKeyNotPressed { .. } => 73,
+        // Every check for a key while waiting
+        WaitForKey { .. } => 45,
+        LoadDelay { .. } | SetDelay { .. } | SetSound { .. } => 45,
+        AddIndex { .. } => 86,
+        LoadFont { .. } => 91,
+        StoreBcd { .. } => 927,
+        StoreRegisters { x } | LoadRegisters { x } => {
+            REGISTERS_BASE + REGISTERS_EACH * (x as u32 + 1)
+        }
+    };
+    micros as f32 / 1_000_000.0
+}
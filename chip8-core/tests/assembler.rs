@@ -0,0 +1,152 @@
+//! Octo sources assembled into ROMs, checked by their bytes or by running them.
+
+use chip8_core::{assemble, Assembly, Emulator, Instruction};
+
+fn words(rom: &[u8]) -> Vec<u16> {
+    rom.chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+fn run(source: &str, cycles: usize) -> Emulator {
+    let rom = assemble(source).unwrap();
+    let mut emulator = Emulator::builder().seed(0).build();
+    emulator.load_rom_bytes(&rom).unwrap();
+    emulator.run_cycles(cycles);
+    emulator
+}
+
+#[test]
+fn assembles_every_instruction() {
+    let source = "
+        clear return ; jump 0x300 jump0 0x300 native 0x123 :call 0x300
+        if v1 == 2 then if v1 != 2 then if v1 == v2 then if v1 != v2 then
+        if v1 key then if v1 -key then
+        v1 := 2 v1 += 2 v1 -= 2 v1 := v2 v1 |= v2 v1 &= v2 v1 ^= v2
+        v1 += v2 v1 -= v2 v1 >>= v2 v1 =- v2 v1 <<= v2
+        i := 0x300 v1 := random 0x0F sprite v1 v2 5
+        v1 := delay v1 := key delay := v1 buzzer := v1
+        i += v1 i := hex v1 bcd v1 save v1 load v1
+    ";
+    let expected = [
+        0x00E0, 0x00EE, 0x00EE, 0x1300, 0xB300, 0x0123, 0x2300, //
+        0x4102, 0x3102, 0x9120, 0x5120, 0xE1A1, 0xE19E, //
+        0x6102, 0x7102, 0x71FE, 0x8120, 0x8121, 0x8122, 0x8123, //
+        0x8124, 0x8125, 0x8126, 0x8127, 0x812E, //
+        0xA300, 0xC10F, 0xD125, //
+        0xF107, 0xF10A, 0xF115, 0xF118, //
+        0xF11E, 0xF129, 0xF133, 0xF155, 0xF165,
+    ];
+    assert_eq!(words(&assemble(source).unwrap()), expected);
+}
+
+#[test]
+fn labels_resolve_forward_and_backward() {
+    let source = "
+        : start  jump later   # forward
+        : later  i := shape  start
+        : shape  0xF0 0x90
+    ";
+    assert_eq!(
+        assemble(source).unwrap(),
+        [0x12, 0x02, 0xA2, 0x06, 0x22, 0x00, 0xF0, 0x90]
+    );
+}
+
+#[test]
+fn main_gets_a_jump_unless_it_comes_first() {
+    assert_eq!(
+        words(&assemble(": helper return : main helper").unwrap()),
+        [0x1204, 0x00EE, 0x2202]
+    );
+    assert_eq!(words(&assemble(": main return").unwrap()), [0x00EE]);
+}
+
+#[test]
+fn constants_and_aliases() {
+    let source = ":const speed 3 :alias x v4 x := speed x += -1 :byte speed";
+    assert_eq!(assemble(source).unwrap(), [0x64, 0x03, 0x74, 0xFF, 0x03]);
+}
+
+#[test]
+fn if_else_chooses_a_branch() {
+    let source = "
+        : main
+        v0 := 1
+        if v0 == 1 begin v1 := 10 else v1 := 20 end
+        if v0 != 1 begin v2 := 10 else v2 := 20 end
+        loop again
+    ";
+    let emulator = run(source, 20);
+    assert_eq!(emulator.registers()[1], 10);
+    assert_eq!(emulator.registers()[2], 20);
+}
+
+#[test]
+fn while_leaves_loop() {
+    let source = "
+        : main
+        v0 := 0 v1 := 0
+        loop
+            v0 += 1
+            while v0 != 5
+            v1 += 2
+        again
+        : done jump done
+    ";
+    let emulator = run(source, 100);
+    assert_eq!(emulator.registers()[0], 5);
+    assert_eq!(emulator.registers()[1], 8);
+}
+
+#[test]
+fn org_moves_output() {
+    let rom = assemble("return :org 0x206 0xAB").unwrap();
+    assert_eq!(rom, [0x00, 0xEE, 0, 0, 0, 0, 0xAB]);
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    let error = assemble("clear\n\njump nowhere").unwrap_err();
+    assert_eq!(error.line, 3);
+    assert!(error.message.contains("nowhere"));
+
+    let error = assemble("v0 := 256").unwrap_err();
+    assert_eq!(error.line, 1);
+
+    let error = assemble("loop\nv0 += 1").unwrap_err();
+    assert!(error.message.contains("again"));
+
+    let error = assemble(": a\n: a").unwrap_err();
+    assert_eq!(error.line, 2);
+
+    let error = assemble("if v0 < 3 then clear").unwrap_err();
+    assert!(error.message.contains("isn't supported"));
+
+    assert!(assemble(":macro foo { clear }").is_err());
+}
+
+#[test]
+fn octo_statements_reassemble_to_their_opcode() {
+    for opcode in 0..=0xFFFF {
+        let statement = match Instruction::decode(opcode).to_octo() {
+            Some(statement) => statement,
+            None => continue,
+        };
+        // A lone `if ... then` needs a statement to guard
+        let rom = assemble(&format!("{} clear", statement)).unwrap();
+        assert_eq!(rom[..2], opcode.to_be_bytes(), "{}", statement);
+    }
+}
+
+#[test]
+fn labels_find_the_same_place_after_edits() {
+    let before = Assembly::new(": main  v0 := 1  : draw  i := hex v0  sprite v0 v0 5  ;").unwrap();
+    let after =
+        Assembly::new(": main  clear  v0 := 1  : draw  i := hex v0  sprite v0 v0 5  ;").unwrap();
+    let (label, offset) = before.locate(0x204).unwrap();
+    assert_eq!((label, offset), ("draw", 2));
+    assert_eq!(after.resolve(label, offset), Some(0x206));
+    assert_eq!(after.resolve("missing", 0), None);
+    assert_eq!(after.resolve("draw", 0x100), None);
+}
@@ -0,0 +1,62 @@
+//! Cached block execution has to be indistinguishable from stepping.
+//! Run with `cargo test -p chip8-core --features block-cache`.
+
+#![cfg(feature = "block-cache")]
+
+use chip8_core::{Emulator, Quirks};
+use std::path::Path;
+
+mod common;
+
+fn load(rom: &[u8]) -> Emulator {
+    let mut emulator = Emulator::builder().quirks(Quirks::OCTO).seed(3).build();
+    emulator.load_rom_bytes(rom).unwrap();
+    emulator
+}
+
+fn assert_same_as_stepping(rom: &[u8], cycles: usize) {
+    let mut cached = load(rom);
+    cached.run_cycles(cycles);
+
+    let mut stepped = load(rom);
+    for _ in 0..cycles {
+        stepped.step();
+    }
+    assert_eq!(cached.state_hash(), stepped.state_hash());
+}
+
+#[test]
+fn bundled_roms() {
+    let roms = Path::new(env!("CARGO_MANIFEST_DIR")).join("../roms");
+    for rom in &[
+        "games/Brix [Andreas Gustafsson, 1990].ch8",
+        "demos/Maze [David Winter, 199x].ch8",
+        "demos/Particle Demo [zeroZshadow, 2008].ch8",
+    ] {
+        let rom = std::fs::read(roms.join(rom)).unwrap();
+        assert_same_as_stepping(&rom, 20_000);
+    }
+}
+
+#[test]
+fn self_modifying_code() {
+    #[rustfmt::skip]
+    let program: &[u16] = &[
+        0x6063, // 200: V0 = 0x63
+        0x6155, // 202: V1 = 0x55
+        0x1206, // 204: start a block at 206
+        0xA208, // 206: I = 0x208
+        0x6322, // 208: V3 = 0x22, becomes V3 = 0x55 after the first pass
+        0xF155, // 20A: store V0..V1 over the instruction at 208
+        0x3355, // 20C: done once the patched instruction ran
+        0x1206, // 20E: run the block again
+        0x1210, // 210: park
+    ];
+    let rom = common::rom(program);
+
+    let mut emulator = load(&rom);
+    emulator.run_cycles(20);
+    assert_eq!(emulator.registers()[3], 0x55);
+    assert_eq!(emulator.pc(), 0x210);
+    assert_same_as_stepping(&rom, 100);
+}
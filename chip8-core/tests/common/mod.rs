@@ -0,0 +1,23 @@
+//! Helpers shared by the test files, pulled in with `mod common;`
+
+// Each test file is its own crate and uses only some of these
+#![allow(dead_code)]
+
+use chip8_core::{Emulator, EmulatorBuilder};
+
+/// ROM bytes of `program`, one big-endian opcode per instruction
+pub fn rom(program: &[u16]) -> Vec<u8> {
+    program.iter().flat_map(|op| op.to_be_bytes()).collect()
+}
+
+/// Emulator from `builder` with `program` loaded
+pub fn load_with(builder: EmulatorBuilder, program: &[u16]) -> Emulator {
+    let mut emulator = builder.build();
+    emulator.load_rom_bytes(&rom(program)).unwrap();
+    emulator
+}
+
+/// Emulator with a fixed seed and `program` loaded
+pub fn load(program: &[u16]) -> Emulator {
+    load_with(Emulator::builder().seed(0), program)
+}
@@ -0,0 +1,144 @@
+//! Property tests for `Screen::draw_sprite`, checked against a straightforward model.
+
+use chip8_core::{Screen, SCREEN_SIZE};
+use proptest::prelude::*;
+
+const W: usize = SCREEN_SIZE.0;
+const H: usize = SCREEN_SIZE.1;
+
+fn screen_from(pixels: &[bool]) -> Screen {
+    let mut screen = Screen::default();
+    for (i, &v) in pixels.iter().enumerate() {
+        screen.set_pixel(i % W, i / W, v);
+    }
+    screen
+}
+
+fn pixels_of(screen: &Screen) -> Vec<bool> {
+    (0..W * H).map(|i| screen.get_pixel(i % W, i / W)).collect()
+}
+
+/// Screen coordinates covered by the sprite's set bits. The start position wraps,
+/// bits past the edges wrap too unless clipped.
+fn sprite_pixels(x: usize, y: usize, sprite: &[u8], clip: bool) -> Vec<(usize, usize)> {
+    let mut pixels = Vec::new();
+    for (row, bits) in sprite.iter().enumerate() {
+        for col in 0..8 {
+            if bits & (0x80 >> col) == 0 {
+                continue;
+            }
+            let (px, py) = (x % W + col, y % H + row);
+            if clip && (px >= W || py >= H) {
+                continue;
+            }
+            pixels.push((px % W, py % H));
+        }
+    }
+    pixels
+}
+
+fn pixels() -> impl Strategy<Value = Vec<bool>> {
+    prop::collection::vec(any::<bool>(), W * H)
+}
+
+fn sprite() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 0..=15)
+}
+
+proptest! {
+    #[test]
+    fn drawing_twice_restores_screen(
+        pixels in pixels(), x in 0..=255usize, y in 0..=255usize, sprite in sprite(), clip: bool
+    ) {
+        let mut screen = screen_from(&pixels);
+        screen.draw_sprite(x, y, &sprite, clip);
+        screen.draw_sprite(x, y, &sprite, clip);
+        prop_assert_eq!(pixels_of(&screen), pixels);
+    }
+
+    #[test]
+    fn matches_model(
+        pixels in pixels(), x in 0..=255usize, y in 0..=255usize, sprite in sprite(), clip: bool
+    ) {
+        let mut expected = pixels.clone();
+        let mut collision = false;
+        for (px, py) in sprite_pixels(x, y, &sprite, clip) {
+            let pixel = &mut expected[px + py * W];
+            collision |= *pixel;
+            *pixel = !*pixel;
+        }
+
+        let mut screen = screen_from(&pixels);
+        prop_assert_eq!(screen.draw_sprite(x, y, &sprite, clip), collision);
+        prop_assert_eq!(pixels_of(&screen), expected);
+    }
+
+    #[test]
+    fn no_collision_on_blank_screen(
+        x in 0..=255usize, y in 0..=255usize, sprite in sprite(), clip: bool
+    ) {
+        let mut screen = Screen::default();
+        prop_assert!(!screen.draw_sprite(x, y, &sprite, clip));
+    }
+
+    #[test]
+    fn clipped_sprite_stays_right_and_below_start(
+        x in 0..=255usize, y in 0..=255usize, sprite in sprite()
+    ) {
+        let mut screen = Screen::default();
+        screen.draw_sprite(x, y, &sprite, true);
+        for (i, lit) in pixels_of(&screen).into_iter().enumerate() {
+            if lit {
+                prop_assert!(i % W >= x % W && i / W >= y % H);
+            }
+        }
+    }
+
+    #[test]
+    fn wrapped_sprite_keeps_all_pixels(
+        x in 0..=255usize, y in 0..=255usize, sprite in prop::collection::vec(any::<u8>(), 0..=8)
+    ) {
+        // Up to 8 rows can't overlap themselves when wrapping on a 64x32 screen
+        let mut screen = Screen::default();
+        screen.draw_sprite(x, y, &sprite, false);
+        let lit = pixels_of(&screen).into_iter().filter(|&lit| lit).count();
+        let bits: u32 = sprite.iter().map(|row| row.count_ones()).sum();
+        prop_assert_eq!(lit as u32, bits);
+    }
+
+    #[test]
+    fn framebuffer_views_match_pixels(pixels in pixels()) {
+        let screen = screen_from(&pixels);
+        prop_assert_eq!(screen.resolution(), (W, H));
+        let rows: Vec<bool> = screen.iter_rows().flatten().collect();
+        prop_assert_eq!(&rows, &pixels);
+        let bits: Vec<bool> = screen
+            .as_slice()
+            .iter()
+            .flat_map(|row| (0..W).map(move |x| row & (1 << (63 - x)) != 0))
+            .collect();
+        prop_assert_eq!(bits, pixels);
+    }
+
+    #[test]
+    fn dirty_rects_cover_changed_pixels(
+        pixels in pixels(), x in 0..=255usize, y in 0..=255usize, sprite in sprite(), clip: bool
+    ) {
+        let mut screen = screen_from(&pixels);
+        screen.reset_dirty();
+        prop_assert!(screen.dirty_rects().is_empty());
+
+        screen.draw_sprite(x, y, &sprite, clip);
+        let rects = screen.dirty_rects();
+        prop_assert_eq!(screen.is_dirty(), !rects.is_empty());
+        for (i, (before, after)) in pixels.iter().zip(pixels_of(&screen)).enumerate() {
+            if *before != after {
+                let (px, py) = (i % W, i / W);
+                let covered = rects.iter().any(|r| {
+                    (r.x..r.x + r.width).contains(&px) && (r.y..r.y + r.height).contains(&py)
+                });
+                prop_assert!(covered);
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Static control flow graphs of small programs.
+
+use chip8_core::{ByteKind, Edge, EdgeKind, FlowGraph};
+
+mod common;
+use common::rom;
+
+fn build(program: &[u16]) -> FlowGraph {
+    FlowGraph::build(&rom(program), 0x200, 0x200)
+}
+
+fn edge(kind: EdgeKind, target: u16) -> Edge {
+    Edge { kind, target }
+}
+
+#[test]
+fn straight_code_is_one_block() {
+    let graph = build(&[0x6001, 0x7001, 0x00EE]);
+    assert_eq!(graph.blocks().len(), 1);
+    let block = &graph.blocks()[0];
+    assert_eq!(block.instructions.len(), 3);
+    assert!(block.edges.is_empty());
+}
+
+#[test]
+fn jump_target_splits_block() {
+    // 200: V0 = 1; 202: V0 += 1; 204: jump 202
+    let graph = build(&[0x6001, 0x7001, 0x1202]);
+    let starts: Vec<u16> = graph.blocks().iter().map(|b| b.start).collect();
+    assert_eq!(starts, [0x200, 0x202]);
+    assert_eq!(graph.blocks()[0].edges, [edge(EdgeKind::Next, 0x202)]);
+    assert_eq!(graph.blocks()[1].edges, [edge(EdgeKind::Jump, 0x202)]);
+}
+
+#[test]
+fn call_returns_to_next_instruction() {
+    // 200: call 206; 202: jump 202; 204: data; 206: V0 = 1; 208: return
+    let graph = build(&[0x2206, 0x1202, 0xFFFF, 0x6001, 0x00EE]);
+    let call = graph.block_at(0x200).unwrap();
+    assert_eq!(
+        call.edges,
+        [edge(EdgeKind::Call, 0x206), edge(EdgeKind::Next, 0x202)]
+    );
+    let subroutine = graph.block_at(0x206).unwrap();
+    assert_eq!(subroutine.instructions.len(), 2);
+    assert!(subroutine.edges.is_empty());
+    // Data between them isn't reachable
+    assert!(graph.block_at(0x204).is_none());
+}
+
+#[test]
+fn skips_branch_two_ways() {
+    // 200: skip if V0 == 0; 202: V1 = 1; 204: V2 = 2; 206: jump 206
+    let graph = build(&[0x3000, 0x6101, 0x6202, 0x1206]);
+    let skip = graph.block_at(0x200).unwrap();
+    assert_eq!(
+        skip.edges,
+        [edge(EdgeKind::Next, 0x202), edge(EdgeKind::Skip, 0x204)]
+    );
+    assert_eq!(
+        graph.block_at(0x202).unwrap().edges,
+        [edge(EdgeKind::Next, 0x204)]
+    );
+}
+
+#[test]
+fn targets_outside_of_code_are_not_followed() {
+    let graph = build(&[0x1300]);
+    assert_eq!(graph.blocks().len(), 1);
+    assert_eq!(graph.blocks()[0].edges, [edge(EdgeKind::Jump, 0x300)]);
+}
+
+#[test]
+fn subroutines_list_their_calls() {
+    // 200: call 206; 202: call 20C; 204: jump 204
+    // 206: skip if V0 == 0; 208: call 206; 20A: return (recursive)
+    // 20C: jump 20A, shares the return of 206
+    let graph = build(&[0x2206, 0x220C, 0x1204, 0x3000, 0x2206, 0x00EE, 0x120A]);
+    let subroutines = graph.subroutines();
+    let entries: Vec<u16> = subroutines.iter().map(|s| s.entry).collect();
+    assert_eq!(entries, [0x200, 0x206, 0x20C]);
+    assert_eq!(subroutines[0].calls, [0x206, 0x20C]);
+    assert_eq!(subroutines[1].calls, [0x206]);
+    assert!(subroutines[2].calls.is_empty());
+}
+
+#[test]
+fn sprites_pair_index_with_draw() {
+    // 200: I = 20C; 202: draw 5 rows; 204: I = 20C; 206: draw 16x16; 208: I += V0; 20A: draw 4 rows
+    let graph = build(&[0xA20C, 0xD015, 0xA20C, 0xD010, 0xF01E, 0xD014]);
+    let sprites = graph.sprites(0x22C);
+    assert_eq!(sprites.len(), 1);
+    assert_eq!(sprites[0].address, 0x20C);
+    assert_eq!((sprites[0].width, sprites[0].height), (16, 16));
+    assert_eq!(sprites[0].size(), 32);
+    assert!(!sprites[0].guessed);
+}
+
+#[test]
+fn undrawn_index_targets_are_guessed() {
+    // 200: I = 206; 202: call 20A; 204: jump 204; 206: 4 bytes of data; 20A: return
+    let graph = build(&[0xA206, 0x220A, 0x1204, 0x1234, 0x5678, 0x00EE]);
+    let sprites = graph.sprites(0x20C);
+    assert_eq!(sprites.len(), 1);
+    assert_eq!((sprites[0].address, sprites[0].height), (0x206, 4));
+    assert!(sprites[0].guessed);
+}
+
+#[test]
+fn byte_kinds_follow_odd_jumps() {
+    // 200: jump 203; 202: data; 203: V0 = 1; 205: jump 205; 207: data
+    let code = [0x12, 0x03, 0xAA, 0x60, 0x01, 0x12, 0x05, 0xBB];
+    let graph = FlowGraph::build(&code, 0x200, 0x200);
+    let kinds = graph.byte_kinds(0x200, code.len());
+    let code_bytes: Vec<usize> = (0..code.len())
+        .filter(|&i| kinds[i] == ByteKind::Code)
+        .collect();
+    assert_eq!(code_bytes, [0, 1, 3, 4, 5, 6]);
+    assert_eq!(kinds[2], ByteKind::Data);
+    assert_eq!(kinds[7], ByteKind::Data);
+}
@@ -0,0 +1,97 @@
+//! Runs ROMs from `roms/` for a fixed number of cycles and compares the screen
+//! with golden images in `tests/golden/`.
+//!
+//! Images are plain PBM files, so they can be opened by image viewers and diffed
+//! as text. Run with `UPDATE_GOLDEN=1` to (re)generate them after an intended change.
+
+use chip8_core::{Emulator, Screen, SCREEN_SIZE};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SEED: u64 = 0x8;
+
+fn roms_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../roms")
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+        .with_extension("pbm")
+}
+
+fn to_pbm(screen: &Screen) -> String {
+    let (width, height) = SCREEN_SIZE;
+    let mut pbm = format!("P1\n{} {}\n", width, height);
+    for y in 0..height {
+        let row: Vec<&str> = (0..width)
+            .map(|x| if screen.get_pixel(x, y) { "1" } else { "0" })
+            .collect();
+        pbm += &row.join(" ");
+        pbm += "\n";
+    }
+    pbm
+}
+
+fn check(name: &str, rom: &str, cycles: usize) {
+    let mut emulator = Emulator::builder().seed(SEED).build();
+    emulator.load_rom(&roms_dir().join(rom)).unwrap();
+    emulator.run_cycles(cycles);
+    let actual = to_pbm(emulator.screen());
+
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Can't read {}: {}", path.display(), e));
+    assert!(
+        actual == expected,
+        "Screen of '{}' after {} cycles doesn't match {}, got:\n{}",
+        rom,
+        cycles,
+        path.display(),
+        actual
+    );
+}
+
+#[test]
+fn ibm_logo() {
+    check("ibm_logo", "programs/IBM Logo.ch8", 100);
+}
+
+#[test]
+fn chip8_picture() {
+    check("chip8_picture", "programs/Chip8 Picture.ch8", 1000);
+}
+
+#[test]
+fn maze() {
+    check("maze", "demos/Maze [David Winter, 199x].ch8", 5000);
+}
+
+#[test]
+fn sierpinski() {
+    check(
+        "sierpinski",
+        "demos/Sierpinski [Sergey Naydenov, 2010].ch8",
+        20000,
+    );
+}
+
+#[test]
+fn particle_demo() {
+    check(
+        "particle_demo",
+        "demos/Particle Demo [zeroZshadow, 2008].ch8",
+        5000,
+    );
+}
+
+#[test]
+fn brix() {
+    check("brix", "games/Brix [Andreas Gustafsson, 1990].ch8", 3000);
+}
@@ -0,0 +1,52 @@
+//! Programs that are done or wait for a key, told apart from ones still working.
+
+use chip8_core::{Emulator, Idle};
+
+mod common;
+use common::load;
+
+#[test]
+fn jump_to_itself_is_finished() {
+    assert_eq!(load(&[0x1200]).idle(), Some(Idle::Finished));
+    // Two jumps back and forth
+    assert_eq!(load(&[0x1202, 0x1200]).idle(), Some(Idle::Finished));
+    assert_eq!(Emulator::new().idle(), None);
+}
+
+#[test]
+fn busy_loop_is_working() {
+    let mut emulator = load(&[0x7001, 0x1200]);
+    assert_eq!(emulator.idle(), None);
+    emulator.step();
+    assert_eq!(emulator.idle(), None);
+}
+
+#[test]
+fn wait_for_key() {
+    let mut emulator = load(&[0xF00A, 0x1202]);
+    assert_eq!(emulator.idle(), Some(Idle::WaitingForKey));
+    emulator.set_key(4, true);
+    emulator.step();
+    assert_eq!(emulator.idle(), Some(Idle::Finished));
+}
+
+#[test]
+fn key_spin_waits_for_key() {
+    let mut emulator = load(&[
+        0x6005, // V0 = 5
+        0xE0A1, // skip unless key 5 is down
+        0x1208, // key is down, go on
+        0x1202, // check again
+        0x7101, // V1 += 1
+        0x120A,
+    ]);
+    emulator.step();
+    assert_eq!(emulator.idle(), Some(Idle::WaitingForKey));
+    emulator.step();
+    assert_eq!(emulator.pc(), 0x206);
+    assert_eq!(emulator.idle(), Some(Idle::WaitingForKey));
+
+    // The loop is left once the key goes down
+    emulator.set_key(5, true);
+    assert_eq!(emulator.idle(), None);
+}
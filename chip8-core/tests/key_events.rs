@@ -0,0 +1,99 @@
+//! Key events queued with frame timestamps and how programs see them.
+
+use chip8_core::{Audio, Display, Emulator, Input, KeyEvent, Screen, StepEvent, TIMER_HZ};
+
+mod common;
+use common::load;
+
+const FRAME: f32 = 1.0 / TIMER_HZ;
+
+fn event(key: u8, down: bool, frame: u64) -> KeyEvent {
+    KeyEvent { key, down, frame }
+}
+
+#[test]
+fn events_wait_for_their_frame() {
+    let mut emulator = load(&[0x1200]);
+    emulator.push_key_event(event(3, true, 2));
+    emulator.push_key_event(event(3, false, 4));
+    assert_eq!(emulator.keypad().pending().count(), 2);
+    assert!(!emulator.keypad().will_be_pressed(3));
+
+    for _ in 0..8 {
+        emulator.run_for(FRAME);
+        let frame = emulator.frame();
+        assert_eq!(emulator.keypad().is_pressed(3), (2..4).contains(&frame));
+    }
+    assert_eq!(emulator.keypad().pending().count(), 0);
+    assert_eq!(emulator.stats().key_presses[3], 1);
+}
+
+#[test]
+fn short_tap_is_seen_by_the_program() {
+    // V1 counts the frames key 0 is down: wait for a tick, then check the key
+    let mut emulator = load(&[
+        0xF015, // delay = V0
+        0xF207, // V2 = delay
+        0x3200, // skip unless the delay is over
+        0x1202, 0x6001, // V0 = 1, wait a frame each loop
+        0xE39E, // skip if key V3 is down
+        0x1200, 0x7101, // V1 += 1
+        0x1200,
+    ]);
+    let frame = emulator.frame();
+    emulator.push_key_event(event(0, true, frame));
+    emulator.push_key_event(event(0, false, frame));
+    for _ in 0..10 {
+        emulator.run_for(FRAME);
+    }
+    assert_eq!(emulator.registers()[1], 1);
+}
+
+#[test]
+fn wait_for_key_on_release() {
+    let mut emulator = Emulator::builder().wait_for_release(true).build();
+    emulator.load_rom_bytes(&[0xF3, 0x0A]).unwrap();
+    assert_eq!(emulator.step(), StepEvent::WaitingForKey);
+
+    emulator.set_key(0xB, true);
+    assert_eq!(emulator.step(), StepEvent::WaitingForKey);
+    // Other keys going down meanwhile don't change the key being waited for
+    emulator.set_key(0x2, true);
+    assert_eq!(emulator.step(), StepEvent::WaitingForKey);
+    emulator.set_key(0xB, false);
+    assert_eq!(emulator.step(), StepEvent::Normal);
+    assert_eq!(emulator.registers()[3], 0xB);
+    assert_eq!(emulator.pc(), 0x202);
+}
+
+struct Keys(u16);
+
+impl Input for Keys {
+    fn is_key_down(&self, key: u8) -> bool {
+        self.0 & (1 << key) != 0
+    }
+}
+
+struct NoOutput;
+
+impl Display for NoOutput {
+    fn draw(&mut self, _: &Screen) {}
+}
+
+impl Audio for NoOutput {
+    fn set_beep(&mut self, _: bool) {}
+}
+
+#[test]
+fn run_frame_queues_input_changes() {
+    let mut emulator = load(&[0x1200]);
+    emulator.run_frame(FRAME, &Keys(0b1010), &mut NoOutput, &mut NoOutput);
+    assert!(emulator.keypad().is_pressed(1));
+    assert!(emulator.keypad().is_pressed(3));
+    assert_eq!(emulator.stats().key_presses[1], 1);
+
+    // Held keys aren't pressed again
+    emulator.run_frame(FRAME, &Keys(0b0010), &mut NoOutput, &mut NoOutput);
+    assert!(!emulator.keypad().is_pressed(3));
+    assert_eq!(emulator.stats().key_presses[1], 1);
+}
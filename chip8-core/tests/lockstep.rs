@@ -0,0 +1,56 @@
+//! Runs programs on two emulators with different quirks and checks where they part.
+
+use chip8_core::{Emulator, Lockstep, Quirks};
+
+mod common;
+use common::rom;
+
+#[test]
+fn same_quirks_never_diverge() {
+    // Random numbers and a counting loop, the generators must stay in step
+    let rom = rom(&[0xC0FF, 0xC1FF, 0x7201, 0xA300, 0xD012, 0x1200]);
+    let mut lockstep = Lockstep::new(Emulator::builder(), [Quirks::VIP; 2], &rom).unwrap();
+    assert_eq!(lockstep.run(10_000), None);
+    assert_eq!(lockstep.steps(), 10_000);
+}
+
+#[test]
+fn stops_at_the_instruction_using_a_quirk() {
+    let rom = rom(&[
+        0x6005, // V0 = 5
+        0x6103, // V1 = 3
+        0x8011, // V0 |= V1, resets VF with the logic quirk
+        0x6F01, // VF = 1
+        0x8011, // Only here VF differs
+        0x1208,
+    ]);
+    let quirks = [
+        Quirks::OCTO,
+        Quirks {
+            logic: true,
+            ..Quirks::OCTO
+        },
+    ];
+    let mut lockstep = Lockstep::new(Emulator::builder(), quirks, &rom).unwrap();
+    let divergence = lockstep.run(100).unwrap();
+    assert_eq!(divergence.address, 0x208);
+    assert_eq!(divergence.opcode, 0x8011);
+    assert_eq!(divergence.step, 5);
+
+    // Stays put once found
+    assert_eq!(lockstep.run(100), Some(divergence));
+    assert_eq!(lockstep.steps(), 5);
+    let [a, b] = lockstep.emulators();
+    assert_eq!(a.registers()[0xF], 1);
+    assert_eq!(b.registers()[0xF], 0);
+}
+
+#[test]
+fn stops_when_both_halt() {
+    let rom = rom(&[0x1FFF]); // Jump out of memory
+    let mut lockstep =
+        Lockstep::new(Emulator::builder(), [Quirks::OCTO, Quirks::VIP], &rom).unwrap();
+    assert_eq!(lockstep.run(100), None);
+    assert!(lockstep.halt_reason().is_some());
+    assert_eq!(lockstep.steps(), 2);
+}
@@ -0,0 +1,643 @@
+//! Executes single opcodes (with a few setup instructions) and checks their effects.
+
+use chip8_core::{
+    Emulator, Extension, Fault, HaltReason, Instruction, Quirks, StepEvent, STACK_DEPTH,
+};
+
+mod common;
+use common::{load_with, rom};
+
+/// Fast enough clock that timers don't tick during a test
+const CLOCK_HZ: f32 = 1_000_000.0;
+
+fn load(quirks: Quirks, program: &[u16]) -> Emulator {
+    load_with(
+        Emulator::builder()
+            .quirks(quirks)
+            .clock_hz(CLOCK_HZ)
+            .seed(0),
+        program,
+    )
+}
+
+/// Executes one step per instruction, skipped instructions leave 0000 (ignored) steps at the end
+fn run_with(quirks: Quirks, program: &[u16]) -> Emulator {
+    let mut emulator = load(quirks, program);
+    for _ in program {
+        emulator.step();
+    }
+    emulator
+}
+
+fn run(program: &[u16]) -> Emulator {
+    run_with(Quirks::default(), program)
+}
+
+fn lit_pixels(emulator: &Emulator) -> usize {
+    let screen = emulator.screen();
+    (0..32)
+        .flat_map(|y| (0..64).map(move |x| (x, y)))
+        .filter(|&(x, y)| screen.get_pixel(x, y))
+        .count()
+}
+
+#[test]
+fn clear_screen() {
+    // Draw font digit 0, then clear
+    let emulator = run(&[0xD005, 0x00E0]);
+    assert_eq!(lit_pixels(&emulator), 0);
+}
+
+#[test]
+fn call_and_return() {
+    let mut emulator = load(Quirks::default(), &[0x2206, 0x6101, 0x1204, 0x00EE]);
+    emulator.step();
+    assert_eq!(emulator.pc(), 0x206);
+    assert_eq!(emulator.stack(), &[0x202]);
+    emulator.step();
+    assert_eq!(emulator.pc(), 0x202);
+    assert!(emulator.stack().is_empty());
+}
+
+#[test]
+fn system_call_is_ignored() {
+    let emulator = run(&[0x0123]);
+    assert_eq!(emulator.pc(), 0x202);
+}
+
+#[test]
+fn jump() {
+    let emulator = run(&[0x1ABC]);
+    assert_eq!(emulator.pc(), 0xABC);
+}
+
+#[test]
+fn skip_if_equal() {
+    let emulator = run(&[0x6005, 0x3005, 0x6101, 0x6202]);
+    assert_eq!(emulator.registers()[1], 0);
+    assert_eq!(emulator.registers()[2], 2);
+
+    let emulator = run(&[0x6005, 0x3006, 0x6101]);
+    assert_eq!(emulator.registers()[1], 1);
+}
+
+#[test]
+fn skip_if_not_equal() {
+    let emulator = run(&[0x6005, 0x4006, 0x6101, 0x6202]);
+    assert_eq!(emulator.registers()[1], 0);
+    assert_eq!(emulator.registers()[2], 2);
+
+    let emulator = run(&[0x6005, 0x4005, 0x6101]);
+    assert_eq!(emulator.registers()[1], 1);
+}
+
+#[test]
+fn skip_if_registers_equal() {
+    let emulator = run(&[0x6005, 0x6105, 0x5010, 0x6201, 0x6302]);
+    assert_eq!(emulator.registers()[2], 0);
+    assert_eq!(emulator.registers()[3], 2);
+
+    let emulator = run(&[0x6005, 0x6106, 0x5010, 0x6201]);
+    assert_eq!(emulator.registers()[2], 1);
+}
+
+#[test]
+fn skip_if_registers_not_equal() {
+    let emulator = run(&[0x6005, 0x6106, 0x9010, 0x6201, 0x6302]);
+    assert_eq!(emulator.registers()[2], 0);
+    assert_eq!(emulator.registers()[3], 2);
+
+    let emulator = run(&[0x6005, 0x6105, 0x9010, 0x6201]);
+    assert_eq!(emulator.registers()[2], 1);
+}
+
+#[test]
+fn load_and_add_immediate() {
+    let emulator = run(&[0x6A12, 0x7A34]);
+    assert_eq!(emulator.registers()[0xA], 0x46);
+}
+
+#[test]
+fn add_immediate_wraps_without_carry() {
+    let emulator = run(&[0x60FF, 0x7002]);
+    assert_eq!(emulator.registers()[0], 0x01);
+    assert_eq!(emulator.registers()[0xF], 0);
+}
+
+#[test]
+fn move_register() {
+    let emulator = run(&[0x6142, 0x8010]);
+    assert_eq!(emulator.registers()[0], 0x42);
+}
+
+#[test]
+fn bitwise_operations() {
+    let emulator = run(&[0x600C, 0x610A, 0x8011]);
+    assert_eq!(emulator.registers()[0], 0x0E);
+    let emulator = run(&[0x600C, 0x610A, 0x8012]);
+    assert_eq!(emulator.registers()[0], 0x08);
+    let emulator = run(&[0x600C, 0x610A, 0x8013]);
+    assert_eq!(emulator.registers()[0], 0x06);
+}
+
+#[test]
+fn logic_quirk_resets_vf() {
+    for op in [0x8011, 0x8012, 0x8013] {
+        let emulator = run(&[0x6F05, 0x600C, 0x610A, op]);
+        assert_eq!(emulator.registers()[0xF], 5, "{:04X}", op);
+
+        let emulator = run_with(Quirks::VIP, &[0x6F05, 0x600C, 0x610A, op]);
+        assert_eq!(emulator.registers()[0xF], 0, "{:04X}", op);
+    }
+}
+
+#[test]
+fn add_registers_sets_carry() {
+    let emulator = run(&[0x6010, 0x6120, 0x8014]);
+    assert_eq!(emulator.registers()[0], 0x30);
+    assert_eq!(emulator.registers()[0xF], 0);
+
+    let emulator = run(&[0x60F0, 0x6120, 0x8014]);
+    assert_eq!(emulator.registers()[0], 0x10);
+    assert_eq!(emulator.registers()[0xF], 1);
+}
+
+#[test]
+fn subtract_sets_not_borrow() {
+    let emulator = run(&[0x6030, 0x6110, 0x8015]);
+    assert_eq!(emulator.registers()[0], 0x20);
+    assert_eq!(emulator.registers()[0xF], 1);
+
+    let emulator = run(&[0x6010, 0x6130, 0x8015]);
+    assert_eq!(emulator.registers()[0], 0xE0);
+    assert_eq!(emulator.registers()[0xF], 0);
+}
+
+#[test]
+fn subtract_reversed_sets_not_borrow() {
+    let emulator = run(&[0x6010, 0x6130, 0x8017]);
+    assert_eq!(emulator.registers()[0], 0x20);
+    assert_eq!(emulator.registers()[0xF], 1);
+
+    let emulator = run(&[0x6030, 0x6110, 0x8017]);
+    assert_eq!(emulator.registers()[0], 0xE0);
+    assert_eq!(emulator.registers()[0xF], 0);
+}
+
+#[test]
+fn shift_right() {
+    // Shift quirk: VX is shifted in place
+    let emulator = run(&[0x6005, 0x6110, 0x8016]);
+    assert_eq!(emulator.registers()[0], 0x02);
+    assert_eq!(emulator.registers()[0xF], 1);
+
+    // VIP: VY is shifted into VX
+    let emulator = run_with(Quirks::VIP, &[0x6005, 0x6110, 0x8016]);
+    assert_eq!(emulator.registers()[0], 0x08);
+    assert_eq!(emulator.registers()[1], 0x10);
+    assert_eq!(emulator.registers()[0xF], 0);
+}
+
+#[test]
+fn shift_left() {
+    let emulator = run(&[0x6081, 0x6101, 0x801E]);
+    assert_eq!(emulator.registers()[0], 0x02);
+    assert_eq!(emulator.registers()[0xF], 1);
+
+    let emulator = run_with(Quirks::VIP, &[0x6081, 0x6101, 0x801E]);
+    assert_eq!(emulator.registers()[0], 0x02);
+    assert_eq!(emulator.registers()[1], 0x01);
+    assert_eq!(emulator.registers()[0xF], 0);
+}
+
+#[test]
+fn load_index() {
+    let emulator = run(&[0xA123]);
+    assert_eq!(emulator.index(), 0x123);
+}
+
+#[test]
+fn jump_with_offset() {
+    let emulator = run(&[0x6010, 0x6220, 0xB300]);
+    assert_eq!(emulator.pc(), 0x310);
+
+    // Jump quirk uses VX, X being the top nibble of NNN
+    let emulator = run_with(Quirks::SCHIP, &[0x6010, 0x6320, 0xB300]);
+    assert_eq!(emulator.pc(), 0x320);
+}
+
+#[test]
+fn random_is_masked() {
+    let emulator = run(&[0xC00F]);
+    assert_eq!(emulator.registers()[0] & 0xF0, 0);
+    let emulator = run(&[0x60FF, 0xC000]);
+    assert_eq!(emulator.registers()[0], 0);
+}
+
+#[test]
+fn draw_sets_collision() {
+    let mut emulator = load(Quirks::default(), &[0xD005, 0xD005]);
+    assert_eq!(emulator.step(), StepEvent::DrewSprite { collision: false });
+    // Font digit 0 has 14 lit pixels
+    assert_eq!(lit_pixels(&emulator), 14);
+    assert_eq!(emulator.registers()[0xF], 0);
+
+    assert_eq!(emulator.step(), StepEvent::DrewSprite { collision: true });
+    assert_eq!(lit_pixels(&emulator), 0);
+    assert_eq!(emulator.registers()[0xF], 1);
+}
+
+#[test]
+fn draw_at_registers() {
+    let emulator = run(&[0x600A, 0x6105, 0xD011]);
+    // Top row of digit 0 is 0xF0
+    for x in 10..14 {
+        assert!(emulator.screen().get_pixel(x, 5));
+    }
+    assert_eq!(lit_pixels(&emulator), 4);
+}
+
+#[test]
+fn draw_clip_quirk() {
+    // Digit 0 at x = 62 is cut off or wraps around to the left edge
+    let emulator = run(&[0x603E, 0xD015]);
+    assert!(emulator.screen().get_pixel(0, 0));
+
+    let emulator = run_with(Quirks::SCHIP, &[0x603E, 0xD015]);
+    assert!(!emulator.screen().get_pixel(0, 0));
+    assert!(emulator.screen().get_pixel(63, 0));
+}
+
+#[test]
+fn draw_vblank_quirk() {
+    let mut emulator = load(Quirks::VIP, &[0xD005, 0x6001]);
+    emulator.step();
+    assert_eq!(emulator.step(), StepEvent::WaitingForVBlank);
+    assert_eq!(emulator.registers()[0], 0);
+    // Waiting for vblank doesn't count as executing
+    assert_eq!(emulator.instruction_count(), 1);
+}
+
+#[test]
+fn skip_if_key_pressed() {
+    let mut emulator = load(Quirks::default(), &[0x6007, 0xE09E, 0x6101, 0x6202]);
+    emulator.set_key(7, true);
+    emulator.run_cycles(4);
+    assert_eq!(emulator.registers()[1], 0);
+    assert_eq!(emulator.registers()[2], 2);
+
+    let emulator = run(&[0x6007, 0xE09E, 0x6101]);
+    assert_eq!(emulator.registers()[1], 1);
+}
+
+#[test]
+fn skip_if_key_not_pressed() {
+    let emulator = run(&[0x6007, 0xE0A1, 0x6101, 0x6202]);
+    assert_eq!(emulator.registers()[1], 0);
+    assert_eq!(emulator.registers()[2], 2);
+
+    let mut emulator = load(Quirks::default(), &[0x6007, 0xE0A1, 0x6101]);
+    emulator.set_key(7, true);
+    emulator.run_cycles(3);
+    assert_eq!(emulator.registers()[1], 1);
+}
+
+#[test]
+fn skip_if_key_uses_the_low_nibble() {
+    // V0 = 0x1F checks key F
+    let mut emulator = load(Quirks::default(), &[0x601F, 0xE09E, 0x6101, 0xE0A1, 0x6201]);
+    emulator.set_key(0xF, true);
+    emulator.run_cycles(5);
+    assert_eq!(emulator.halt_reason(), None);
+    assert_eq!(emulator.registers()[1], 0);
+    assert_eq!(emulator.registers()[2], 1);
+    assert_eq!(emulator.stats().key_queries[0xF], 2);
+}
+
+#[test]
+fn key_usage_is_counted() {
+    // Check key 7 in a loop, EXA1 is skipped while it's down
+    let mut emulator = load(Quirks::default(), &[0x6007, 0xE09E, 0xE0A1, 0x1202]);
+    emulator.set_key(7, true);
+    emulator.set_key(7, true); // Held, not pressed again
+    emulator.run_cycles(4);
+    emulator.set_key(7, false);
+    emulator.set_key(7, true);
+    let stats = emulator.stats();
+    assert_eq!(stats.key_presses[7], 2);
+    assert_eq!(stats.key_queries[7], 2);
+    assert_eq!(stats.key_queries.iter().sum::<u64>(), 2);
+}
+
+#[test]
+fn wait_for_key() {
+    let mut emulator = load(Quirks::default(), &[0xF30A]);
+    assert_eq!(emulator.step(), StepEvent::WaitingForKey);
+    assert_eq!(emulator.pc(), 0x200);
+
+    emulator.set_key(0xB, true);
+    emulator.step();
+    assert_eq!(emulator.registers()[3], 0xB);
+    assert_eq!(emulator.pc(), 0x202);
+}
+
+#[test]
+fn timers() {
+    let emulator = run(&[0x6030, 0xF015, 0xF107]);
+    assert_eq!(emulator.delay_timer(), 0x30);
+    assert_eq!(emulator.registers()[1], 0x30);
+
+    let emulator = run(&[0x6030, 0xF018]);
+    assert_eq!(emulator.sound_timer(), 0x30);
+}
+
+#[test]
+fn add_index() {
+    let emulator = run(&[0xA100, 0x6020, 0xF01E]);
+    assert_eq!(emulator.index(), 0x120);
+}
+
+#[test]
+fn load_font() {
+    let emulator = run(&[0x600A, 0xF029]);
+    assert_eq!(emulator.index(), 0xA * 5);
+}
+
+#[test]
+fn store_bcd() {
+    let emulator = run(&[0xA300, 0x60FE, 0xF033]);
+    assert_eq!(&emulator.memory()[0x300..0x303], &[2, 5, 4]);
+}
+
+#[test]
+fn store_registers() {
+    let program = [0xA300, 0x6011, 0x6122, 0x6233, 0xF155];
+    let emulator = run(&program);
+    assert_eq!(&emulator.memory()[0x300..0x303], &[0x11, 0x22, 0]);
+    assert_eq!(emulator.index(), 0x302);
+
+    let emulator = run_with(Quirks::SCHIP, &program);
+    assert_eq!(&emulator.memory()[0x300..0x303], &[0x11, 0x22, 0]);
+    assert_eq!(emulator.index(), 0x300);
+}
+
+#[test]
+fn load_registers() {
+    // I = font digit 0: F0 90 90
+    let program = [0xF029, 0x6255, 0xF165];
+    let emulator = run(&program);
+    assert_eq!(&emulator.registers()[0..3], &[0xF0, 0x90, 0x55]);
+    assert_eq!(emulator.index(), 2);
+
+    let emulator = run_with(Quirks::SCHIP, &program);
+    assert_eq!(&emulator.registers()[0..3], &[0xF0, 0x90, 0x55]);
+    assert_eq!(emulator.index(), 0);
+}
+
+#[test]
+fn unknown_opcode_is_skipped() {
+    let mut emulator = load(Quirks::default(), &[0x5001, 0x6001]);
+    assert_eq!(
+        emulator.step(),
+        StepEvent::IllegalOpcode {
+            pc: 0x200,
+            opcode: 0x5001
+        }
+    );
+    emulator.step();
+    assert_eq!(emulator.registers()[0], 1);
+    assert_eq!(emulator.stats().illegal_opcodes, 1);
+    assert_eq!(emulator.stats().first_illegal_opcode, Some((0x200, 0x5001)));
+    assert_eq!(emulator.stats().faults, 1);
+}
+
+#[test]
+fn halts_at_end_of_memory() {
+    let mut emulator = load(Quirks::default(), &[0x1FFE]);
+    emulator.step();
+    assert_eq!(emulator.pc(), 0xFFE);
+    emulator.run_cycles(0x10000);
+    assert!(matches!(
+        emulator.halt_reason(),
+        Some(HaltReason::PcOutOfBounds(_))
+    ));
+    // Steps while halted don't count again
+    assert_eq!(emulator.stats().faults, 1);
+}
+
+fn fault_of(program: &[u16]) -> Option<HaltReason> {
+    let mut emulator = load(Quirks::default(), program);
+    emulator.set_halt_on_fault(true);
+    emulator.run_cycles(100);
+    emulator.halt_reason()
+}
+
+#[test]
+fn faults_halt_when_asked_to() {
+    assert_eq!(
+        fault_of(&[0x6001, 0x5121]),
+        Some(HaltReason::Fault {
+            pc: 0x202,
+            opcode: 0x5121,
+            fault: Fault::IllegalOpcode
+        })
+    );
+    assert_eq!(
+        fault_of(&[0x6001, 0x00EE]),
+        Some(HaltReason::Fault {
+            pc: 0x202,
+            opcode: 0x00EE,
+            fault: Fault::StackUnderflow
+        })
+    );
+    // Calls itself until the stack is full
+    assert_eq!(
+        fault_of(&[0x2200]),
+        Some(HaltReason::Fault {
+            pc: 0x200,
+            opcode: 0x2200,
+            fault: Fault::StackOverflow
+        })
+    );
+    // I = FFF, store V0 and V1: the second byte is past 4KB
+    assert_eq!(
+        fault_of(&[0xAFFF, 0xF155]),
+        Some(HaltReason::Fault {
+            pc: 0x202,
+            opcode: 0xF155,
+            fault: Fault::MemoryOutOfBounds(0x1000)
+        })
+    );
+}
+
+#[test]
+fn faults_are_counted_once() {
+    let mut emulator = load(Quirks::default(), &[0x2200]);
+    emulator.set_halt_on_fault(true);
+    emulator.run_cycles(100);
+    assert_eq!(emulator.stack().len(), STACK_DEPTH + 1);
+    assert_eq!(emulator.stats().faults, 1);
+
+    // The call already happened, continuing runs into the next one
+    emulator.ignore_fault();
+    emulator.run_cycles(100);
+    assert_eq!(emulator.stack().len(), STACK_DEPTH + 2);
+    assert_eq!(emulator.stats().faults, 2);
+}
+
+#[test]
+fn faults_run_on_by_default() {
+    let mut emulator = load(Quirks::default(), &[0x00EE, 0xAFFF, 0xF155, 0x2206]);
+    emulator.run_cycles(100);
+    assert_eq!(emulator.halt_reason(), None);
+    assert!(emulator.stack().len() > STACK_DEPTH);
+    assert_eq!(emulator.stats().faults, 0);
+}
+
+#[test]
+fn history_keeps_the_last_instructions() {
+    let mut emulator = load(Quirks::default(), &[0x6001, 0x7001, 0x1202]);
+    assert_eq!(emulator.history().count(), 0);
+
+    emulator.set_history_len(3);
+    emulator.run_cycles(9);
+    let history: Vec<_> = emulator.history().collect();
+    assert_eq!(history, [(0x204, 0x1202), (0x202, 0x7001), (0x204, 0x1202)]);
+
+    // Shrinking drops the oldest, a reset forgets them but keeps the length
+    emulator.set_history_len(1);
+    assert_eq!(emulator.history().collect::<Vec<_>>(), [(0x204, 0x1202)]);
+    emulator.reset();
+    assert_eq!(emulator.history().count(), 0);
+    emulator.step();
+    assert_eq!(emulator.history().collect::<Vec<_>>(), [(0x200, 0x6001)]);
+}
+
+#[test]
+fn set_pc_recovers_from_halt() {
+    let mut emulator = load(Quirks::default(), &[0x6001, 0x1FFE]);
+    emulator.run_cycles(0x10000);
+    assert!(emulator.is_halted());
+    emulator.set_pc(0x200);
+    assert!(!emulator.is_halted());
+    emulator.step();
+    assert_eq!(emulator.pc(), 0x202);
+}
+
+#[test]
+fn memory_accesses_wrap_around() {
+    // I = 0xFFF; store V0..V1, the second byte lands at 0x000
+    let emulator = run(&[0xAFFF, 0x60AB, 0x61CD, 0xF155]);
+    assert_eq!(emulator.memory()[0xFFF], 0xAB);
+    assert_eq!(emulator.memory()[0x000], 0xCD);
+    // I itself isn't masked
+    assert_eq!(emulator.index(), 0x1001);
+}
+
+#[test]
+fn xo_chip_memory_size() {
+    let mut emulator = Emulator::builder()
+        .memory_size(chip8_core::XO_CHIP_MEMORY_SIZE)
+        .build();
+    // I = 0xFFF + 0xFF goes past standard memory
+    emulator
+        .load_rom_bytes(&[0xAF, 0xFF, 0x60, 0xFF, 0xF0, 0x1E, 0xF0, 0x55])
+        .unwrap();
+    emulator.run_cycles(4);
+    assert_eq!(emulator.memory().len(), 0x10000);
+    assert_eq!(emulator.memory()[0x10FE], 0xFF);
+}
+
+#[test]
+fn memory_size_changes_where_accesses_wrap() {
+    // I = 0xFFF; store V0..V1
+    let mut emulator = load(Quirks::default(), &[0xAFFF, 0x60AB, 0x61CD, 0xF155]);
+    emulator
+        .set_memory_size(chip8_core::XO_CHIP_MEMORY_SIZE)
+        .unwrap();
+    emulator.run_cycles(4);
+    assert_eq!(emulator.memory()[0x1000], 0xCD);
+
+    // Back to 4KB the ROM starts over and wraps again
+    emulator.set_memory_size(chip8_core::MEMORY_SIZE).unwrap();
+    assert_eq!(emulator.pc(), 0x200);
+    emulator.run_cycles(4);
+    assert_eq!(emulator.memory()[0x000], 0xCD);
+
+    // A ROM that only fits into 64KB keeps it
+    let mut emulator = Emulator::builder()
+        .memory_size(chip8_core::XO_CHIP_MEMORY_SIZE)
+        .build();
+    emulator.load_rom_bytes(&[0; 0x2000]).unwrap();
+    assert!(emulator.set_memory_size(chip8_core::MEMORY_SIZE).is_err());
+    assert_eq!(emulator.memory().len(), 0x10000);
+}
+
+#[test]
+fn encode_inverts_decode() {
+    for opcode in 0..=0xFFFF {
+        assert_eq!(
+            Instruction::decode(opcode).encode(),
+            opcode,
+            "{:04X}",
+            opcode
+        );
+    }
+}
+
+#[test]
+fn extension_opcodes() {
+    let super_chip = [
+        0x00C4, 0x00FB, 0x00FC, 0x00FD, 0x00FE, 0x00FF, 0xD120, 0xF330, 0xF575, 0xF785,
+    ];
+    let xo_chip = [0x00D2, 0x5122, 0x5343, 0xF000, 0xF201, 0xF002, 0xF43A];
+    for &opcode in &super_chip {
+        assert_eq!(
+            Extension::of(opcode),
+            Some(Extension::SuperChip),
+            "{:04X}",
+            opcode
+        );
+    }
+    for &opcode in &xo_chip {
+        assert_eq!(
+            Extension::of(opcode),
+            Some(Extension::XoChip),
+            "{:04X}",
+            opcode
+        );
+    }
+    for &opcode in &[
+        0x00E0, 0x00EE, 0x5120, 0xD125, 0xF155, 0xF165, 0xF129, 0x0200,
+    ] {
+        assert_eq!(Extension::of(opcode), None, "{:04X}", opcode);
+    }
+}
+
+#[test]
+fn detect_extension() {
+    // Plain CHIP-8: clear, draw, jump to itself
+    assert_eq!(Extension::detect(&rom(&[0x00E0, 0xD015, 0x1204])), None);
+    // Hires right at the start
+    assert_eq!(
+        Extension::detect(&rom(&[0x00E0, 0x00FF, 0x1204])),
+        Some(Extension::SuperChip)
+    );
+    // Big sprites and flag registers
+    assert_eq!(
+        Extension::detect(&rom(&[0xD010, 0xF175, 0x1204])),
+        Some(Extension::SuperChip)
+    );
+    // Long I loads and register ranges win over the SUPER-CHIP opcodes
+    assert_eq!(
+        Extension::detect(&rom(&[0x00FF, 0xF000, 0x0300, 0x5122, 0x1208])),
+        Some(Extension::XoChip)
+    );
+    // A single scroll could be data the flow graph took for code
+    assert_eq!(Extension::detect(&rom(&[0x6001, 0x00D1, 0x1204])), None);
+    // Opcodes after the program loops forever are data
+    assert_eq!(
+        Extension::detect(&rom(&[0x1200, 0x00FB, 0xF175, 0xF000, 0x5122])),
+        None
+    );
+}
@@ -0,0 +1,181 @@
+//! Replays scripted input into bundled ROMs and checks the machine ends up in
+//! the recorded state, so a fixed bug can ship with a replay that locks it in.
+//!
+//! A replay in `tests/replays/*.replay` is a list of lines:
+//!
+//! ```text
+//! # comment
+//! rom games/Brix [Andreas Gustafsson, 1990].ch8   (relative to roms/)
+//! rom_hash 8a1f...                                (FNV-1a of the ROM file)
+//! seed 1
+//! quirks vip                                      (preset name)
+//! cycles 5000
+//! key 2000 6 down                                 (before instruction 2000, press key 6)
+//! key 2600 6 up
+//! state_hash 3c9e...                              (`Emulator::state_hash` at the end)
+//! ```
+//!
+//! Run with `UPDATE_REPLAYS=1` to fill in `rom_hash` and `state_hash` after an intended change.
+
+use chip8_core::{Emulator, Quirks};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |hash, &b| (hash ^ b as u64).wrapping_mul(FNV_PRIME))
+}
+
+struct KeyEvent {
+    cycle: usize,
+    key: u8,
+    down: bool,
+}
+
+struct Replay {
+    rom: String,
+    rom_hash: Option<u64>,
+    seed: u64,
+    quirks: Quirks,
+    cycles: usize,
+    keys: Vec<KeyEvent>,
+    state_hash: Option<u64>,
+}
+
+fn parse(text: &str) -> Result<Replay, String> {
+    let mut replay = Replay {
+        rom: String::new(),
+        rom_hash: None,
+        seed: 0,
+        quirks: Quirks::default(),
+        cycles: 0,
+        keys: Vec::new(),
+        state_hash: None,
+    };
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, value) = line.split_once(' ').unwrap_or((line, ""));
+        let value = value.trim();
+        let hex =
+            |value: &str| u64::from_str_radix(value, 16).map_err(|e| format!("{}: {}", line, e));
+        let number = |value: &str| value.parse::<u64>().map_err(|e| format!("{}: {}", line, e));
+        match keyword {
+            "rom" => replay.rom = value.to_string(),
+            "rom_hash" => replay.rom_hash = Some(hex(value)?),
+            "seed" => replay.seed = number(value)?,
+            "quirks" => {
+                replay.quirks =
+                    Quirks::preset(value).ok_or_else(|| format!("Unknown quirks '{}'", value))?
+            }
+            "cycles" => replay.cycles = number(value)? as usize,
+            "key" => {
+                let parts: Vec<&str> = value.split_whitespace().collect();
+                let (cycle, key, state) = match parts[..] {
+                    [cycle, key, state] => (cycle, key, state),
+                    _ => return Err(format!("Expected 'key <cycle> <key> down|up': {}", line)),
+                };
+                replay.keys.push(KeyEvent {
+                    cycle: number(cycle)? as usize,
+                    key: u8::from_str_radix(key, 16).map_err(|e| format!("{}: {}", line, e))?,
+                    down: match state {
+                        "down" => true,
+                        "up" => false,
+                        _ => return Err(format!("Expected down or up: {}", line)),
+                    },
+                });
+            }
+            "state_hash" => replay.state_hash = Some(hex(value)?),
+            _ => return Err(format!("Unknown line: {}", line)),
+        }
+    }
+    Ok(replay)
+}
+
+fn run(replay: &Replay, rom: &[u8]) -> Emulator {
+    let mut emulator = Emulator::builder()
+        .quirks(replay.quirks)
+        .seed(replay.seed)
+        .build();
+    emulator.load_rom_bytes(rom).unwrap();
+    let mut keys = replay.keys.iter().peekable();
+    for cycle in 0..replay.cycles {
+        while let Some(event) = keys.next_if(|event| event.cycle <= cycle) {
+            emulator.set_key(event.key, event.down);
+        }
+        emulator.step();
+    }
+    emulator
+}
+
+/// Replaces the value of `keyword` lines, appending one if missing
+fn set_value(text: &str, keyword: &str, value: &str) -> String {
+    let line = format!("{} {}", keyword, value);
+    let mut found = false;
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|l| {
+            if l.trim().split(' ').next() == Some(keyword) {
+                found = true;
+                line.clone()
+            } else {
+                l.to_string()
+            }
+        })
+        .collect();
+    if !found {
+        lines.push(line);
+    }
+    lines.join("\n") + "\n"
+}
+
+fn replay_files() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replays");
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "replay"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn replays() {
+    let roms = Path::new(env!("CARGO_MANIFEST_DIR")).join("../roms");
+    let update = std::env::var_os("UPDATE_REPLAYS").is_some();
+    let files = replay_files();
+    assert!(!files.is_empty());
+    for path in files {
+        let text = fs::read_to_string(&path).unwrap();
+        let replay = parse(&text).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let rom = fs::read(roms.join(&replay.rom)).unwrap();
+        let rom_hash = fnv1a(FNV_OFFSET, &rom);
+        let state = run(&replay, &rom).state_hash();
+
+        if update {
+            let text = set_value(&text, "rom_hash", &format!("{:016x}", rom_hash));
+            let text = set_value(&text, "state_hash", &format!("{:016x}", state));
+            fs::write(&path, text).unwrap();
+            continue;
+        }
+        assert_eq!(
+            replay.rom_hash,
+            Some(rom_hash),
+            "{}: ROM '{}' has changed",
+            path.display(),
+            replay.rom
+        );
+        assert_eq!(
+            replay.state_hash,
+            Some(state),
+            "{}: final state differs",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Encoded save states, and corrupted ones being turned away before they reach an emulator.
+
+use chip8_core::{Emulator, SaveState, StateError, STACK_DEPTH};
+
+mod common;
+use common::load;
+
+fn state() -> SaveState {
+    // Call a subroutine that loops, so the stack isn't empty
+    let mut emulator = load(&[0x2204, 0x1200, 0x6005, 0x1206]);
+    emulator.run_cycles(3);
+    emulator.save_state()
+}
+
+fn decode(state: &SaveState) -> Result<SaveState, StateError> {
+    SaveState::from_bytes(&state.to_bytes())
+}
+
+#[test]
+fn state_round_trips() {
+    let state = state();
+    assert_eq!(state.stack.len(), 1);
+    assert_eq!(decode(&state), Ok(state.clone()));
+
+    let mut emulator = Emulator::new();
+    emulator.load_state(&decode(&state).unwrap());
+    assert_eq!(emulator.registers()[0], 5);
+}
+
+#[test]
+fn rom_larger_than_memory_is_rejected() {
+    let mut state = state();
+    state.rom = vec![0; state.memory.len() - 0x200];
+    assert!(decode(&state).is_ok());
+    state.rom.push(0);
+    assert_eq!(
+        decode(&state),
+        Err(StateError::RomTooLarge(state.memory.len() - 0x200 + 1))
+    );
+}
+
+#[test]
+fn clock_speed_has_to_be_positive() {
+    for &clock_hz in &[0.0, -500.0, f32::NAN, f32::INFINITY] {
+        let mut state = state();
+        state.clock_hz = clock_hz;
+        assert_eq!(
+            decode(&state),
+            Err(StateError::BadClockSpeed),
+            "{}",
+            clock_hz
+        );
+    }
+}
+
+#[test]
+fn stack_deeper_than_the_emulator_is_rejected() {
+    let mut state = state();
+    state.stack = vec![0x200; STACK_DEPTH];
+    assert!(decode(&state).is_ok());
+    state.stack.push(0x200);
+    assert_eq!(
+        decode(&state),
+        Err(StateError::StackTooDeep(STACK_DEPTH + 1))
+    );
+}
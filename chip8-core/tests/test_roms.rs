@@ -0,0 +1,66 @@
+//! Runs community test ROMs placed in `roms/tests/` (they aren't distributed
+//! with the emulator), missing ROMs are skipped.
+
+use chip8_core::{Quirks, TestRom, TEST_ROMS};
+use std::fs;
+use std::path::Path;
+
+const OK: &[u8] = &[0x11, 0x22];
+const FAIL: &[u8] = &[0x33, 0x44];
+
+fn synthetic(cycles: usize) -> TestRom {
+    TestRom {
+        name: "synthetic",
+        file: "synthetic.ch8",
+        quirks: Quirks::default(),
+        cycles,
+        pass_glyph: OK,
+        fail_glyph: FAIL,
+    }
+}
+
+// Draws the glyphs at 0x20C/0x20E/0x210 and parks in a jump-to-self loop
+const ROM: [u8; 18] = [
+    0xA2, 0x0C, // I = OK
+    0xD0, 0x02, // draw
+    0xA2, 0x0E, // I = FAIL
+    0xD0, 0x02, // draw
+    0x12, 0x08, // jump to self
+    0x00, 0x00, //
+    0x11, 0x22, // OK
+    0x33, 0x44, // FAIL
+    0x00, 0x00,
+];
+
+#[test]
+fn counts_result_glyphs() {
+    let report = synthetic(100).run(&ROM);
+    assert_eq!(report.passed, 1);
+    assert_eq!(report.failed, 1);
+    assert!(report.finished);
+    assert!(!report.is_success());
+}
+
+#[test]
+fn unfinished_run_is_not_a_success() {
+    let report = synthetic(2).run(&ROM);
+    assert_eq!(report.passed, 1);
+    assert!(!report.finished);
+    assert!(!report.is_success());
+}
+
+#[test]
+fn community_test_roms() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../roms/tests");
+    for test in TEST_ROMS.iter() {
+        let rom = match fs::read(dir.join(test.file)) {
+            Ok(rom) => rom,
+            Err(_) => {
+                eprintln!("{} not found in {}, skipped", test.file, dir.display());
+                continue;
+            }
+        };
+        let report = test.run(&rom);
+        assert!(report.is_success(), "{}: {:?}", test.name, report);
+    }
+}
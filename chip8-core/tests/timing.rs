@@ -0,0 +1,44 @@
+//! Runs programs with COSMAC VIP instruction times and checks the pace they get.
+
+use chip8_core::Emulator;
+
+mod common;
+use common::load_with;
+
+fn load_vip(program: &[u16]) -> Emulator {
+    load_with(
+        Emulator::builder().vip_timing(true).clock_hz(1_000_000.0),
+        program,
+    )
+}
+
+#[test]
+fn instructions_take_their_vip_time() {
+    // Jump to self, 105us on the VIP whatever the clock speed is set to
+    let mut emulator = load_vip(&[0x1200]);
+    emulator.run_for(1.0);
+    let count = emulator.instruction_count();
+    assert!((9_500..=9_550).contains(&count), "{}", count);
+
+    // Setting a register is faster
+    let mut emulator = load_vip(&[0x6001, 0x6002, 0x1200]);
+    emulator.run_for(1.0);
+    assert!(emulator.instruction_count() > count * 3 / 2);
+}
+
+#[test]
+fn draws_wait_for_the_display() {
+    // Draw the sprite at I = 0, loop
+    let mut emulator = load_vip(&[0xD005, 0x1200]);
+    emulator.run_for(1.0);
+    let draws = emulator.stats().draws;
+    assert!((59..=61).contains(&draws), "{}", draws);
+}
+
+#[test]
+fn uniform_timing_follows_the_clock() {
+    let mut emulator = load_vip(&[0x1200]);
+    emulator.set_vip_timing(false);
+    emulator.run_for(0.01);
+    assert_eq!(emulator.instruction_count(), 10_000);
+}
@@ -0,0 +1,10 @@
+// Regenerates include/chip8.h from the exported functions
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap();
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("Unable to generate C header")
+        .write_to_file(format!("{}/include/chip8.h", crate_dir));
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
@@ -0,0 +1,45 @@
+/* Runs a ROM for one second of emulated time and prints the screen as text.
+ *
+ *   cargo build --release -p chip8-ffi
+ *   cc chip8-ffi/examples/run.c -Ichip8-ffi/include target/release/libchip8.a -lpthread -ldl -lm -o run
+ *   ./run "roms/programs/IBM Logo.ch8"
+ */
+#include <stdio.h>
+
+#include "chip8.h"
+
+int main(int argc, char **argv) {
+    if (argc < 2) {
+        fprintf(stderr, "usage: %s ROM\n", argv[0]);
+        return 1;
+    }
+
+    FILE *file = fopen(argv[1], "rb");
+    if (!file) {
+        perror(argv[1]);
+        return 1;
+    }
+    static uint8_t rom[4096];
+    size_t len = fread(rom, 1, sizeof(rom), file);
+    fclose(file);
+
+    Chip8Emulator *emulator = chip8_create();
+    if (chip8_load(emulator, rom, len) != 0) {
+        fprintf(stderr, "can't load %s\n", argv[1]);
+        chip8_destroy(emulator);
+        return 1;
+    }
+    chip8_run_for(emulator, 1.0f);
+
+    uint8_t screen[CHIP8_SCREEN_WIDTH * CHIP8_SCREEN_HEIGHT];
+    chip8_get_framebuffer(emulator, screen, sizeof(screen));
+    for (int y = 0; y < CHIP8_SCREEN_HEIGHT; y++) {
+        for (int x = 0; x < CHIP8_SCREEN_WIDTH; x++) {
+            putchar(screen[y * CHIP8_SCREEN_WIDTH + x] ? '#' : ' ');
+        }
+        putchar('\n');
+    }
+
+    chip8_destroy(emulator);
+    return 0;
+}
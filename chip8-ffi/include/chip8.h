@@ -0,0 +1,120 @@
+#ifndef CHIP8_H
+#define CHIP8_H
+
+/* Generated by cbindgen from chip8-ffi/src/lib.rs, don't edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define CHIP8_SCREEN_WIDTH 64
+
+#define CHIP8_SCREEN_HEIGHT 32
+
+/**
+ * What happened during `chip8_step`
+ */
+typedef enum Chip8StepEvent {
+  CHIP8_STEP_EVENT_NORMAL,
+  /**
+   * Sprite was drawn without erasing any pixels
+   */
+  CHIP8_STEP_EVENT_DREW_SPRITE,
+  /**
+   * Sprite was drawn and erased some pixels (VF = 1)
+   */
+  CHIP8_STEP_EVENT_COLLISION,
+  CHIP8_STEP_EVENT_WAITING_FOR_KEY,
+  CHIP8_STEP_EVENT_WAITING_FOR_VBLANK,
+  CHIP8_STEP_EVENT_SOUND_STARTED,
+  CHIP8_STEP_EVENT_SOUND_STOPPED,
+  /**
+   * Nothing was executed: no ROM loaded, breakpoint or PC out of memory
+   */
+  CHIP8_STEP_EVENT_HALTED,
+  /**
+   * Unknown opcode was skipped
+   */
+  CHIP8_STEP_EVENT_ILLEGAL_OPCODE,
+} Chip8StepEvent;
+
+/**
+ * Opaque emulator handle
+ */
+typedef struct Chip8Emulator Chip8Emulator;
+
+/**
+ * Creates a halted emulator, free it with `chip8_destroy`.
+ */
+struct Chip8Emulator *chip8_create(void);
+
+/**
+ * # Safety
+ * `emulator` must come from `chip8_create` and not be used afterwards.
+ */
+void chip8_destroy(struct Chip8Emulator *emulator);
+
+/**
+ * Resets the machine and loads `len` bytes of ROM. Returns 0 on success,
+ * -1 if the ROM doesn't fit in memory or arguments are NULL.
+ *
+ * # Safety
+ * `emulator` must be NULL or valid, `rom` must point to `len` readable bytes.
+ */
+int chip8_load(struct Chip8Emulator *emulator, const uint8_t *rom, size_t len);
+
+/**
+ * Executes a single instruction.
+ *
+ * # Safety
+ * `emulator` must be NULL or valid.
+ */
+enum Chip8StepEvent chip8_step(struct Chip8Emulator *emulator);
+
+/**
+ * Runs as many instructions as fit into `seconds` at the current clock speed.
+ *
+ * # Safety
+ * `emulator` must be NULL or valid.
+ */
+void chip8_run_for(struct Chip8Emulator *emulator, float seconds);
+
+/**
+ * Presses (`down` = true) or releases a key of the hex keypad (0x0..=0xF),
+ * other key values are ignored.
+ *
+ * # Safety
+ * `emulator` must be NULL or valid.
+ */
+void chip8_set_key(struct Chip8Emulator *emulator, uint8_t key, bool down);
+
+/**
+ * Copies the screen into `out` row by row, one byte per pixel (1 for lit pixels).
+ * Returns the number of bytes the whole screen needs
+ * (`CHIP8_SCREEN_WIDTH * CHIP8_SCREEN_HEIGHT`), nothing is written if `len` is smaller.
+ *
+ * # Safety
+ * `emulator` must be NULL or valid, `out` must point to `len` writable bytes.
+ */
+size_t chip8_get_framebuffer(const struct Chip8Emulator *emulator, uint8_t *out, size_t len);
+
+/**
+ * True while the sound timer is running.
+ *
+ * # Safety
+ * `emulator` must be NULL or valid.
+ */
+bool chip8_is_beeping(const struct Chip8Emulator *emulator);
+
+/**
+ * Hash of registers, memory and screen, equal for equal machine states on
+ * any platform. Returns 0 for NULL.
+ *
+ * # Safety
+ * `emulator` must be NULL or valid.
+ */
+uint64_t chip8_state_hash(const struct Chip8Emulator *emulator);
+
+#endif  /* CHIP8_H */
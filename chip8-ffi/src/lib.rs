@@ -0,0 +1,192 @@
+//! C ABI for the emulator core, so it can be embedded into C/C++ frontends
+//! and used from other languages with a C FFI.
+//!
+//! `include/chip8.h` is generated by the build script. All functions accept
+//! NULL emulator pointers and do nothing (or return an error) for them.
+
+use chip8_core as chip8;
+use std::os::raw::c_int;
+use std::ptr;
+use std::slice;
+
+pub const CHIP8_SCREEN_WIDTH: usize = 64;
+pub const CHIP8_SCREEN_HEIGHT: usize = 32;
+
+// cbindgen needs literal values above, make sure they match the core
+const _: () = assert!(
+    CHIP8_SCREEN_WIDTH == chip8::SCREEN_SIZE.0 && CHIP8_SCREEN_HEIGHT == chip8::SCREEN_SIZE.1
+);
+
+/// Opaque emulator handle
+pub struct Chip8Emulator {
+    emulator: chip8::Emulator,
+}
+
+/// What happened during `chip8_step`
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum Chip8StepEvent {
+    Normal,
+    /// Sprite was drawn without erasing any pixels
+    DrewSprite,
+    /// Sprite was drawn and erased some pixels (VF = 1)
+    Collision,
+    WaitingForKey,
+    WaitingForVblank,
+    SoundStarted,
+    SoundStopped,
+    /// Nothing was executed: no ROM loaded, breakpoint or PC out of memory
+    Halted,
+    /// Unknown opcode was skipped
+    IllegalOpcode,
+}
+
+impl From<chip8::StepEvent> for Chip8StepEvent {
+    fn from(event: chip8::StepEvent) -> Self {
+        use chip8::StepEvent::*;
+        match event {
+            Normal => Chip8StepEvent::Normal,
+            DrewSprite { collision: false } => Chip8StepEvent::DrewSprite,
+            DrewSprite { collision: true } => Chip8StepEvent::Collision,
+            WaitingForKey => Chip8StepEvent::WaitingForKey,
+            WaitingForVBlank => Chip8StepEvent::WaitingForVblank,
+            Beep(true) => Chip8StepEvent::SoundStarted,
+            Beep(false) => Chip8StepEvent::SoundStopped,
+            Halted(_) => Chip8StepEvent::Halted,
+            IllegalOpcode { .. } => Chip8StepEvent::IllegalOpcode,
+        }
+    }
+}
+
+/// Creates a halted emulator, free it with `chip8_destroy`.
+#[no_mangle]
+pub extern "C" fn chip8_create() -> *mut Chip8Emulator {
+    Box::into_raw(Box::new(Chip8Emulator {
+        emulator: chip8::Emulator::new(),
+    }))
+}
+
+/// # Safety
+/// `emulator` must come from `chip8_create` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_destroy(emulator: *mut Chip8Emulator) {
+    if !emulator.is_null() {
+        drop(Box::from_raw(emulator));
+    }
+}
+
+/// Resets the machine and loads `len` bytes of ROM. Returns 0 on success,
+/// -1 if the ROM doesn't fit in memory or arguments are NULL.
+///
+/// # Safety
+/// `emulator` must be NULL or valid, `rom` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load(
+    emulator: *mut Chip8Emulator,
+    rom: *const u8,
+    len: usize,
+) -> c_int {
+    let emulator = match emulator.as_mut() {
+        Some(emulator) => emulator,
+        None => return -1,
+    };
+    if rom.is_null() {
+        return -1;
+    }
+    match emulator
+        .emulator
+        .load_rom_bytes(slice::from_raw_parts(rom, len))
+    {
+        Ok(()) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Executes a single instruction.
+///
+/// # Safety
+/// `emulator` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_step(emulator: *mut Chip8Emulator) -> Chip8StepEvent {
+    match emulator.as_mut() {
+        Some(emulator) => emulator.emulator.step().into(),
+        None => Chip8StepEvent::Halted,
+    }
+}
+
+/// Runs as many instructions as fit into `seconds` at the current clock speed.
+///
+/// # Safety
+/// `emulator` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_run_for(emulator: *mut Chip8Emulator, seconds: f32) {
+    if let Some(emulator) = emulator.as_mut() {
+        emulator.emulator.run_for(seconds);
+    }
+}
+
+/// Presses (`down` = true) or releases a key of the hex keypad (0x0..=0xF),
+/// other key values are ignored.
+///
+/// # Safety
+/// `emulator` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_set_key(emulator: *mut Chip8Emulator, key: u8, down: bool) {
+    if let Some(emulator) = emulator.as_mut() {
+        if (key as usize) < chip8::Keypad::KEY_COUNT {
+            emulator.emulator.set_key(key, down);
+        }
+    }
+}
+
+/// Copies the screen into `out` row by row, one byte per pixel (1 for lit pixels).
+/// Returns the number of bytes the whole screen needs
+/// (`CHIP8_SCREEN_WIDTH * CHIP8_SCREEN_HEIGHT`), nothing is written if `len` is smaller.
+///
+/// # Safety
+/// `emulator` must be NULL or valid, `out` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_framebuffer(
+    emulator: *const Chip8Emulator,
+    out: *mut u8,
+    len: usize,
+) -> usize {
+    let size = CHIP8_SCREEN_WIDTH * CHIP8_SCREEN_HEIGHT;
+    let emulator = match emulator.as_ref() {
+        Some(emulator) => emulator,
+        None => return size,
+    };
+    if out.is_null() || len < size {
+        return size;
+    }
+    let screen = emulator.emulator.screen();
+    for (i, on) in screen.iter_rows().flatten().enumerate() {
+        ptr::write(out.add(i), on as u8);
+    }
+    size
+}
+
+/// True while the sound timer is running.
+///
+/// # Safety
+/// `emulator` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_is_beeping(emulator: *const Chip8Emulator) -> bool {
+    match emulator.as_ref() {
+        Some(emulator) => emulator.emulator.sound_timer() > 0,
+        None => false,
+    }
+}
+
+/// Hash of registers, memory and screen, equal for equal machine states on
+/// any platform. Returns 0 for NULL.
+///
+/// # Safety
+/// `emulator` must be NULL or valid.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_state_hash(emulator: *const Chip8Emulator) -> u64 {
+    match emulator.as_ref() {
+        Some(emulator) => emulator.emulator.state_hash(),
+        None => 0,
+    }
+}
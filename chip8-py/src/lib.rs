@@ -0,0 +1,221 @@
+//! Python bindings for the emulator core, meant for scripts and RL agents:
+//!
+//! ```python
+//! import chip8
+//!
+//! emulator = chip8.Emulator(quirks="vip", seed=1)
+//! emulator.load_rom(open("Pong.ch8", "rb").read())
+//! emulator.set_key(0x1, True)
+//! emulator.run_cycles(1000)
+//! print(emulator.framebuffer.shape)  # (32, 64), 1 for lit pixels
+//! ```
+//!
+//! Build with `maturin develop` from the `chip8-py` directory.
+
+// Triggered by code generated by pyo3 macros for methods returning PyResult
+#![allow(clippy::useless_conversion)]
+
+use chip8_core as chip8;
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+/// chip8 emulator: CPU, memory, screen and keypad
+#[pyclass(module = "chip8", name = "Emulator")]
+struct PyEmulator {
+    emulator: chip8::Emulator,
+    keys: [bool; 16],
+}
+
+#[pymethods]
+impl PyEmulator {
+    /// `quirks` is a preset name ("default", "vip", "schip" or "octo"),
+    /// `seed` makes CXNN random numbers reproducible.
+    #[new]
+    #[pyo3(signature = (quirks = "default", clock_hz = chip8::DEFAULT_CLOCK_HZ, seed = None))]
+    fn new(quirks: &str, clock_hz: f32, seed: Option<u64>) -> PyResult<Self> {
+        let quirks = chip8::Quirks::preset(quirks)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown quirks preset '{}'", quirks)))?;
+        check_clock_hz(clock_hz)?;
+        let mut builder = chip8::Emulator::builder().quirks(quirks).clock_hz(clock_hz);
+        if let Some(seed) = seed {
+            builder = builder.seed(seed);
+        }
+        Ok(PyEmulator {
+            emulator: builder.build(),
+            keys: [false; 16],
+        })
+    }
+
+    /// Resets the machine and loads ROM from bytes. Keys stay as they were.
+    fn load_rom(&mut self, rom: &[u8]) -> PyResult<()> {
+        self.emulator
+            .load_rom_bytes(rom)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        self.sync_keys();
+        Ok(())
+    }
+
+    /// Resets the machine and loads ROM from a file.
+    fn load_rom_file(&mut self, path: std::path::PathBuf) -> PyResult<()> {
+        self.emulator.load_rom(&path).map_err(|e| match e {
+            chip8::LoadError::Io(e) => PyIOError::new_err(e.to_string()),
+            e => PyValueError::new_err(e.to_string()),
+        })?;
+        self.sync_keys();
+        Ok(())
+    }
+
+    /// Executes a single instruction and returns `(event, value)`:
+    /// ("normal", None), ("drew_sprite", collision), ("waiting_for_key", None),
+    /// ("waiting_for_vblank", None), ("beep", on), ("halted", reason) or
+    /// ("illegal_opcode", opcode).
+    fn step(&mut self, py: Python) -> (&'static str, PyObject) {
+        use chip8::StepEvent::*;
+        match self.emulator.step() {
+            Normal => ("normal", py.None()),
+            DrewSprite { collision } => ("drew_sprite", collision.into_py(py)),
+            WaitingForKey => ("waiting_for_key", py.None()),
+            WaitingForVBlank => ("waiting_for_vblank", py.None()),
+            Beep(on) => ("beep", on.into_py(py)),
+            Halted(reason) => ("halted", format!("{:?}", reason).into_py(py)),
+            IllegalOpcode { opcode, .. } => ("illegal_opcode", opcode.into_py(py)),
+        }
+    }
+
+    /// Runs exactly `cycles` instructions.
+    fn run_cycles(&mut self, cycles: usize) {
+        self.emulator.run_cycles(cycles);
+    }
+
+    /// Runs as many instructions as fit into `seconds` at the current clock speed.
+    fn run_for(&mut self, seconds: f32) {
+        self.emulator.run_for(seconds);
+    }
+
+    /// Presses or releases a key of the hex keypad (0x0..=0xF).
+    fn set_key(&mut self, key: u8, down: bool) -> PyResult<()> {
+        if key as usize >= self.keys.len() {
+            return Err(PyValueError::new_err("key must be in 0..=15"));
+        }
+        self.keys[key as usize] = down;
+        self.emulator.set_key(key, down);
+        Ok(())
+    }
+
+    /// State of the 16 keys, indexed by key value
+    #[getter]
+    fn keys(&self) -> Vec<bool> {
+        self.keys.to_vec()
+    }
+
+    #[setter]
+    fn set_keys(&mut self, keys: Vec<bool>) -> PyResult<()> {
+        if keys.len() != self.keys.len() {
+            return Err(PyValueError::new_err("expected 16 key states"));
+        }
+        self.keys.copy_from_slice(&keys);
+        self.sync_keys();
+        Ok(())
+    }
+
+    /// Screen as a (height, width) uint8 numpy array, 1 for lit pixels
+    #[getter]
+    fn framebuffer<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<u8>> {
+        let screen = self.emulator.screen();
+        let (w, h) = screen.resolution();
+        let pixels = screen.iter_rows().flatten().map(|on| on as u8).collect();
+        Array2::from_shape_vec((h, w), pixels)
+            .expect("screen has width * height pixels")
+            .into_pyarray_bound(py)
+    }
+
+    /// Data registers V0..VF
+    #[getter]
+    fn registers(&self) -> Vec<u8> {
+        self.emulator.registers().to_vec()
+    }
+
+    /// I register
+    #[getter]
+    fn index(&self) -> u16 {
+        self.emulator.index()
+    }
+
+    #[getter]
+    fn pc(&self) -> u16 {
+        self.emulator.pc()
+    }
+
+    #[getter]
+    fn delay_timer(&self) -> u8 {
+        self.emulator.delay_timer()
+    }
+
+    #[getter]
+    fn sound_timer(&self) -> u8 {
+        self.emulator.sound_timer()
+    }
+
+    #[getter]
+    fn stack(&self) -> Vec<u16> {
+        self.emulator.stack().to_vec()
+    }
+
+    /// Whole memory as bytes
+    #[getter]
+    fn memory(&self) -> Vec<u8> {
+        self.emulator.memory().to_vec()
+    }
+
+    /// Hash of registers, memory and screen, equal for equal machine states
+    fn state_hash(&self) -> u64 {
+        self.emulator.state_hash()
+    }
+
+    #[getter]
+    fn halted(&self) -> bool {
+        self.emulator.is_halted()
+    }
+
+    #[getter]
+    fn clock_hz(&self) -> f32 {
+        self.emulator.clock_hz()
+    }
+
+    #[setter]
+    fn set_clock_hz(&mut self, hz: f32) -> PyResult<()> {
+        check_clock_hz(hz)?;
+        self.emulator.set_clock_hz(hz);
+        Ok(())
+    }
+}
+
+// NaN and infinity would get past a plain `> 0` check, the emulator can't run at either
+fn check_clock_hz(hz: f32) -> PyResult<()> {
+    if !(hz > 0.0 && hz.is_finite()) {
+        return Err(PyValueError::new_err(
+            "clock_hz must be a positive finite number",
+        ));
+    }
+    Ok(())
+}
+
+impl PyEmulator {
+    // Loading a ROM resets the keypad, so held keys are pushed again
+    fn sync_keys(&mut self) {
+        for (key, &down) in self.keys.iter().enumerate() {
+            self.emulator.set_key(key as u8, down);
+        }
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "chip8")]
+fn chip8_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyEmulator>()?;
+    m.add("SCREEN_WIDTH", chip8::SCREEN_SIZE.0)?;
+    m.add("SCREEN_HEIGHT", chip8::SCREEN_SIZE.1)?;
+    Ok(())
+}
@@ -0,0 +1,24 @@
+# Run with `maturin develop && python -m pytest tests` in chip8-py
+import math
+
+import pytest
+
+import chip8
+
+
+@pytest.mark.parametrize("hz", [0.0, -500.0, math.nan, math.inf])
+def test_bad_clock_speed_is_rejected(hz):
+    with pytest.raises(ValueError):
+        chip8.Emulator(clock_hz=hz)
+
+    emulator = chip8.Emulator()
+    with pytest.raises(ValueError):
+        emulator.clock_hz = hz
+    assert emulator.clock_hz == 500.0
+
+
+def test_clock_speed_can_be_set():
+    emulator = chip8.Emulator(clock_hz=1000.0)
+    assert emulator.clock_hz == 1000.0
+    emulator.clock_hz = 250.0
+    assert emulator.clock_hz == 250.0
@@ -0,0 +1,187 @@
+//! Browser frontend: chip8 screen is drawn into a 2D canvas and keys are read
+//! from keyboard events of the page.
+//!
+//! Build with `wasm-pack build chip8-web --target web` and serve `chip8-web`
+//! directory, `www/index.html` shows how to drive it from JavaScript.
+
+use chip8_core as chip8;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::{Clamped, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData, KeyboardEvent};
+
+/// Longest time step emulated per frame, so a backgrounded tab doesn't try to catch up
+const MAX_FRAME_TIME: f32 = 0.1;
+
+/// Pixel colors used for the canvas (RGBA)
+const ON_COLOR: [u8; 4] = [0x17, 0x99, 0x00, 0xFF];
+const OFF_COLOR: [u8; 4] = [0x00, 0x00, 0x00, 0xFF];
+
+// Same layout as the desktop app: 1-4, Q-R, A-F, Z-V
+fn keypad_index(code: &str) -> Option<u8> {
+    let key = match code {
+        "Digit1" => 0,
+        "Digit2" => 1,
+        "Digit3" => 2,
+        "Digit4" => 3,
+        "KeyQ" => 4,
+        "KeyW" => 5,
+        "KeyE" => 6,
+        "KeyR" => 7,
+        "KeyA" => 8,
+        "KeyS" => 9,
+        "KeyD" => 10,
+        "KeyF" => 11,
+        "KeyZ" => 12,
+        "KeyX" => 13,
+        "KeyC" => 14,
+        "KeyV" => 15,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// Converts screen into RGBA pixels and puts them into the canvas
+struct CanvasDisplay {
+    context: CanvasRenderingContext2d,
+    data: Vec<u8>,
+}
+
+impl chip8::Display for CanvasDisplay {
+    fn draw(&mut self, screen: &chip8::Screen) {
+        let (w, h) = screen.resolution();
+        let pixels = screen.iter_rows().flatten();
+        for (rgba, on) in self.data.chunks_exact_mut(4).zip(pixels) {
+            rgba.copy_from_slice(if on { &ON_COLOR } else { &OFF_COLOR });
+        }
+
+        let image = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&self.data[..]),
+            w as u32,
+            h as u32,
+        );
+        if let Ok(image) = image {
+            // Only the changed parts are put into the canvas
+            for rect in screen.dirty_rects() {
+                let _ = self
+                    .context
+                    .put_image_data_with_dirty_x_and_dirty_y_and_dirty_width_and_dirty_height(
+                        &image,
+                        0.0,
+                        0.0,
+                        rect.x as f64,
+                        rect.y as f64,
+                        rect.width as f64,
+                        rect.height as f64,
+                    );
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeyState {
+    down: [bool; 16],
+}
+
+impl chip8::Input for KeyState {
+    fn is_key_down(&self, key: u8) -> bool {
+        self.down[key as usize]
+    }
+}
+
+// No sound in the browser yet
+struct Silence;
+
+impl chip8::Audio for Silence {
+    fn set_beep(&mut self, _on: bool) {}
+}
+
+struct State {
+    emulator: chip8::Emulator,
+    display: CanvasDisplay,
+    keys: KeyState,
+    last_timestamp: Option<f64>,
+}
+
+/// Emulator bound to a canvas element.
+#[wasm_bindgen]
+pub struct WebEmulator {
+    state: Rc<RefCell<State>>,
+}
+
+#[wasm_bindgen]
+impl WebEmulator {
+    /// Binds to the canvas with id `canvas_id` and starts listening to keyboard events.
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas_id: &str) -> Result<WebEmulator, JsValue> {
+        let window = web_sys::window().ok_or("no window")?;
+        let document = window.document().ok_or("no document")?;
+        let canvas: HtmlCanvasElement = document
+            .get_element_by_id(canvas_id)
+            .ok_or_else(|| format!("no element with id '{}'", canvas_id))?
+            .dyn_into()?;
+
+        let (w, h) = chip8::SCREEN_SIZE;
+        canvas.set_width(w as u32);
+        canvas.set_height(h as u32);
+        let context: CanvasRenderingContext2d = canvas
+            .get_context("2d")?
+            .ok_or("2d canvas is not supported")?
+            .dyn_into()?;
+
+        let state = Rc::new(RefCell::new(State {
+            emulator: chip8::Emulator::new(),
+            display: CanvasDisplay {
+                context,
+                data: vec![0; w * h * 4],
+            },
+            keys: KeyState::default(),
+            last_timestamp: None,
+        }));
+
+        for (event, down) in &[("keydown", true), ("keyup", false)] {
+            let state = state.clone();
+            let down = *down;
+            let listener = Closure::wrap(Box::new(move |e: KeyboardEvent| {
+                if let Some(key) = keypad_index(&e.code()) {
+                    state.borrow_mut().keys.down[key as usize] = down;
+                    e.prevent_default();
+                }
+            }) as Box<dyn FnMut(KeyboardEvent)>);
+            window.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())?;
+            // Listeners live as long as the page
+            listener.forget();
+        }
+
+        Ok(WebEmulator { state })
+    }
+
+    /// Resets the machine and starts running `rom`.
+    pub fn load_rom(&self, rom: &[u8]) -> Result<(), JsValue> {
+        self.state
+            .borrow_mut()
+            .emulator
+            .load_rom_bytes(rom)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Runs emulation up to `timestamp` (milliseconds, as passed to `requestAnimationFrame`)
+    /// and redraws the canvas if the screen changed.
+    pub fn frame(&self, timestamp: f64) {
+        let state = &mut *self.state.borrow_mut();
+        let dt = match state.last_timestamp {
+            Some(last) => ((timestamp - last) / 1000.0) as f32,
+            None => 0.0,
+        };
+        state.last_timestamp = Some(timestamp);
+
+        state.emulator.run_frame(
+            dt.clamp(0.0, MAX_FRAME_TIME),
+            &state.keys,
+            &mut state.display,
+            &mut Silence,
+        );
+    }
+}
@@ -1,28 +1,87 @@
-use crate::chip8;
+use crate::cli::{self, Args};
+use crate::clipboard::Clipboard;
+use crate::config::{key_name, Config, Hotkey};
+use crate::fonts::FontSettings;
+use crate::gpu::{self, WindowSurface};
+use crate::i18n::{self, im, tr, trf};
 use crate::imgui_wgpu::Renderer;
-use futures::executor::block_on;
-use glob::glob;
+use crate::instance::Instance;
+use crate::kiosk::{Kiosk, Playlist};
+use crate::layouts::LayoutPreset;
+use crate::loader::{LoadResult, Loader, RomData, RomList};
+use crate::logger;
+use crate::observe::{self, Observer};
+use crate::perf::{FrameTimes, PerfGraphs};
+use crate::theme::Theme;
+use crate::toasts::Toasts;
+use crate::views::{self, Action, DebugView, ViewContext};
+use crate::watch::FileWatch;
+use chip8_core::{Extension, Quirks};
 use imgui::*;
-use imgui_winit_support;
+use std::ffi::{CStr, CString};
 use std::path::PathBuf;
+use std::ptr;
 use std::rc::Rc;
-use std::time::Instant;
-use wgpu::{Device, Queue};
+use std::time::{Duration, Instant};
 use winit::{
     dpi::LogicalSize,
     event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::Window,
+    window::{Fullscreen, Window, WindowBuilder},
 };
 
-fn find_roms() -> glob::Paths {
-    let exe_path = std::env::current_exe();
-    let rom_path = exe_path.unwrap().parent().unwrap().join("../../roms");
+/// UI refresh period while something is running, the screen can't change faster than the timers tick
+const ACTIVE_FRAME_TIME: f32 = 1.0 / chip8_core::TIMER_HZ;
 
-    glob(rom_path.join("**/*.ch8").to_str().unwrap()).unwrap()
+/// UI refresh period while every emulator is halted or empty, or the window is minimized
+const IDLE_FRAME_TIME: f32 = 0.25;
+
+/// Holding the advance frames key longer than this repeats it, so a tap advances one frame
+const ADVANCE_DELAY: Duration = Duration::from_millis(400);
+
+/// Frames advanced per second once advancing repeats
+const ADVANCE_RATE: f32 = 10.0;
+
+// Picks one flag out of Quirks, used for the toggles in the Quirks menu
+type QuirkField = fn(&mut Quirks) -> &mut bool;
+
+// Profile offered for a ROM that looks written for an extension the settings don't fit
+struct ProfilePrompt {
+    instance_id: usize,
+    rom_file: PathBuf,
+    extension: Extension,
+    /// Quirks preset the extension's ROMs usually want
+    preset: &'static str,
+    xo_chip_memory: bool,
+}
+
+impl ProfilePrompt {
+    // `None` if the ROM is plain CHIP-8 or the settings already fit it
+    fn check(instance_id: usize, data: &RomData, config: &Config, args: &Args) -> Option<Self> {
+        let extension = data.extension?;
+        let (preset, xo_chip_memory) = match extension {
+            Extension::SuperChip => ("schip", false),
+            Extension::XoChip => ("octo", true),
+        };
+        // Octo options and --quirks pick the quirks themselves
+        let quirks_fit = matches!(data.options, Ok(Some(_)))
+            || args.quirks.is_some()
+            || Quirks::preset(preset) == Some(config.quirks);
+        let memory_fits = !xo_chip_memory || config.xo_chip_memory;
+        if quirks_fit && memory_fits {
+            return None;
+        }
+        Some(ProfilePrompt {
+            instance_id,
+            rom_file: data.path.clone(),
+            extension,
+            preset,
+            xo_chip_memory,
+        })
+    }
 }
 
-fn to_rgb01(color: [i32; 4]) -> [f32; 4] {
+pub fn to_rgb01(color: [i32; 4]) -> [f32; 4] {
     [
         color[0] as f32 / 255.0,
         color[1] as f32 / 255.0,
@@ -31,238 +90,1162 @@ fn to_rgb01(color: [i32; 4]) -> [f32; 4] {
     ]
 }
 
-// Screen is used to store and update screen buffer and draw it as window with a texture
-struct ScreenBuffer {
-    size: (usize, usize),
-    data: Vec<u8>,
-    ui_scale: f32,
-    ui_color: [f32; 4],
-    texture_id: TextureId,
+pub struct Chip8App {
+    roms: RomList,
+    loader: Loader,
+    config: Config,
+    args: Args,
+    instances: Vec<Instance>,
+    focused: usize, // Index of the instance receiving keyboard input
+    next_id: usize,
+    views: Vec<(Box<dyn DebugView>, bool)>, // Registered views and whether they are open
+    about_open: bool,
+    status_bar: bool,
+    perf_graphs: Option<PerfGraphs>, // Timings kept while View > Performance graphs is on
+    observer: Option<Observer>,      // Tools watching the focused instance, see `--observe`
+    toasts: Toasts,
+    pending_rom: Option<PathBuf>, // ROM waiting for the user to confirm the switch
+    pending_profile: Option<ProfilePrompt>, // Profile waiting for the user to accept it
+    kept_profile: Vec<PathBuf>,   // ROMs the user didn't want another profile for
+    auto_paused: Vec<usize>,      // Ids of instances paused because the window went inactive
+    drawing_stalled: bool, // The window surface keeps failing, see `WindowSurface::is_stalled`
+    quit: bool,            // Exit was picked in the File menu
+    kiosk: Option<Kiosk>,  // Playlist shown instead of the UI
+    pending_layout: Option<LayoutPreset>, // Preset whose windows are moved once they exist
 }
 
-impl ScreenBuffer {
-    fn new(renderer: &mut Renderer, device: &Device) -> Self {
-        let size = (chip8::SCREEN_SIZE.0, chip8::SCREEN_SIZE.1);
-        let texture_id = renderer.create_texture(&device, size.0 as u32, size.1 as u32);
-
-        ScreenBuffer {
-            size: size,
-            data: vec![0; size.0 * size.1 * 4],
-            ui_scale: 9.0_f32,
-            ui_color: [0.09_f32, 0.6_f32, 0.0_f32, 1.0_f32],
-            texture_id: texture_id,
-        }
-    }
-
-    fn draw_ui(&mut self, ui: &imgui::Ui) {
-        // Screen window
-        let window = imgui::Window::new(im_str!("Screen")).always_auto_resize(true);
-        window
-            .position([500.0, 200.0], Condition::Once)
-            .build(&ui, || {
-                let size = [
-                    (self.size.0 as f32) * self.ui_scale,
-                    (self.size.1 as f32) * self.ui_scale,
-                ];
-                Image::new(self.texture_id, size)
-                    .tint_col(self.ui_color)
-                    .build(&ui);
-                ui.drag_float(im_str!("Scale"), &mut self.ui_scale).build();
-                ui.same_line(0.0);
-                imgui::ColorEdit::new(im_str!("Color"), &mut self.ui_color).build(&ui);
+impl Chip8App {
+    pub fn new(args: Args) -> Self {
+        let config = Config::load();
+        let loader = Loader::new();
+        loader.scan(config.rom_dir());
+        let mut roms = RomList::default();
+        roms.start_scan();
+
+        let mut instance = Instance::new(1, &config, &args);
+        if let Some(trace) = &args.trace {
+            match cli::file_trace_hook(trace) {
+                Ok(hook) => instance.emulator.lock().set_trace_hook(Some(hook)),
+                Err(e) => log::error!("Can't create '{0}'. Error: {1}", trace.display(), e),
+            }
+        }
+        let kiosk = args
+            .kiosk
+            .as_ref()
+            .and_then(|path| match Playlist::load(path) {
+                Ok(playlist) => Some(Kiosk::new(playlist)),
+                Err(e) => {
+                    log::error!("Can't load playlist '{0}'. Error: {1}", path.display(), e);
+                    None
+                }
             });
-    }
+        if let Some(kiosk) = &kiosk {
+            loader.load_rom(instance.id, kiosk.current_path().to_path_buf());
+        } else if let Some(rom_file) = &args.rom {
+            loader.load_rom(instance.id, rom_file.clone());
+            if args.watch {
+                instance.watch = Some(FileWatch::new(rom_file));
+            }
+        }
 
-    fn update(
-        &mut self,
-        emulator: &chip8::Emulator,
-        renderer: &mut Renderer,
-        device: &Device,
-        mut queue: &mut Queue,
-    ) {
-        // Update pixels in screen buffer from emulator's screen
-        for x in 0..self.size.0 {
-            for y in 0..self.size.1 {
-                let v = if emulator.screen.get_pixel(x, y) {
-                    0xFF
-                } else {
-                    0
+        let observer = args
+            .observe
+            .as_ref()
+            .and_then(|addr| match Observer::start(addr) {
+                Ok(observer) => Some(observer),
+                Err(e) => {
+                    log::error!("Can't listen on '{0}'. Error: {1}", addr, e);
+                    None
+                }
+            });
+
+        let views = views::all()
+            .into_iter()
+            .map(|view| {
+                let open = match &config.window.open_views {
+                    Some(names) => names.iter().any(|name| name == view.name()),
+                    None => view.open_by_default(),
                 };
+                (view, open)
+            })
+            .collect();
+
+        Chip8App {
+            roms,
+            loader,
+            config,
+            args,
+            instances: vec![instance],
+            focused: 0,
+            next_id: 2,
+            views,
+            about_open: false,
+            status_bar: true,
+            perf_graphs: None,
+            observer,
+            toasts: Toasts::default(),
+            pending_rom: None,
+            pending_profile: None,
+            kept_profile: Vec::new(),
+            auto_paused: Vec::new(),
+            drawing_stalled: false,
+            quit: false,
+            kiosk,
+            pending_layout: None,
+        }
+    }
+
+    // Adds an instance running the same ROM as the focused one, handy for comparing quirks
+    fn add_instance(&mut self) {
+        let instance = Instance::new(self.next_id, &self.config, &self.args);
+        self.next_id += 1;
+        if let Some(rom_file) = self.instances[self.focused].rom_file.clone() {
+            self.loader.load_rom(instance.id, rom_file);
+        }
+        self.instances.push(instance);
+        self.focus(self.instances.len() - 1);
+    }
 
-                let x0 = x * 4;
-                let y0 = y * 4;
-                let pos = y0 * self.size.0;
-                self.data[pos + x0..pos + x0 + 4].copy_from_slice(&[v, v, v, 0xFF]);
+    fn close_instance(&mut self, index: usize, renderer: &mut Renderer) {
+        let mut instance = self.instances.remove(index);
+        instance.write_save_memory();
+        instance.save_debug_session();
+        if let Some(texture_id) = instance.screen.texture_id {
+            renderer.remove_texture(texture_id);
+        }
+        if let Some(recording) = instance.recording.take() {
+            recording.finish();
+        }
+        // Flushes the trace file
+        instance.emulator.lock().set_trace_hook(None);
+        if self.focused >= index && self.focused > 0 {
+            self.focused -= 1;
+        }
+    }
+
+    // Restarts the ROM without reading it again
+    fn reset(&mut self, index: usize) {
+        let instance = &mut self.instances[index];
+        instance.write_save_memory();
+        let mut emulator = instance.emulator.lock();
+        emulator.reset();
+        if let Some(rom_file) = &instance.rom_file {
+            instance.restore_save_memory(&mut emulator, rom_file);
+        }
+        // Quirks changed before the reset are the ones the ROM runs with now
+        let quirks = emulator.quirks();
+        drop(emulator);
+        instance.start_quirks = quirks;
+    }
+
+    // Reads ROM and its options from disk again, picks up changes made by an assembler
+    fn reload(&mut self, index: usize) {
+        let instance = &self.instances[index];
+        if let Some(rom_file) = instance.rom_file.clone() {
+            self.loader.load_rom(instance.id, rom_file);
+        }
+    }
+
+    fn focus(&mut self, index: usize) {
+        if index != self.focused {
+            // Keys held down in the previous instance would get stuck otherwise
+            let instance = &mut self.instances[self.focused];
+            instance.keys = [false; 16];
+            instance.emulator.set_keys(instance.keys);
+            if instance.fast_forward.take().is_some() {
+                instance.apply_speed();
             }
+            instance.advancing = None;
+            self.focused = index;
         }
+    }
 
-        // Uploaded updated screen texture data
-        renderer.update_texture(
-            self.texture_id,
-            &device,
-            &mut queue,
-            &self.data,
-            self.size.0 as u32,
-            self.size.1 as u32,
-        );
+    // Runs a hotkey action on the focused instance, fast-forward and fullscreen are handled by the caller
+    fn hotkey(&mut self, hotkey: Hotkey) {
+        let focused = self.focused;
+        let instance = &mut self.instances[focused];
+        match hotkey {
+            Hotkey::Quit => self.quit = true,
+            Hotkey::Pause => instance.set_paused(!instance.paused),
+            Hotkey::Step => instance.step(),
+            Hotkey::Reset => self.reset(focused),
+            Hotkey::Reload => self.reload(focused),
+            Hotkey::Screenshot => instance.screenshot(),
+            Hotkey::CopyScreen => instance.copy_screen(),
+            Hotkey::SaveState => instance.save_state(),
+            Hotkey::LoadState => instance.load_state(),
+            Hotkey::SlowMotion25 => instance.toggle_slow_motion(0.25),
+            Hotkey::SlowMotion50 => instance.toggle_slow_motion(0.5),
+            Hotkey::NextBookmark | Hotkey::PreviousBookmark => {
+                let address = instance.next_bookmark(hotkey == Hotkey::NextBookmark);
+                if address.is_some() {
+                    instance.show_code = address;
+                    self.open_view("Code");
+                }
+            }
+            Hotkey::FastForward | Hotkey::Fullscreen | Hotkey::AdvanceFrames => {}
+        }
     }
-}
 
-pub struct Chip8App {
-    rom_files: Vec<PathBuf>,
-    emulator: chip8::Emulator,
-}
+    fn set_fast_forward(&mut self, on: bool) {
+        let instance = &mut self.instances[self.focused];
+        let speed = if on {
+            Some(self.config.fast_forward_speed)
+        } else {
+            None
+        };
+        if instance.fast_forward != speed {
+            instance.fast_forward = speed;
+            instance.apply_speed();
+        }
+    }
 
-impl Chip8App {
-    pub fn new() -> Self {
-        let roms = find_roms().map(|res| res.unwrap()).collect();
+    // Advances a frame when the key goes down and keeps advancing while it's held
+    fn set_frame_advance(&mut self, held: bool) {
+        let instance = &mut self.instances[self.focused];
+        match (held, instance.advancing) {
+            (true, None) => {
+                instance.advance_frame();
+                instance.advancing = Some(Instant::now() + ADVANCE_DELAY);
+            }
+            (false, _) => instance.advancing = None,
+            // Repeated key events while held, `repeat_frame_advance` keeps the pace
+            (true, Some(_)) => {}
+        }
+    }
 
-        Chip8App {
-            rom_files: roms,
-            emulator: chip8::Emulator::new(),
-        }
-    }
-
-    fn draw_ui(&mut self, ui: &imgui::Ui) {
-        // Window with list of ROMs
-        let window = imgui::Window::new(im_str!("ROMs"));
-        window
-            .size([400.0, 600.0], Condition::Once)
-            .position([5.0, 5.0], Condition::Once)
-            .build(&ui, || {
-                for rom_file in &self.rom_files {
-                    let filename = ImString::new(rom_file.file_name().unwrap().to_str().unwrap());
-                    if ui.button(&filename, [0 as f32, 0 as f32]) {
-                        self.emulator.load_rom(rom_file);
+    fn repeat_frame_advance(&mut self) {
+        let now = Instant::now();
+        for instance in &mut self.instances {
+            if instance.advancing.is_some_and(|next| now >= next) {
+                instance.advance_frame();
+                instance.advancing = Some(now + Duration::from_secs_f32(1.0 / ADVANCE_RATE));
+            }
+        }
+    }
+
+    // Main menu bar, items act on the focused instance
+    fn draw_menu(&mut self, ui: &imgui::Ui) -> Vec<Action> {
+        let mut actions = Vec::new();
+        let instance = &self.instances[self.focused];
+        let has_rom = instance.rom_file.is_some();
+        let recent_roms = &self.config.recent_roms;
+        let views = &mut self.views;
+        let about_open = &mut self.about_open;
+        let status_bar = &mut self.status_bar;
+        let perf_graphs = &mut self.perf_graphs;
+        let current_theme = self.config.theme;
+        let layouts = &self.config.layouts;
+        let window_config = &mut self.config.window;
+        let hotkeys = &self.config.hotkeys;
+        let shortcut = |hotkey| ImString::new(key_name(hotkeys.key(hotkey)));
+        ui.main_menu_bar(|| {
+            ui.menu(&im("File"), true, || {
+                if MenuItem::new(&im("Open ROM...")).build(ui) {
+                    actions.push(Action::OpenRomDialog);
+                }
+                ui.menu(&im("Recent"), !recent_roms.is_empty(), || {
+                    for rom_file in recent_roms {
+                        let label = ImString::new(rom_file.display().to_string());
+                        if MenuItem::new(&label).build(ui) {
+                            actions.push(Action::LoadRom(rom_file.clone()));
+                        }
                     }
+                });
+                if MenuItem::new(&im("New instance")).build(ui) {
+                    actions.push(Action::NewInstance);
+                }
+                if MenuItem::new(&im("Export patched ROM..."))
+                    .enabled(has_rom)
+                    .build(ui)
+                {
+                    actions.push(Action::ExportPatchedRom);
+                }
+                ui.separator();
+                if MenuItem::new(&im("Exit"))
+                    .shortcut(&shortcut(Hotkey::Quit))
+                    .build(ui)
+                {
+                    actions.push(Action::Quit);
                 }
             });
 
-        // Window with CPU state
-        let window = imgui::Window::new(im_str!("CPU"));
-        window
-            .size([395.0, 200.0], Condition::FirstUseEver)
-            .position([1200.0, 5.0], Condition::Once)
-            .build(&ui, || {
-                ui.text(format!("PC: {:#X}", self.emulator.pc));
-                ui.text(format!("I: {:#X}", self.emulator.ri));
-                for i in 0..self.emulator.rs.len() {
-                    ui.text(format!("V{:X}: {:#X} ", i, self.emulator.rs[i]));
-                    if (i + 1) % 4 != 0 {
-                        ui.same_line(0.0);
+            ui.menu(&im("Emulation"), true, || {
+                if MenuItem::new(&im("Pause"))
+                    .shortcut(&shortcut(Hotkey::Pause))
+                    .selected(instance.paused)
+                    .enabled(has_rom)
+                    .build(ui)
+                {
+                    actions.push(Action::TogglePause);
+                }
+                if MenuItem::new(&im("Reset"))
+                    .shortcut(&shortcut(Hotkey::Reset))
+                    .enabled(has_rom)
+                    .build(ui)
+                {
+                    actions.push(Action::Reset);
+                }
+                if MenuItem::new(&im("Reload ROM"))
+                    .shortcut(&shortcut(Hotkey::Reload))
+                    .enabled(has_rom)
+                    .build(ui)
+                {
+                    actions.push(Action::Reload);
+                }
+                if MenuItem::new(&im("Watch ROM file"))
+                    .selected(instance.watch.is_some())
+                    .enabled(has_rom)
+                    .build(ui)
+                {
+                    actions.push(Action::SetWatch(instance.watch.is_none()));
+                }
+                if MenuItem::new(&im("Resume at same label"))
+                    .selected(instance.resume_at_label)
+                    .enabled(instance.assembly.is_some())
+                    .build(ui)
+                {
+                    actions.push(Action::SetResumeAtLabel(!instance.resume_at_label));
+                }
+                for &hotkey in &[
+                    Hotkey::Step,
+                    Hotkey::SaveState,
+                    Hotkey::LoadState,
+                    Hotkey::Screenshot,
+                    Hotkey::CopyScreen,
+                ] {
+                    let label = im(hotkey.name());
+                    if MenuItem::new(&label)
+                        .shortcut(&shortcut(hotkey))
+                        .enabled(has_rom)
+                        .build(ui)
+                    {
+                        actions.push(Action::Hotkey(hotkey));
                     }
                 }
-                ui.text(format!("timer: {}", self.emulator.delay));
-
-                ui.text(format!("stack (size: {}):", self.emulator.stack.len()));
-                for v in self.emulator.stack.iter() {
-                    ui.same_line(0.0);
-                    ui.text(format!("{:X}", v));
+                if MenuItem::new(&im("Skip instruction"))
+                    .enabled(has_rom && instance.paused)
+                    .build(ui)
+                {
+                    actions.push(Action::SkipInstruction);
                 }
+                ui.separator();
+                ui.menu(&im("Speed"), true, || {
+                    for &speed in &[0.25, 0.5, 1.0, 2.0, 5.0, 10.0] {
+                        let label = ImString::new(format!("{}x", speed));
+                        if MenuItem::new(&label)
+                            .selected(instance.speed == speed)
+                            .build(ui)
+                        {
+                            actions.push(Action::SetSpeed(speed));
+                        }
+                    }
+                });
+                ui.menu(&im("Quirks"), true, || {
+                    let quirks = instance.emulator.lock().quirks();
+                    for name in Quirks::PRESET_NAMES.iter() {
+                        let preset = Quirks::preset(name).unwrap();
+                        let label = ImString::new(trf("{} preset", name));
+                        if MenuItem::new(&label).selected(quirks == preset).build(ui) {
+                            actions.push(Action::SetQuirks(preset));
+                        }
+                    }
+                    ui.separator();
+                    let toggles: [(&ImStr, QuirkField); 6] = [
+                        (im_str!("shift"), |q| &mut q.shift),
+                        (im_str!("load/store"), |q| &mut q.load_store),
+                        (im_str!("jump"), |q| &mut q.jump),
+                        (im_str!("logic"), |q| &mut q.logic),
+                        (im_str!("clip"), |q| &mut q.clip),
+                        (im_str!("vblank"), |q| &mut q.vblank),
+                    ];
+                    for (label, field) in toggles.iter() {
+                        let mut changed = quirks;
+                        let value = field(&mut changed);
+                        if MenuItem::new(label).build_with_ref(ui, value) {
+                            actions.push(Action::SetQuirks(changed));
+                        }
+                    }
+                });
             });
 
-        // Window with program code
-        let window = imgui::Window::new(im_str!("Code"));
-        window
-            .size([395.0, 600.0], Condition::FirstUseEver)
-            .position([1200.0, 220.0], Condition::Once)
-            .build(&ui, || {
-                let code_range = self.emulator.get_code_range();
-                let pc = self.emulator.pc as usize;
-                let code = &self.emulator.memory[code_range.0..code_range.1];
-                for i in (1..code.len()).step_by(2) {
-                    let mut color_stack: Option<ColorStackToken> = None;
-                    if pc == (i + code_range.0 - 1) {
-                        ui.set_scroll_here_y();
-                        color_stack =
-                            Some(ui.push_style_color(StyleColor::Text, to_rgb01([0, 255, 0, 255])));
+            ui.menu(&im("View"), true, || {
+                for (view, open) in views.iter_mut() {
+                    let name = im(view.name());
+                    MenuItem::new(&name).build_with_ref(ui, open);
+                }
+                ui.separator();
+                MenuItem::new(&im("Status bar")).build_with_ref(ui, status_bar);
+                if MenuItem::new(&im("Performance graphs"))
+                    .selected(perf_graphs.is_some())
+                    .build(ui)
+                {
+                    *perf_graphs = match perf_graphs {
+                        Some(_) => None,
+                        None => Some(PerfGraphs::default()),
+                    };
+                }
+                ui.menu(&im("Theme"), true, || {
+                    for &theme in Theme::ALL.iter() {
+                        let label = im(theme.name());
+                        if MenuItem::new(&label)
+                            .selected(theme == current_theme)
+                            .build(ui)
+                        {
+                            actions.push(Action::SetTheme(theme));
+                        }
+                    }
+                });
+                ui.menu(&im("Layout"), true, || {
+                    for &name in LayoutPreset::BUILT_IN_NAMES.iter() {
+                        if MenuItem::new(&im(name)).build(ui) {
+                            let preset = LayoutPreset::built_in(name).unwrap();
+                            actions.push(Action::ApplyLayout(preset));
+                        }
                     }
-                    ui.text(format!("{:>4}: {:02X}{:02X}", i, code[i - 1], code[i]));
-                    if let Some(c) = color_stack {
-                        c.pop(&ui);
+                    if !layouts.is_empty() {
+                        ui.separator();
+                    }
+                    for preset in layouts {
+                        if MenuItem::new(&ImString::new(&preset.name)).build(ui) {
+                            actions.push(Action::ApplyLayout(preset.clone()));
+                        }
                     }
+                    ui.separator();
+                    if MenuItem::new(&im("Save layout...")).build(ui) {
+                        actions.push(Action::SaveLayout);
+                    }
+                    ui.menu(&im("Delete saved layout"), !layouts.is_empty(), || {
+                        for (i, preset) in layouts.iter().enumerate() {
+                            if MenuItem::new(&ImString::new(&preset.name)).build(ui) {
+                                actions.push(Action::DeleteLayout(i));
+                            }
+                        }
+                    });
+                });
+                ui.separator();
+                MenuItem::new(&im("Always on top"))
+                    .build_with_ref(ui, &mut window_config.always_on_top);
+                Slider::new(&im("Opacity"), 0.2..=1.0)
+                    .display_format(im_str!("%.2f"))
+                    .build(ui, &mut window_config.opacity);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(tr(
+                        "A window that started opaque turns see-through after a restart",
+                    ));
                 }
             });
 
-        // Help Window
-        let window = imgui::Window::new(im_str!("Help"));
-        window
-            .size([395.0, 160.0], Condition::FirstUseEver)
-            .position([5.0, 660.0], Condition::Once)
-            .build(&ui, || {
-                ui.text(im_str!("Select ROM file, to control use keys:\n1,2,3,4,\nQ,W,E,R,\nA,S,D,F,\nZ,X,C,V\n\nHave fun!"));
+            ui.menu(&im("Help"), true, || {
+                if MenuItem::new(&im("Controls")).build(ui) {
+                    for (view, open) in views.iter_mut() {
+                        if view.name() == "Help" {
+                            *open = true;
+                        }
+                    }
+                }
+                if MenuItem::new(&im("Export bug report"))
+                    .enabled(has_rom)
+                    .build(ui)
+                {
+                    actions.push(Action::ExportBugReport);
+                }
+                ui.separator();
+                MenuItem::new(&im("About")).build_with_ref(ui, about_open);
             });
+        });
+
+        if self.about_open {
+            imgui::Window::new(&i18n::window_title("About", ""))
+                .opened(&mut self.about_open)
+                .always_auto_resize(true)
+                .build(ui, || {
+                    ui.text(format!("chip8-rust {}", env!("CARGO_PKG_VERSION")));
+                    ui.text(tr("chip8 emulator and debugger"));
+                });
+        }
+        actions
     }
 
-    fn set_key_state(&mut self, code: VirtualKeyCode, state: bool) {
-        self.emulator.keypad.set(
-            match code {
-                VirtualKeyCode::Key1 => 0,
-                VirtualKeyCode::Key2 => 1,
-                VirtualKeyCode::Key3 => 2,
-                VirtualKeyCode::Key4 => 3,
-                VirtualKeyCode::Q => 4,
-                VirtualKeyCode::W => 5,
-                VirtualKeyCode::E => 6,
-                VirtualKeyCode::R => 7,
-                VirtualKeyCode::A => 8,
-                VirtualKeyCode::S => 9,
-                VirtualKeyCode::D => 10,
-                VirtualKeyCode::F => 11,
-                VirtualKeyCode::Z => 12,
-                VirtualKeyCode::X => 13,
-                VirtualKeyCode::C => 14,
-                VirtualKeyCode::V => 15,
-                _ => return,
+    // Strip along the bottom of the window with the state of the focused instance
+    fn draw_status_bar(&self, ui: &imgui::Ui) {
+        let instance = &self.instances[self.focused];
+        let rom_name = match &instance.rom_file {
+            Some(rom_file) => rom_file.file_name().unwrap_or_default().to_string_lossy(),
+            None => tr("No ROM").into(),
+        };
+        let emulator = instance.emulator.lock();
+        let state = match emulator.halt_reason() {
+            _ if instance.paused => tr("Paused").to_string(),
+            // Tells a program that's done or waits for input apart from a hang
+            None => match emulator.idle() {
+                Some(idle) => tr(idle.description()).to_string(),
+                None => tr("Running").to_string(),
             },
-            state,
-        )
+            Some(chip8_core::HaltReason::NoRom) => tr("Idle").to_string(),
+            Some(chip8_core::HaltReason::Breakpoint(pc)) => {
+                trf("Breakpoint at {}", format_args!("{:03X}", pc))
+            }
+            Some(chip8_core::HaltReason::PcOutOfBounds(pc)) => {
+                trf("Halted, PC {}", format_args!("{:03X}", pc))
+            }
+            Some(chip8_core::HaltReason::Fault { pc, .. }) => {
+                trf("Fault at {}", format_args!("{:03X}", pc))
+            }
+        };
+        drop(emulator);
+
+        let [width, height] = ui.io().display_size;
+        let bar_height = ui.frame_height_with_spacing();
+        imgui::Window::new(im_str!("Status"))
+            .position([0.0, height - bar_height], Condition::Always)
+            .size([width, bar_height], Condition::Always)
+            .title_bar(false)
+            .resizable(false)
+            .movable(false)
+            .scroll_bar(false)
+            .save_settings(false)
+            .bring_to_front_on_focus(false)
+            .build(ui, || {
+                ui.text(format!(
+                    "{:.0} FPS | {:.0} IPS ({}) | {} | {}",
+                    ui.io().framerate,
+                    instance.ips.ips,
+                    trf(
+                        "target {}",
+                        format_args!(
+                            "{:.0}",
+                            instance.emulator.lock().clock_hz()
+                                * instance.fast_forward.unwrap_or(instance.speed)
+                        )
+                    ),
+                    rom_name,
+                    state
+                ));
+            });
+    }
+
+    // Pauses running instances while the window is inactive and resumes them once it's back.
+    // Instances the user paused or resumed in the meantime are left alone.
+    fn set_window_active(&mut self, focused: bool, minimized: bool) {
+        let inactive = (!focused && self.config.pause_when_unfocused)
+            || (minimized && self.config.pause_when_minimized)
+            || self.drawing_stalled;
+        if inactive {
+            for instance in &mut self.instances {
+                if !instance.paused && instance.rom_file.is_some() {
+                    instance.set_paused(true);
+                    self.auto_paused.push(instance.id);
+                }
+            }
+        } else {
+            for id in self.auto_paused.drain(..) {
+                if let Some(instance) = self.instances.iter_mut().find(|i| i.id == id) {
+                    instance.set_paused(false);
+                }
+            }
+        }
+    }
+
+    // OS window title with the focused instance's ROM and state, to tell windows apart in the taskbar
+    fn window_title(&self) -> String {
+        let instance = &self.instances[self.focused];
+        let mut title = String::from("chip8-rust");
+        if let Some(rom_file) = &instance.rom_file {
+            let name = rom_file.file_name().unwrap_or_default().to_string_lossy();
+            title = format!("{} - {}", name, title);
+        }
+        if self.instances.len() > 1 {
+            title = format!("#{} {}", instance.id, title);
+        }
+        let states: Vec<&str> = [
+            (instance.paused, "paused"),
+            (instance.fast_forward.is_some(), "fast-forward"),
+            (instance.recording.is_some(), "recording"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|&(_, state)| tr(state))
+        .collect();
+        if !states.is_empty() {
+            title = format!("{} [{}]", title, states.join(", "));
+        }
+        title
+    }
+
+    // Lets the user pick a ROM file, blocks until the dialog is closed
+    fn open_rom_dialog(&mut self) {
+        let rom_dir = self.config.rom_dir();
+        let path = tinyfiledialogs::open_file_dialog(
+            "Open ROM",
+            &format!("{}/", rom_dir.display()),
+            Some((
+                &["*.ch8", "*.c8", "*.sc8", "*.xo8", "*.hc8", "*.8o"],
+                "chip8 ROMs and Octo sources",
+            )),
+        );
+        if let Some(path) = path {
+            self.request_rom(PathBuf::from(path));
+        }
+    }
+
+    // Loads a ROM into the focused instance, asking first if that ends a running game
+    fn request_rom(&mut self, rom_file: PathBuf) {
+        if self.config.confirm_rom_switch && self.instances[self.focused].in_game() {
+            self.pending_rom = Some(rom_file);
+        } else {
+            self.switch_rom(rom_file);
+        }
+    }
+
+    fn switch_rom(&mut self, rom_file: PathBuf) {
+        let instance = &self.instances[self.focused];
+        if self.config.save_state_on_switch && instance.in_game() {
+            instance.save_state();
+        }
+        self.loader.load_rom(instance.id, rom_file);
+    }
+
+    // Modal asking whether the running game can be replaced
+    fn draw_switch_prompt(&mut self, ui: &imgui::Ui) {
+        let rom_file = match &self.pending_rom {
+            Some(rom_file) => rom_file.clone(),
+            None => return,
+        };
+        let title = &i18n::window_title("Switch ROM?", "");
+        ui.open_popup(title);
+        let mut answer = None;
+        let config = &mut self.config;
+        ui.popup_modal(title).always_auto_resize(true).build(|| {
+            let name = rom_file.file_name().unwrap_or_default().to_string_lossy();
+            ui.text(trf("Load '{}'? The running game will be lost.", name));
+            ui.checkbox(
+                &im("Save a state of the running game first"),
+                &mut config.save_state_on_switch,
+            );
+            let mut dont_ask = !config.confirm_rom_switch;
+            if ui.checkbox(&im("Don't ask again"), &mut dont_ask) {
+                config.confirm_rom_switch = !dont_ask;
+            }
+            ui.separator();
+            if ui.button(&im("Load"), [80.0, 0.0]) {
+                answer = Some(true);
+            }
+            ui.same_line(0.0);
+            if ui.button(&im("Cancel"), [80.0, 0.0]) {
+                answer = Some(false);
+            }
+            if answer.is_some() {
+                ui.close_current_popup();
+            }
+        });
+        match answer {
+            Some(true) => {
+                self.pending_rom = None;
+                self.switch_rom(rom_file);
+            }
+            Some(false) => self.pending_rom = None,
+            None => {}
+        }
+    }
+
+    // Modal offering the quirks preset and memory size an extension ROM wants
+    fn draw_profile_prompt(&mut self, ui: &imgui::Ui) {
+        let prompt = match &self.pending_profile {
+            Some(prompt) => prompt,
+            None => return,
+        };
+        let title = &i18n::window_title("Switch profile?", "");
+        ui.open_popup(title);
+        let mut answer = None;
+        let config = &mut self.config;
+        ui.popup_modal(title).always_auto_resize(true).build(|| {
+            let name = prompt
+                .rom_file
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            ui.text(trf("'{}' looks like a {} ROM.", name).replacen(
+                "{}",
+                prompt.extension.name(),
+                1,
+            ));
+            ui.text(trf("Switch to the {} quirks preset?", prompt.preset));
+            if prompt.xo_chip_memory {
+                ui.text(tr("Memory is also raised to 64KB."));
+            }
+            ui.text_disabled(tr("Opcodes of the extension itself aren't emulated."));
+            let mut dont_ask = !config.suggest_profile;
+            if ui.checkbox(&im("Don't ask again"), &mut dont_ask) {
+                config.suggest_profile = !dont_ask;
+            }
+            ui.separator();
+            if ui.button(&im("Switch"), [80.0, 0.0]) {
+                answer = Some(true);
+            }
+            ui.same_line(0.0);
+            if ui.button(&im("Keep"), [80.0, 0.0]) {
+                answer = Some(false);
+            }
+            if answer.is_some() {
+                ui.close_current_popup();
+            }
+        });
+        let prompt = match (answer, self.pending_profile.take()) {
+            (Some(true), Some(prompt)) => prompt,
+            (Some(false), Some(prompt)) => {
+                self.kept_profile.push(prompt.rom_file);
+                return;
+            }
+            (_, prompt) => {
+                self.pending_profile = prompt;
+                return;
+            }
+        };
+        if let Some(quirks) = Quirks::preset(prompt.preset) {
+            self.config.quirks = quirks;
+        }
+        self.config.xo_chip_memory |= prompt.xo_chip_memory;
+        log::info!(
+            target: logger::NOTIFY,
+            "Switched to the {} profile",
+            prompt.preset
+        );
+        // The instance may have been closed meanwhile
+        if self
+            .instances
+            .iter()
+            .any(|instance| instance.id == prompt.instance_id)
+        {
+            self.loader.load_rom(prompt.instance_id, prompt.rom_file);
+        }
+    }
+
+    // Window background, see-through in overlay mode and below full opacity
+    fn clear_color(&self, theme: Theme) -> wgpu::Color {
+        if self.args.overlay {
+            return wgpu::Color::TRANSPARENT;
+        }
+        wgpu::Color {
+            a: self.config.window.opacity as f64,
+            ..theme.clear_color()
+        }
+    }
+
+    // Screen filling the window with nothing behind it, so off pixels show the desktop
+    // where the platform supports transparent windows. The Quit hotkey exits.
+    fn draw_overlay(&self, ui: &imgui::Ui) {
+        let instance = &self.instances[self.focused];
+        let display = ui.io().display_size;
+        let style = ui.push_style_vars(&[
+            StyleVar::WindowPadding([0.0, 0.0]),
+            StyleVar::WindowBorderSize(0.0),
+        ]);
+        let color = ui.push_style_color(StyleColor::WindowBg, [0.0, 0.0, 0.0, 0.0]);
+        imgui::Window::new(im_str!("Overlay"))
+            .position([0.0, 0.0], Condition::Always)
+            .size(display, Condition::Always)
+            .no_decoration()
+            .movable(false)
+            .save_settings(false)
+            .build(ui, || {
+                let screen = &instance.screen;
+                let scale =
+                    (display[0] / screen.size.0 as f32).min(display[1] / screen.size.1 as f32);
+                let size = [screen.size.0 as f32 * scale, screen.size.1 as f32 * scale];
+                let p0 = [(display[0] - size[0]) / 2.0, (display[1] - size[1]) / 2.0];
+                ui.set_cursor_screen_pos(p0);
+                if let Some(texture_id) = screen.texture_id {
+                    Image::new(texture_id, size)
+                        .tint_col(instance.palette.fg)
+                        .build(ui);
+                }
+                if instance.beep {
+                    self.config
+                        .beep_indicator
+                        .draw(ui, p0, size, instance.palette.fg);
+                }
+            });
+        color.pop(ui);
+        style.pop(ui);
+    }
+
+    // Draws menu and all open views, returns index of an instance to close
+    fn draw_ui(&mut self, ui: &imgui::Ui) -> Option<usize> {
+        i18n::set_language(self.config.language);
+        let focused = self.focused;
+        if let Some(kiosk) = &mut self.kiosk {
+            let instance = &mut self.instances[focused];
+            if let Some(rom_file) = kiosk.advance() {
+                self.loader.load_rom(instance.id, rom_file);
+            }
+            if let Some(demo) = kiosk.demo_keys() {
+                let mut keys = instance.keys;
+                for (key, &pressed) in keys.iter_mut().zip(demo.iter()) {
+                    *key |= pressed;
+                }
+                instance.emulator.set_keys(keys);
+            }
+            kiosk.draw(ui, instance, self.config.beep_indicator);
+            return None;
+        }
+        if self.args.overlay {
+            self.draw_overlay(ui);
+            return None;
+        }
+        let mut actions: Vec<(usize, Action)> = self
+            .draw_menu(ui)
+            .into_iter()
+            .map(|action| (focused, action))
+            .collect();
+        if self.status_bar {
+            self.draw_status_bar(ui);
+        }
+        if let Some(perf_graphs) = &self.perf_graphs {
+            perf_graphs.draw(ui);
+        }
+
+        // The Screen window sets it again while it has focus
+        for instance in &mut self.instances {
+            instance.screen.focused = false;
+        }
+        let can_close = self.instances.len() > 1;
+        for (view, open) in self.views.iter_mut() {
+            if !*open {
+                continue;
+            }
+            for i in 0..self.instances.len() {
+                if !view.per_instance() && i != self.focused {
+                    continue;
+                }
+                let mut ctx = ViewContext {
+                    instance: &mut self.instances[i],
+                    config: &self.config,
+                    has_keyboard: i == self.focused,
+                    can_close,
+                    roms: &self.roms,
+                    actions: Vec::new(),
+                };
+                view.draw(ui, &mut ctx, open);
+                actions.extend(ctx.actions.into_iter().map(|action| (i, action)));
+            }
+        }
+        // Windows a preset opened exist now, they can be moved
+        if let Some(preset) = self.pending_layout.take() {
+            move_windows(&preset);
+        }
+        // Toasts go on top of the views, above the status bar
+        let bottom = if self.status_bar {
+            ui.frame_height_with_spacing()
+        } else {
+            0.0
+        };
+        self.toasts.draw(ui, bottom);
+        self.draw_switch_prompt(ui);
+        // One modal at a time, the ROM switch comes first
+        if self.pending_rom.is_none() {
+            self.draw_profile_prompt(ui);
+        }
+
+        let mut close = None;
+        for (i, action) in actions {
+            match action {
+                Action::LoadRom(rom_file) => self.request_rom(rom_file),
+                Action::OpenRomDialog => self.open_rom_dialog(),
+                Action::NewInstance => self.add_instance(),
+                Action::TogglePause => {
+                    let instance = &mut self.instances[i];
+                    instance.set_paused(!instance.paused);
+                }
+                Action::SetSpeed(speed) => {
+                    let instance = &mut self.instances[i];
+                    instance.speed = speed;
+                    instance.apply_speed();
+                }
+                Action::SetQuirks(quirks) => self.instances[i].emulator.lock().set_quirks(quirks),
+                Action::Hotkey(hotkey) => self.hotkey(hotkey),
+                Action::SetTheme(theme) => self.set_theme(theme),
+                Action::ApplyLayout(preset) => self.apply_layout(preset),
+                Action::SaveLayout => self.save_layout(),
+                Action::DeleteLayout(index) => {
+                    self.config.layouts.remove(index);
+                }
+                Action::ApplySettings(config) => self.apply_settings(*config),
+                Action::SaveSettings(config) => {
+                    self.apply_settings(*config);
+                    if let Err(e) = self.config.save() {
+                        log::error!("Can't save config. Error: {}", e);
+                    }
+                }
+                Action::Quit => self.quit = true,
+                Action::Reset => self.reset(i),
+                Action::Reload => self.reload(i),
+                Action::SetWatch(on) => self.instances[i].set_watch(on),
+                Action::SetResumeAtLabel(on) => self.instances[i].resume_at_label = on,
+                Action::Focus => self.focus(i),
+                Action::Close => close = Some(i),
+                Action::ExportBugReport => self.instances[i].export_bug_report(&self.config),
+                Action::ExportPatchedRom => self.instances[i].export_patched_rom(),
+                Action::ShowCode(address) => {
+                    self.instances[i].show_code = Some(address);
+                    self.open_view("Code");
+                }
+                Action::ShowMemory(address) => {
+                    self.instances[i].show_memory = Some(address);
+                    self.open_view("Memory");
+                }
+                Action::SkipInstruction => self.instances[i].skip_instruction(),
+                Action::SetPc(address) => self.instances[i].set_pc(address),
+            }
+        }
+        close
+    }
+
+    fn apply_layout(&mut self, preset: LayoutPreset) {
+        for (view, open) in self.views.iter_mut() {
+            *open = preset.views.iter().any(|name| name == view.name());
+        }
+        if !preset.windows.is_empty() {
+            self.pending_layout = Some(preset);
+        }
+    }
+
+    // Keeps open windows with their positions under a name, replacing a preset with the same name
+    fn save_layout(&mut self) {
+        let name = match tinyfiledialogs::input_box("Save layout", "Layout name:", "") {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => return,
+        };
+        let windows = unsafe { CStr::from_ptr(sys::igSaveIniSettingsToMemory(ptr::null_mut())) };
+        let preset = LayoutPreset {
+            name,
+            views: self
+                .views
+                .iter()
+                .filter(|(_, open)| *open)
+                .map(|(view, _)| view.name().to_string())
+                .collect(),
+            windows: windows.to_string_lossy().into_owned(),
+        };
+        log::info!(target: logger::NOTIFY, "Saved layout '{}'", preset.name);
+        let layouts = &mut self.config.layouts;
+        match layouts.iter_mut().find(|saved| saved.name == preset.name) {
+            Some(saved) => *saved = preset,
+            None => layouts.push(preset),
+        }
+    }
+
+    fn open_view(&mut self, name: &str) {
+        for (view, open) in self.views.iter_mut() {
+            if view.name() == name {
+                *open = true;
+            }
+        }
+    }
+
+    // Screens switch to the theme's palette too, ROMs loaded later still use their own colors
+    fn set_theme(&mut self, theme: Theme) {
+        self.config.theme = theme;
+        self.config.palette = theme.palette();
+        for instance in &mut self.instances {
+            instance.palette = theme.palette();
+        }
+    }
+
+    // Switches to settings edited in the Settings window
+    fn apply_settings(&mut self, mut config: Config) {
+        // Kept up to date by the app while the window was open
+        config.recent_roms = std::mem::take(&mut self.config.recent_roms);
+        config.window.always_on_top = self.config.window.always_on_top;
+        config.window.opacity = self.config.window.opacity;
+        config.layouts = std::mem::take(&mut self.config.layouts);
+        let rescan = config.rom_dir() != self.config.rom_dir();
+        self.config = config;
+
+        // Emulation defaults and palette are used for the next loaded ROM
+        for instance in &mut self.instances {
+            instance.screen.ui_scale = self.config.window.screen_scale;
+            instance
+                .emulator
+                .set_low_latency(self.config.low_latency_input);
+        }
+        if rescan {
+            self.roms.start_scan();
+            self.loader.scan(self.config.rom_dir());
+        }
+    }
+
+    // Picks up finished background work
+    fn poll_loader(&mut self) {
+        for instance in &mut self.instances {
+            if let Some(watch) = &mut instance.watch {
+                if watch.changed() {
+                    log::info!("'{}' changed, reloading", watch.path().display());
+                    self.loader
+                        .load_rom(instance.id, watch.path().to_path_buf());
+                }
+            }
+        }
+        for result in self.loader.poll() {
+            // Scan results are taken in by the ROM list
+            if let Some(LoadResult::RomLoaded(id, data)) = self.roms.update(result) {
+                if self.config.suggest_profile
+                    && self.kiosk.is_none()
+                    && !self.kept_profile.contains(&data.path)
+                {
+                    if let Some(prompt) = ProfilePrompt::check(id, &data, &self.config, &self.args)
+                    {
+                        self.pending_profile = Some(prompt);
+                    }
+                }
+                // The instance may have been closed while the ROM was read
+                match self.instances.iter_mut().find(|instance| instance.id == id) {
+                    Some(instance) => {
+                        let rom_file = data.path.clone();
+                        // Playlist ROMs don't go into the recent list
+                        if instance.load_rom(*data, &self.config, &self.args)
+                            && self.kiosk.is_none()
+                        {
+                            self.config.add_recent_rom(&rom_file);
+                        }
+                    }
+                    None => log::debug!(
+                        "Emulator #{} is gone, dropping '{}'",
+                        id,
+                        data.path.display()
+                    ),
+                }
+            }
+        }
+    }
+
+    // Nothing on screen can change without user input
+    fn is_idle(&self) -> bool {
+        !self.roms.is_scanning()
+            && self.toasts.is_empty()
+            && self.pending_rom.is_none()
+            && self.pending_profile.is_none()
+            && self.instances.iter().all(|instance| {
+                instance.advancing.is_none()
+                    && (instance.rom_file.is_none()
+                        || instance.paused
+                        || instance.emulator.lock().is_halted())
+            })
+    }
+
+    // Saves settings and flushes traces before exiting
+    fn shutdown(&mut self, window: &Window, imgui: &mut imgui::Context) {
+        for instance in &self.instances {
+            instance.write_save_memory();
+            instance.save_debug_session();
+        }
+        // Kiosk and overlay modes have no layout worth keeping, their window would replace it
+        if self.kiosk.is_some() || self.args.overlay {
+            for instance in &self.instances {
+                instance.emulator.lock().set_trace_hook(None);
+            }
+            return;
+        }
+        // Remember window layout for the next run
+        let size = window.inner_size().to_logical::<f64>(window.scale_factor());
+        self.config.window.width = size.width;
+        self.config.window.height = size.height;
+        let focused = &self.instances[self.focused];
+        self.config.window.screen_scale = focused.screen.ui_scale;
+        self.config.palette = focused.palette.clone();
+        self.config.window.open_views = Some(
+            self.views
+                .iter()
+                .filter(|(_, open)| *open)
+                .map(|(view, _)| view.name().to_string())
+                .collect(),
+        );
+        if let Err(e) = self.config.save() {
+            log::error!("Can't save config. Error: {}", e);
+        }
+        let mut layout = String::new();
+        imgui.save_ini_settings(&mut layout);
+        if let Err(e) = Config::save_layout(&layout) {
+            log::error!("Can't save window layout. Error: {}", e);
+        }
+        // Flushes the trace file
+        for instance in &self.instances {
+            instance.emulator.lock().set_trace_hook(None);
+        }
+        // Recordings are written before the process exits
+        for instance in &mut self.instances {
+            if let Some(recording) = instance.recording.take() {
+                let _ = recording.finish().join();
+            }
+        }
+    }
+
+    // Presses reach the game while its Screen window has focus or only the screen is shown
+    fn set_key_state(&mut self, code: VirtualKeyCode, state: bool, fullscreen: bool) {
+        let instance = &self.instances[self.focused];
+        let screen_only = fullscreen || self.kiosk.is_some() || self.args.overlay;
+        if state && !screen_only && !instance.screen.focused {
+            return;
+        }
+        if let Some(key) = self.config.keypad_index(code) {
+            let instance = &mut self.instances[self.focused];
+            instance.keys[key] = state;
+            instance.emulator.set_keys(instance.keys);
+        }
     }
 
     pub fn run(mut self: Rc<Self>) {
         // Set up window and GPU
         let event_loop = EventLoop::new();
-        let mut hidpi_factor = 1.0;
-        let (window, mut size, surface) = {
-            let window = Window::new(&event_loop).unwrap();
-            window.set_inner_size(LogicalSize {
-                width: 1600.0,
-                height: 900.0,
-            });
+        let (window, size) = {
+            let window = WindowBuilder::new()
+                .with_transparent(self.args.overlay || self.config.window.opacity < 1.0)
+                .build(&event_loop)
+                .unwrap();
+            let size = if self.args.overlay {
+                // Just the screen at the configured scale
+                let scale = self.config.window.screen_scale as f64;
+                let (width, height) = chip8_core::SCREEN_SIZE;
+                LogicalSize::new(width as f64 * scale, height as f64 * scale)
+            } else {
+                LogicalSize::new(self.config.window.width, self.config.window.height)
+            };
+            window.set_inner_size(size);
+            window.set_always_on_top(self.config.window.always_on_top);
             window.set_title("chip8-rust");
+            if self.kiosk.is_some() {
+                window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+                window.set_cursor_visible(false);
+            }
             let size = window.inner_size();
 
-            let surface = wgpu::Surface::create(&window);
-
-            (window, size, surface)
+            (window, size)
         };
 
-        let adapter = block_on(wgpu::Adapter::request(
-            &wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: Some(&surface),
-            },
-            wgpu::BackendBit::PRIMARY,
-        ))
-        .unwrap();
+        let instance = gpu::instance();
+        // The window outlives the surface, both are owned by the event loop closure
+        let surface = unsafe { instance.create_surface(&window) };
 
-        let (mut device, mut queue) = block_on(adapter.request_device(&wgpu::DeviceDescriptor {
-            extensions: wgpu::Extensions {
-                anisotropic_filtering: false,
-            },
-            limits: wgpu::Limits::default(),
-        }));
-
-        // Set up swap chain
-        let mut sc_desc = wgpu::SwapChainDescriptor {
-            usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-            format: wgpu::TextureFormat::Bgra8Unorm,
-            width: size.width as u32,
-            height: size.height as u32,
-            present_mode: wgpu::PresentMode::Mailbox,
+        let (device, queue) = match gpu::open(&instance, &surface, &self.config.gpu) {
+            Ok(gpu) => gpu,
+            Err(e) => {
+                log::error!("{}", e);
+                std::process::exit(1);
+            }
         };
 
-        let mut swap_chain = device.create_swap_chain(&surface, &sc_desc);
+        // Set up the surface, it is reconfigured on resize and when it gets outdated or lost
+        let mut surface = WindowSurface::new(
+            surface,
+            wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                format: wgpu::TextureFormat::Bgra8Unorm,
+                width: size.width,
+                height: size.height,
+                present_mode: wgpu::PresentMode::Mailbox,
+            },
+            &device,
+        );
 
         // Set up dear imgui
         let mut imgui = imgui::Context::create();
@@ -272,117 +1255,79 @@ impl Chip8App {
             &window,
             imgui_winit_support::HiDpiMode::Default,
         );
+        // Layout is saved on exit together with the config, not whenever imgui feels like it
         imgui.set_ini_filename(None);
+        if let Some(clipboard) = Clipboard::new() {
+            imgui.set_clipboard_backend(Box::new(clipboard));
+        }
+        if let Some(layout) = Config::load_layout() {
+            imgui.load_ini_settings(&layout);
+        }
 
-        let font_size = (13.0 * hidpi_factor) as f32;
-        imgui.io_mut().font_global_scale = (1.0 / hidpi_factor) as f32;
-
-        imgui.fonts().add_font(&[FontSource::DefaultFontData {
-            config: Some(imgui::FontConfig {
-                oversample_h: 1,
-                pixel_snap_h: true,
-                size_pixels: font_size,
-                ..Default::default()
-            }),
-        }]);
+        let mut fonts = FontSettings::new(&self.config.window, platform.hidpi_factor());
+        fonts.build(&mut imgui);
 
-        // Restyle a bit
+        // Restyle a bit, colors come from the theme and sizes follow the UI scale
         let style = imgui.style_mut();
         style.window_rounding = 8.0;
         style.scrollbar_rounding = 8.0;
         style.frame_rounding = 8.0;
-        style[imgui::StyleColor::TitleBg] = to_rgb01([110, 110, 100, 62]);
-        style[imgui::StyleColor::TitleBgCollapsed] = to_rgb01([110, 110, 100, 52]);
-        style[imgui::StyleColor::TitleBgActive] = to_rgb01([110, 110, 100, 87]);
-        style[imgui::StyleColor::Header] = to_rgb01([110, 110, 110, 52]);
-        style[imgui::StyleColor::HeaderHovered] = to_rgb01([110, 110, 110, 92]);
-        style[imgui::StyleColor::HeaderActive] = to_rgb01([110, 110, 110, 72]);
-        style[imgui::StyleColor::ScrollbarBg] = to_rgb01([110, 110, 110, 12]);
-        style[imgui::StyleColor::ScrollbarGrab] = to_rgb01([110, 110, 110, 52]);
-        style[imgui::StyleColor::ScrollbarGrabHovered] = to_rgb01([110, 110, 110, 92]);
-        style[imgui::StyleColor::ScrollbarGrabActive] = to_rgb01([110, 110, 110, 72]);
-        style[imgui::StyleColor::SliderGrab] = to_rgb01([110, 110, 110, 52]);
-        style[imgui::StyleColor::SliderGrabActive] = to_rgb01([110, 110, 110, 72]);
-        style[imgui::StyleColor::Button] = to_rgb01([182, 182, 182, 60]);
-        style[imgui::StyleColor::ButtonHovered] = to_rgb01([182, 182, 182, 200]);
-        style[imgui::StyleColor::ButtonActive] = to_rgb01([182, 182, 182, 140]);
-        style[imgui::StyleColor::PopupBg] = to_rgb01([0, 0, 0, 230]);
-        style[imgui::StyleColor::TextSelectedBg] = to_rgb01([10, 23, 18, 180]);
-        style[imgui::StyleColor::FrameBg] = to_rgb01([70, 70, 70, 30]);
-        style[imgui::StyleColor::FrameBgHovered] = to_rgb01([70, 70, 70, 70]);
-        style[imgui::StyleColor::FrameBgActive] = to_rgb01([70, 70, 70, 50]);
-        style[imgui::StyleColor::MenuBarBg] = to_rgb01([70, 70, 70, 30]);
+        let base_style = *style;
+        fonts.scale_style(&base_style, style);
+        let mut theme = self.config.theme;
+        theme.apply(style);
+        let mut always_on_top = self.config.window.always_on_top;
+        let mut opacity = self.config.window.opacity;
+        style.alpha = opacity;
 
         // Setup dear imgui wgpu renderer
-        let clear_color = wgpu::Color {
-            r: 0.03,
-            g: 0.03,
-            b: 0.03,
-            a: 1.0,
-        };
         let mut renderer = Renderer::new(
             &mut imgui,
             &device,
-            &mut queue,
-            sc_desc.format,
-            Some(clear_color),
+            &queue,
+            surface.config.format,
+            Some(self.clear_color(theme)),
         );
 
         let mut last_frame = Instant::now();
-
-        let mut screen = ScreenBuffer::new(&mut renderer, &device);
+        let mut next_frame = last_frame;
+        let mut minimized = false;
+        let mut focused = true;
 
         let mut last_cursor = None;
+        let mut last_title = String::new();
 
         // Event loop
         event_loop.run(move |event, _, control_flow| {
             let self_mut = Rc::get_mut(&mut self).unwrap();
 
-            *control_flow = if cfg!(feature = "metal-auto-capture") {
-                ControlFlow::Exit
-            } else {
-                ControlFlow::Poll
-            };
             match event {
-                Event::WindowEvent {
-                    event: WindowEvent::ScaleFactorChanged { scale_factor, .. },
-                    ..
-                } => {
-                    hidpi_factor = scale_factor;
-                }
                 Event::WindowEvent {
                     event: WindowEvent::Resized(_),
                     ..
                 } => {
-                    size = window.inner_size();
-
-                    sc_desc = wgpu::SwapChainDescriptor {
-                        usage: wgpu::TextureUsage::OUTPUT_ATTACHMENT,
-                        format: wgpu::TextureFormat::Bgra8Unorm,
-                        width: size.width as u32,
-                        height: size.height as u32,
-                        present_mode: wgpu::PresentMode::Mailbox,
-                    };
+                    let size = window.inner_size();
+                    // Windows reports a zero size when minimized, there's nothing to draw to
+                    minimized = size.width == 0 || size.height == 0;
+                    self_mut.set_window_active(focused, minimized);
+                    if minimized {
+                        return;
+                    }
 
-                    swap_chain = device.create_swap_chain(&surface, &sc_desc);
+                    surface.resize(&device, size);
                 }
                 Event::WindowEvent {
-                    event:
-                        WindowEvent::KeyboardInput {
-                            input:
-                                KeyboardInput {
-                                    virtual_keycode: Some(VirtualKeyCode::Escape),
-                                    state: ElementState::Pressed,
-                                    ..
-                                },
-                            ..
-                        },
+                    event: WindowEvent::Focused(now_focused),
                     ..
+                } => {
+                    focused = now_focused;
+                    self_mut.set_window_active(focused, minimized);
                 }
-                | Event::WindowEvent {
+                Event::WindowEvent {
                     event: WindowEvent::CloseRequested,
                     ..
                 } => {
+                    self_mut.shutdown(&window, &mut imgui);
                     *control_flow = ControlFlow::Exit;
                 }
                 Event::WindowEvent {
@@ -398,40 +1343,132 @@ impl Chip8App {
                         },
                     ..
                 } => {
-                    self_mut.set_key_state(virtual_keycode, state == ElementState::Pressed);
+                    let pressed = state == ElementState::Pressed;
+                    // Keys typed into a text field are for the UI, releases still go
+                    // through so nothing stays held down
+                    let typing = pressed && imgui.io().want_capture_keyboard;
+                    let fullscreen = window.fullscreen().is_some();
+                    match self_mut.config.hotkeys.find(virtual_keycode) {
+                        _ if typing => {}
+                        Some(Hotkey::FastForward) => self_mut.set_fast_forward(pressed),
+                        Some(Hotkey::AdvanceFrames) => self_mut.set_frame_advance(pressed),
+                        Some(Hotkey::Fullscreen) if pressed => {
+                            let fullscreen = match window.fullscreen() {
+                                Some(_) => None,
+                                None => Some(Fullscreen::Borderless(window.current_monitor())),
+                            };
+                            window.set_fullscreen(fullscreen);
+                        }
+                        Some(hotkey) if pressed => self_mut.hotkey(hotkey),
+                        Some(_) => {}
+                        None => self_mut.set_key_state(virtual_keycode, pressed, fullscreen),
+                    }
                 }
-                Event::MainEventsCleared => {
+                Event::MainEventsCleared if !minimized && Instant::now() >= next_frame => {
                     window.request_redraw();
                 }
-                Event::RedrawEventsCleared => {
+                Event::RedrawRequested(_) => {
                     last_frame = imgui.io_mut().update_delta_time(last_frame);
+                    let frame_time = if self_mut.is_idle() {
+                        IDLE_FRAME_TIME
+                    } else {
+                        ACTIVE_FRAME_TIME
+                    };
+                    next_frame = last_frame + Duration::from_secs_f32(frame_time);
 
-                    let frame = match swap_chain.get_next_texture() {
-                        Ok(frame) => frame,
+                    let frame = surface.next_frame(&instance, &window, &device);
+                    if surface.is_stalled() != self_mut.drawing_stalled {
+                        // Games wait while nothing can be shown
+                        self_mut.drawing_stalled = surface.is_stalled();
+                        self_mut.set_window_active(focused, minimized);
+                    }
+                    let frame = match frame {
+                        Ok(Some(frame)) => frame,
+                        Ok(None) => return,
                         Err(e) => {
-                            eprintln!("dropped frame: {:?}", e);
+                            log::error!("{}", e);
+                            self_mut.shutdown(&window, &mut imgui);
+                            *control_flow = ControlFlow::Exit;
                             return;
                         }
                     };
+                    let view = frame
+                        .texture
+                        .create_view(&wgpu::TextureViewDescriptor::default());
+                    // Theme or opacity picked in the last frame
+                    let window_config = &self_mut.config.window;
+                    if theme != self_mut.config.theme || opacity != window_config.opacity {
+                        theme = self_mut.config.theme;
+                        opacity = window_config.opacity;
+                        theme.apply(imgui.style_mut());
+                        imgui.style_mut().alpha = opacity;
+                        renderer.set_clear_color(Some(self_mut.clear_color(theme)));
+                    }
+                    if always_on_top != window_config.always_on_top {
+                        always_on_top = window_config.always_on_top;
+                        window.set_always_on_top(always_on_top);
+                    }
+                    // UI scale or font changed in settings, or the window moved to another display
+                    let new_fonts =
+                        FontSettings::new(&self_mut.config.window, platform.hidpi_factor());
+                    if new_fonts != fonts {
+                        fonts = new_fonts;
+                        fonts.build(&mut imgui);
+                        fonts.scale_style(&base_style, imgui.style_mut());
+                        renderer.reload_font_texture(&mut imgui, &device, &queue);
+                    }
                     platform
                         .prepare_frame(imgui.io_mut(), &window)
                         .expect("Failed to prepare frame");
                     let ui = imgui.frame();
 
-                    // Run emulator update
-                    self_mut.emulator.update(ui.io().delta_time);
-
-                    // Read and update screen buffer if changed:
-                    if self_mut.emulator.screen.is_dirty() {
-                        self_mut.emulator.screen.reset_dirty();
+                    self_mut.poll_loader();
+                    self_mut.repeat_frame_advance();
 
-                        screen.update(&self_mut.emulator, &mut renderer, &device, &mut queue);
+                    if let Some(kiosk) = &mut self_mut.kiosk {
+                        kiosk.create_texture(&mut renderer, &device, &queue);
+                    }
+                    // Emulators run on their own threads, pick up their latest output
+                    let mut times = FrameTimes {
+                        frame: ui.io().delta_time * 1000.0,
+                        ..FrameTimes::default()
+                    };
+                    for instance in &mut self_mut.instances {
+                        instance.screen.create_texture(&mut renderer, &device);
+                        if let Some(frame) = instance.emulator.poll_frame() {
+                            times.emulation += frame.emulation_time.as_secs_f32() * 1000.0;
+                            if let Some(screen) = &frame.screen {
+                                let started = Instant::now();
+                                instance.screen.update(screen, &mut renderer, &queue);
+                                times.upload += started.elapsed().as_secs_f32() * 1000.0;
+                                if let Some(recording) = &mut instance.recording {
+                                    recording.push(screen);
+                                }
+                            }
+                            instance.beep = frame.beep;
+                        }
+                        instance.update_ips();
+                        instance.check_triggers();
+                    }
+                    if let Some(perf_graphs) = &mut self_mut.perf_graphs {
+                        perf_graphs.push(times);
+                    }
+                    if let Some(observer) = &self_mut.observer {
+                        let instance = &self_mut.instances[self_mut.focused];
+                        let snapshot =
+                            observe::snapshot(&instance.emulator.lock(), instance.paused);
+                        observer.publish(snapshot);
                     }
 
                     // Draw actual app UI
-                    self_mut.draw_ui(&ui);
-                    // Draw screen window
-                    screen.draw_ui(&ui);
+                    if let Some(index) = self_mut.draw_ui(&ui) {
+                        self_mut.close_instance(index, &mut renderer);
+                    }
+                    let title = self_mut.window_title();
+                    if title != last_title {
+                        window.set_title(&title);
+                        last_title = title;
+                    }
 
                     let mut encoder: wgpu::CommandEncoder = device
                         .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
@@ -441,15 +1478,60 @@ impl Chip8App {
                         platform.prepare_render(&ui, &window);
                     }
                     renderer
-                        .render(ui.render(), &mut device, &mut encoder, &frame.view)
+                        .render(ui.render(), &device, &queue, &mut encoder, &view)
                         .expect("Rendering failed");
 
-                    queue.submit(&[encoder.finish()]);
+                    queue.submit(Some(encoder.finish()));
+                    frame.present();
+
+                    if self_mut.quit {
+                        self_mut.shutdown(&window, &mut imgui);
+                        *control_flow = ControlFlow::Exit;
+                    }
                 }
                 _ => (),
             }
 
+            // Input makes the UI responsive again without waiting out an idle frame
+            if let Event::WindowEvent { .. } = event {
+                next_frame =
+                    next_frame.min(last_frame + Duration::from_secs_f32(ACTIVE_FRAME_TIME));
+            }
+            if *control_flow != ControlFlow::Exit {
+                *control_flow = if minimized {
+                    // Restoring the window sends a resize, which wakes the loop
+                    ControlFlow::Wait
+                } else {
+                    ControlFlow::WaitUntil(next_frame)
+                };
+            }
+
             platform.handle_event(imgui.io_mut(), &window, &event);
         });
     }
 }
+
+// Moves and resizes windows that exist to where a preset has them
+fn move_windows(preset: &LayoutPreset) {
+    for rect in preset.window_rects() {
+        let title = match CString::new(rect.title) {
+            Ok(title) => title,
+            Err(_) => continue,
+        };
+        let [x, y] = rect.pos;
+        unsafe {
+            sys::igSetWindowPosStr(
+                title.as_ptr(),
+                sys::ImVec2 { x, y },
+                Condition::Always as i32,
+            );
+            if let Some([x, y]) = rect.size {
+                sys::igSetWindowSizeStr(
+                    title.as_ptr(),
+                    sys::ImVec2 { x, y },
+                    Condition::Always as i32,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::config::Palette;
+use crate::http;
+use crate::screenshot;
+use chip8_core::{Emulator, TIMER_HZ};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Drives the emulator with text commands read from stdin, one per line, until
+/// stdin is closed or `quit` is read. Every command answers with one line on
+/// stdout, `ok`, the result or `error: <message>`, so scripts can wait for it:
+///
+/// ```text
+/// press 5          # Hold keypad key 5 (hex, 0-F)
+/// release 5
+/// wait 10          # Run 10 frames (1/60 s each), 1 if no count is given
+/// run 500          # Execute 500 instructions
+/// screenshot a.png # Save the screen as PNG, the file name is the rest of the line
+/// dump-state       # Print CPU state as JSON, same as /state.json of --serve
+/// quit
+/// ```
+///
+/// Empty lines and text after `#` are ignored.
+pub fn run(mut emulator: Emulator, palette: &Palette, scale: u32) -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let command = line.split('#').next().unwrap_or_default().trim();
+        if command.is_empty() {
+            continue;
+        }
+        if command == "quit" {
+            writeln!(out, "ok")?;
+            break;
+        }
+        match execute(&mut emulator, command, palette, scale) {
+            Ok(reply) => writeln!(out, "{}", reply)?,
+            Err(e) => writeln!(out, "error: {}", e)?,
+        }
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// Runs one command, returns the line to answer with
+fn execute(
+    emulator: &mut Emulator,
+    command: &str,
+    palette: &Palette,
+    scale: u32,
+) -> Result<String, String> {
+    let (name, rest) = command
+        .split_once(char::is_whitespace)
+        .map_or((command, ""), |(name, rest)| (name, rest.trim()));
+    // A file name is the rest of the line, so it can have spaces
+    if name == "screenshot" {
+        if rest.is_empty() {
+            return Err("'screenshot' needs a file name".into());
+        }
+        let path = Path::new(rest);
+        screenshot::save(emulator.screen(), palette, scale, path)
+            .map_err(|e| format!("can't save '{0}': {1}", path.display(), e))?;
+        return Ok("ok".into());
+    }
+    let mut words = rest.split_whitespace();
+    let argument = words.next();
+    if words.next().is_some() {
+        return Err(format!("too many arguments for '{}'", name));
+    }
+    let count = |default: u64| match argument {
+        Some(text) => text
+            .parse::<u64>()
+            .map_err(|_| format!("'{}' isn't a count", text)),
+        None => Ok(default),
+    };
+    let key = || {
+        let text = argument.ok_or_else(|| format!("'{}' needs a key", name))?;
+        match u8::from_str_radix(text, 16) {
+            Ok(key) if key <= 0xF => Ok(key),
+            _ => Err(format!("'{}' isn't a keypad key", text)),
+        }
+    };
+
+    match name {
+        "press" => emulator.set_key(key()?, true),
+        "release" => emulator.set_key(key()?, false),
+        "wait" => {
+            for _ in 0..count(1)? {
+                emulator.run_for(1.0 / TIMER_HZ);
+            }
+        }
+        "run" => emulator.run_cycles(count(1)? as usize),
+        "dump-state" => return Ok(http::state_json(emulator).to_string()),
+        _ => return Err(format!("unknown command '{}'", name)),
+    }
+    Ok("ok".into())
+}
@@ -0,0 +1,68 @@
+//! Battery-backed memory: a range of RAM set in the ROM's options is kept in a
+//! `.sav` file next to the ROM, so games that keep high scores in memory remember them.
+
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// `Game.ch8` -> `Game.sav`
+pub fn path_for_rom(rom_file: &Path) -> PathBuf {
+    rom_file.with_extension("sav")
+}
+
+/// Last address a range can include, the end of XO-CHIP's 64KB
+const MAX_ADDRESS: usize = 0xFFFF;
+
+/// Parses "3F0-3FF" (hex, both ends included, "0x" allowed) into a byte range
+pub fn parse_range(text: &str) -> Option<Range<usize>> {
+    let hex = |s: &str| {
+        let s = s.trim();
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        usize::from_str_radix(s, 16).ok()
+    };
+    let (start, end) = text.split_once('-')?;
+    let (start, end) = (hex(start)?, hex(end)?);
+    if start > end || end > MAX_ADDRESS {
+        return None;
+    }
+    Some(start..end.checked_add(1)?)
+}
+
+/// Saved bytes for the ROM, `Ok(None)` if nothing was saved yet
+pub fn load(rom_file: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path_for_rom(rom_file)) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn save(rom_file: &Path, bytes: &[u8]) -> io::Result<()> {
+    fs::write(path_for_rom(rom_file), bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_includes_both_ends() {
+        assert_eq!(parse_range("3F0-3FF"), Some(0x3F0..0x400));
+        assert_eq!(parse_range(" 0x3F0 - 0x3F0 "), Some(0x3F0..0x3F1));
+        assert_eq!(parse_range("0-FFFF"), Some(0..0x10000));
+    }
+
+    #[test]
+    fn broken_ranges_are_rejected() {
+        for text in ["", "3F0", "3FF-3F0", "3F0-xyz", "-3FF"] {
+            assert_eq!(parse_range(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn ranges_past_memory_are_rejected() {
+        assert_eq!(parse_range("FFF0-10000"), None);
+        assert_eq!(parse_range("0-FFFFFFFFFFFFFFFF"), None);
+    }
+}
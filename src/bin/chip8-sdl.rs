@@ -0,0 +1,274 @@
+//! Lightweight SDL2 frontend: plain window with the scaled chip8 screen and a beep.
+//! Built only with `--features sdl`, usage: `chip8-sdl <rom file>`.
+//! The audio buffer size is read from the debugger's config file, see `Config::audio_buffer`.
+
+use chip8_core as chip8;
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::event::Event;
+use sdl2::keyboard::Scancode;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::AudioSubsystem;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// Window pixels per chip8 pixel
+const SCALE: u32 = 10;
+
+/// Pixel colors (RGB)
+const ON_COLOR: [u8; 3] = [0x17, 0x99, 0x00];
+const OFF_COLOR: [u8; 3] = [0x00, 0x00, 0x00];
+
+/// Beep sample rate, matches `config::AUDIO_SAMPLE_RATE` of the debugger
+const SAMPLE_RATE: i32 = 44100;
+
+// The part of the debugger's config.toml this frontend uses, other values are ignored
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SdlConfig {
+    audio_buffer: Option<u16>,
+}
+
+impl SdlConfig {
+    fn load() -> Self {
+        let path = match dirs::config_dir() {
+            Some(dir) => dir.join("chip8-rust").join("config.toml"),
+            None => return SdlConfig::default(),
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).unwrap_or_else(|e| {
+                eprintln!("Can't read config '{0}'. Error: {1}", path.display(), e);
+                SdlConfig::default()
+            }),
+            Err(_) => SdlConfig::default(),
+        }
+    }
+}
+
+// Same layout as the imgui app: 1-4, Q-R, A-F, Z-V
+fn keypad_index(code: Scancode) -> Option<usize> {
+    let key = match code {
+        Scancode::Num1 => 0,
+        Scancode::Num2 => 1,
+        Scancode::Num3 => 2,
+        Scancode::Num4 => 3,
+        Scancode::Q => 4,
+        Scancode::W => 5,
+        Scancode::E => 6,
+        Scancode::R => 7,
+        Scancode::A => 8,
+        Scancode::S => 9,
+        Scancode::D => 10,
+        Scancode::F => 11,
+        Scancode::Z => 12,
+        Scancode::X => 13,
+        Scancode::C => 14,
+        Scancode::V => 15,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// Screen converted to RGB24, the changed parts are uploaded into a texture by the main loop
+struct PixelBuffer {
+    data: Vec<u8>,
+    dirty: Vec<chip8::DirtyRect>,
+}
+
+impl chip8::Display for PixelBuffer {
+    fn draw(&mut self, screen: &chip8::Screen) {
+        let (w, _) = screen.resolution();
+        for rect in screen.dirty_rects() {
+            for y in rect.y..rect.y + rect.height {
+                for x in rect.x..rect.x + rect.width {
+                    let color = if screen.get_pixel(x, y) {
+                        ON_COLOR
+                    } else {
+                        OFF_COLOR
+                    };
+                    let pos = (x + y * w) * 3;
+                    self.data[pos..pos + 3].copy_from_slice(&color);
+                }
+            }
+            self.dirty.push(rect);
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeyState {
+    down: [bool; 16],
+}
+
+impl chip8::Input for KeyState {
+    fn is_key_down(&self, key: u8) -> bool {
+        self.down[key as usize]
+    }
+}
+
+// Square wave generator for the beep
+struct SquareWave {
+    phase_inc: f32,
+    phase: f32,
+    volume: f32,
+}
+
+impl AudioCallback for SquareWave {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for x in out.iter_mut() {
+            *x = if self.phase <= 0.5 {
+                self.volume
+            } else {
+                -self.volume
+            };
+            self.phase = (self.phase + self.phase_inc) % 1.0;
+        }
+    }
+}
+
+struct Beeper {
+    device: AudioDevice<SquareWave>,
+}
+
+impl chip8::Audio for Beeper {
+    fn set_beep(&mut self, on: bool) {
+        if on {
+            self.device.resume();
+        } else {
+            self.device.pause();
+        }
+    }
+}
+
+// Opens playback with the configured buffer size. A size the device rejects falls back
+// to the one SDL picks, so a bad setting costs latency instead of the sound.
+fn open_beeper(
+    audio: &AudioSubsystem,
+    buffer: Option<u16>,
+) -> Result<AudioDevice<SquareWave>, String> {
+    let open = |samples| {
+        let spec = AudioSpecDesired {
+            freq: Some(SAMPLE_RATE),
+            channels: Some(1),
+            samples,
+        };
+        audio.open_playback(None, &spec, |spec| SquareWave {
+            phase_inc: 440.0 / spec.freq as f32,
+            phase: 0.0,
+            volume: 0.25,
+        })
+    };
+    let device = match buffer {
+        Some(samples) => open(Some(samples)).or_else(|e| {
+            eprintln!(
+                "Can't open audio with a {0} sample buffer, using the default. Error: {1}",
+                samples, e
+            );
+            open(None)
+        })?,
+        None => open(None)?,
+    };
+    let obtained = device.spec().samples;
+    if buffer.is_some_and(|samples| samples != obtained) {
+        log::info!("Audio device uses a {} sample buffer", obtained);
+    }
+    Ok(device)
+}
+
+fn main() -> Result<(), String> {
+    let rom = std::env::args()
+        .nth(1)
+        .ok_or("usage: chip8-sdl <rom file>")?;
+
+    let mut emulator = chip8::Emulator::new();
+    emulator
+        .load_rom(Path::new(&rom))
+        .map_err(|e| format!("Can't read file: '{}'. Error: {}", rom, e))?;
+
+    let sdl = sdl2::init()?;
+    let video = sdl.video()?;
+    let audio = sdl.audio()?;
+
+    let (w, h) = chip8::SCREEN_SIZE;
+    let window = video
+        .window("chip8-rust", w as u32 * SCALE, h as u32 * SCALE)
+        .position_centered()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut canvas = window
+        .into_canvas()
+        .present_vsync()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator
+        .create_texture_streaming(PixelFormatEnum::RGB24, w as u32, h as u32)
+        .map_err(|e| e.to_string())?;
+
+    let device = open_beeper(&audio, SdlConfig::load().audio_buffer)?;
+    let mut beeper = Beeper { device };
+
+    let mut pixels = PixelBuffer {
+        data: vec![0; w * h * 3],
+        dirty: Vec::new(),
+    };
+    let mut keys = KeyState::default();
+    let mut events = sdl.event_pump()?;
+    let mut last_frame = Instant::now();
+
+    'running: loop {
+        for event in events.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    scancode: Some(Scancode::Escape),
+                    ..
+                } => break 'running,
+                Event::KeyDown {
+                    scancode: Some(code),
+                    ..
+                } => {
+                    if let Some(key) = keypad_index(code) {
+                        keys.down[key] = true;
+                    }
+                }
+                Event::KeyUp {
+                    scancode: Some(code),
+                    ..
+                } => {
+                    if let Some(key) = keypad_index(code) {
+                        keys.down[key] = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+        let dt = now.duration_since(last_frame).as_secs_f32();
+        last_frame = now;
+        emulator.run_frame(dt, &keys, &mut pixels, &mut beeper);
+
+        for rect in pixels.dirty.drain(..) {
+            let area = Rect::new(
+                rect.x as i32,
+                rect.y as i32,
+                rect.width as u32,
+                rect.height as u32,
+            );
+            // Starts at the rect's first pixel, rows stay a full screen width apart
+            let start = (rect.x + rect.y * w) * 3;
+            texture
+                .update(area, &pixels.data[start..], w * 3)
+                .map_err(|e| e.to_string())?;
+        }
+        canvas.copy(&texture, None, None)?;
+        canvas.present();
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use crate::config::{Config, Palette};
+use crate::logger;
+use crate::octo::OctoOptions;
+use crate::screenshot;
+use chip8_core::Emulator;
+use image::ImageOutputFormat;
+use std::fs::File;
+use std::io::{self, Cursor, Write};
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// Everything needed to reproduce what an instance does, captured at one moment
+pub struct BugReport {
+    info: String,
+    state: Vec<u8>,
+    options: String,
+    config: String,
+    log: String,
+    screenshot: Vec<u8>,
+}
+
+impl BugReport {
+    /// Captures the emulator with the ROM it runs, the ROM itself isn't included, only its hash
+    pub fn capture(
+        emulator: &Emulator,
+        rom_file: &Path,
+        options: &OctoOptions,
+        palette: &Palette,
+        config: &Config,
+    ) -> Self {
+        let state = emulator.save_state();
+        let mut info = String::new();
+        info.push_str(&format!("chip8-rust {}\n", env!("CARGO_PKG_VERSION")));
+        info.push_str(&format!("ROM: {}\n", rom_file.display()));
+        info.push_str(&format!(
+            "ROM SHA-1: {}\n",
+            sha1_smol::Sha1::from(&state.rom).digest()
+        ));
+        info.push_str(&format!("ROM size: {} bytes\n", state.rom.len()));
+        info.push_str(&format!("PC: {:03X}\n", emulator.pc()));
+        info.push_str(&format!("Halt: {:?}\n", emulator.halt_reason()));
+        info.push_str(&format!("Instructions: {}\n", emulator.instruction_count()));
+        info.push_str(&format!("Clock: {} Hz\n", emulator.clock_hz()));
+        info.push_str(&format!("Quirks: {:?}\n", emulator.quirks()));
+        info.push_str(&format!("State hash: {:016x}\n", emulator.state_hash()));
+
+        let mut log = String::new();
+        if let Some(logger) = logger::get() {
+            logger.for_each_line(|level, line| log.push_str(&format!("{:<5} {}\n", level, line)));
+        }
+
+        let mut screenshot = Vec::new();
+        let image = screenshot::render(emulator.screen(), palette, 8);
+        // Encoding into memory can't fail
+        let _ = image::DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut screenshot), ImageOutputFormat::Png);
+
+        BugReport {
+            info,
+            state: state.to_bytes(),
+            options: serde_json::to_string_pretty(options).unwrap_or_default(),
+            config: toml::to_string(config).unwrap_or_default(),
+            log,
+            screenshot,
+        }
+    }
+
+    /// Writes the report as a zip archive
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut zip = ZipWriter::new(File::create(path)?);
+        let files: [(&str, &[u8]); 6] = [
+            ("info.txt", self.info.as_bytes()),
+            ("state.state", &self.state),
+            ("options.json", self.options.as_bytes()),
+            ("config.toml", self.config.as_bytes()),
+            ("log.txt", self.log.as_bytes()),
+            ("screenshot.png", &self.screenshot),
+        ];
+        for (name, data) in files.iter() {
+            zip.start_file(*name, FileOptions::default())?;
+            zip.write_all(data)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
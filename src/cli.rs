@@ -0,0 +1,309 @@
+use chip8_core::Quirks;
+use clap::{crate_version, value_t, App, Arg, SubCommand};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Command line arguments
+pub struct Args {
+    /// ROM to load on startup
+    pub rom: Option<PathBuf>,
+    /// Overrides speed from config and ROM options
+    pub speed: Option<f32>,
+    /// Overrides quirks from config and ROM options
+    pub quirks: Option<Quirks>,
+    pub scale: Option<f32>,
+    /// Playlist to show full screen without UI, see `kiosk::Playlist`
+    pub kiosk: Option<PathBuf>,
+    /// See-through window showing only the screen, see `Chip8App::draw_overlay`
+    pub overlay: bool,
+    /// Reload the ROM whenever its file is saved
+    pub watch: bool,
+    /// File to write executed instructions into
+    pub trace: Option<PathBuf>,
+    pub headless: bool,
+    pub cycles: u64,
+    pub screenshot: Option<PathBuf>,
+    /// Address to serve screen and CPU state on in headless mode
+    pub serve: Option<String>,
+    /// Address to stream the focused instance's state on, see `observe`
+    pub observe: Option<String>,
+    /// Take commands from stdin in headless mode, see `automation::run`
+    pub stdin: bool,
+    /// `smoke` subcommand
+    pub smoke: Option<SmokeArgs>,
+    /// `test-all` subcommand
+    pub test_all: Option<TestAllArgs>,
+    /// `diff-state` subcommand
+    pub diff_state: Option<DiffStateArgs>,
+}
+
+/// Arguments of the `smoke` subcommand
+pub struct SmokeArgs {
+    /// Directory to scan, ROM directory from config if not given
+    pub dir: Option<PathBuf>,
+    /// Instructions to run per ROM
+    pub cycles: u64,
+}
+
+/// Arguments of the `test-all` subcommand
+pub struct TestAllArgs {
+    /// Directory to scan, ROM directory from config if not given
+    pub dir: Option<PathBuf>,
+    /// Instructions to run per ROM and preset
+    pub cycles: u64,
+    pub format: MatrixFormat,
+    /// File to write the matrix into, stdout if not given
+    pub output: Option<PathBuf>,
+}
+
+pub enum MatrixFormat {
+    Markdown,
+    Csv,
+}
+
+/// Arguments of the `diff-state` subcommand
+pub struct DiffStateArgs {
+    pub a: PathBuf,
+    pub b: PathBuf,
+}
+
+impl Args {
+    pub fn parse() -> Self {
+        let matches = App::new("chip8-rust")
+            .version(crate_version!())
+            .about("CHIP-8 emulator with a debugger UI")
+            .arg(
+                Arg::with_name("rom")
+                    .long("rom")
+                    .value_name("FILE")
+                    .help("ROM file to load on startup"),
+            )
+            .arg(
+                Arg::with_name("speed")
+                    .long("speed")
+                    .value_name("HZ")
+                    .help("Instructions per second"),
+            )
+            .arg(
+                Arg::with_name("quirks-preset")
+                    .long("quirks-preset")
+                    .value_name("PRESET")
+                    .possible_values(&Quirks::PRESET_NAMES)
+                    .case_insensitive(true)
+                    .help("Interpreter quirks to emulate"),
+            )
+            .arg(
+                Arg::with_name("scale")
+                    .long("scale")
+                    .value_name("N")
+                    .help("Pixels per chip8 pixel on screen and in screenshots"),
+            )
+            .arg(
+                Arg::with_name("kiosk")
+                    .long("kiosk")
+                    .value_name("PLAYLIST")
+                    .conflicts_with("headless")
+                    .help("Cycle full screen through the ROMs of a TOML playlist, without UI"),
+            )
+            .arg(
+                Arg::with_name("overlay")
+                    .long("overlay")
+                    .conflicts_with_all(&["kiosk", "headless"])
+                    .help("Show only the screen in a see-through window with transparent off pixels, as a desktop overlay"),
+            )
+            .arg(
+                Arg::with_name("watch")
+                    .long("watch")
+                    .requires("rom")
+                    .help("Reload the ROM whenever its file changes, Octo sources are assembled again"),
+            )
+            .arg(
+                Arg::with_name("trace")
+                    .long("trace")
+                    .value_name("FILE")
+                    .help("Write address and opcode of every executed instruction to FILE"),
+            )
+            .arg(
+                Arg::with_name("headless")
+                    .long("headless")
+                    .requires("rom")
+                    .help("Run without a window and exit"),
+            )
+            .arg(
+                Arg::with_name("cycles")
+                    .long("cycles")
+                    .value_name("N")
+                    .requires("headless")
+                    .help("Instructions to run in headless mode [default: 1000]"),
+            )
+            .arg(
+                Arg::with_name("screenshot")
+                    .long("screenshot")
+                    .value_name("FILE")
+                    .requires("headless")
+                    .help("Save screen as PNG after a headless run"),
+            )
+            .arg(
+                Arg::with_name("serve")
+                    .long("serve")
+                    .value_name("ADDR")
+                    .requires("headless")
+                    .conflicts_with_all(&["cycles", "screenshot"])
+                    .help("Run headless in real time and serve /screen.png and /state.json on ADDR (e.g. 127.0.0.1:8080)"),
+            )
+            .arg(
+                Arg::with_name("observe")
+                    .long("observe")
+                    .value_name("ADDR")
+                    .conflicts_with("headless")
+                    .help("Stream the screen and CPU state of the focused instance to tools connecting to ADDR (e.g. 127.0.0.1:8765)"),
+            )
+            .arg(
+                Arg::with_name("stdin")
+                    .long("stdin")
+                    .requires("headless")
+                    .conflicts_with_all(&["cycles", "screenshot", "serve"])
+                    .help("Run headless on commands read from stdin: press K, release K, wait N, run N, screenshot FILE, dump-state, quit"),
+            )
+            .subcommand(
+                SubCommand::with_name("smoke")
+                    .about("Runs every ROM in a directory headlessly and prints a compatibility report")
+                    .arg(
+                        Arg::with_name("dir")
+                            .long("dir")
+                            .value_name("DIR")
+                            .help("Directory with ROMs [default: ROM directory from config]"),
+                    )
+                    .arg(
+                        Arg::with_name("cycles")
+                            .long("cycles")
+                            .value_name("N")
+                            .help("Instructions to run per ROM [default: 100000]"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("test-all")
+                    .about("Runs every ROM in a directory under its suggested quirks and writes a compatibility matrix")
+                    .arg(
+                        Arg::with_name("dir")
+                            .long("dir")
+                            .value_name("DIR")
+                            .help("Directory with ROMs [default: ROM directory from config]"),
+                    )
+                    .arg(
+                        Arg::with_name("cycles")
+                            .long("cycles")
+                            .value_name("N")
+                            .help("Instructions to run per ROM and quirks preset [default: 100000]"),
+                    )
+                    .arg(
+                        Arg::with_name("format")
+                            .long("format")
+                            .value_name("FORMAT")
+                            .possible_values(&["markdown", "csv"])
+                            .default_value("markdown")
+                            .help("Matrix format"),
+                    )
+                    .arg(
+                        Arg::with_name("output")
+                            .long("output")
+                            .value_name("FILE")
+                            .help("Write the matrix into FILE instead of stdout"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("diff-state")
+                    .about("Prints the registers, memory and screen that differ between two save states")
+                    .arg(Arg::with_name("a").value_name("A").required(true))
+                    .arg(Arg::with_name("b").value_name("B").required(true)),
+            )
+            .get_matches();
+
+        let speed = if matches.is_present("speed") {
+            Some(value_t!(matches, "speed", f32).unwrap_or_else(|e| e.exit()))
+        } else {
+            None
+        };
+        let scale = if matches.is_present("scale") {
+            Some(value_t!(matches, "scale", f32).unwrap_or_else(|e| e.exit()))
+        } else {
+            None
+        };
+        let cycles = if matches.is_present("cycles") {
+            value_t!(matches, "cycles", u64).unwrap_or_else(|e| e.exit())
+        } else {
+            1000
+        };
+
+        let smoke = matches.subcommand_matches("smoke").map(|smoke| SmokeArgs {
+            dir: smoke.value_of("dir").map(PathBuf::from),
+            cycles: if smoke.is_present("cycles") {
+                value_t!(smoke, "cycles", u64).unwrap_or_else(|e| e.exit())
+            } else {
+                100_000
+            },
+        });
+
+        let test_all = matches
+            .subcommand_matches("test-all")
+            .map(|test_all| TestAllArgs {
+                dir: test_all.value_of("dir").map(PathBuf::from),
+                cycles: if test_all.is_present("cycles") {
+                    value_t!(test_all, "cycles", u64).unwrap_or_else(|e| e.exit())
+                } else {
+                    100_000
+                },
+                format: match test_all.value_of("format") {
+                    Some("csv") => MatrixFormat::Csv,
+                    _ => MatrixFormat::Markdown,
+                },
+                output: test_all.value_of("output").map(PathBuf::from),
+            });
+
+        let diff_state = matches
+            .subcommand_matches("diff-state")
+            .map(|diff| DiffStateArgs {
+                a: PathBuf::from(diff.value_of("a").unwrap()),
+                b: PathBuf::from(diff.value_of("b").unwrap()),
+            });
+
+        Args {
+            rom: matches.value_of("rom").map(PathBuf::from),
+            speed: speed.filter(|&hz| hz > 0.0),
+            quirks: matches.value_of("quirks-preset").and_then(Quirks::preset),
+            scale,
+            kiosk: matches.value_of("kiosk").map(PathBuf::from),
+            overlay: matches.is_present("overlay"),
+            watch: matches.is_present("watch"),
+            trace: matches.value_of("trace").map(PathBuf::from),
+            headless: matches.is_present("headless"),
+            cycles,
+            screenshot: matches.value_of("screenshot").map(PathBuf::from),
+            serve: matches.value_of("serve").map(String::from),
+            observe: matches.value_of("observe").map(String::from),
+            stdin: matches.is_present("stdin"),
+            smoke,
+            test_all,
+            diff_state,
+        }
+    }
+
+    /// Applies speed and quirks given on the command line
+    pub fn apply_overrides(&self, emulator: &mut chip8_core::Emulator) {
+        if let Some(speed) = self.speed {
+            emulator.set_clock_hz(speed);
+        }
+        if let Some(quirks) = self.quirks {
+            emulator.set_quirks(quirks);
+        }
+    }
+}
+
+/// Trace hook writing "PC OPCODE" lines into a file
+pub fn file_trace_hook(path: &Path) -> io::Result<chip8_core::TraceHook> {
+    let mut out = BufWriter::new(File::create(path)?);
+    Ok(Box::new(move |pc, opcode| {
+        let _ = writeln!(out, "{:04X} {:04X}", pc, opcode);
+    }))
+}
@@ -0,0 +1,40 @@
+use image::RgbaImage;
+use imgui::{ClipboardBackend, ImStr, ImString};
+use std::borrow::Cow;
+
+/// System clipboard for imgui, without it copied text only stays inside the app
+pub struct Clipboard(arboard::Clipboard);
+
+impl Clipboard {
+    /// `None` if the system clipboard can't be opened, e.g. without a display server
+    pub fn new() -> Option<Self> {
+        match arboard::Clipboard::new() {
+            Ok(clipboard) => Some(Clipboard(clipboard)),
+            Err(e) => {
+                log::info!("System clipboard isn't available. Error: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl ClipboardBackend for Clipboard {
+    fn get(&mut self) -> Option<ImString> {
+        self.0.get_text().ok().map(ImString::new)
+    }
+
+    fn set(&mut self, value: &ImStr) {
+        if let Err(e) = self.0.set_text(value.to_string()) {
+            log::error!("Can't copy to clipboard. Error: {}", e);
+        }
+    }
+}
+
+/// Puts an image on the system clipboard. On Linux it can be pasted for as long as the app runs.
+pub fn copy_image(image: &RgbaImage) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new()?.set_image(arboard::ImageData {
+        width: image.width() as usize,
+        height: image.height() as usize,
+        bytes: Cow::Borrowed(image.as_raw()),
+    })
+}
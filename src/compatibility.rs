@@ -0,0 +1,128 @@
+//! Compatibility report: runs a ROM without a window under every quirk preset
+//! and tells which ones get through without faults, to suggest quirks for it.
+
+use chip8_core::{Emulator, HaltReason, Instruction, LoadError, Quirks, StepEvent};
+
+/// Instructions each preset runs for, about a minute of a game at usual speeds
+pub const CYCLES: u64 = 1_000_000;
+
+/// Calls nested deeper than this overflow the SUPER-CHIP stack, the VIP has less
+const MAX_STACK: usize = 16;
+
+/// How a run under one preset went
+pub struct PresetRun {
+    pub preset: &'static str,
+    pub quirks: Quirks,
+    /// First fault, the run stops there
+    pub fault: Option<String>,
+    /// Instructions that ran until the fault, the end of the program or `CYCLES`
+    pub instructions: u64,
+    /// Machine state at the end, runs with the same hash did the same
+    pub state_hash: u64,
+}
+
+pub struct Report {
+    /// One run per preset, in `Quirks::PRESET_NAMES` order
+    pub runs: Vec<PresetRun>,
+}
+
+impl Report {
+    /// Runs `rom` under every preset, blocks until all runs are done
+    pub fn run(
+        rom: &[u8],
+        clock_hz: f32,
+        memory_size: usize,
+        cycles: u64,
+    ) -> Result<Self, LoadError> {
+        let runs = Quirks::PRESET_NAMES
+            .iter()
+            .map(|&preset| {
+                let quirks = Quirks::preset(preset).unwrap();
+                let mut emulator = Emulator::builder()
+                    .quirks(quirks)
+                    .clock_hz(clock_hz)
+                    .memory_size(memory_size)
+                    .seed(0)
+                    .build();
+                emulator.load_rom_bytes(rom)?;
+                let fault = run_until_fault(&mut emulator, cycles, &mut |_| {});
+                Ok(PresetRun {
+                    preset,
+                    quirks,
+                    fault,
+                    instructions: emulator.instruction_count(),
+                    state_hash: emulator.state_hash(),
+                })
+            })
+            .collect::<Result<_, LoadError>>()?;
+        Ok(Report { runs })
+    }
+
+    /// Best run: one without faults, `current` quirks if they are among them.
+    /// If every run faulted, the one that got furthest.
+    pub fn suggestion(&self, current: Quirks) -> Option<&PresetRun> {
+        let ok = || self.runs.iter().filter(|run| run.fault.is_none());
+        ok().find(|run| run.quirks == current)
+            .or_else(|| ok().next())
+            .or_else(|| self.runs.iter().max_by_key(|run| run.instructions))
+    }
+
+    /// Every run without faults ended in the same state, so the quirks don't
+    /// matter for the part of the program that ran
+    pub fn quirks_matter(&self) -> bool {
+        let mut ok = self.runs.iter().filter(|run| run.fault.is_none());
+        match ok.next() {
+            Some(first) => ok.any(|run| run.state_hash != first.state_hash),
+            None => true,
+        }
+    }
+}
+
+/// Steps until something goes wrong, pressing a key whenever the program waits for one.
+/// Checks the instruction before running it, so illegal opcodes aren't logged.
+/// `inspect` sees the opcode of every instruction about to run.
+pub fn run_until_fault(
+    emulator: &mut Emulator,
+    cycles: u64,
+    inspect: &mut dyn FnMut(u16),
+) -> Option<String> {
+    let mut pressed = None;
+    for _ in 0..cycles {
+        if let Some(key) = pressed.take() {
+            emulator.set_key(key, false);
+        }
+        let pc = emulator.pc();
+        let bytes = emulator.memory().get(pc as usize..pc as usize + 2);
+        let opcode = bytes.map(|op| u16::from_be_bytes([op[0], op[1]]));
+        if let Some(opcode) = opcode {
+            inspect(opcode);
+        }
+        match opcode.map(Instruction::decode) {
+            Some(Instruction::Unknown(opcode)) => {
+                return Some(format!("illegal opcode {:04X} at {:03X}", opcode, pc));
+            }
+            Some(Instruction::Return) if emulator.stack().is_empty() => {
+                return Some(format!("return with an empty stack at {:03X}", pc));
+            }
+            Some(Instruction::Call { .. }) if emulator.stack().len() >= MAX_STACK => {
+                return Some(format!("stack overflow at {:03X}", pc));
+            }
+            // Parked in a jump-to-self loop, the program is done
+            Some(Instruction::Jump { nnn }) if nnn == pc => return None,
+            _ => {}
+        }
+        match emulator.step() {
+            StepEvent::Halted(HaltReason::PcOutOfBounds(pc)) => {
+                return Some(format!("PC out of memory at {:04X}", pc));
+            }
+            StepEvent::Halted(_) => return None,
+            StepEvent::WaitingForKey => {
+                let key = (emulator.stats().key_waits % 16) as u8;
+                emulator.set_key(key, true);
+                pressed = Some(key);
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,586 @@
+use crate::fonts;
+use crate::i18n::Language;
+use crate::layouts::LayoutPreset;
+use crate::octo::{format_color, parse_color};
+use crate::theme::Theme;
+use chip8_core::{Quirks, DEFAULT_CLOCK_HZ, MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use winit::event::VirtualKeyCode;
+
+/// Application settings stored in `<config dir>/chip8-rust/config.toml`.
+/// Missing values fall back to defaults, so a partial file is fine.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Directory scanned for ROMs, `roms` next to the repo checkout if not set
+    pub rom_dir: Option<PathBuf>,
+    /// Speed used for ROMs that don't come with their own options
+    pub clock_hz: f32,
+    /// Keyboard key for each of the 16 chip8 keys, indexed by hex key value
+    pub keys: [VirtualKeyCode; 16],
+    /// UI colors, picking a theme also resets `palette` to match it
+    pub theme: Theme,
+    /// Language of menus, labels and the help text
+    pub language: Language,
+    /// 64KB of memory like XO-CHIP instead of the standard 4KB
+    pub xo_chip_memory: bool,
+    /// Instructions take as long as on the COSMAC VIP, instead of `clock_hz`
+    pub vip_timing: bool,
+    /// Speed multiplier while the fast-forward key is held
+    pub fast_forward_speed: f32,
+    /// Emulators wake up on every key change and run in shorter steps, see `EmulatorThread::set_low_latency`
+    pub low_latency_input: bool,
+    /// Recently loaded ROMs for the File menu, most recent first
+    pub recent_roms: Vec<PathBuf>,
+    /// Ask before a ROM replaces a game that is still running
+    pub confirm_rom_switch: bool,
+    /// Save a state of the running game before another ROM replaces it
+    pub save_state_on_switch: bool,
+    /// Offer a matching profile when a loaded ROM looks written for SUPER-CHIP or XO-CHIP
+    pub suggest_profile: bool,
+    /// Pause emulators while another application has keyboard focus
+    pub pause_when_unfocused: bool,
+    /// Pause emulators while the window is minimized
+    pub pause_when_minimized: bool,
+    /// Shown on the screen while the sound timer runs
+    pub beep_indicator: BeepIndicator,
+    /// Audio output buffer in samples for the SDL frontend, `None` lets the device pick.
+    /// Bigger buffers crackle less, smaller ones start and stop beeps sooner.
+    pub audio_buffer: Option<u16>,
+    // Tables from here on, TOML can't have plain values after them
+    pub palette: Palette,
+    /// Quirks used for ROMs that don't come with their own options
+    pub quirks: Quirks,
+    pub hotkeys: Hotkeys,
+    /// Layout presets saved from the View menu, the built-in ones aren't kept here
+    // An empty array would be a plain value after the tables above
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub layouts: Vec<LayoutPreset>,
+    pub window: WindowConfig,
+    /// GPU the window is drawn with, changes apply on the next start
+    pub gpu: GpuConfig,
+}
+
+/// Sample rate the SDL frontend asks for, used to show buffer sizes as latency
+pub const AUDIO_SAMPLE_RATE: u32 = 44100;
+
+/// Buffer sizes offered in the settings, audio devices want powers of two
+pub const AUDIO_BUFFER_SIZES: [u16; 5] = [256, 512, 1024, 2048, 4096];
+
+/// How a beep shows on the screen, for playing without sound
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BeepIndicator {
+    #[default]
+    Off,
+    /// Frame around the screen in the foreground color
+    Border,
+    /// Speaker in the top left corner
+    Icon,
+}
+
+impl BeepIndicator {
+    pub const ALL: [BeepIndicator; 3] = [
+        BeepIndicator::Off,
+        BeepIndicator::Border,
+        BeepIndicator::Icon,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BeepIndicator::Off => "Off",
+            BeepIndicator::Border => "Border flash",
+            BeepIndicator::Icon => "Speaker icon",
+        }
+    }
+
+    /// Draws the indicator over a screen at `p0` of `size`
+    pub fn draw(self, ui: &imgui::Ui, p0: [f32; 2], size: [f32; 2], color: [f32; 4]) {
+        let draw_list = ui.get_window_draw_list();
+        match self {
+            BeepIndicator::Off => {}
+            BeepIndicator::Border => {
+                let [x, y] = p0;
+                draw_list
+                    .add_rect(
+                        [x - 2.0, y - 2.0],
+                        [x + size[0] + 2.0, y + size[1] + 2.0],
+                        color,
+                    )
+                    .thickness(4.0)
+                    .build();
+            }
+            BeepIndicator::Icon => {
+                // Box and cone of the speaker with two sound waves
+                let [x, y] = [p0[0] + 8.0, p0[1] + 8.0];
+                draw_list
+                    .add_rect([x, y + 5.0], [x + 5.0, y + 11.0], color)
+                    .filled(true)
+                    .build();
+                draw_list
+                    .add_triangle(
+                        [x + 4.0, y + 8.0],
+                        [x + 11.0, y],
+                        [x + 11.0, y + 16.0],
+                        color,
+                    )
+                    .filled(true)
+                    .build();
+                for &(dx, height) in &[(14.0, 3.0), (18.0, 6.0)] {
+                    draw_list
+                        .add_line(
+                            [x + dx, y + 8.0 - height],
+                            [x + dx, y + 8.0 + height],
+                            color,
+                        )
+                        .thickness(2.0)
+                        .build();
+                }
+            }
+        }
+    }
+}
+
+/// Chip8 keys in the order they sit on the COSMAC VIP keypad, row by row
+pub const KEYPAD_LAYOUT: [usize; 16] = [
+    0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+];
+
+/// Short name of a keyboard key for the UI, `Key1` shows as `1`
+pub fn key_name(code: VirtualKeyCode) -> String {
+    let name = format!("{:?}", code);
+    match name.strip_prefix("Key") {
+        Some(digit) => digit.to_string(),
+        None => name,
+    }
+}
+
+/// Emulator actions triggered from the keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hotkey {
+    Quit,
+    Pause,
+    Step,
+    Reset,
+    Reload,
+    FastForward,
+    Screenshot,
+    CopyScreen,
+    SaveState,
+    LoadState,
+    Fullscreen,
+    SlowMotion25,
+    SlowMotion50,
+    AdvanceFrames,
+    NextBookmark,
+    PreviousBookmark,
+}
+
+impl Hotkey {
+    pub const ALL: [Hotkey; 16] = [
+        Hotkey::Quit,
+        Hotkey::Pause,
+        Hotkey::Step,
+        Hotkey::Reset,
+        Hotkey::Reload,
+        Hotkey::FastForward,
+        Hotkey::Screenshot,
+        Hotkey::CopyScreen,
+        Hotkey::SaveState,
+        Hotkey::LoadState,
+        Hotkey::Fullscreen,
+        Hotkey::SlowMotion25,
+        Hotkey::SlowMotion50,
+        Hotkey::AdvanceFrames,
+        Hotkey::NextBookmark,
+        Hotkey::PreviousBookmark,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hotkey::Quit => "Quit",
+            Hotkey::Pause => "Pause",
+            Hotkey::Step => "Step",
+            Hotkey::Reset => "Reset",
+            Hotkey::Reload => "Reload ROM",
+            Hotkey::FastForward => "Fast-forward (hold)",
+            Hotkey::Screenshot => "Screenshot",
+            Hotkey::CopyScreen => "Copy screen",
+            Hotkey::SaveState => "Save state",
+            Hotkey::LoadState => "Load state",
+            Hotkey::Fullscreen => "Fullscreen",
+            Hotkey::SlowMotion25 => "Slow motion 25%",
+            Hotkey::SlowMotion50 => "Slow motion 50%",
+            Hotkey::AdvanceFrames => "Advance frames (hold)",
+            Hotkey::NextBookmark => "Next bookmark",
+            Hotkey::PreviousBookmark => "Previous bookmark",
+        }
+    }
+}
+
+/// Keyboard key for each hotkey, these take precedence over keypad keys
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Hotkeys {
+    pub quit: VirtualKeyCode,
+    pub pause: VirtualKeyCode,
+    pub step: VirtualKeyCode,
+    pub reset: VirtualKeyCode,
+    pub reload: VirtualKeyCode,
+    pub fast_forward: VirtualKeyCode,
+    pub screenshot: VirtualKeyCode,
+    pub copy_screen: VirtualKeyCode,
+    pub save_state: VirtualKeyCode,
+    pub load_state: VirtualKeyCode,
+    pub fullscreen: VirtualKeyCode,
+    pub slow_motion_25: VirtualKeyCode,
+    pub slow_motion_50: VirtualKeyCode,
+    pub advance_frames: VirtualKeyCode,
+    pub next_bookmark: VirtualKeyCode,
+    pub previous_bookmark: VirtualKeyCode,
+}
+
+/// Colors used to draw the emulator screen
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Palette {
+    #[serde(with = "hex_color")]
+    pub fg: [f32; 4],
+    #[serde(with = "hex_color")]
+    pub bg: [f32; 4],
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub width: f64,
+    pub height: f64,
+    /// Screen window pixels per chip8 pixel
+    pub screen_scale: f32,
+    /// Size of text and widgets, on top of the display's own scale factor
+    pub ui_scale: f32,
+    /// TTF font for the UI, imgui's built-in font if not set
+    pub font: Option<PathBuf>,
+    /// Font size in pixels at UI scale 1
+    pub font_size: f32,
+    /// Names of the windows open on exit, views decide for themselves if not set
+    pub open_views: Option<Vec<String>>,
+    /// Keep the window over other applications
+    pub always_on_top: bool,
+    /// 1 is opaque, lower values let the desktop show through the whole window
+    pub opacity: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct GpuConfig {
+    /// Adapter name as the driver reports it, picked by `power` if not set or not found
+    pub adapter: Option<String>,
+    pub power: GpuPower,
+}
+
+/// Kind of GPU to prefer when more than one is available
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum GpuPower {
+    /// Discrete GPU on laptops that have one
+    #[default]
+    HighPerformance,
+    /// Integrated GPU, easier on the battery
+    LowPower,
+}
+
+impl GpuPower {
+    pub const ALL: [GpuPower; 2] = [GpuPower::HighPerformance, GpuPower::LowPower];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GpuPower::HighPerformance => "High performance",
+            GpuPower::LowPower => "Low power",
+        }
+    }
+
+    pub fn preference(self) -> wgpu::PowerPreference {
+        match self {
+            GpuPower::HighPerformance => wgpu::PowerPreference::HighPerformance,
+            GpuPower::LowPower => wgpu::PowerPreference::LowPower,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        use VirtualKeyCode::*;
+        Config {
+            rom_dir: None,
+            clock_hz: DEFAULT_CLOCK_HZ,
+            keys: [Key1, Key2, Key3, Key4, Q, W, E, R, A, S, D, F, Z, X, C, V],
+            theme: Theme::default(),
+            language: Language::default(),
+            xo_chip_memory: false,
+            vip_timing: false,
+            fast_forward_speed: 10.0,
+            low_latency_input: false,
+            recent_roms: Vec::new(),
+            confirm_rom_switch: true,
+            save_state_on_switch: false,
+            suggest_profile: true,
+            pause_when_unfocused: false,
+            pause_when_minimized: true,
+            beep_indicator: BeepIndicator::default(),
+            audio_buffer: None,
+            palette: Palette::default(),
+            quirks: Quirks::default(),
+            hotkeys: Hotkeys::default(),
+            layouts: Vec::new(),
+            window: WindowConfig::default(),
+            gpu: GpuConfig::default(),
+        }
+    }
+}
+
+impl Default for Hotkeys {
+    fn default() -> Self {
+        use VirtualKeyCode::*;
+        Hotkeys {
+            quit: Escape,
+            pause: P,
+            step: F10,
+            reset: F5,
+            reload: F6,
+            fast_forward: Tab,
+            screenshot: F12,
+            copy_screen: F4,
+            save_state: F2,
+            load_state: F3,
+            fullscreen: F11,
+            slow_motion_25: F7,
+            slow_motion_50: F8,
+            advance_frames: F9,
+            next_bookmark: PageDown,
+            previous_bookmark: PageUp,
+        }
+    }
+}
+
+impl Hotkeys {
+    pub fn key_mut(&mut self, hotkey: Hotkey) -> &mut VirtualKeyCode {
+        match hotkey {
+            Hotkey::Quit => &mut self.quit,
+            Hotkey::Pause => &mut self.pause,
+            Hotkey::Step => &mut self.step,
+            Hotkey::Reset => &mut self.reset,
+            Hotkey::Reload => &mut self.reload,
+            Hotkey::FastForward => &mut self.fast_forward,
+            Hotkey::Screenshot => &mut self.screenshot,
+            Hotkey::CopyScreen => &mut self.copy_screen,
+            Hotkey::SaveState => &mut self.save_state,
+            Hotkey::LoadState => &mut self.load_state,
+            Hotkey::Fullscreen => &mut self.fullscreen,
+            Hotkey::SlowMotion25 => &mut self.slow_motion_25,
+            Hotkey::SlowMotion50 => &mut self.slow_motion_50,
+            Hotkey::AdvanceFrames => &mut self.advance_frames,
+            Hotkey::NextBookmark => &mut self.next_bookmark,
+            Hotkey::PreviousBookmark => &mut self.previous_bookmark,
+        }
+    }
+
+    pub fn key(&self, hotkey: Hotkey) -> VirtualKeyCode {
+        match hotkey {
+            Hotkey::Quit => self.quit,
+            Hotkey::Pause => self.pause,
+            Hotkey::Step => self.step,
+            Hotkey::Reset => self.reset,
+            Hotkey::Reload => self.reload,
+            Hotkey::FastForward => self.fast_forward,
+            Hotkey::Screenshot => self.screenshot,
+            Hotkey::CopyScreen => self.copy_screen,
+            Hotkey::SaveState => self.save_state,
+            Hotkey::LoadState => self.load_state,
+            Hotkey::Fullscreen => self.fullscreen,
+            Hotkey::SlowMotion25 => self.slow_motion_25,
+            Hotkey::SlowMotion50 => self.slow_motion_50,
+            Hotkey::AdvanceFrames => self.advance_frames,
+            Hotkey::NextBookmark => self.next_bookmark,
+            Hotkey::PreviousBookmark => self.previous_bookmark,
+        }
+    }
+
+    /// Hotkey bound to a keyboard key
+    pub fn find(&self, code: VirtualKeyCode) -> Option<Hotkey> {
+        Hotkey::ALL
+            .iter()
+            .copied()
+            .find(|&hotkey| self.key(hotkey) == code)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            fg: [0.09_f32, 0.6_f32, 0.0_f32, 1.0_f32],
+            bg: [0.0_f32, 0.0_f32, 0.0_f32, 1.0_f32],
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            width: 1600.0,
+            height: 900.0,
+            screen_scale: 9.0,
+            ui_scale: 1.0,
+            font: None,
+            font_size: fonts::DEFAULT_SIZE,
+            open_views: None,
+            always_on_top: false,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Config {
+    /// Memory size in bytes for new emulators and loaded ROMs
+    pub fn memory_size(&self) -> usize {
+        if self.xo_chip_memory {
+            XO_CHIP_MEMORY_SIZE
+        } else {
+            MEMORY_SIZE
+        }
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("chip8-rust").join("config.toml"))
+    }
+
+    /// Where crash dumps are written, next to the config file
+    pub fn crash_dir() -> Option<PathBuf> {
+        Self::path().map(|path| path.with_file_name("crashes"))
+    }
+
+    /// Where imgui window positions and sizes are kept, next to the config file
+    pub fn layout_path() -> Option<PathBuf> {
+        Self::path().map(|path| path.with_file_name("layout.ini"))
+    }
+
+    /// Reads window layout saved by `save_layout`, `None` on the first run
+    pub fn load_layout() -> Option<String> {
+        let path = Self::layout_path()?;
+        match fs::read_to_string(&path) {
+            Ok(layout) => Some(layout),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                log::warn!("Can't read layout '{0}'. Error: {1}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn save_layout(layout: &str) -> io::Result<()> {
+        let path = Self::layout_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, layout)
+    }
+
+    /// Reads config file, defaults are used if it doesn't exist or can't be parsed.
+    pub fn load() -> Self {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Config::default(),
+        };
+        let parsed = fs::read_to_string(&path).and_then(|text| {
+            toml::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        });
+        match parsed {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("Can't read config '{0}'. Error: {1}", path.display(), e);
+                Config::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text =
+            toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, text)
+    }
+
+    /// ROM directory from config, or `roms` relative to the executable in `target/<profile>`
+    pub fn rom_dir(&self) -> PathBuf {
+        match &self.rom_dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let exe_path = std::env::current_exe().unwrap();
+                exe_path.parent().unwrap().join("../../roms")
+            }
+        }
+    }
+
+    /// Moves `rom_file` to the top of the recent ROMs list
+    pub fn add_recent_rom(&mut self, rom_file: &Path) {
+        const MAX_RECENT_ROMS: usize = 10;
+        self.recent_roms.retain(|recent| recent != rom_file);
+        self.recent_roms.insert(0, rom_file.to_path_buf());
+        self.recent_roms.truncate(MAX_RECENT_ROMS);
+    }
+
+    /// chip8 key bound to a keyboard key
+    pub fn keypad_index(&self, code: VirtualKeyCode) -> Option<usize> {
+        self.keys.iter().position(|&k| k == code)
+    }
+}
+
+// Colors are stored as "#RRGGBB" strings, same as in Octo options
+mod hex_color {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(color: &[f32; 4], s: S) -> Result<S::Ok, S::Error> {
+        format_color(*color).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[f32; 4], D::Error> {
+        let s = String::deserialize(d)?;
+        parse_color(&s).ok_or_else(|| serde::de::Error::custom(format!("bad color '{}'", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_round_trips() {
+        let text = toml::to_string(&Config::default()).unwrap();
+        let config: Config = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string(&config).unwrap(), text);
+    }
+
+    #[test]
+    fn config_with_every_list_set_round_trips() {
+        let mut config = Config {
+            rom_dir: Some("roms".into()),
+            audio_buffer: Some(512),
+            recent_roms: vec!["a.ch8".into(), "b.ch8".into()],
+            layouts: vec![LayoutPreset::built_in("Debug").unwrap()],
+            ..Config::default()
+        };
+        config.window.open_views = Some(vec!["Screen".into()]);
+        config.gpu.adapter = Some("GPU".into());
+        let text = toml::to_string(&config).unwrap();
+        let parsed: Config = toml::from_str(&text).unwrap();
+        assert_eq!(toml::to_string(&parsed).unwrap(), text);
+    }
+}
@@ -0,0 +1,202 @@
+//! Panic hook that writes a crash dump: the panic with its backtrace, the state
+//! of every emulator with its ROM hash and last instructions, and the log. The
+//! dump is a zip in the crashes directory next to the config file, its path is
+//! printed and shown in a message box so it can go into a bug report.
+
+use crate::config::Config;
+use crate::logger;
+use chip8_core::{Emulator, Instruction};
+use std::backtrace::Backtrace;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, TryLockError, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// Instructions each emulator keeps for the dump, see `Emulator::set_history_len`
+pub const TRACE_LEN: usize = 64;
+
+/// How long to wait for an emulator another thread is using before leaving it out
+const LOCK_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Emulators that go into a dump, by instance id. Weak, so closed instances just drop out.
+static EMULATORS: Mutex<Vec<(usize, Weak<Mutex<Emulator>>)>> = Mutex::new(Vec::new());
+
+/// Panic of an emulator thread, written once the thread has unwound and let go of its emulator
+static PENDING: Mutex<Option<String>> = Mutex::new(None);
+
+/// Dump written by an earlier panic, later panics are usually fallout from it
+static WRITTEN: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Name of the threads `EmulatorThread` runs emulators on
+pub const EMULATOR_THREAD: &str = "emulator";
+
+/// Installs the panic hook, the default hook still prints the panic first
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let panic = describe(info);
+        if thread::current().name() == Some(EMULATOR_THREAD) {
+            // The emulator is locked by this thread until it unwinds, see `write_pending`
+            PENDING
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert(panic);
+        } else {
+            write(&panic);
+        }
+    }));
+}
+
+/// Adds an instance's emulator to the dumps
+pub fn register(id: usize, emulator: Weak<Mutex<Emulator>>) {
+    let mut emulators = EMULATORS.lock().unwrap();
+    emulators.retain(|(_, emulator)| emulator.strong_count() > 0);
+    emulators.push((id, emulator));
+}
+
+/// Writes the dump for a panic of an emulator thread, called once it has unwound
+pub fn write_pending() {
+    let panic = PENDING.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(panic) = panic {
+        write(&panic);
+    }
+}
+
+fn describe(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    let location = info.location().map_or_else(String::new, |location| {
+        format!(" at {}:{}", location.file(), location.line())
+    });
+    format!(
+        "Thread '{}' panicked{}: {}\n\n{}\n",
+        thread::current().name().unwrap_or("unnamed"),
+        location,
+        message,
+        Backtrace::force_capture()
+    )
+}
+
+fn write(panic: &str) {
+    let mut written = WRITTEN.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(path) = &*written {
+        eprintln!("Crash dump was already saved to '{}'", path.display());
+        return;
+    }
+    let path = match Config::crash_dir() {
+        Some(dir) => {
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs());
+            dir.join(format!("crash-{}.zip", time))
+        }
+        None => return,
+    };
+    match save(panic, &path) {
+        Ok(()) => {
+            let text = format!(
+                "chip8-rust crashed, a dump was saved to '{}'",
+                path.display()
+            );
+            log::error!("{}", text);
+            tinyfiledialogs::message_box_ok(
+                "chip8-rust",
+                &text,
+                tinyfiledialogs::MessageBoxIcon::Error,
+            );
+            *written = Some(path);
+        }
+        Err(e) => log::error!("Can't save crash dump '{0}'. Error: {1}", path.display(), e),
+    }
+}
+
+fn save(panic: &str, path: &Path) -> io::Result<()> {
+    let mut info = format!("chip8-rust {}\n{}", env!("CARGO_PKG_VERSION"), panic);
+    let mut files = Vec::new();
+    let emulators: Vec<_> = EMULATORS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .filter_map(|(id, emulator)| Some((*id, emulator.upgrade()?)))
+        .collect();
+    for (id, emulator) in emulators {
+        let _ = writeln!(info, "\nEmulator #{}", id);
+        match lock(&emulator, |emulator| describe_emulator(emulator, &mut info)) {
+            Some(state) => files.push((format!("emulator-{}.state", id), state)),
+            None => info.push_str("State unavailable, the emulator was in use\n"),
+        }
+    }
+
+    let mut log = String::new();
+    if let Some(logger) = logger::get() {
+        logger.for_each_line(|level, line| log.push_str(&format!("{:<5} {}\n", level, line)));
+    }
+    files.push(("crash.txt".to_string(), info.into_bytes()));
+    files.push(("log.txt".to_string(), log.into_bytes()));
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut zip = ZipWriter::new(File::create(path)?);
+    for (name, data) in &files {
+        zip.start_file(name.as_str(), FileOptions::default())?;
+        zip.write_all(data)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+// Runs `f` on the emulator, also when a panic poisoned it. Gives up if another
+// thread keeps it locked, like the panicking thread itself.
+fn lock<T>(emulator: &Arc<Mutex<Emulator>>, f: impl FnOnce(&Emulator) -> T) -> Option<T> {
+    let start = Instant::now();
+    loop {
+        match emulator.try_lock() {
+            Ok(emulator) => return Some(f(&emulator)),
+            Err(TryLockError::Poisoned(e)) => return Some(f(&e.into_inner())),
+            Err(TryLockError::WouldBlock) if start.elapsed() < LOCK_TIMEOUT => {
+                thread::sleep(Duration::from_millis(5));
+            }
+            Err(TryLockError::WouldBlock) => return None,
+        }
+    }
+}
+
+// Adds the emulator's state to `info`, returns its save state
+fn describe_emulator(emulator: &Emulator, info: &mut String) -> Vec<u8> {
+    let state = emulator.save_state();
+    let _ = writeln!(
+        info,
+        "ROM SHA-1: {}",
+        sha1_smol::Sha1::from(&state.rom).digest()
+    );
+    let _ = writeln!(info, "ROM size: {} bytes", state.rom.len());
+    let _ = writeln!(info, "PC: {:03X}", emulator.pc());
+    let _ = writeln!(info, "Halt: {:?}", emulator.halt_reason());
+    let _ = writeln!(info, "Instructions: {}", emulator.instruction_count());
+    let _ = writeln!(info, "Clock: {} Hz", emulator.clock_hz());
+    let _ = writeln!(info, "Quirks: {:?}", emulator.quirks());
+    let _ = writeln!(info, "State hash: {:016x}", emulator.state_hash());
+    info.push_str("Last instructions:\n");
+    for (pc, opcode) in emulator.history() {
+        let _ = writeln!(
+            info,
+            "{:04X} {:04X} {}",
+            pc,
+            opcode,
+            Instruction::decode(opcode)
+        );
+    }
+    state.to_bytes()
+}
@@ -0,0 +1,178 @@
+//! Breakpoints, code comments, labels, bookmarks and notes of a ROM, kept
+//! between runs in the config directory under the SHA-1 of the ROM
+//! (`sessions/<sha1>.toml`), so they come back whenever the same program is
+//! opened, wherever its file is.
+
+use crate::config::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize)]
+struct Comment {
+    address: u16,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct DebugSession {
+    #[serde(default)]
+    pub breakpoints: Vec<u16>,
+    /// Free-form notes on the whole ROM
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    // Lists of tables from here on, TOML can't have plain values after them,
+    // and an empty list would be one
+    /// TOML keys have to be strings, so comments are a list
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    comments: Vec<Comment>,
+    /// Names given to addresses, stored like comments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<Comment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bookmarks: Vec<Comment>,
+}
+
+fn to_list(map: &BTreeMap<u16, String>) -> Vec<Comment> {
+    map.iter()
+        .map(|(&address, text)| Comment {
+            address,
+            text: text.clone(),
+        })
+        .collect()
+}
+
+fn to_map(list: &[Comment]) -> BTreeMap<u16, String> {
+    list.iter()
+        .map(|comment| (comment.address, comment.text.clone()))
+        .collect()
+}
+
+/// What `DebugSession::new` keeps besides breakpoints, by address
+pub struct Annotations<'a> {
+    pub comments: &'a BTreeMap<u16, String>,
+    pub labels: &'a BTreeMap<u16, String>,
+    pub bookmarks: &'a BTreeMap<u16, String>,
+    pub notes: &'a str,
+}
+
+impl DebugSession {
+    pub fn new(breakpoints: Vec<u16>, annotations: Annotations) -> Self {
+        DebugSession {
+            breakpoints,
+            comments: to_list(annotations.comments),
+            labels: to_list(annotations.labels),
+            bookmarks: to_list(annotations.bookmarks),
+            notes: annotations.notes.to_string(),
+        }
+    }
+
+    pub fn comments(&self) -> BTreeMap<u16, String> {
+        to_map(&self.comments)
+    }
+
+    pub fn labels(&self) -> BTreeMap<u16, String> {
+        to_map(&self.labels)
+    }
+
+    pub fn bookmarks(&self) -> BTreeMap<u16, String> {
+        to_map(&self.bookmarks)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.breakpoints.is_empty()
+            && self.comments.is_empty()
+            && self.labels.is_empty()
+            && self.bookmarks.is_empty()
+            && self.notes.trim().is_empty()
+    }
+
+    pub fn path_for_rom(rom: &[u8]) -> Option<PathBuf> {
+        let hash = sha1_smol::Sha1::from(rom).digest().to_string();
+        let path = Config::path()?.with_file_name("sessions");
+        Some(path.join(hash).with_extension("toml"))
+    }
+
+    /// Session saved for the ROM, `Ok(None)` if there is none
+    pub fn load(rom: &[u8]) -> io::Result<Option<Self>> {
+        let path = match Self::path_for_rom(rom) {
+            Some(path) if path.exists() => path,
+            _ => return Ok(None),
+        };
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Writes the session, an empty one removes the file so nothing comes back
+    pub fn save(&self, rom: &[u8]) -> io::Result<()> {
+        let path = match Self::path_for_rom(rom) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if self.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(session: &DebugSession) -> DebugSession {
+        let text = toml::to_string_pretty(session).unwrap();
+        toml::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn session_with_every_field_round_trips() {
+        let map = |text: &str| BTreeMap::from([(0x200, text.to_string())]);
+        let (comments, labels, bookmarks) = (map("comment"), map("main"), map("bookmark"));
+        let session = DebugSession::new(
+            vec![0x200, 0x2A4],
+            Annotations {
+                comments: &comments,
+                labels: &labels,
+                bookmarks: &bookmarks,
+                notes: "Needs the shift quirk",
+            },
+        );
+        let parsed = round_trip(&session);
+        assert_eq!(parsed.breakpoints, vec![0x200, 0x2A4]);
+        assert_eq!(parsed.comments(), comments);
+        assert_eq!(parsed.labels(), labels);
+        assert_eq!(parsed.bookmarks(), bookmarks);
+        assert_eq!(parsed.notes, "Needs the shift quirk");
+    }
+
+    #[test]
+    fn session_with_some_lists_empty_round_trips() {
+        let empty = BTreeMap::new();
+        let comments = BTreeMap::from([(0x300, "loop".to_string())]);
+        let session = DebugSession::new(
+            Vec::new(),
+            Annotations {
+                comments: &comments,
+                labels: &empty,
+                bookmarks: &empty,
+                notes: "",
+            },
+        );
+        let parsed = round_trip(&session);
+        assert_eq!(parsed.comments(), comments);
+        assert!(parsed.labels().is_empty());
+    }
+}
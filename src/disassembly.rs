@@ -0,0 +1,350 @@
+use chip8_core::{Emulator, FlowGraph, Instruction};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// What a line of the ROM turned out to be
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    /// Ran while memory access was tracked
+    Executed,
+    /// Reachable from the entry, but didn't run
+    Code,
+    Data,
+}
+
+impl LineKind {
+    fn name(self) -> &'static str {
+        match self {
+            LineKind::Executed => "executed",
+            LineKind::Code => "code",
+            LineKind::Data => "data",
+        }
+    }
+}
+
+struct Line {
+    address: u16,
+    bytes: Vec<u8>, // One byte only for an odd sized ROM's last line
+    kind: LineKind,
+    comment: Option<String>,
+}
+
+/// Disassembly of the loaded ROM with labels, code and data told apart, comments and
+/// cross-references, written as text or HTML
+pub struct DisassemblyReport {
+    title: String,
+    /// Coverage was tracked, otherwise code is only what's reachable from the entry
+    tracked: bool,
+    lines: Vec<Line>,
+    labels: BTreeMap<u16, String>,
+    /// Addresses of the instructions jumping to, calling or pointing I at each address
+    xrefs: BTreeMap<u16, BTreeSet<u16>>,
+}
+
+impl DisassemblyReport {
+    pub fn new(emulator: &Emulator, rom_file: &Path, comments: &BTreeMap<u16, String>) -> Self {
+        // The ROM as loaded, the program may have written over parts of it since
+        let code = emulator.rom();
+        let origin = emulator.get_code_range().0 as u16;
+        let graph = FlowGraph::build(code, origin, origin);
+        let access = emulator.memory_access();
+
+        let mut lines = Vec::new();
+        for (i, bytes) in code.chunks(2).enumerate() {
+            let address = origin + 2 * i as u16;
+            let executed = access.is_some_and(|access| access.executes[address as usize] > 0);
+            let kind = if bytes.len() < 2 {
+                LineKind::Data
+            } else if executed {
+                LineKind::Executed
+            } else if graph.block_at(address).is_some() {
+                LineKind::Code
+            } else {
+                LineKind::Data
+            };
+            lines.push(Line {
+                address,
+                bytes: bytes.to_vec(),
+                kind,
+                comment: comments.get(&address).cloned(),
+            });
+        }
+
+        let mut targets = Vec::new();
+        let mut xrefs: BTreeMap<u16, BTreeSet<u16>> = BTreeMap::new();
+        for line in lines.iter().filter(|line| line.kind != LineKind::Data) {
+            let (rank, target) = match decode(&line.bytes) {
+                Instruction::LoadIndex { nnn } => (0, nnn),
+                Instruction::Jump { nnn } => (1, nnn),
+                Instruction::Call { nnn } => (2, nnn),
+                _ => continue,
+            };
+            xrefs.entry(target).or_default().insert(line.address);
+            targets.push((rank, target));
+        }
+        // Calls name a place over jumps, jumps over data
+        targets.sort_unstable();
+        let mut labels = BTreeMap::new();
+        for (rank, target) in targets {
+            let prefix = ["data", "label", "sub"][rank];
+            labels.insert(target, format!("{}_{:03X}", prefix, target));
+        }
+        labels.insert(graph.entry(), "main".to_string());
+
+        DisassemblyReport {
+            title: rom_file
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            tracked: access.is_some(),
+            lines,
+            labels,
+            xrefs,
+        }
+    }
+
+    fn label_or_address(&self, address: u16) -> String {
+        match self.labels.get(&address) {
+            Some(label) => label.clone(),
+            None => format!("{:03X}", address),
+        }
+    }
+
+    // Instruction or data bytes of a line, with targets replaced by labels
+    fn line_text(&self, line: &Line) -> String {
+        if line.kind == LineKind::Data {
+            let bytes: Vec<_> = line.bytes.iter().map(|b| format!("0x{:02X}", b)).collect();
+            return format!("db {}", bytes.join(", "));
+        }
+        let instruction = decode(&line.bytes);
+        let text = instruction.to_string();
+        match instruction {
+            Instruction::Jump { nnn }
+            | Instruction::Call { nnn }
+            | Instruction::LoadIndex { nnn } => {
+                text.replace(&format!("0x{:03X}", nnn), &self.label_or_address(nnn))
+            }
+            _ => text,
+        }
+    }
+
+    fn xref_list(&self, address: u16) -> Option<Vec<String>> {
+        let sources = self.xrefs.get(&address)?;
+        Some(sources.iter().map(|a| format!("{:03X}", a)).collect())
+    }
+
+    fn summary(&self) -> String {
+        let count = |kind| self.lines.iter().filter(|line| line.kind == kind).count();
+        let mut summary = format!(
+            "{} lines: {} executed, {} code, {} data",
+            self.lines.len(),
+            count(LineKind::Executed),
+            count(LineKind::Code),
+            count(LineKind::Data)
+        );
+        if !self.tracked {
+            summary.push_str(". Coverage wasn't tracked, turn it on in the Memory map window");
+        }
+        summary
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(text, "; Disassembly of {}", self.title);
+        let _ = writeln!(text, "; {}", self.summary());
+        let _ = writeln!(
+            text,
+            "; Columns: address, bytes, kind (X executed, C code, D data)"
+        );
+        for line in &self.lines {
+            if let Some(label) = self.labels.get(&line.address) {
+                let _ = writeln!(text);
+                let _ = write!(text, "{}:", label);
+                if let Some(xrefs) = self.xref_list(line.address) {
+                    let _ = write!(text, "  ; from {}", xrefs.join(", "));
+                }
+                let _ = writeln!(text);
+            }
+            let bytes: String = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            let kind = match line.kind {
+                LineKind::Executed => 'X',
+                LineKind::Code => 'C',
+                LineKind::Data => 'D',
+            };
+            let mut row = format!(
+                "{:03X}: {:<4} {}  {}",
+                line.address,
+                bytes,
+                kind,
+                self.line_text(line)
+            );
+            if let Some(comment) = &line.comment {
+                row = format!("{:<40}; {}", row, comment);
+            }
+            let _ = writeln!(text, "{}", row.trim_end());
+        }
+        text
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = writeln!(
+            html,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">"
+        );
+        let _ = writeln!(html, "<title>{}</title>", escape(&self.title));
+        let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+        let _ = writeln!(html, "<h1>{}</h1>", escape(&self.title));
+        let _ = writeln!(html, "<p>{}</p>\n<table>", escape(&self.summary()));
+        for line in &self.lines {
+            if let Some(label) = self.labels.get(&line.address) {
+                let xrefs = self.xref_list(line.address).map_or(String::new(), |xrefs| {
+                    let links: Vec<_> = xrefs
+                        .iter()
+                        .map(|a| format!("<a href=\"#a{0}\">{0}</a>", a))
+                        .collect();
+                    format!(" <span class=\"xref\">from {}</span>", links.join(", "))
+                });
+                let _ = writeln!(
+                    html,
+                    "<tr class=\"label\"><td colspan=\"4\" id=\"{0}\">{0}:{1}</td></tr>",
+                    label, xrefs
+                );
+            }
+            let bytes: String = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            let mut text = escape(&self.line_text(line));
+            // Labels in operands link to where they are
+            if line.kind != LineKind::Data {
+                if let Instruction::Jump { nnn }
+                | Instruction::Call { nnn }
+                | Instruction::LoadIndex { nnn } = decode(&line.bytes)
+                {
+                    if let Some(label) = self.labels.get(&nnn) {
+                        text = text
+                            .replace(label.as_str(), &format!("<a href=\"#{0}\">{0}</a>", label));
+                    }
+                }
+            }
+            let comment = line.comment.as_deref().map(escape).unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<tr class=\"{kind}\" id=\"a{address:03X}\"><td>{address:03X}</td><td>{bytes}</td><td>{text}</td><td class=\"comment\">{comment}</td></tr>",
+                kind = line.kind.name(),
+                address = line.address,
+                bytes = bytes,
+                text = text,
+                comment = comment
+            );
+        }
+        let _ = writeln!(html, "</table>\n</body>\n</html>");
+        html
+    }
+
+    /// Octo source that assembles back to the same bytes. Labels that would fall inside
+    /// an instruction or outside of the ROM stay numbers.
+    pub fn to_octo(&self) -> String {
+        let mut placeable = BTreeSet::new();
+        for line in &self.lines {
+            if octo_statement(line).is_some() {
+                placeable.insert(line.address);
+            } else {
+                placeable.extend((0..line.bytes.len() as u16).map(|i| line.address + i));
+            }
+        }
+        let label_at = |address: u16| {
+            self.labels
+                .get(&address)
+                .filter(|_| placeable.contains(&address))
+        };
+
+        let mut source = String::new();
+        let _ = writeln!(source, "# Disassembly of {}", self.title);
+        let _ = writeln!(source, "# {}", self.summary());
+        for line in &self.lines {
+            let comment = line
+                .comment
+                .as_ref()
+                .map_or(String::new(), |comment| format!("  # {}", comment));
+            let statement = match octo_statement(line) {
+                Some(statement) => statement,
+                None => {
+                    // Data goes byte by byte, so labels can point anywhere in it
+                    let mut row = String::new();
+                    for (i, byte) in line.bytes.iter().enumerate() {
+                        if let Some(label) = label_at(line.address + i as u16) {
+                            if !row.is_empty() {
+                                let _ = writeln!(source, "\t{}", row.trim_end());
+                                row.clear();
+                            }
+                            let _ = writeln!(source, ": {}", label);
+                        }
+                        let _ = write!(row, "0x{:02X} ", byte);
+                    }
+                    let _ = writeln!(source, "\t{}{}", row.trim_end(), comment);
+                    continue;
+                }
+            };
+            if let Some(label) = label_at(line.address) {
+                let _ = writeln!(source, ": {}", label);
+            }
+            let statement = match decode(&line.bytes) {
+                Instruction::Jump { nnn }
+                | Instruction::Call { nnn }
+                | Instruction::LoadIndex { nnn }
+                | Instruction::JumpOffset { nnn } => match label_at(nnn) {
+                    Some(label) => statement.replace(&format!("0x{:03X}", nnn), label),
+                    None => statement,
+                },
+                _ => statement,
+            };
+            let _ = writeln!(source, "\t{}{}", statement, comment);
+        }
+        source
+    }
+
+    /// Writes text for files ending with `.txt`, Octo source for `.8o` and HTML otherwise
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let extension = path.extension().map_or(String::new(), |extension| {
+            extension.to_string_lossy().to_lowercase()
+        });
+        match extension.as_str() {
+            "txt" => fs::write(path, self.to_text()),
+            "8o" => fs::write(path, self.to_octo()),
+            _ => fs::write(path, self.to_html()),
+        }
+    }
+}
+
+const STYLE: &str = "body { font-family: monospace; background: #1e1e1e; color: #ddd; }
+table { border-collapse: collapse; }
+td { padding: 0 12px 0 0; white-space: pre; }
+a { color: #8cf; }
+.label td { color: #fc0; padding-top: 8px; }
+.xref, .comment { color: #8a8; }
+.executed td:first-child { border-left: 4px solid #4c4; }
+.code td:first-child { border-left: 4px solid #666; }
+.data { color: #999; }
+.data td:first-child { border-left: 4px solid #48f; }";
+
+// Octo statement of a code line, `None` for data and opcodes Octo has no statement for
+fn octo_statement(line: &Line) -> Option<String> {
+    if line.kind == LineKind::Data {
+        return None;
+    }
+    decode(&line.bytes).to_octo()
+}
+
+fn decode(bytes: &[u8]) -> Instruction {
+    Instruction::decode(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
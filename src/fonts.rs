@@ -0,0 +1,71 @@
+use crate::config::WindowConfig;
+use imgui::{Context, FontConfig, FontSource, Style};
+use std::fs;
+use std::path::PathBuf;
+
+/// Size of imgui's built-in font, it is a bitmap font and looks best at exactly this size
+pub const DEFAULT_SIZE: f32 = 13.0;
+
+/// Everything the font atlas depends on, fonts are rebuilt when any of it changes
+#[derive(Clone, PartialEq)]
+pub struct FontSettings {
+    file: Option<PathBuf>,
+    size: f32,
+    ui_scale: f32,
+    hidpi_factor: f64,
+}
+
+impl FontSettings {
+    pub fn new(window: &WindowConfig, hidpi_factor: f64) -> Self {
+        FontSettings {
+            file: window.font.clone(),
+            size: window.font_size.max(6.0),
+            ui_scale: window.ui_scale.clamp(0.5, 4.0),
+            hidpi_factor,
+        }
+    }
+
+    /// Replaces the fonts in the atlas, the renderer has to reload the font texture afterwards.
+    ///
+    /// Glyphs are rasterized at the display's physical resolution and scaled back down,
+    /// so text stays sharp on high DPI displays.
+    pub fn build(&self, imgui: &mut Context) {
+        let scale = self.ui_scale * self.hidpi_factor as f32;
+        let config = FontConfig {
+            oversample_h: 1,
+            pixel_snap_h: true,
+            size_pixels: self.size * scale,
+            ..Default::default()
+        };
+
+        let data = self.file.as_ref().and_then(|file| match fs::read(file) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                log::error!("Can't read font '{0}'. Error: {1}", file.display(), e);
+                None
+            }
+        });
+        let mut fonts = imgui.fonts();
+        fonts.clear();
+        match &data {
+            Some(data) => fonts.add_font(&[FontSource::TtfData {
+                data,
+                size_pixels: self.size * scale,
+                config: Some(config),
+            }]),
+            None => fonts.add_font(&[FontSource::DefaultFontData {
+                config: Some(config),
+            }]),
+        };
+        drop(fonts);
+        imgui.io_mut().font_global_scale = 1.0 / self.hidpi_factor as f32;
+    }
+
+    /// Scales paddings, spacings and roundings of `base` by the UI scale, colors are kept
+    pub fn scale_style(&self, base: &Style, style: &mut Style) {
+        let colors = style.colors;
+        *style = *base;
+        style.scale_all_sizes(self.ui_scale);
+        style.colors = colors;
+    }
+}
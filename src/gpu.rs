@@ -0,0 +1,201 @@
+//! GPU adapter and device for drawing the window. The adapter picked in the
+//! settings is tried first, then adapters of either power preference and at
+//! last a software one, so a machine without a fast GPU still gets a window.
+
+use crate::config::{GpuConfig, GpuPower};
+use futures::executor::block_on;
+use winit::dpi::PhysicalSize;
+use winit::window::Window;
+
+/// Backends adapters are looked for on
+const BACKENDS: wgpu::Backends = wgpu::Backends::PRIMARY;
+
+/// Frames in a row that can't be drawn before emulators should wait, a frame
+/// or two gets dropped now and then while the window is resized
+const STALL_FRAMES: u32 = 3;
+
+/// Out of memory errors in a row before giving up on drawing
+const MAX_OUT_OF_MEMORY: u32 = 10;
+
+pub fn instance() -> wgpu::Instance {
+    wgpu::Instance::new(BACKENDS)
+}
+
+/// Names of the adapters on this machine, for picking one in the settings
+pub fn adapter_names() -> Vec<String> {
+    let mut names: Vec<String> = instance()
+        .enumerate_adapters(BACKENDS)
+        .map(|adapter| adapter.get_info().name)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Opens the adapter `config` asks for, or the best one left that can draw to `surface`
+pub fn open(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface,
+    config: &GpuConfig,
+) -> Result<(wgpu::Device, wgpu::Queue), String> {
+    let adapter = pick_adapter(instance, surface, config)
+        .ok_or_else(|| "No GPU adapter can draw to the window".to_string())?;
+    let info = adapter.get_info();
+    log::info!(
+        "Using GPU '{}' ({:?}, {:?})",
+        info.name,
+        info.device_type,
+        info.backend
+    );
+    open_device(&adapter)
+}
+
+fn pick_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface,
+    config: &GpuConfig,
+) -> Option<wgpu::Adapter> {
+    if let Some(name) = &config.adapter {
+        let named = instance.enumerate_adapters(BACKENDS).find(|adapter| {
+            &adapter.get_info().name == name && adapter.is_surface_supported(surface)
+        });
+        if named.is_some() {
+            return named;
+        }
+        log::warn!("GPU '{}' isn't available, picking another one", name);
+    }
+
+    // The preferred kind, the other kind, then a software renderer
+    let preferred = config.power;
+    let other = match preferred {
+        GpuPower::HighPerformance => GpuPower::LowPower,
+        GpuPower::LowPower => GpuPower::HighPerformance,
+    };
+    let attempts = [(preferred, false), (other, false), (preferred, true)];
+    attempts.iter().find_map(|&(power, software)| {
+        let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: power.preference(),
+            compatible_surface: Some(surface),
+            force_fallback_adapter: software,
+        }));
+        if adapter.is_none() {
+            log::warn!(
+                "No {} GPU adapter{}",
+                power.name().to_lowercase(),
+                if software { " in software" } else { "" }
+            );
+        }
+        adapter
+    })
+}
+
+fn open_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), String> {
+    let request = |limits| {
+        block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: wgpu::Features::empty(),
+                limits,
+            },
+            None,
+        ))
+    };
+    // Older GPUs and software renderers may only manage the lower limits
+    request(wgpu::Limits::default())
+        .or_else(|e| {
+            log::warn!("Can't open GPU device, trying lower limits. Error: {}", e);
+            request(wgpu::Limits::downlevel_defaults())
+        })
+        .map_err(|e| format!("Can't open GPU device. Error: {}", e))
+}
+
+/// Window surface that gets reconfigured or recreated when it stops working,
+/// like after minimizing, moving to another monitor or a driver reset
+pub struct WindowSurface {
+    surface: wgpu::Surface,
+    pub config: wgpu::SurfaceConfiguration,
+    /// Frames in a row that couldn't be drawn
+    failures: u32,
+    out_of_memory: u32,
+}
+
+impl WindowSurface {
+    pub fn new(
+        surface: wgpu::Surface,
+        config: wgpu::SurfaceConfiguration,
+        device: &wgpu::Device,
+    ) -> Self {
+        surface.configure(device, &config);
+        WindowSurface {
+            surface,
+            config,
+            failures: 0,
+            out_of_memory: 0,
+        }
+    }
+
+    /// Follows the window size, a minimized window has nothing to draw to and is skipped
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.config.width = size.width;
+        self.config.height = size.height;
+        self.surface.configure(device, &self.config);
+    }
+
+    /// Texture for the next frame, `None` drops the frame and fixes the surface
+    /// for the next one. Fails only when the GPU keeps running out of memory.
+    pub fn next_frame(
+        &mut self,
+        instance: &wgpu::Instance,
+        window: &Window,
+        device: &wgpu::Device,
+    ) -> Result<Option<wgpu::SurfaceTexture>, String> {
+        let size = window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            return Ok(None);
+        }
+        let error = match self.surface.get_current_texture() {
+            Ok(frame) => {
+                self.failures = 0;
+                self.out_of_memory = 0;
+                return Ok(Some(frame));
+            }
+            Err(e) => e,
+        };
+        self.failures += 1;
+        match error {
+            wgpu::SurfaceError::Timeout => log::debug!("Dropped frame, the surface timed out"),
+            wgpu::SurfaceError::Outdated => {
+                // Resize events don't come for every change, like moving to a monitor with another scale
+                log::debug!("Dropped frame, reconfiguring the outdated surface");
+                self.resize(device, size);
+            }
+            wgpu::SurfaceError::Lost => {
+                log::warn!("Surface lost, recreating it");
+                self.recreate(instance, window, device);
+            }
+            wgpu::SurfaceError::OutOfMemory => {
+                self.out_of_memory += 1;
+                if self.out_of_memory >= MAX_OUT_OF_MEMORY {
+                    return Err("Out of GPU memory".to_string());
+                }
+                log::warn!("Out of GPU memory, recreating the surface");
+                self.recreate(instance, window, device);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Drawing has failed for a few frames in a row, emulators shouldn't run unseen
+    pub fn is_stalled(&self) -> bool {
+        self.failures >= STALL_FRAMES
+    }
+
+    fn recreate(&mut self, instance: &wgpu::Instance, window: &Window, device: &wgpu::Device) {
+        // The window outlives the surface, both are owned by the event loop closure
+        self.surface = unsafe { instance.create_surface(window) };
+        self.resize(device, window.inner_size());
+    }
+}
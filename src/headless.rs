@@ -0,0 +1,91 @@
+use crate::automation;
+use crate::cli::{self, Args};
+use crate::config::Config;
+use crate::http;
+use crate::octo::{parse_color, OctoOptions};
+use crate::rom_patch;
+use crate::screenshot;
+use chip8_core::Emulator;
+
+/// Runs the ROM from `args` for the given number of cycles without a window,
+/// then prints CPU state and optionally saves a screenshot.
+/// With `--serve` it runs in real time and serves the state over HTTP instead,
+/// with `--stdin` it follows commands read from stdin.
+pub fn run(args: &Args) -> Result<(), String> {
+    let rom_file = args.rom.as_ref().ok_or("--headless needs --rom")?;
+    let mut config = Config::load();
+
+    let mut emulator = Emulator::builder()
+        .quirks(config.quirks)
+        .clock_hz(config.clock_hz)
+        .vip_timing(config.vip_timing)
+        .memory_size(config.memory_size())
+        .build();
+    let options = OctoOptions::load_for_rom(rom_file).map_err(|e| {
+        format!(
+            "Can't read options for '{0}'. Error: {1}",
+            rom_file.display(),
+            e
+        )
+    })?;
+    if let Some(options) = options {
+        emulator.set_quirks(options.quirks());
+        emulator.set_clock_hz(options.clock_hz());
+        if let Some(fg) = parse_color(&options.fill_color) {
+            config.palette.fg = fg;
+        }
+        if let Some(bg) = parse_color(&options.background_color) {
+            config.palette.bg = bg;
+        }
+    }
+    args.apply_overrides(&mut emulator);
+
+    if let Some(trace) = &args.trace {
+        let hook = cli::file_trace_hook(trace)
+            .map_err(|e| format!("Can't create '{0}'. Error: {1}", trace.display(), e))?;
+        emulator.set_trace_hook(Some(hook));
+    }
+
+    let mut rom = std::fs::read(rom_file)
+        .map_err(|e| format!("Can't read file: '{0}'. Error: {1}", rom_file.display(), e))?;
+    for patch in rom_patch::apply_for_rom(rom_file, &mut rom).map_err(|e| {
+        format!(
+            "Can't apply patch for '{0}'. Error: {1}",
+            rom_file.display(),
+            e
+        )
+    })? {
+        log::info!("Applied patch '{}'", patch.display());
+    }
+    emulator
+        .load_rom_bytes(&rom)
+        .map_err(|e| format!("Can't read file: '{0}'. Error: {1}", rom_file.display(), e))?;
+
+    if let Some(addr) = &args.serve {
+        let scale = args.scale.unwrap_or(1.0) as u32;
+        return http::serve(emulator, config.palette, scale, addr)
+            .map_err(|e| format!("Can't serve on '{0}'. Error: {1}", addr, e));
+    }
+    if args.stdin {
+        let scale = args.scale.unwrap_or(1.0) as u32;
+        return automation::run(emulator, &config.palette, scale)
+            .map_err(|e| format!("Can't read commands. Error: {}", e));
+    }
+
+    emulator.run_cycles(args.cycles as usize);
+    // Flushes the trace file
+    emulator.set_trace_hook(None);
+
+    println!("PC: {:#X} I: {:#X}", emulator.pc(), emulator.index());
+    for (i, v) in emulator.registers().iter().enumerate() {
+        print!("V{:X}: {:#X} ", i, v);
+    }
+    println!();
+
+    if let Some(path) = &args.screenshot {
+        let scale = args.scale.unwrap_or(1.0) as u32;
+        screenshot::save(emulator.screen(), &config.palette, scale, path)
+            .map_err(|e| format!("Can't save '{0}'. Error: {1}", path.display(), e))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use crate::config::Palette;
+use crate::screenshot;
+use chip8_core::Emulator;
+use image::{DynamicImage, ImageOutputFormat};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a client gets to send its request line before it is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs the emulator in real time and serves its state over HTTP until the process is killed:
+///
+/// * `GET /screen.png` - current screen
+/// * `GET /state.json` - CPU registers, timers and stack
+pub fn serve(emulator: Emulator, palette: Palette, scale: u32, addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    log::info!(
+        "Serving emulator state on http://{}",
+        listener.local_addr()?
+    );
+
+    let emulator = Arc::new(Mutex::new(emulator));
+    let server_emulator = emulator.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log::warn!("HTTP connection failed. Error: {}", e);
+                    continue;
+                }
+            };
+            // A slow client gets its own thread, so it can't hold up the others
+            let emulator = server_emulator.clone();
+            let palette = palette.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle_request(stream, &emulator, &palette, scale) {
+                    log::warn!("HTTP request failed. Error: {}", e);
+                }
+            });
+        }
+    });
+
+    let mut last_update = Instant::now();
+    loop {
+        thread::sleep(Duration::from_millis(1000 / 60));
+        let now = Instant::now();
+        let dt = now - last_update;
+        last_update = now;
+        emulator.lock().unwrap().run_for(dt.as_secs_f32());
+    }
+}
+
+// The emulator is locked only to copy what is served, never while talking to the client
+fn handle_request(
+    mut stream: TcpStream,
+    emulator: &Mutex<Emulator>,
+    palette: &Palette,
+    scale: u32,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/screen.png")) => {
+            let screen = emulator.lock().unwrap().screen().clone();
+            let image = screenshot::render(&screen, palette, scale);
+            let mut png = Vec::new();
+            DynamicImage::ImageRgba8(image)
+                .write_to(&mut png, ImageOutputFormat::Png)
+                .map_err(io::Error::other)?;
+            ("200 OK", "image/png", png)
+        }
+        (Some("GET"), Some("/state.json")) => {
+            let state = state_json(&emulator.lock().unwrap());
+            ("200 OK", "application/json", state.to_string().into_bytes())
+        }
+        _ => ("404 Not Found", "text/plain", b"not found".to_vec()),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(&body)
+}
+
+/// CPU registers, timers, stack and halt reason, as served on `/state.json`
+pub fn state_json(emulator: &Emulator) -> serde_json::Value {
+    serde_json::json!({
+        "pc": emulator.pc(),
+        "i": emulator.index(),
+        "v": emulator.registers(),
+        "delay": emulator.delay_timer(),
+        "sound": emulator.sound_timer(),
+        "stack": emulator.stack(),
+        "halted": emulator.halt_reason().map(|reason| format!("{:?}", reason)),
+    })
+}
@@ -0,0 +1,292 @@
+use imgui::ImString;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// UI language. The English text of a string is the key its translations are looked up by,
+/// so untranslated strings simply show up in English.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// Name of the language in itself
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+}
+
+// Language used by `tr`, set by the app before drawing the UI
+static LANGUAGE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::German,
+        _ => Language::English,
+    }
+}
+
+/// Translation of an English UI string to the current language
+pub fn tr(text: &'static str) -> &'static str {
+    let translated = match language() {
+        Language::English => None,
+        Language::German => german(text),
+    };
+    translated.unwrap_or(text)
+}
+
+/// `tr` with the first `{}` of the translation replaced by `arg`, for strings that embed a value
+pub fn trf(text: &'static str, arg: impl std::fmt::Display) -> String {
+    tr(text).replacen("{}", &arg.to_string(), 1)
+}
+
+/// `tr` for imgui labels
+pub fn im(text: &'static str) -> ImString {
+    ImString::new(tr(text))
+}
+
+/// Translated window title that keeps the English ID, so windows keep their layout
+/// when the language changes. `suffix` tells apart windows of different instances.
+pub fn window_title(name: &'static str, suffix: &str) -> ImString {
+    let text = tr(name);
+    if text == name {
+        ImString::new(format!("{}{}", name, suffix))
+    } else {
+        ImString::new(format!("{}{}###{}{}", text, suffix, name, suffix))
+    }
+}
+
+fn german(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // Menus
+        "File" => "Datei",
+        "Open ROM..." => "ROM öffnen...",
+        "Recent" => "Zuletzt geöffnet",
+        "New instance" => "Neue Instanz",
+        "Scanning..." => "Suche...",
+        "{} ROMs are copies of others" => "{} ROMs sind Kopien anderer",
+        "Hide copies" => "Kopien ausblenden",
+        "({} copies)" => "({} Kopien)",
+        "Export patched ROM..." => "Gepatchtes ROM exportieren...",
+        "Exit" => "Beenden",
+        "Emulation" => "Emulation",
+        "Pause" => "Pause",
+        "Reset" => "Zurücksetzen",
+        "Speed" => "Geschwindigkeit",
+        "Quirks" => "Eigenheiten",
+        "{} preset" => "Vorgabe {}",
+        "View" => "Ansicht",
+        "Status bar" => "Statusleiste",
+        "Performance graphs" => "Leistungsgraphen",
+        "Frame time" => "Bildzeit",
+        "Texture upload" => "Textur-Upload",
+        "Layout" => "Anordnung",
+        "Play" => "Spielen",
+        "Debug" => "Debuggen",
+        "Dev" => "Entwickeln",
+        "Save layout..." => "Anordnung speichern...",
+        "Delete saved layout" => "Gespeicherte Anordnung löschen",
+        "Always on top" => "Immer im Vordergrund",
+        "Opacity" => "Deckkraft",
+        "A window that started opaque turns see-through after a restart" => {
+            "Ein undurchsichtig gestartetes Fenster wird erst nach einem Neustart durchsichtig"
+        }
+        "Theme" => "Design",
+        "Help" => "Hilfe",
+        "Controls" => "Steuerung",
+        "Export bug report" => "Fehlerbericht exportieren",
+        "About" => "Über",
+        "chip8 emulator and debugger" => "chip8-Emulator und Debugger",
+
+        // Hotkeys
+        "Quit" => "Beenden",
+        "Step" => "Einzelschritt",
+        "Skip instruction" => "Befehl überspringen",
+        "Reload ROM" => "ROM neu laden",
+        "Watch ROM file" => "ROM-Datei überwachen",
+        "Resume at same label" => "Am selben Label fortsetzen",
+        "Fast-forward (hold)" => "Vorspulen (halten)",
+        "Screenshot" => "Bildschirmfoto",
+        "Copy screen" => "Bildschirm kopieren",
+        "Save state" => "Zustand speichern",
+        "Load state" => "Zustand laden",
+        "Fullscreen" => "Vollbild",
+        "Slow motion 25%" => "Zeitlupe 25%",
+        "Slow motion 50%" => "Zeitlupe 50%",
+        "Advance frames (hold)" => "Bildweise vorwärts (halten)",
+        "Next bookmark" => "Nächstes Lesezeichen",
+        "Previous bookmark" => "Vorheriges Lesezeichen",
+
+        // Themes
+        "Dark" => "Dunkel",
+        "Light" => "Hell",
+        "Classic green" => "Klassisch grün",
+
+        // Windows
+        "ROMs" => "ROMs",
+        "Screen" => "Bildschirm",
+        "CPU" => "CPU",
+        "Code" => "Code",
+        "Flow graph" => "Ablaufgraph",
+        "Call graph" => "Aufrufgraph",
+        "Sprite editor" => "Sprite-Editor",
+        "Sprite gallery" => "Sprite-Galerie",
+        "Learn" => "Lernen",
+        "Compare quirks" => "Quirks vergleichen",
+        "Compatibility" => "Kompatibilität",
+        "Triggers" => "Auslöser",
+        "Bookmarks" => "Lesezeichen",
+        "Log" => "Protokoll",
+        "Options" => "Optionen",
+        "Test ROMs" => "Test-ROMs",
+        "Instructions" => "Befehle",
+        "History" => "Verlauf",
+        "Memory" => "Speicher",
+        "Memory map" => "Speicherkarte",
+        "Statistics" => "Statistik",
+        "Settings" => "Einstellungen",
+
+        // Status bar
+        "No ROM" => "Kein ROM",
+        "Paused" => "Pausiert",
+        "Running" => "Läuft",
+        "Program finished" => "Programm beendet",
+        "Waiting for key" => "Wartet auf Taste",
+        "Idle" => "Leerlauf",
+        "Breakpoint at {}" => "Haltepunkt bei {}",
+        "Halted, PC {}" => "Angehalten, PC {}",
+        "Fault at {}" => "Fehler bei {}",
+        "target {}" => "Ziel {}",
+
+        // Window title
+        "paused" => "pausiert",
+        "fast-forward" => "vorspulen",
+        "recording" => "Aufnahme",
+
+        // ROM switch prompt
+        "Switch ROM?" => "ROM wechseln?",
+        "Load '{}'? The running game will be lost." => {
+            "'{}' laden? Das laufende Spiel geht verloren."
+        }
+        "Save a state of the running game first" => "Vorher den Zustand des Spiels speichern",
+        "Don't ask again" => "Nicht mehr fragen",
+        "Load" => "Laden",
+        "Cancel" => "Abbrechen",
+
+        // Fault panel
+        "Illegal opcode" => "Ungültiger Opcode",
+        "Return with an empty stack" => "Rücksprung mit leerem Stack",
+        "Call with a full stack" => "Aufruf mit vollem Stack",
+        "Memory access past the end of memory" => "Speicherzugriff hinter dem Speicherende",
+        "PC ran past the end of memory" => "PC ist hinter das Speicherende gelaufen",
+        "PC: {}" => "PC: {}",
+        "Opcode: {}" => "Opcode: {}",
+        "Address: {}" => "Adresse: {}",
+        "No saved state" => "Kein gespeicherter Zustand",
+        "Ignore and continue" => "Ignorieren und fortfahren",
+        "Show code" => "Code anzeigen",
+        "Stop on faults" => "Bei Fehlern anhalten",
+
+        // Profile prompt
+        "Switch profile?" => "Profil wechseln?",
+        "'{}' looks like a {} ROM." => "'{}' sieht wie ein {}-ROM aus.",
+        "Switch to the {} quirks preset?" => "Zur Vorgabe {} wechseln?",
+        "Memory is also raised to 64KB." => "Der Speicher wird außerdem auf 64KB erhöht.",
+        "Opcodes of the extension itself aren't emulated." => {
+            "Die Opcodes der Erweiterung selbst werden nicht emuliert."
+        }
+        "Switch" => "Wechseln",
+        "Keep" => "Behalten",
+
+        // Help window
+        "Select ROM file, to control use keys:" => {
+            "ROM-Datei auswählen, gesteuert wird mit den Tasten:"
+        }
+        "Keys are changed in the Settings window.\n\nWith several instances, ROMs are loaded into\nand keys go to the last clicked one.\nClosed windows are reopened from the View menu,\nROMs can also be opened from the File menu.\n\nHave fun!" => {
+            "Tasten werden im Fenster Einstellungen geändert.\n\nMit mehreren Instanzen gehen ROMs und Tasten\nan die zuletzt angeklickte.\nGeschlossene Fenster öffnet das Menü Ansicht wieder,\nROMs lassen sich auch über das Menü Datei öffnen.\n\nViel Spaß!"
+        }
+
+        // Screen context menu
+        "Start GIF recording" => "GIF-Aufnahme starten",
+        "Stop GIF recording" => "GIF-Aufnahme beenden",
+        "Palette" => "Palette",
+        "Swap colors" => "Farben tauschen",
+        "Scale" => "Skalierung",
+        "Pixel grid" => "Pixelraster",
+
+        // Settings window
+        "Video" => "Bild",
+        "Audio" => "Ton",
+        "Input" => "Eingabe",
+        "Paths" => "Pfade",
+        "Language" => "Sprache",
+        "Screen scale" => "Bildschirmskalierung",
+        "Color" => "Farbe",
+        "Background" => "Hintergrund",
+        "UI scale" => "UI-Skalierung",
+        "Font" => "Schriftart",
+        "Browse..." => "Durchsuchen...",
+        "Empty uses the built-in font" => "Leer nutzt die eingebaute Schrift",
+        "Font size" => "Schriftgröße",
+        "GPU" => "Grafikkarte",
+        "Falls back to another GPU, or to drawing in software, if this one can't be used" => "Weicht auf eine andere Grafikkarte oder Software-Rendering aus, wenn diese nicht nutzbar ist",
+        "GPU preference" => "Bevorzugte Grafikkarte",
+        "High performance" => "Hohe Leistung",
+        "Low power" => "Stromsparend",
+        "GPU changes apply after a restart" => "Grafikkarten-Änderungen gelten nach einem Neustart",
+        "There is no audio output yet" => "Es gibt noch keine Tonausgabe",
+        "Show beeps" => "Töne anzeigen",
+        "Auto" => "Automatisch",
+        "{} samples" => "{} Samples",
+        "Buffer size" => "Puffergröße",
+        "Used by the SDL frontend. Bigger buffers crackle less, smaller ones make beeps more responsive" => "Wird vom SDL-Frontend verwendet. Größere Puffer knacksen weniger, kleinere lassen Töne schneller reagieren",
+        "Shown on the screen while the sound timer runs" => {
+            "Wird auf dem Bildschirm gezeigt, solange der Sound-Timer läuft"
+        }
+        "Off" => "Aus",
+        "Border flash" => "Rahmen blinkt",
+        "Speaker icon" => "Lautsprechersymbol",
+        "Press a key, click the button again to cancel" => {
+            "Taste drücken, erneuter Klick bricht ab"
+        }
+        "Keypad keys taken by hotkeys: {}" => "Von Kurztasten belegte Tasten: {}",
+        "Fast-forward speed" => "Vorspulgeschwindigkeit",
+        "Low-latency input" => "Eingabe mit geringer Latenz",
+        "Key changes reach the game right away and it runs in 1ms steps instead of 4ms, for fast games. Uses more CPU" => {
+            "Tastenänderungen erreichen das Spiel sofort und es läuft in 1ms- statt 4ms-Schritten, für schnelle Spiele. Braucht mehr CPU"
+        }
+        "Defaults for ROMs without their own options" => "Vorgaben für ROMs ohne eigene Optionen",
+        "Instructions per frame" => "Befehle pro Bild",
+        "Quirks preset" => "Eigenheiten-Vorgabe",
+        "64KB memory (XO-CHIP)" => "64KB Speicher (XO-CHIP)",
+        "COSMAC VIP timing" => "COSMAC-VIP-Zeitverhalten",
+        "Ask before replacing a running game" => "Vor dem Ersetzen eines laufenden Spiels fragen",
+        "Offer a profile for SUPER-CHIP and XO-CHIP ROMs" => {
+            "Profil für SUPER-CHIP- und XO-CHIP-ROMs anbieten"
+        }
+        "Save a state before replacing a running game" => {
+            "Vor dem Ersetzen eines laufenden Spiels den Zustand speichern"
+        }
+        "Pause when the window loses focus" => "Pausieren, wenn das Fenster den Fokus verliert",
+        "Pause when the window is minimized" => "Pausieren, wenn das Fenster minimiert ist",
+        "ROM directory" => "ROM-Verzeichnis",
+        "Empty uses roms next to the executable" => "Leer nutzt roms neben dem Programm",
+        "Config file: {}" => "Konfigurationsdatei: {}",
+        "Apply" => "Anwenden",
+        "Save" => "Speichern",
+        "Revert" => "Verwerfen",
+        _ => return None,
+    })
+}
@@ -0,0 +1,35 @@
+struct VertexOutput {
+    [[location(0)]] uv: vec2<f32>;
+    [[location(1)]] color: vec4<f32>;
+    [[builtin(position)]] position: vec4<f32>;
+};
+
+[[block]]
+struct View {
+    matrix: mat4x4<f32>;
+};
+[[group(0), binding(0)]]
+var<uniform> view: View;
+
+[[stage(vertex)]]
+fn vs_main(
+    [[location(0)]] position: vec2<f32>,
+    [[location(1)]] uv: vec2<f32>,
+    [[location(2)]] color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = uv;
+    out.color = color;
+    out.position = view.matrix * vec4<f32>(position, 0.0, 1.0);
+    return out;
+}
+
+[[group(1), binding(0)]]
+var u_texture: texture_2d<f32>;
+[[group(1), binding(1)]]
+var u_sampler: sampler;
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    return in.color * textureSample(u_texture, u_sampler, in.uv);
+}
@@ -1,47 +1,19 @@
 use imgui::{
     Context, DrawCmd::Elements, DrawData, DrawIdx, DrawList, DrawVert, TextureId, Textures,
 };
+use std::borrow::Cow;
 use std::mem::size_of;
+use std::num::NonZeroU32;
+use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::*;
 
 pub type RendererResult<T> = Result<T, RendererError>;
 
-// TODO: This value may change
-// https://github.com/gfx-rs/wgpu-rs/issues/199
-const WHOLE_BUFFER: u64 = 0;
-
 #[derive(Clone, Debug)]
 pub enum RendererError {
     BadTexture(TextureId),
 }
 
-#[allow(dead_code)]
-enum ShaderStage {
-    Vertex,
-    Fragment,
-    Compute,
-}
-
-#[cfg(feature = "glsl-to-spirv")]
-struct Shaders;
-
-#[cfg(feature = "glsl-to-spirv")]
-impl Shaders {
-    fn compile_glsl(code: &str, stage: ShaderStage) -> Vec<u32> {
-        let ty = match stage {
-            ShaderStage::Vertex => glsl_to_spirv::ShaderType::Vertex,
-            ShaderStage::Fragment => glsl_to_spirv::ShaderType::Fragment,
-            ShaderStage::Compute => glsl_to_spirv::ShaderType::Compute,
-        };
-
-        read_spirv(glsl_to_spirv::compile(&code, ty).unwrap()).unwrap()
-    }
-
-    fn get_program_code() -> (&'static str, &'static str) {
-        (include_str!("imgui.vert"), include_str!("imgui.frag"))
-    }
-}
-
 /// A container for a bindable texture to be used internally.
 pub struct Texture {
     bind_group: BindGroup,
@@ -52,7 +24,7 @@ impl Texture {
     /// Creates a new imgui texture from a wgpu texture.
     pub fn new(texture: wgpu::Texture, layout: &BindGroupLayout, device: &Device) -> Self {
         // Extract the texture view.
-        let view = texture.create_default_view();
+        let view = texture.create_view(&TextureViewDescriptor::default());
 
         // Create the texture sampler.
         let sampler = device.create_sampler(&SamplerDescriptor {
@@ -64,19 +36,19 @@ impl Texture {
             mipmap_filter: FilterMode::Linear,
             lod_min_clamp: -100.0,
             lod_max_clamp: 100.0,
-            compare: CompareFunction::Always,
+            ..Default::default()
         });
 
         // Create the texture bind group from the layout.
         let bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: None,
             layout,
-            bindings: &[
-                Binding {
+            entries: &[
+                BindGroupEntry {
                     binding: 0,
                     resource: BindingResource::TextureView(&view),
                 },
-                Binding {
+                BindGroupEntry {
                     binding: 1,
                     resource: BindingResource::Sampler(&sampler),
                 },
@@ -103,82 +75,41 @@ pub struct Renderer {
 }
 
 impl Renderer {
-    /// Create a new imgui wgpu renderer with newly compiled shaders.
-    #[cfg(feature = "glsl-to-spirv")]
-    pub fn new_glsl(
-        imgui: &mut Context,
-        device: &Device,
-        queue: &mut Queue,
-        format: TextureFormat,
-        clear_color: Option<Color>,
-    ) -> Renderer {
-        let (vs_code, fs_code) = Shaders::get_program_code();
-        let vs_raw = Shaders::compile_glsl(vs_code, ShaderStage::Vertex);
-        let fs_raw = Shaders::compile_glsl(fs_code, ShaderStage::Fragment);
-        Self::new_impl(imgui, device, queue, format, clear_color, vs_raw, fs_raw)
-    }
-
-    /// Create a new imgui wgpu renderer, using prebuilt spirv shaders.
+    /// Create a new imgui wgpu renderer.
     pub fn new(
         imgui: &mut Context,
         device: &Device,
-        queue: &mut Queue,
+        queue: &Queue,
         format: TextureFormat,
         clear_color: Option<Color>,
-    ) -> Renderer {
-        let vs_bytes = include_bytes!("imgui.vert.spv");
-        let fs_bytes = include_bytes!("imgui.frag.spv");
-
-        fn compile(shader: &[u8]) -> Vec<u32> {
-            let mut words = vec![];
-            for bytes4 in shader.chunks(4) {
-                words.push(u32::from_le_bytes([
-                    bytes4[0], bytes4[1], bytes4[2], bytes4[3],
-                ]));
-            }
-            words
-        }
-
-        Self::new_impl(
-            imgui,
-            device,
-            queue,
-            format,
-            clear_color,
-            compile(vs_bytes),
-            compile(fs_bytes),
-        )
-    }
-
-    /// Create an entirely new imgui wgpu renderer.
-    fn new_impl(
-        imgui: &mut Context,
-        device: &Device,
-        queue: &mut Queue,
-        format: TextureFormat,
-        clear_color: Option<Color>,
-        vs_raw: Vec<u32>,
-        fs_raw: Vec<u32>,
     ) -> Renderer {
         // Load shaders.
-        let vs_module = device.create_shader_module(&vs_raw);
-        let fs_module = device.create_shader_module(&fs_raw);
+        let shader = device.create_shader_module(&ShaderModuleDescriptor {
+            label: Some("imgui"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("imgui.wgsl"))),
+        });
 
         // Create the uniform matrix buffer.
         let size = 64;
         let uniform_buffer = device.create_buffer(&BufferDescriptor {
             label: None,
             size,
-            usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
         // Create the uniform matrix buffer bind group layout.
         let uniform_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: None,
-            bindings: &[BindGroupLayoutEntry {
+            entries: &[BindGroupLayoutEntry {
                 binding: 0,
-                visibility: wgpu::ShaderStage::VERTEX,
-                ty: BindingType::UniformBuffer { dynamic: false },
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
             }],
         });
 
@@ -186,102 +117,87 @@ impl Renderer {
         let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: None,
             layout: &uniform_layout,
-            bindings: &[Binding {
+            entries: &[BindGroupEntry {
                 binding: 0,
-                resource: BindingResource::Buffer {
-                    buffer: &uniform_buffer,
-                    range: 0..size,
-                },
+                resource: uniform_buffer.as_entire_binding(),
             }],
         });
 
         // Create the texture layout for further usage.
         let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: None,
-            bindings: &[
+            entries: &[
                 BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStage::FRAGMENT,
-                    ty: BindingType::SampledTexture {
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
                         multisampled: false,
-                        component_type: TextureComponentType::Float,
-                        dimension: TextureViewDimension::D2,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
                     },
+                    count: None,
                 },
                 BindGroupLayoutEntry {
                     binding: 1,
-                    visibility: wgpu::ShaderStage::FRAGMENT,
-                    ty: BindingType::Sampler { comparison: false },
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler {
+                        filtering: true,
+                        comparison: false,
+                    },
+                    count: None,
                 },
             ],
         });
 
         // Create the render pipeline layout.
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: None,
             bind_group_layouts: &[&uniform_layout, &texture_layout],
+            push_constant_ranges: &[],
         });
 
         // Create the render pipeline.
         let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            layout: &pipeline_layout,
-            vertex_stage: ProgrammableStageDescriptor {
-                module: &vs_module,
-                entry_point: "main",
+            label: Some("imgui"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[VertexBufferLayout {
+                    array_stride: size_of::<DrawVert>() as BufferAddress,
+                    step_mode: VertexStepMode::Vertex,
+                    // Position, UV and RGBA color packed into a u32
+                    attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Unorm8x4],
+                }],
             },
-            fragment_stage: Some(ProgrammableStageDescriptor {
-                module: &fs_module,
-                entry_point: "main",
-            }),
-            rasterization_state: Some(RasterizationStateDescriptor {
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
                 front_face: FrontFace::Cw,
-                cull_mode: CullMode::None,
-                depth_bias: 0,
-                depth_bias_slope_scale: 0.0,
-                depth_bias_clamp: 0.0,
-            }),
-            primitive_topology: PrimitiveTopology::TriangleList,
-            color_states: &[ColorStateDescriptor {
-                format,
-                color_blend: BlendDescriptor {
-                    src_factor: BlendFactor::SrcAlpha,
-                    dst_factor: BlendFactor::OneMinusSrcAlpha,
-                    operation: BlendOperation::Add,
-                },
-                alpha_blend: BlendDescriptor {
-                    src_factor: BlendFactor::OneMinusDstAlpha,
-                    dst_factor: BlendFactor::One,
-                    operation: BlendOperation::Add,
-                },
-                write_mask: ColorWrite::ALL,
-            }],
-            depth_stencil_state: None,
-            vertex_state: VertexStateDescriptor {
-                index_format: IndexFormat::Uint16,
-                vertex_buffers: &[VertexBufferDescriptor {
-                    stride: size_of::<DrawVert>() as BufferAddress,
-                    step_mode: InputStepMode::Vertex,
-                    attributes: &[
-                        VertexAttributeDescriptor {
-                            format: VertexFormat::Float2,
-                            shader_location: 0,
-                            offset: 0,
-                        },
-                        VertexAttributeDescriptor {
-                            format: VertexFormat::Float2,
-                            shader_location: 1,
-                            offset: 8,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[ColorTargetState {
+                    format,
+                    blend: Some(BlendState {
+                        color: BlendComponent {
+                            src_factor: BlendFactor::SrcAlpha,
+                            dst_factor: BlendFactor::OneMinusSrcAlpha,
+                            operation: BlendOperation::Add,
                         },
-                        VertexAttributeDescriptor {
-                            format: VertexFormat::Uint,
-                            shader_location: 2,
-                            offset: 16,
+                        alpha: BlendComponent {
+                            src_factor: BlendFactor::OneMinusDstAlpha,
+                            dst_factor: BlendFactor::One,
+                            operation: BlendOperation::Add,
                         },
-                    ],
+                    }),
+                    write_mask: ColorWrites::ALL,
                 }],
-            },
-            sample_count: 1,
-            sample_mask: !0,
-            alpha_to_coverage_enabled: false,
+            }),
         });
 
         let mut renderer = Renderer {
@@ -302,11 +218,12 @@ impl Renderer {
     }
 
     /// Render the current imgui frame.
-    pub fn render<'r>(
-        &'r mut self,
+    pub fn render(
+        &mut self,
         draw_data: &DrawData,
         device: &Device,
-        encoder: &'r mut CommandEncoder,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
         view: &TextureView,
     ) -> RendererResult<()> {
         let fb_width = draw_data.display_size[0] * draw_data.framebuffer_scale[0];
@@ -334,29 +251,7 @@ impl Renderer {
             [0.0, 0.0, 1.0, 0.0],
             [-1.0, 1.0, 0.0, 1.0],
         ];
-        self.update_uniform_buffer(device, encoder, &matrix);
-
-        // Start a new renderpass and prepare it properly.
-        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
-            color_attachments: &[RenderPassColorAttachmentDescriptor {
-                attachment: &view,
-                resolve_target: None,
-                load_op: match self.clear_color {
-                    Some(_) => LoadOp::Clear,
-                    _ => LoadOp::Load,
-                },
-                store_op: StoreOp::Store,
-                clear_color: self.clear_color.unwrap_or(Color {
-                    r: 0.0,
-                    g: 0.0,
-                    b: 0.0,
-                    a: 1.0,
-                }),
-            }],
-            depth_stencil_attachment: None,
-        });
-        rpass.set_pipeline(&self.pipeline);
-        rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        self.update_uniform_buffer(queue, &matrix);
 
         self.vertex_buffers.clear();
         self.index_buffers.clear();
@@ -368,6 +263,25 @@ impl Renderer {
                 .push(self.upload_index_buffer(device, draw_list.idx_buffer()));
         }
 
+        // Start a new renderpass and prepare it properly.
+        let mut rpass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("imgui"),
+            color_attachments: &[RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: match self.clear_color {
+                        Some(color) => LoadOp::Clear(color),
+                        None => LoadOp::Load,
+                    },
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+
         // Execute all the imgui render work.
         for (draw_list_buffers_index, draw_list) in draw_data.draw_lists().enumerate() {
             self.render_draw_list(
@@ -397,8 +311,8 @@ impl Renderer {
         let vertex_buffer = &self.vertex_buffers[draw_list_buffers_index];
 
         // Make sure the current buffers are attached to the render pass.
-        rpass.set_index_buffer(&index_buffer, 0, WHOLE_BUFFER);
-        rpass.set_vertex_buffer(0, &vertex_buffer, 0, WHOLE_BUFFER);
+        rpass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint16);
+        rpass.set_vertex_buffer(0, vertex_buffer.slice(..));
 
         for cmd in draw_list.commands() {
             match cmd {
@@ -439,36 +353,37 @@ impl Renderer {
     }
 
     /// Updates the current uniform buffer containing the transform matrix.
-    fn update_uniform_buffer(
-        &mut self,
-        device: &Device,
-        encoder: &mut CommandEncoder,
-        matrix: &[[f32; 4]; 4],
-    ) {
-        let data = as_byte_slice(matrix);
-        // Create a new buffer.
-        let buffer = device.create_buffer_with_data(data, BufferUsage::COPY_SRC);
-
-        // Copy the new buffer to the real buffer.
-        encoder.copy_buffer_to_buffer(&buffer, 0, &self.uniform_buffer, 0, 64);
+    fn update_uniform_buffer(&mut self, queue: &Queue, matrix: &[[f32; 4]; 4]) {
+        queue.write_buffer(&self.uniform_buffer, 0, as_byte_slice(matrix));
     }
 
     /// Upload the vertex buffer to the gPU.
     fn upload_vertex_buffer(&self, device: &Device, vertices: &[DrawVert]) -> Buffer {
-        let data = as_byte_slice(&vertices);
-        device.create_buffer_with_data(data, BufferUsage::VERTEX)
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: as_byte_slice(vertices),
+            usage: BufferUsages::VERTEX,
+        })
     }
 
     /// Upload the index buffer to the GPU.
     fn upload_index_buffer(&self, device: &Device, indices: &[DrawIdx]) -> Buffer {
-        let data = as_byte_slice(&indices);
-        device.create_buffer_with_data(data, BufferUsage::INDEX)
+        device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: as_byte_slice(indices),
+            usage: BufferUsages::INDEX,
+        })
     }
 
     /// Updates the texture on the GPU corresponding to the current imgui font atlas.
     ///
     /// This has to be called after loading a font.
-    pub fn reload_font_texture(&mut self, imgui: &mut Context, device: &Device, queue: &mut Queue) {
+    /// Changes the color the target is cleared to, `None` draws over it
+    pub fn set_clear_color(&mut self, clear_color: Option<Color>) {
+        self.clear_color = clear_color;
+    }
+
+    pub fn reload_font_texture(&mut self, imgui: &mut Context, device: &Device, queue: &Queue) {
         let mut atlas = imgui.fonts();
         let handle = atlas.build_rgba32_texture();
         let font_texture_id =
@@ -477,50 +392,44 @@ impl Renderer {
         atlas.tex_id = font_texture_id;
     }
 
-    /// Creates and uploads a new wgpu texture made from the imgui font atlas.
+    /// Replaces the contents of a texture created with `create_texture` with tightly
+    /// packed RGBA rows.
+    ///
+    /// The data goes through the queue's staging memory, so there is no buffer or
+    /// submit per upload and no 256 byte row alignment to pad to.
     pub fn update_texture(
         &mut self,
         id: TextureId,
-        device: &Device,
-        queue: &mut Queue,
+        queue: &Queue,
         data: &[u8],
         width: u32,
         height: u32,
     ) -> Option<bool> {
-        // Create the wgpu texture.
-        //let texture = self.textures.get(id)?.texture;
-
-        // Upload the actual data to a wgpu buffer.
-        let bytes = data.len();
-        let buffer = device.create_buffer_with_data(data, BufferUsage::COPY_SRC);
-
-        // Make sure we have an active encoder.
-        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor { label: None });
+        let bytes_per_row = width * 4;
+        if data.len() < (bytes_per_row * height) as usize {
+            return None;
+        }
 
-        // Schedule a copy from the buffer to the texture.
-        encoder.copy_buffer_to_texture(
-            BufferCopyView {
-                buffer: &buffer,
-                offset: 0,
-                bytes_per_row: bytes as u32 / height,
-                rows_per_image: height,
-            },
-            TextureCopyView {
+        queue.write_texture(
+            ImageCopyTexture {
                 texture: &self.textures.get(id)?.texture,
                 mip_level: 0,
-                array_layer: 0,
-                origin: Origin3d { x: 0, y: 0, z: 0 },
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: NonZeroU32::new(height),
             },
             Extent3d {
                 width,
                 height,
-                depth: 1,
+                depth_or_array_layers: 1,
             },
         );
 
-        // Resolve the actual copy process.
-        queue.submit(&[encoder.finish()]);
-
         Some(true)
     }
 
@@ -532,32 +441,36 @@ impl Renderer {
             size: Extent3d {
                 width,
                 height,
-                depth: 1,
+                depth_or_array_layers: 1,
             },
-            array_layer_count: 1,
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
             format: TextureFormat::Rgba8Unorm,
-            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
         });
 
         let texture = Texture::new(texture, &self.texture_layout, device);
         self.textures.insert(texture)
     }
 
+    /// Frees a texture created with `create_texture`.
+    pub fn remove_texture(&mut self, id: TextureId) {
+        self.textures.remove(id);
+    }
+
     /// Creates and uploads a new wgpu texture made from the imgui font atlas.
     pub fn upload_texture(
         &mut self,
         device: &Device,
-        queue: &mut Queue,
+        queue: &Queue,
         data: &[u8],
         width: u32,
         height: u32,
     ) -> TextureId {
         // Create the wgpu texture.
         let id = self.create_texture(device, width, height);
-        self.update_texture(id, device, queue, data, width, height);
+        self.update_texture(id, queue, data, width, height);
         id
     }
 }
@@ -0,0 +1,873 @@
+use crate::battery;
+use crate::bug_report::BugReport;
+use crate::cli::Args;
+use crate::clipboard;
+use crate::config::{Config, Palette};
+use crate::crash;
+use crate::debug_session::{Annotations, DebugSession};
+use crate::disassembly::DisassemblyReport;
+use crate::i18n;
+use crate::imgui_wgpu::Renderer;
+use crate::loader::RomData;
+use crate::logger;
+use crate::octo::{format_color, parse_color, OctoOptions};
+use crate::runner::EmulatorThread;
+use crate::screenshot;
+use crate::triggers::{ApprovedCommands, Trigger, TriggerAction, Triggers};
+use crate::watch::FileWatch;
+use chip8_core as chip8;
+use imgui::{ImString, TextureId};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use wgpu::{Device, Queue};
+
+/// Where ROMs start in memory
+const ROM_START: usize = 0x200;
+
+// Screen is used to store and update screen buffer and draw it as window with a texture
+pub struct ScreenBuffer {
+    pub size: (usize, usize),
+    data: Vec<u8>,
+    pub ui_scale: f32,
+    pub grid: bool,                    // Draw lines between chip8 pixels
+    pub texture_id: Option<TextureId>, // Created once renderer is available, see create_texture
+    pub focused: bool, // Screen window had focus last frame, only then keypad keys reach the game
+    pub take_focus: bool, // Screen window grabs focus next frame, set when a new ROM is loaded
+}
+
+impl ScreenBuffer {
+    fn new(ui_scale: f32) -> Self {
+        let size = (chip8::SCREEN_SIZE.0, chip8::SCREEN_SIZE.1);
+
+        ScreenBuffer {
+            size,
+            data: vec![0; size.0 * size.1 * 4],
+            ui_scale,
+            grid: false,
+            texture_id: None,
+            focused: false,
+            take_focus: true,
+        }
+    }
+
+    pub fn create_texture(&mut self, renderer: &mut Renderer, device: &Device) {
+        if self.texture_id.is_none() {
+            self.texture_id =
+                Some(renderer.create_texture(device, self.size.0 as u32, self.size.1 as u32));
+        }
+    }
+
+    pub fn update(&mut self, screen: &chip8::Screen, renderer: &mut Renderer, queue: &Queue) {
+        let texture_id = match self.texture_id {
+            Some(texture_id) => texture_id,
+            None => return,
+        };
+
+        // Update pixels in screen buffer from emulator's screen
+        let pixels = screen.iter_rows().flatten();
+        for (rgba, on) in self.data.chunks_exact_mut(4).zip(pixels) {
+            let v = if on { 0xFF } else { 0 };
+            rgba.copy_from_slice(&[v, v, v, v]);
+        }
+
+        // Uploaded updated screen texture data
+        renderer.update_texture(
+            texture_id,
+            queue,
+            &self.data,
+            self.size.0 as u32,
+            self.size.1 as u32,
+        );
+    }
+}
+
+// Measures how many instructions per second an emulator actually executes
+pub struct IpsMeter {
+    last_count: u64,
+    last_time: Instant,
+    pub ips: f32, // Average over the last measuring period
+}
+
+impl IpsMeter {
+    // Longer periods give steadier numbers but react slower
+    const PERIOD: f32 = 0.5;
+
+    fn new() -> Self {
+        IpsMeter {
+            last_count: 0,
+            last_time: Instant::now(),
+            ips: 0.0,
+        }
+    }
+
+    fn update(&mut self, count: u64) {
+        let elapsed = self.last_time.elapsed().as_secs_f32();
+        if elapsed < Self::PERIOD {
+            return;
+        }
+        // The count starts over when a ROM is loaded
+        let executed = count.checked_sub(self.last_count).unwrap_or(count);
+        self.ips = executed as f32 / elapsed;
+        self.last_count = count;
+        self.last_time = Instant::now();
+    }
+}
+
+// Emulator with its own ROM, options, screen and keypad, several of them can run side by side
+pub struct Instance {
+    pub id: usize,
+    pub rom_file: Option<PathBuf>,
+    pub options: OctoOptions,
+    pub palette: Palette,
+    pub emulator: EmulatorThread,
+    pub keys: [bool; 16], // Keypad state collected from window keyboard events
+    pub beep: bool, // There is no audio output yet, beep is only shown on screen and in the CPU window
+    pub screen: ScreenBuffer,
+    pub speed: f32, // Emulation speed multiplier set in the Options window
+    pub fast_forward: Option<f32>, // Speed while the fast-forward key is held
+    pub advancing: Option<Instant>, // Next repeated frame advance while its key is held
+    pub paused: bool,
+    pub ips: IpsMeter,
+    pub recording: Option<screenshot::GifRecording>,
+    pub show_code: Option<u16>, // Address the Code window brings into view next, set by other windows
+    pub comments: BTreeMap<u16, String>, // Notes on code lines by address, written in the Code window
+    pub labels: BTreeMap<u16, String>,   // Names given to addresses in the Code window
+    pub bookmarks: BTreeMap<u16, String>, // Named addresses listed in the Bookmarks window
+    pub bookmark: Option<u16>,           // Bookmark the last hotkey jump went to
+    pub show_memory: Option<u16>, // Address the Memory window brings into view next, set by other windows
+    pub notes: String,            // Notes on the whole ROM, written in the Code window
+    pub assembly: Option<chip8::Assembly>, // Labels of the Octo source the ROM was assembled from
+    pub watch: Option<FileWatch>, // Reloads the ROM when its file is saved
+    /// After reloading an Octo source, continue at the same label instead of restarting
+    pub resume_at_label: bool,
+    pub triggers: Triggers, // Conditions with actions, read from a file next to the ROM
+    pub start_quirks: chip8::Quirks, // Quirks the ROM started with, to tell when they change mid-run
+}
+
+impl Instance {
+    pub fn new(id: usize, config: &Config, args: &Args) -> Self {
+        let mut emulator = chip8::Emulator::builder()
+            .quirks(config.quirks)
+            .clock_hz(config.clock_hz)
+            .vip_timing(config.vip_timing)
+            .memory_size(config.memory_size())
+            .halt_on_fault(true)
+            .history_len(crash::TRACE_LEN)
+            .build();
+        args.apply_overrides(&mut emulator);
+        let start_quirks = emulator.quirks();
+        emulator.subscribe(Box::new(move |_, event| match event {
+            chip8::EmulatorEvent::Halted(reason) => {
+                log::warn!("Emulator #{} halted: {:?}", id, reason)
+            }
+            chip8::EmulatorEvent::BreakpointHit(pc) => {
+                log::info!(target: logger::NOTIFY, "Emulator #{} hit breakpoint at {:03X}", id, pc)
+            }
+            _ => {}
+        }));
+
+        let emulator = EmulatorThread::spawn(emulator, config.low_latency_input);
+        crash::register(id, emulator.downgrade());
+
+        Instance {
+            id,
+            rom_file: None,
+            options: OctoOptions::default(),
+            palette: config.palette.clone(),
+            emulator,
+            keys: [false; 16],
+            beep: false,
+            screen: ScreenBuffer::new(config.window.screen_scale),
+            speed: 1.0,
+            fast_forward: None,
+            advancing: None,
+            paused: false,
+            ips: IpsMeter::new(),
+            recording: None,
+            show_code: None,
+            comments: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            bookmarks: BTreeMap::new(),
+            bookmark: None,
+            show_memory: None,
+            notes: String::new(),
+            assembly: None,
+            watch: None,
+            resume_at_label: false,
+            triggers: Triggers::default(),
+            start_quirks,
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.emulator.set_paused(paused);
+    }
+
+    /// A game is running or paused, loading another ROM would lose it
+    pub fn in_game(&self) -> bool {
+        let halt = self.emulator.lock().halt_reason();
+        self.rom_file.is_some()
+            && !matches!(
+                halt,
+                Some(chip8::HaltReason::NoRom) | Some(chip8::HaltReason::PcOutOfBounds(_))
+            )
+    }
+
+    pub fn update_ips(&mut self) {
+        let count = self.emulator.lock().instruction_count();
+        self.ips.update(count);
+    }
+
+    /// Pauses and executes a single instruction, a breakpoint at PC is stepped over
+    pub fn step(&mut self) {
+        self.set_paused(true);
+        let mut emulator = self.emulator.lock();
+        emulator.resume();
+        emulator.step();
+    }
+
+    /// Pauses and moves PC past the next instruction without running it
+    pub fn skip_instruction(&mut self) {
+        self.set_paused(true);
+        let mut emulator = self.emulator.lock();
+        let pc = emulator.pc();
+        emulator.set_pc(pc.wrapping_add(2));
+    }
+
+    /// Pauses and continues at `address` from the next step
+    pub fn set_pc(&mut self, address: u16) {
+        self.set_paused(true);
+        self.emulator.lock().set_pc(address);
+    }
+
+    /// Bookmark after the last one jumped to, or before it going back, wrapping around
+    pub fn next_bookmark(&mut self, forward: bool) -> Option<u16> {
+        let current = self.bookmark;
+        let mut addresses = self.bookmarks.keys().copied();
+        let next = if forward {
+            let first = self.bookmarks.keys().next().copied();
+            addresses
+                .find(|&address| current.is_none_or(|current| address > current))
+                .or(first)
+        } else {
+            let last = self.bookmarks.keys().next_back().copied();
+            addresses
+                .rev()
+                .find(|&address| current.is_none_or(|current| address < current))
+                .or(last)
+        };
+        self.bookmark = next;
+        next
+    }
+
+    /// Pauses and runs one frame, unlike `step` timers and input work as in the game
+    pub fn advance_frame(&mut self) {
+        self.set_paused(true);
+        self.emulator.lock().resume();
+        self.emulator.advance_frame();
+    }
+
+    /// Switches between `factor` and normal speed
+    pub fn toggle_slow_motion(&mut self, factor: f32) {
+        self.speed = if self.speed == factor { 1.0 } else { factor };
+        self.apply_speed();
+        log::info!(
+            target: logger::NOTIFY,
+            "Speed {}%",
+            (self.speed * 100.0).round()
+        );
+    }
+
+    /// Quick save slot, kept next to the ROM
+    pub fn state_path(rom_file: &Path) -> PathBuf {
+        rom_file.with_extension("state")
+    }
+
+    /// A state saved for the current ROM exists, see `load_state`
+    pub fn has_state(&self) -> bool {
+        self.rom_file
+            .as_ref()
+            .is_some_and(|rom_file| Self::state_path(rom_file).exists())
+    }
+
+    pub fn save_state(&self) {
+        let rom_file = match &self.rom_file {
+            Some(rom_file) => rom_file,
+            None => return,
+        };
+        let path = Self::state_path(rom_file);
+        let state = self.emulator.lock().save_state();
+        match fs::write(&path, state.to_bytes()) {
+            Ok(()) => log::info!(target: logger::NOTIFY, "Saved state to '{}'", path.display()),
+            Err(e) => log::error!("Can't save state '{0}'. Error: {1}", path.display(), e),
+        }
+    }
+
+    pub fn load_state(&self) {
+        let rom_file = match &self.rom_file {
+            Some(rom_file) => rom_file,
+            None => return,
+        };
+        let path = Self::state_path(rom_file);
+        let state = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| chip8::SaveState::from_bytes(&data).map_err(|e| e.to_string()));
+        match state {
+            Ok(state) => {
+                self.emulator.lock().load_state(&state);
+                log::info!(target: logger::NOTIFY, "Loaded state from '{}'", path.display());
+            }
+            Err(e) => log::error!("Can't load state '{0}'. Error: {1}", path.display(), e),
+        }
+    }
+
+    // File next to the ROM named after it and the current time, for screenshots and recordings
+    fn capture_path(&self, extension: &str) -> Option<PathBuf> {
+        let rom_file = self.rom_file.as_ref()?;
+        let stem = rom_file.file_stem().unwrap_or_default().to_string_lossy();
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        Some(rom_file.with_file_name(format!("{}-{}.{}", stem, time, extension)))
+    }
+
+    /// Saves the screen as PNG next to the ROM, named after it and the current time
+    pub fn screenshot(&self) {
+        let path = match self.capture_path("png") {
+            Some(path) => path,
+            None => return,
+        };
+        let screen = self.emulator.lock().screen().clone();
+        let scale = self.screen.ui_scale.round() as u32;
+        match screenshot::save(&screen, &self.palette, scale, &path) {
+            Ok(()) => log::info!(target: logger::NOTIFY, "Saved screenshot '{}'", path.display()),
+            Err(e) => log::error!("Can't save screenshot '{0}'. Error: {1}", path.display(), e),
+        }
+    }
+
+    /// Puts the screen on the system clipboard as an image, scaled like a screenshot
+    pub fn copy_screen(&self) {
+        let screen = self.emulator.lock().screen().clone();
+        let scale = self.screen.ui_scale.round() as u32;
+        let image = screenshot::render(&screen, &self.palette, scale);
+        match clipboard::copy_image(&image) {
+            Ok(()) => log::info!(target: logger::NOTIFY, "Copied screen to clipboard"),
+            Err(e) => log::error!("Can't copy screen to clipboard. Error: {}", e),
+        }
+    }
+
+    /// Saves a zip with what's needed to reproduce the current state next to the ROM
+    pub fn export_bug_report(&self, config: &Config) {
+        let (rom_file, path) = match (&self.rom_file, self.capture_path("zip")) {
+            (Some(rom_file), Some(path)) => (rom_file, path),
+            _ => return,
+        };
+        let report = BugReport::capture(
+            &self.emulator.lock(),
+            rom_file,
+            &self.options,
+            &self.palette,
+            config,
+        );
+        match report.save(&path) {
+            Ok(()) => log::info!(target: logger::NOTIFY, "Saved bug report '{}'", path.display()),
+            Err(e) => log::error!("Can't save bug report '{0}'. Error: {1}", path.display(), e),
+        }
+    }
+
+    /// Writes the disassembly of the ROM with comments to an HTML, text or Octo source file
+    /// picked by the user, blocks until the dialog is closed
+    pub fn export_disassembly(&self) {
+        let rom_file = match &self.rom_file {
+            Some(rom_file) => rom_file,
+            None => return,
+        };
+        let default_path = rom_file.with_extension("html").display().to_string();
+        let path = match tinyfiledialogs::save_file_dialog_with_filter(
+            "Export disassembly",
+            &default_path,
+            &["*.html", "*.txt", "*.8o"],
+            "HTML, text or Octo source",
+        ) {
+            Some(path) => PathBuf::from(path),
+            None => return,
+        };
+        let report = DisassemblyReport::new(&self.emulator.lock(), rom_file, &self.comments);
+        match report.save(&path) {
+            Ok(()) => log::info!(
+                target: logger::NOTIFY,
+                "Exported disassembly to '{}'",
+                path.display()
+            ),
+            Err(e) => log::error!(
+                "Can't export disassembly to '{0}'. Error: {1}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    /// Writes the ROM as it is in memory now, with patches, to a new file
+    pub fn export_patched_rom(&self) {
+        let rom_file = match &self.rom_file {
+            Some(rom_file) => rom_file,
+            None => return,
+        };
+        let default_path = rom_file.with_extension("patched.ch8").display().to_string();
+        let path = match tinyfiledialogs::save_file_dialog_with_filter(
+            "Export patched ROM",
+            &default_path,
+            &["*.ch8"],
+            "CHIP-8 ROM",
+        ) {
+            Some(path) => PathBuf::from(path),
+            None => return,
+        };
+        let rom = self.emulator.lock().rom_in_memory().to_vec();
+        match fs::write(&path, rom) {
+            Ok(()) => log::info!(
+                target: logger::NOTIFY,
+                "Exported patched ROM to '{}'",
+                path.display()
+            ),
+            Err(e) => log::error!(
+                "Can't export patched ROM to '{0}'. Error: {1}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    /// Starts recording the screen to a GIF next to the ROM, or stops and saves the recording
+    pub fn toggle_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            recording.finish();
+            return;
+        }
+        let path = match self.capture_path("gif") {
+            Some(path) => path,
+            None => return,
+        };
+        let scale = self.screen.ui_scale.round() as u32;
+        let mut recording = screenshot::GifRecording::new(path, self.palette.clone(), scale);
+        recording.push(self.emulator.lock().screen());
+        log::info!(
+            target: logger::NOTIFY,
+            "Recording to '{}'",
+            recording.path().display()
+        );
+        self.recording = Some(recording);
+    }
+
+    /// Sends the current speed multiplier to the emulator thread
+    pub fn apply_speed(&self) {
+        self.emulator
+            .set_speed(self.fast_forward.unwrap_or(self.speed));
+    }
+
+    // Window title, windows of the first instance keep plain names
+    pub fn title(&self, name: &'static str) -> ImString {
+        if self.id == 1 {
+            i18n::window_title(name, "")
+        } else {
+            i18n::window_title(name, &format!(" #{}", self.id))
+        }
+    }
+
+    // Windows of each next instance are shifted a bit, so they don't cover each other completely
+    pub fn position(&self, x: f32, y: f32) -> [f32; 2] {
+        let offset = ((self.id - 1) % 8) as f32 * 30.0;
+        [x + offset, y + offset]
+    }
+
+    /// Returns true if the ROM was loaded, errors are logged. A ROM that can't be
+    /// loaded leaves the running one and its settings as they were.
+    pub fn load_rom(&mut self, data: RomData, config: &Config, args: &Args) -> bool {
+        let rom_file = &data.path;
+        // Checked before anything is switched over to the new ROM
+        let max = config.memory_size() - ROM_START;
+        let rom = data
+            .rom
+            .map_err(chip8::LoadError::from)
+            .and_then(|rom: Vec<u8>| match rom.len() {
+                size if size > max => Err(chip8::LoadError::TooLarge { size, max }),
+                _ => Ok(rom),
+            });
+        let rom = match rom {
+            Ok(rom) => rom,
+            Err(e) => {
+                log::error!("Can't read file: '{0}'. Error: {1}", rom_file.display(), e);
+                return false;
+            }
+        };
+
+        // Keeps the scores of the ROM that was running, a reload reads them back
+        self.write_save_memory();
+        self.save_debug_session();
+        // Octo options next to the ROM override emulator defaults
+        let options = match data.options {
+            Ok(options) => options,
+            Err(e) => {
+                log::warn!(
+                    "Can't read options for '{0}'. Error: {1}",
+                    rom_file.display(),
+                    e
+                );
+                None
+            }
+        };
+        let mut emulator = self.emulator.lock();
+        match &options {
+            Some(options) => {
+                emulator.set_quirks(options.quirks());
+                emulator.set_clock_hz(options.clock_hz());
+                if let Some(fg) = parse_color(&options.fill_color) {
+                    self.palette.fg = fg;
+                }
+                if let Some(bg) = parse_color(&options.background_color) {
+                    self.palette.bg = bg;
+                }
+            }
+            None => {
+                emulator.set_quirks(config.quirks);
+                emulator.set_clock_hz(config.clock_hz);
+            }
+        }
+        self.options = options.unwrap_or_default();
+        args.apply_overrides(&mut emulator);
+        self.start_quirks = emulator.quirks();
+        // Can only fail for the ROM being replaced, memory then stays larger and
+        // the new ROM fits all the same
+        if let Err(e) = emulator.set_memory_size(config.memory_size()) {
+            log::debug!("Keeping memory size. Error: {}", e);
+        }
+
+        let same_file = self.rom_file.as_ref() == Some(&data.path);
+        let pc = emulator.pc();
+        if let Err(e) = emulator.load_rom_bytes(&rom) {
+            log::error!("Can't load '{0}'. Error: {1}", rom_file.display(), e);
+            return false;
+        }
+        log::info!(
+            target: logger::NOTIFY,
+            "Loaded '{}' into emulator #{}",
+            rom_file.display(),
+            self.id
+        );
+        self.restore_save_memory(&mut emulator, rom_file);
+        // An edited source moves code around, follow it by label
+        if let (true, Some(old), Some(new)) = (same_file, &self.assembly, &data.assembly) {
+            move_breakpoints(&mut emulator, old, new);
+            if self.resume_at_label {
+                resume_at_label(&mut emulator, old, new, pc);
+            }
+        }
+        // Reloading keeps the comments, labels, bookmarks and notes
+        if !same_file {
+            let session = restore_debug_session(&mut emulator);
+            self.comments = session.comments();
+            self.labels = session.labels();
+            self.bookmarks = session.bookmarks();
+            self.bookmark = None;
+            self.notes = session.notes;
+            self.screen.take_focus = true;
+            if self.watch.is_some() {
+                self.watch = Some(FileWatch::new(&data.path));
+            }
+        }
+        drop(emulator);
+        self.triggers = data.triggers.unwrap_or_else(|e| {
+            log::warn!(
+                "Can't read triggers for '{0}'. Error: {1}",
+                rom_file.display(),
+                e
+            );
+            Triggers::default()
+        });
+        self.assembly = data.assembly;
+        self.rom_file = Some(data.path);
+        true
+    }
+
+    /// Starts or stops reloading the ROM whenever its file is saved
+    pub fn set_watch(&mut self, on: bool) {
+        self.watch = match (&self.rom_file, on) {
+            (Some(rom_file), true) => {
+                log::info!(
+                    target: logger::NOTIFY,
+                    "Watching '{}' for changes",
+                    rom_file.display()
+                );
+                Some(FileWatch::new(rom_file))
+            }
+            _ => None,
+        };
+    }
+
+    /// Runs the actions of triggers whose condition just became true
+    pub fn check_triggers(&mut self) {
+        if self.triggers.triggers.is_empty() {
+            return;
+        }
+        let firing = self.triggers.check(&self.emulator.lock());
+        for trigger in firing {
+            self.run_trigger(&trigger);
+        }
+    }
+
+    fn run_trigger(&mut self, trigger: &Trigger) {
+        match trigger.action {
+            TriggerAction::Toast => log::info!(target: logger::NOTIFY, "{}", trigger.name),
+            TriggerAction::Pause => {
+                self.set_paused(true);
+                log::info!(target: logger::NOTIFY, "Paused by '{}'", trigger.name);
+            }
+            TriggerAction::Screenshot => self.screenshot(),
+            TriggerAction::Command => {
+                if !self.allow_command(&trigger.command) {
+                    return;
+                }
+                let mut command = if cfg!(windows) {
+                    let mut command = Command::new("cmd");
+                    command.arg("/C");
+                    command
+                } else {
+                    let mut command = Command::new("sh");
+                    command.arg("-c");
+                    command
+                };
+                command
+                    .arg(&trigger.command)
+                    .env("CHIP8_TRIGGER", &trigger.name);
+                if let Some(rom_file) = &self.rom_file {
+                    command.env("CHIP8_ROM", rom_file);
+                }
+                match command.spawn() {
+                    // Waited for, or it stays around as a zombie process until the app exits
+                    Ok(mut child) => {
+                        thread::spawn(move || child.wait());
+                    }
+                    Err(e) => log::error!("Can't run '{0}'. Error: {1}", trigger.command, e),
+                }
+            }
+        }
+    }
+
+    // Asks before running a command from the ROM's trigger file the first time
+    fn allow_command(&mut self, command: &str) -> bool {
+        if self.triggers.declined.contains(command) {
+            return false;
+        }
+        let rom = self.emulator.lock().rom().to_vec();
+        let mut approved = ApprovedCommands::load();
+        if approved.is_approved(&rom, command) {
+            return true;
+        }
+        let allowed = tinyfiledialogs::message_box_yes_no(
+            "Run trigger command?",
+            &format!(
+                "A trigger of this ROM wants to run:\n\n{}\n\nOnly allow commands you trust. \
+                 Allowed commands run without asking next time.",
+                command
+            ),
+            tinyfiledialogs::MessageBoxIcon::Warning,
+            tinyfiledialogs::YesNo::No,
+        ) == tinyfiledialogs::YesNo::Yes;
+        if !allowed {
+            log::warn!("Didn't run trigger command '{}'", command);
+            self.triggers.declined.insert(command.to_string());
+            return false;
+        }
+        if let Err(e) = approved.approve(&rom, command) {
+            log::error!("Can't save approved command '{0}'. Error: {1}", command, e);
+        }
+        true
+    }
+
+    /// Writes the memory range set in the ROM's options to its `.sav` file
+    pub fn write_save_memory(&self) {
+        let rom_file = match &self.rom_file {
+            Some(rom_file) => rom_file,
+            None => return,
+        };
+        let range = match battery::parse_range(&self.options.save_memory) {
+            Some(range) => range,
+            None => return,
+        };
+        let emulator = self.emulator.lock();
+        // A ROM that failed to load would overwrite the saved bytes with zeros
+        if emulator.rom().is_empty() {
+            return;
+        }
+        let bytes = match emulator.memory().get(range) {
+            Some(bytes) => bytes.to_vec(),
+            None => {
+                log::warn!(
+                    "Save memory '{}' is outside of memory",
+                    self.options.save_memory
+                );
+                return;
+            }
+        };
+        drop(emulator);
+        if let Err(e) = battery::save(rom_file, &bytes) {
+            log::error!(
+                "Can't save memory for '{0}'. Error: {1}",
+                rom_file.display(),
+                e
+            );
+        }
+    }
+
+    /// Keeps breakpoints, comments, labels and notes for the next time the ROM is opened
+    pub fn save_debug_session(&self) {
+        let emulator = self.emulator.lock();
+        if emulator.rom().is_empty() {
+            return;
+        }
+        let annotations = Annotations {
+            comments: &self.comments,
+            labels: &self.labels,
+            bookmarks: &self.bookmarks,
+            notes: &self.notes,
+        };
+        let session = DebugSession::new(emulator.breakpoints().collect(), annotations);
+        if let Err(e) = session.save(emulator.rom()) {
+            log::error!("Can't save breakpoints and comments. Error: {}", e);
+        }
+    }
+
+    /// Puts bytes saved by `write_save_memory` back, called right after the ROM is loaded
+    pub fn restore_save_memory(&self, emulator: &mut chip8::Emulator, rom_file: &Path) {
+        let text = &self.options.save_memory;
+        let range = match battery::parse_range(text) {
+            Some(range) => range,
+            None => {
+                if !text.is_empty() {
+                    log::warn!("Save memory '{}' isn't a range like 3F0-3FF", text);
+                }
+                return;
+            }
+        };
+        let bytes = match battery::load(rom_file) {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!(
+                    "Can't read saved memory for '{0}'. Error: {1}",
+                    rom_file.display(),
+                    e
+                );
+                return;
+            }
+        };
+        // The range may have shrunk since the bytes were saved
+        let len = bytes.len().min(range.len());
+        match emulator.load_memory_bytes(range.start, &bytes[..len]) {
+            Ok(()) => log::info!(
+                "Restored {} bytes of saved memory at {:03X}",
+                len,
+                range.start
+            ),
+            Err(e) => log::warn!("Can't restore saved memory. Error: {}", e),
+        }
+    }
+
+    pub fn save_options(&mut self) {
+        let rom_file = match &self.rom_file {
+            Some(rom_file) => rom_file,
+            None => return,
+        };
+        let emulator = self.emulator.lock();
+        self.options.set_quirks(emulator.quirks());
+        self.options.set_clock_hz(emulator.clock_hz());
+        drop(emulator);
+        self.options.fill_color = format_color(self.palette.fg);
+        self.options.background_color = format_color(self.palette.bg);
+        if let Err(e) = self.options.save_for_rom(rom_file) {
+            log::error!(
+                "Can't save options for '{0}'. Error: {1}",
+                rom_file.display(),
+                e
+            );
+        }
+    }
+}
+
+// Replaces the breakpoints with the ones saved for the just loaded ROM, returns the
+// session for its comments, labels and notes
+fn restore_debug_session(emulator: &mut chip8::Emulator) -> DebugSession {
+    let breakpoints: Vec<u16> = emulator.breakpoints().collect();
+    for address in breakpoints {
+        emulator.remove_breakpoint(address);
+    }
+    match DebugSession::load(emulator.rom()) {
+        Ok(Some(session)) => {
+            for &address in &session.breakpoints {
+                emulator.add_breakpoint(address);
+            }
+            log::info!(
+                "Restored {} breakpoints, {} comments and {} labels",
+                session.breakpoints.len(),
+                session.comments().len(),
+                session.labels().len()
+            );
+            session
+        }
+        Ok(None) => DebugSession::default(),
+        Err(e) => {
+            log::warn!("Can't read breakpoints and comments. Error: {}", e);
+            DebugSession::default()
+        }
+    }
+}
+
+// Puts breakpoints back at the same label and offset in a reassembled source
+fn move_breakpoints(emulator: &mut chip8::Emulator, old: &chip8::Assembly, new: &chip8::Assembly) {
+    let breakpoints: Vec<u16> = emulator.breakpoints().collect();
+    for address in breakpoints {
+        let (label, offset) = match old.locate(address) {
+            Some(location) => location,
+            None => continue, // Before the first label, stays where it is
+        };
+        emulator.remove_breakpoint(address);
+        match new.resolve(label, offset) {
+            Some(moved) => emulator.add_breakpoint(moved),
+            None => log::warn!(
+                "Breakpoint at {:03X} ({}+{}) is gone from the source",
+                address,
+                label,
+                offset
+            ),
+        }
+    }
+}
+
+// Continues the reloaded program where the previous one was, by label and offset
+fn resume_at_label(
+    emulator: &mut chip8::Emulator,
+    old: &chip8::Assembly,
+    new: &chip8::Assembly,
+    pc: u16,
+) {
+    let resumed = old
+        .locate(pc)
+        .and_then(|(label, offset)| Some((label, new.resolve(label, offset)?)));
+    match resumed {
+        Some((label, address)) => {
+            emulator.set_pc(address);
+            log::info!(
+                target: logger::NOTIFY,
+                "Resumed at '{}' ({:03X})",
+                label,
+                address
+            );
+        }
+        None => log::warn!("Can't find the label of {:03X}, restarted instead", pc),
+    }
+}
@@ -0,0 +1,330 @@
+use crate::config::BeepIndicator;
+use crate::imgui_wgpu::Renderer;
+use crate::instance::Instance;
+use image::RgbaImage;
+use imgui::*;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use wgpu::{Device, Queue};
+
+/// ROMs shown one after another by kiosk mode, read from a TOML file:
+///
+/// ```toml
+/// duration = 60        # Seconds per ROM, unless the ROM sets its own
+/// title_time = 4       # Seconds the title stays on screen
+///
+/// [[roms]]
+/// path = "games/Brix.ch8"   # Relative to the playlist file
+/// title = "Brix"            # File name if not given
+/// duration = 30
+/// repeat = 10               # Demo presses start over every 10 seconds
+/// demo = [{ at = 1.0, key = 4, hold = 0.5 }, { at = 2.0, key = 6 }]
+///
+/// [bezel]
+/// image = "bezel.png"            # Drawn around the screen, relative to the playlist file
+/// screen = [160, 90, 960, 480]   # Opening for the screen: x, y, width, height in image pixels
+/// ```
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Playlist {
+    pub duration: f32,
+    pub title_time: f32,
+    pub roms: Vec<PlaylistRom>,
+    pub bezel: Option<Bezel>,
+}
+
+/// Frame image around the screen, scaled with it to fit the display
+#[derive(Deserialize, Clone, Debug)]
+pub struct Bezel {
+    pub image: PathBuf,
+    /// Part of the image the screen goes into as x, y, width, height in pixels.
+    /// The bezel is drawn over the screen, so the opening should be transparent.
+    pub screen: [u32; 4],
+}
+
+impl Default for Playlist {
+    fn default() -> Self {
+        Playlist {
+            duration: 60.0,
+            title_time: 4.0,
+            roms: Vec::new(),
+            bezel: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlaylistRom {
+    pub path: PathBuf,
+    pub title: Option<String>,
+    pub duration: Option<f32>,
+    pub repeat: Option<f32>,
+    /// Scripted key presses, so games show something without a player
+    #[serde(default)]
+    pub demo: Vec<DemoPress>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct DemoPress {
+    /// Seconds after the ROM started
+    pub at: f32,
+    /// Keypad key, 0x0..=0xF
+    pub key: u8,
+    #[serde(default = "default_hold")]
+    pub hold: f32,
+}
+
+fn default_hold() -> f32 {
+    0.2
+}
+
+impl PlaylistRom {
+    fn title(&self) -> String {
+        match &self.title {
+            Some(title) => title.clone(),
+            None => self
+                .path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        }
+    }
+}
+
+impl Playlist {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut playlist: Playlist = toml::from_str(&text).map_err(|e| e.to_string())?;
+        if playlist.roms.is_empty() {
+            return Err("No ROMs in the playlist".into());
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        if let Some(bezel) = &mut playlist.bezel {
+            bezel.image = dir.join(&bezel.image);
+        }
+        for rom in &mut playlist.roms {
+            rom.path = dir.join(&rom.path);
+            if let Some(press) = rom.demo.iter().find(|press| press.key > 0xF) {
+                return Err(format!("{} isn't a keypad key", press.key));
+            }
+        }
+        Ok(playlist)
+    }
+}
+
+// Screen and bezel placement on the display as (position, size)
+struct Layout {
+    screen: ([f32; 2], [f32; 2]),
+    bezel: Option<(TextureId, [f32; 2], [f32; 2])>,
+}
+
+/// Plays a playlist full screen with no UI, for exhibitions and as a screensaver
+pub struct Kiosk {
+    playlist: Playlist,
+    current: usize,
+    started: Instant,
+    /// Demo keys last sent to the emulator, on top of the ones the user holds
+    demo_keys: [bool; 16],
+    bezel: Option<BezelImage>,
+}
+
+struct BezelImage {
+    image: RgbaImage,
+    /// Opening in image pixels, inside the image
+    screen: [f32; 4],
+    /// Uploaded by `create_texture`
+    texture_id: Option<TextureId>,
+}
+
+impl BezelImage {
+    fn load(bezel: &Bezel) -> Result<Self, String> {
+        let image = image::open(&bezel.image)
+            .map_err(|e| e.to_string())?
+            .into_rgba8();
+        let [x, y, width, height] = bezel.screen;
+        if width == 0 || height == 0 || x + width > image.width() || y + height > image.height() {
+            return Err(format!(
+                "screen {:?} doesn't fit into the {}x{} image",
+                bezel.screen,
+                image.width(),
+                image.height()
+            ));
+        }
+        Ok(BezelImage {
+            image,
+            screen: [x as f32, y as f32, width as f32, height as f32],
+            texture_id: None,
+        })
+    }
+}
+
+impl Kiosk {
+    pub fn new(playlist: Playlist) -> Self {
+        let bezel = playlist
+            .bezel
+            .as_ref()
+            .and_then(|bezel| match BezelImage::load(bezel) {
+                Ok(image) => Some(image),
+                Err(e) => {
+                    log::error!(
+                        "Can't load bezel '{0}'. Error: {1}",
+                        bezel.image.display(),
+                        e
+                    );
+                    None
+                }
+            });
+        Kiosk {
+            playlist,
+            current: 0,
+            started: Instant::now(),
+            demo_keys: [false; 16],
+            bezel,
+        }
+    }
+
+    /// Uploads the bezel image once the GPU is there
+    pub fn create_texture(&mut self, renderer: &mut Renderer, device: &Device, queue: &Queue) {
+        if let Some(bezel) = &mut self.bezel {
+            if bezel.texture_id.is_none() {
+                let (width, height) = bezel.image.dimensions();
+                bezel.texture_id =
+                    Some(renderer.upload_texture(device, queue, &bezel.image, width, height));
+            }
+        }
+    }
+
+    // Where the screen and the bezel image around it go: screen position and size,
+    // then the bezel's. Without a bezel the screen fills the display at a whole scale.
+    fn layout(&self, display: [f32; 2], screen_size: (usize, usize)) -> Layout {
+        let bezel = match &self.bezel {
+            Some(bezel) => bezel,
+            None => {
+                let scale = (display[0] / screen_size.0 as f32)
+                    .min(display[1] / screen_size.1 as f32)
+                    .floor()
+                    .max(1.0);
+                let size = [screen_size.0 as f32 * scale, screen_size.1 as f32 * scale];
+                let p0 = [(display[0] - size[0]) / 2.0, (display[1] - size[1]) / 2.0];
+                return Layout {
+                    screen: (p0, size),
+                    bezel: None,
+                };
+            }
+        };
+        let (width, height) = bezel.image.dimensions();
+        let scale = (display[0] / width as f32).min(display[1] / height as f32);
+        let size = [width as f32 * scale, height as f32 * scale];
+        let p0 = [(display[0] - size[0]) / 2.0, (display[1] - size[1]) / 2.0];
+        let [x, y, w, h] = bezel.screen;
+        Layout {
+            screen: (
+                [p0[0] + x * scale, p0[1] + y * scale],
+                [w * scale, h * scale],
+            ),
+            bezel: bezel.texture_id.map(|texture_id| (texture_id, p0, size)),
+        }
+    }
+
+    fn rom(&self) -> &PlaylistRom {
+        &self.playlist.roms[self.current]
+    }
+
+    pub fn current_path(&self) -> &Path {
+        &self.rom().path
+    }
+
+    /// Next ROM to load once the current one had its time
+    pub fn advance(&mut self) -> Option<PathBuf> {
+        let duration = self.rom().duration.unwrap_or(self.playlist.duration);
+        if self.started.elapsed() < Duration::from_secs_f32(duration.max(1.0)) {
+            return None;
+        }
+        self.current = (self.current + 1) % self.playlist.roms.len();
+        self.started = Instant::now();
+        Some(self.rom().path.clone())
+    }
+
+    /// Keys the demo script holds down now, `None` if they didn't change since the last call
+    pub fn demo_keys(&mut self) -> Option<[bool; 16]> {
+        let rom = self.rom();
+        let mut time = self.started.elapsed().as_secs_f32();
+        if let Some(repeat) = rom.repeat.filter(|&repeat| repeat > 0.0) {
+            time %= repeat;
+        }
+        let mut keys = [false; 16];
+        for press in &rom.demo {
+            if (press.at..press.at + press.hold).contains(&time) {
+                keys[press.key as usize] = true;
+            }
+        }
+        if keys == self.demo_keys {
+            return None;
+        }
+        self.demo_keys = keys;
+        Some(keys)
+    }
+
+    /// Screen scaled to fill the window with the ROM's title over it for a while
+    pub fn draw(&self, ui: &Ui, instance: &Instance, beep_indicator: BeepIndicator) {
+        let display = ui.io().display_size;
+        let bg = instance.palette.bg;
+        let style = ui.push_style_vars(&[
+            StyleVar::WindowPadding([0.0, 0.0]),
+            StyleVar::WindowBorderSize(0.0),
+        ]);
+        let color = ui.push_style_color(StyleColor::WindowBg, bg);
+        Window::new(im_str!("Kiosk"))
+            .position([0.0, 0.0], Condition::Always)
+            .size(display, Condition::Always)
+            .no_decoration()
+            .movable(false)
+            .save_settings(false)
+            .build(ui, || {
+                let screen = &instance.screen;
+                let layout = self.layout(display, screen.size);
+                let (p0, size) = layout.screen;
+                ui.set_cursor_screen_pos(p0);
+                if let Some(texture_id) = screen.texture_id {
+                    Image::new(texture_id, size)
+                        .tint_col(instance.palette.fg)
+                        .build(ui);
+                }
+                if let Some((texture_id, bezel_p0, bezel_size)) = layout.bezel {
+                    ui.set_cursor_screen_pos(bezel_p0);
+                    Image::new(texture_id, bezel_size).build(ui);
+                }
+                if instance.beep {
+                    beep_indicator.draw(ui, p0, size, instance.palette.fg);
+                }
+
+                // Title fades out over its last second
+                let shown = self.started.elapsed().as_secs_f32();
+                let alpha = (self.playlist.title_time - shown).clamp(0.0, 1.0);
+                if alpha > 0.0 {
+                    ui.set_window_font_scale(3.0);
+                    let title = ImString::new(self.rom().title());
+                    let fg = instance.palette.fg;
+                    let margin = 24.0;
+                    let text_size = ui.calc_text_size(&title, false, 0.0);
+                    let draw_list = ui.get_window_draw_list();
+                    draw_list
+                        .add_rect(
+                            [margin - 8.0, margin - 8.0],
+                            [margin + text_size[0] + 8.0, margin + text_size[1] + 8.0],
+                            [bg[0], bg[1], bg[2], 0.8 * alpha],
+                        )
+                        .filled(true)
+                        .build();
+                    draw_list.add_text([margin, margin], [fg[0], fg[1], fg[2], alpha], &title);
+                    drop(draw_list);
+                    ui.set_window_font_scale(1.0);
+                }
+            });
+        color.pop(ui);
+        style.pop(ui);
+    }
+}
@@ -0,0 +1,88 @@
+//! Window layout presets picked from the View menu: which windows are open and,
+//! for presets the user saved, where they are. Saved presets are kept in the config.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LayoutPreset {
+    pub name: String,
+    /// Names of the open views, see `DebugView::name`
+    pub views: Vec<String>,
+    /// Window positions and sizes in imgui's ini format, empty leaves windows where they are
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub windows: String,
+}
+
+/// Where a window goes when a preset is picked
+#[derive(Debug, PartialEq)]
+pub struct WindowRect {
+    /// Full window title, as imgui knows the window
+    pub title: String,
+    pub pos: [f32; 2],
+    pub size: Option<[f32; 2]>,
+}
+
+impl LayoutPreset {
+    fn new(name: &str, views: &[&str]) -> Self {
+        LayoutPreset {
+            name: name.to_string(),
+            views: views.iter().map(|name| name.to_string()).collect(),
+            windows: String::new(),
+        }
+    }
+
+    /// Presets that come with the app, they can't be changed or deleted
+    pub const BUILT_IN_NAMES: [&'static str; 3] = ["Play", "Debug", "Dev"];
+
+    /// Built-in preset by name, see `BUILT_IN_NAMES`
+    pub fn built_in(name: &str) -> Option<LayoutPreset> {
+        let views: &[&str] = match name {
+            "Play" => &["Screen"],
+            "Debug" => &["Screen", "CPU", "Code", "Memory"],
+            "Dev" => &[
+                "Screen",
+                "Code",
+                "Sprite editor",
+                "Sprite gallery",
+                "Memory map",
+            ],
+            _ => return None,
+        };
+        Some(LayoutPreset::new(name, views))
+    }
+
+    /// Windows with a saved position, from the `[Window][title]` sections of `windows`
+    pub fn window_rects(&self) -> Vec<WindowRect> {
+        let mut rects = Vec::new();
+        let mut title = None;
+        let mut pos = None;
+        let mut size = None;
+        for line in self.windows.lines().map(str::trim) {
+            if line.starts_with('[') {
+                if let (Some(title), Some(pos)) = (title.take(), pos.take()) {
+                    rects.push(WindowRect { title, pos, size });
+                }
+                title = line
+                    .strip_prefix("[Window][")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .map(String::from);
+                pos = None;
+                size = None;
+            } else if let Some(value) = line.strip_prefix("Pos=") {
+                pos = parse_pair(value);
+            } else if let Some(value) = line.strip_prefix("Size=") {
+                size = parse_pair(value);
+            }
+        }
+        if let (Some(title), Some(pos)) = (title, pos) {
+            rects.push(WindowRect { title, pos, size });
+        }
+        rects
+    }
+}
+
+// "60,540" -> [60.0, 540.0]
+fn parse_pair(text: &str) -> Option<[f32; 2]> {
+    let (x, y) = text.split_once(',')?;
+    Some([x.trim().parse().ok()?, y.trim().parse().ok()?])
+}
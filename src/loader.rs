@@ -0,0 +1,301 @@
+use crate::logger;
+use crate::octo::OctoOptions;
+use crate::rom_patch;
+use crate::triggers::Triggers;
+use chip8_core::{Assembly, Extension, XO_CHIP_MEMORY_SIZE};
+use glob::glob;
+use sha1_smol::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+/// ROM file with its Octo options and triggers, read off the UI thread. Patch files
+/// next to it are already applied to `rom`.
+pub struct RomData {
+    pub path: PathBuf,
+    pub rom: io::Result<Vec<u8>>,
+    pub options: io::Result<Option<OctoOptions>>,
+    /// Labels of an Octo source, `None` for binary ROMs
+    pub assembly: Option<Assembly>,
+    pub triggers: io::Result<Triggers>,
+    /// Extension the ROM looks written for, see `Extension::detect`
+    pub extension: Option<Extension>,
+}
+
+impl RomData {
+    /// Reads a ROM, Octo sources (`.8o`) are assembled into one
+    pub fn read(path: &Path) -> Self {
+        let (rom, assembly) = if is_octo_source(path) {
+            match assemble(path) {
+                Ok(assembly) => (Ok(assembly.rom.clone()), Some(assembly)),
+                Err(e) => (Err(e), None),
+            }
+        } else {
+            (fs::read(path), None)
+        };
+        let rom = rom.map(|mut rom| {
+            patch(path, &mut rom);
+            rom
+        });
+        let extension = rom.as_ref().ok().and_then(|rom| Extension::detect(rom));
+        RomData {
+            path: path.to_path_buf(),
+            rom,
+            options: OctoOptions::load_for_rom(path),
+            assembly,
+            triggers: Triggers::load_for_rom(path),
+            extension,
+        }
+    }
+}
+
+// A broken patch is reported and the ROM runs as it is
+fn patch(path: &Path, rom: &mut Vec<u8>) {
+    match rom_patch::apply_for_rom(path, rom) {
+        Ok(applied) => {
+            for patch in applied {
+                log::info!(target: logger::NOTIFY, "Applied patch '{}'", patch.display());
+            }
+        }
+        Err(e) => log::error!("Can't apply patch for '{0}'. Error: {1}", path.display(), e),
+    }
+}
+
+fn is_octo_source(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("8o"))
+}
+
+fn assemble(path: &Path) -> io::Result<Assembly> {
+    let source = fs::read_to_string(path)?;
+    let assembly = Assembly::new(&source)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    log::info!(
+        "Assembled '{}' into {} bytes",
+        path.display(),
+        assembly.rom.len()
+    );
+    Ok(assembly)
+}
+
+/// Extensions ROM collections use for binary ROMs, matched ignoring case
+pub const ROM_EXTENSIONS: [&str; 5] = ["ch8", "c8", "sc8", "xo8", "hc8"];
+
+/// Largest ROM that fits anywhere, the 64KB of XO-CHIP memory after 0x200
+const MAX_ROM_SIZE: u64 = XO_CHIP_MEMORY_SIZE as u64 - 0x200;
+
+/// Whether `path` looks like a ROM: a known extension, or no extension and a size
+/// that fits in memory. Extensionless text files like a README are left out.
+pub fn is_rom_file(path: &Path) -> bool {
+    match path.extension() {
+        Some(extension) => ROM_EXTENSIONS
+            .iter()
+            .any(|rom_extension| extension.eq_ignore_ascii_case(rom_extension)),
+        None => {
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            let fits = fs::metadata(path)
+                .is_ok_and(|meta| meta.is_file() && (2..=MAX_ROM_SIZE).contains(&meta.len()));
+            !hidden && fits && fs::read(path).is_ok_and(|data| !is_text(&data))
+        }
+    }
+}
+
+// Printable ASCII and whitespace only, no ROM gets by without other bytes
+fn is_text(data: &[u8]) -> bool {
+    data.iter()
+        .all(|&byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace())
+}
+
+/// ROM files in `dir` and its subdirectories, see `is_rom_file`
+pub fn find_roms(dir: &Path) -> Result<impl Iterator<Item = PathBuf>, glob::PatternError> {
+    Ok(list_files(dir)?.filter(|path| is_rom_file(path)))
+}
+
+fn list_files(dir: &Path) -> Result<impl Iterator<Item = PathBuf>, glob::PatternError> {
+    let paths = glob(&dir.join("**/*").to_string_lossy())?;
+    Ok(paths.filter_map(Result::ok))
+}
+
+/// ROM file found by a scan, the hash of its contents gives away copies under other names
+#[derive(Clone, Debug)]
+pub struct RomFile {
+    pub path: PathBuf,
+    pub hash: Digest,
+}
+
+/// ROMs found by the latest scan, filled in from `LoadResult`s while the scan runs
+#[derive(Default)]
+pub struct RomList {
+    pub files: Vec<RomFile>,
+    /// Files found per hash, more than one means there are copies
+    counts: HashMap<Digest, usize>,
+    /// Files checked so far and files to check, `None` when no scan is running
+    progress: Option<(usize, usize)>,
+}
+
+impl RomList {
+    /// Forgets the previous scan, results of the next one come in with `update`
+    pub fn start_scan(&mut self) {
+        self.files.clear();
+        self.counts.clear();
+        self.progress = Some((0, 0));
+    }
+
+    /// Takes in a scan result, other results are left to the caller
+    pub fn update(&mut self, result: LoadResult) -> Option<LoadResult> {
+        match result {
+            LoadResult::ScanStarted(total) => self.progress = Some((0, total)),
+            LoadResult::ScanChecked(rom) => {
+                if let Some((checked, _)) = &mut self.progress {
+                    *checked += 1;
+                }
+                if let Some(rom) = rom {
+                    *self.counts.entry(rom.hash).or_default() += 1;
+                    self.files.push(rom);
+                }
+            }
+            LoadResult::ScanFinished => {
+                self.progress = None;
+                self.files.sort_by(|a, b| a.path.cmp(&b.path));
+            }
+            result => return Some(result),
+        }
+        None
+    }
+
+    pub fn is_scanning(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    /// Files checked so far and files to check while scanning
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.progress
+    }
+
+    /// Files with the same contents as `rom`, itself included
+    pub fn copies(&self, rom: &RomFile) -> usize {
+        self.counts.get(&rom.hash).copied().unwrap_or(0)
+    }
+
+    /// Files that are a copy of another one found earlier
+    pub fn duplicate_count(&self) -> usize {
+        self.counts.values().map(|&count| count - 1).sum()
+    }
+}
+
+pub enum LoadResult {
+    /// Scan listed this many files to check
+    ScanStarted(usize),
+    /// Scan checked a file, `Some` if it is a ROM
+    ScanChecked(Option<RomFile>),
+    ScanFinished,
+    /// ROM requested by `Loader::load_rom` for the instance with this id
+    RomLoaded(usize, Box<RomData>),
+}
+
+// Size and modification time of a file when it was hashed, by path
+type HashCache = HashMap<PathBuf, (u64, Option<SystemTime>, Digest)>;
+
+/// Scans directories and reads ROMs on background threads, so slow drives don't freeze the UI.
+/// Results are picked up with `poll` every frame.
+pub struct Loader {
+    sender: Sender<LoadResult>,
+    results: Receiver<LoadResult>,
+    /// Kept between scans, so a rescan only reads files that changed
+    hashes: Arc<Mutex<HashCache>>,
+    /// Bumped by every scan, an older scan still running stops when it sees that
+    scan_id: Arc<AtomicUsize>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        let (sender, results) = mpsc::channel();
+        Loader {
+            sender,
+            results,
+            hashes: Arc::default(),
+            scan_id: Arc::default(),
+        }
+    }
+
+    /// Looks for ROM files in `dir` and its subdirectories and hashes them,
+    /// files are reported one by one as they are checked
+    pub fn scan(&self, dir: PathBuf) {
+        let sender = self.sender.clone();
+        let hashes = self.hashes.clone();
+        let scan_id = self.scan_id.clone();
+        let id = scan_id.fetch_add(1, Ordering::SeqCst) + 1;
+        thread::spawn(move || {
+            // Listing is quick, reading the files is what takes a while in large collections
+            let paths: Vec<PathBuf> = match list_files(&dir) {
+                Ok(paths) => paths.collect(),
+                Err(e) => {
+                    log::error!("Can't scan '{0}'. Error: {1}", dir.display(), e);
+                    Vec::new()
+                }
+            };
+            let _ = sender.send(LoadResult::ScanStarted(paths.len()));
+            for path in paths {
+                if scan_id.load(Ordering::SeqCst) != id {
+                    return;
+                }
+                let rom = if is_rom_file(&path) {
+                    hash_file(&path, &hashes).map(|hash| RomFile { path, hash })
+                } else {
+                    None
+                };
+                if sender.send(LoadResult::ScanChecked(rom)).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(LoadResult::ScanFinished);
+        });
+    }
+
+    pub fn load_rom(&self, instance_id: usize, path: PathBuf) {
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let _ = sender.send(LoadResult::RomLoaded(
+                instance_id,
+                Box::new(RomData::read(&path)),
+            ));
+        });
+    }
+
+    /// Results that arrived since the last call
+    pub fn poll(&self) -> mpsc::TryIter<'_, LoadResult> {
+        self.results.try_iter()
+    }
+}
+
+// SHA-1 of the file, taken from `hashes` if the file didn't change since it was last read
+fn hash_file(path: &Path, hashes: &Mutex<HashCache>) -> Option<Digest> {
+    let meta = fs::metadata(path).ok()?;
+    let (len, modified) = (meta.len(), meta.modified().ok());
+    if let Some(&(cached_len, cached_modified, hash)) = hashes.lock().unwrap().get(path) {
+        if (cached_len, cached_modified) == (len, modified) {
+            return Some(hash);
+        }
+    }
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            log::warn!("Can't read '{0}'. Error: {1}", path.display(), e);
+            return None;
+        }
+    };
+    let hash = Sha1::from(&data).digest();
+    hashes
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), (len, modified, hash));
+    Some(hash)
+}
@@ -0,0 +1,128 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+/// Environment variable with log filters, e.g. `CHIP8_LOG=debug` or
+/// `CHIP8_LOG=info,chip8_core=trace`
+pub const LOG_ENV: &str = "CHIP8_LOG";
+
+/// Number of lines kept for the Log window
+const MAX_LINES: usize = 500;
+
+/// Log target for info messages that are also shown as toasts, warnings and errors always are:
+/// `log::info!(target: logger::NOTIFY, "State saved")`
+pub const NOTIFY: &str = "notify";
+
+/// Toasts kept until the UI picks them up, headless runs never do
+const MAX_TOASTS: usize = 50;
+
+/// Logger writing to stderr and keeping recent lines for the Log window
+pub struct Logger {
+    default_level: LevelFilter,
+    /// Per-module levels, the longest matching target prefix wins
+    filters: Vec<(String, LevelFilter)>,
+    lines: Mutex<VecDeque<(Level, String)>>,
+    toasts: Mutex<Vec<(Level, String)>>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Installs the logger, filters are read from `CHIP8_LOG`
+pub fn init() {
+    let spec = std::env::var(LOG_ENV).unwrap_or_default();
+    let logger = Logger::from_spec(&spec);
+    let max_level = logger
+        .filters
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(logger.default_level, Ord::max);
+    if LOGGER.set(logger).is_ok() && log::set_logger(LOGGER.get().unwrap()).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
+
+/// Installed logger, `None` if `init` wasn't called
+pub fn get() -> Option<&'static Logger> {
+    LOGGER.get()
+}
+
+impl Logger {
+    fn from_spec(spec: &str) -> Self {
+        let mut default_level = LevelFilter::Info;
+        let mut filters = Vec::new();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Ok(level) = level.parse() {
+                        filters.push((target.to_string(), level));
+                    }
+                }
+                None => {
+                    if let Ok(level) = directive.parse() {
+                        default_level = level;
+                    }
+                }
+            }
+        }
+        Logger {
+            default_level,
+            filters,
+            lines: Mutex::new(VecDeque::new()),
+            toasts: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.filters
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default_level, |(_, level)| *level)
+    }
+
+    /// Calls `f` for each kept line, oldest first
+    pub fn for_each_line(&self, mut f: impl FnMut(Level, &str)) {
+        let lines = self.lines.lock().unwrap();
+        for (level, line) in lines.iter() {
+            f(*level, line);
+        }
+    }
+
+    /// Messages to show as toasts since the last call
+    pub fn take_toasts(&self) -> Vec<(Level, String)> {
+        std::mem::take(&mut *self.toasts.lock().unwrap())
+    }
+
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!("[{}] {}", record.target(), record.args());
+        eprintln!("{:<5} {}", record.level(), line);
+
+        if record.level() <= Level::Warn || record.target() == NOTIFY {
+            let mut toasts = self.toasts.lock().unwrap();
+            if toasts.len() < MAX_TOASTS {
+                toasts.push((record.level(), record.args().to_string()));
+            }
+        }
+
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == MAX_LINES {
+            lines.pop_front();
+        }
+        lines.push_back((record.level(), line));
+    }
+
+    fn flush(&self) {}
+}
@@ -1,11 +1,78 @@
 mod app;
-mod chip8;
+mod automation;
+mod battery;
+mod bug_report;
+mod cli;
+mod clipboard;
+mod compatibility;
+mod config;
+mod crash;
+mod debug_session;
+mod disassembly;
+mod fonts;
+mod gpu;
+mod headless;
+mod http;
+mod i18n;
+#[allow(dead_code, unexpected_cfgs, clippy::all)]
 mod imgui_wgpu;
+mod instance;
+mod kiosk;
+mod layouts;
+mod loader;
+mod logger;
+mod observe;
+mod octo;
+mod perf;
+mod rom_patch;
+mod runner;
+mod screenshot;
+mod smoke;
+mod state_diff;
+mod test_all;
+mod theme;
+mod toasts;
+mod triggers;
+mod views;
+mod watch;
 
 use app::Chip8App;
+use cli::Args;
 use std::rc::Rc;
 
 fn main() {
-    let app = Rc::new(Chip8App::new());
+    logger::init();
+    let args = Args::parse();
+    if let Some(smoke) = &args.smoke {
+        if let Err(e) = smoke::run(smoke, &args) {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(test_all) = &args.test_all {
+        if let Err(e) = test_all::run(test_all, &args) {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(diff_state) = &args.diff_state {
+        if let Err(e) = state_diff::run(diff_state) {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.headless {
+        if let Err(e) = headless::run(&args) {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    crash::install();
+    let app = Rc::new(Chip8App::new(args));
     app.run()
 }
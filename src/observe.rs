@@ -0,0 +1,164 @@
+//! Streams the focused emulator's screen and CPU state over a local TCP socket,
+//! for visualizers, OBS plugins and analysis tools running next to the app.
+//! A client gets the latest snapshot when it connects and then a new one
+//! whenever the state changes, at most once per UI frame. Clients that don't
+//! keep up skip snapshots instead of slowing the app down.
+//!
+//! Snapshots are `SNAPSHOT_SIZE` bytes back to back, integers are little-endian:
+//!
+//! | Offset | Size | Field                                                   |
+//! |--------|------|---------------------------------------------------------|
+//! | 0      | 4    | `C8OB`                                                  |
+//! | 4      | 2    | layout version, `VERSION`                               |
+//! | 6      | 2    | snapshot size in bytes                                  |
+//! | 8      | 8    | frame, 60Hz ticks since the ROM was loaded              |
+//! | 16     | 8    | instructions executed                                   |
+//! | 24     | 2    | PC                                                      |
+//! | 26     | 2    | I                                                       |
+//! | 28     | 16   | V0 to VF                                                |
+//! | 44     | 1    | delay timer                                             |
+//! | 45     | 1    | sound timer                                             |
+//! | 46     | 1    | stack depth                                             |
+//! | 47     | 1    | flags: bit 0 halted, bit 1 paused                       |
+//! | 48     | 32   | stack, 16 return addresses from the bottom, unused are 0 |
+//! | 80     | 2    | screen width in pixels                                  |
+//! | 82     | 2    | screen height in pixels                                 |
+//! | 84     | 256  | screen, width / 8 bytes per row from the top, the most significant bit of a byte is its leftmost pixel |
+
+use chip8_core::{Emulator, SCREEN_SIZE, STACK_DEPTH};
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const MAGIC: [u8; 4] = *b"C8OB";
+
+/// Bumped whenever the layout changes
+pub const VERSION: u16 = 1;
+
+const HEADER_SIZE: usize = 84;
+const SCREEN_BYTES: usize = SCREEN_SIZE.0 / 8 * SCREEN_SIZE.1;
+pub const SNAPSHOT_SIZE: usize = HEADER_SIZE + SCREEN_BYTES;
+
+/// Snapshots waiting for a client before newer ones are dropped
+const CLIENT_QUEUE: usize = 4;
+
+type Snapshot = Arc<Vec<u8>>;
+
+/// State of `emulator` in the layout above
+pub fn snapshot(emulator: &Emulator, paused: bool) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(SNAPSHOT_SIZE);
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(SNAPSHOT_SIZE as u16).to_le_bytes());
+    bytes.extend_from_slice(&emulator.frame().to_le_bytes());
+    bytes.extend_from_slice(&emulator.instruction_count().to_le_bytes());
+    bytes.extend_from_slice(&emulator.pc().to_le_bytes());
+    bytes.extend_from_slice(&emulator.index().to_le_bytes());
+    bytes.extend_from_slice(emulator.registers());
+
+    let stack = emulator.stack();
+    let flags = emulator.halt_reason().is_some() as u8 | (paused as u8) << 1;
+    bytes.extend_from_slice(&[
+        emulator.delay_timer(),
+        emulator.sound_timer(),
+        stack.len() as u8,
+        flags,
+    ]);
+    for i in 0..STACK_DEPTH {
+        let address = stack.get(i).copied().unwrap_or(0);
+        bytes.extend_from_slice(&address.to_le_bytes());
+    }
+
+    let (width, height) = SCREEN_SIZE;
+    bytes.extend_from_slice(&(width as u16).to_le_bytes());
+    bytes.extend_from_slice(&(height as u16).to_le_bytes());
+    for row in emulator.screen().rows() {
+        bytes.extend_from_slice(&row.to_be_bytes());
+    }
+    debug_assert_eq!(bytes.len(), SNAPSHOT_SIZE);
+    bytes
+}
+
+#[derive(Default)]
+struct Clients {
+    senders: Vec<SyncSender<Snapshot>>,
+    /// Sent to clients as they connect
+    last: Option<Snapshot>,
+}
+
+/// Listens for observers and hands them snapshots, see the module docs
+pub struct Observer {
+    clients: Arc<Mutex<Clients>>,
+}
+
+impl Observer {
+    pub fn start(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        log::info!("Streaming emulator state on {}", listener.local_addr()?);
+
+        let clients = Arc::new(Mutex::new(Clients::default()));
+        let accept_clients = clients.clone();
+        thread::Builder::new()
+            .name("observer".into())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => accept(stream, &accept_clients),
+                        Err(e) => log::warn!("Observer connection failed. Error: {}", e),
+                    }
+                }
+            })?;
+        Ok(Observer { clients })
+    }
+
+    /// Sends `snapshot` to every client, unless it's the same as the last one
+    pub fn publish(&self, snapshot: Vec<u8>) {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.last.as_deref() == Some(&snapshot) {
+            return;
+        }
+        let snapshot = Arc::new(snapshot);
+        // A full queue means the client is behind, it gets a later snapshot instead
+        clients.senders.retain(|sender| {
+            !matches!(
+                sender.try_send(snapshot.clone()),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+        clients.last = Some(snapshot);
+    }
+}
+
+fn accept(stream: TcpStream, clients: &Mutex<Clients>) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_string(), |addr| addr.to_string());
+    log::info!("Observer connected from {}", peer);
+    // Snapshots are small and should go out right away
+    let _ = stream.set_nodelay(true);
+
+    let (sender, receiver) = mpsc::sync_channel(CLIENT_QUEUE);
+    let mut clients = clients.lock().unwrap();
+    if let Some(last) = &clients.last {
+        let _ = sender.try_send(last.clone());
+    }
+    let spawned = thread::Builder::new()
+        .name("observer client".into())
+        .spawn(move || send_snapshots(stream, receiver, &peer));
+    match spawned {
+        Ok(_) => clients.senders.push(sender),
+        Err(e) => log::warn!("Can't start observer thread. Error: {}", e),
+    }
+}
+
+// Writes snapshots until the client goes away, dropping `receiver` takes it off the list
+fn send_snapshots(mut stream: TcpStream, receiver: Receiver<Snapshot>, peer: &str) {
+    for snapshot in receiver {
+        if stream.write_all(&snapshot).is_err() {
+            break;
+        }
+    }
+    log::info!("Observer {} disconnected", peer);
+}
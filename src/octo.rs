@@ -0,0 +1,122 @@
+use chip8_core::Quirks;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Octo runs tickrate instructions per 60Hz frame
+const OCTO_FRAME_RATE: f32 = 60.0;
+
+/// Per-ROM options in the format Octo saves (`options.json`).
+/// Fields this emulator doesn't use are kept as is, so files round-trip.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OctoOptions {
+    pub tickrate: u32,
+    pub fill_color: String,
+    pub background_color: String,
+    pub shift_quirks: bool,
+    pub load_store_quirks: bool,
+    pub jump_quirks: bool,
+    pub logic_quirks: bool,
+    pub clip_quirks: bool,
+    pub v_blank_quirks: bool,
+    /// Memory kept between sessions as "3F0-3FF", see `battery`. Octo doesn't have it
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub save_memory: String,
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+impl Default for OctoOptions {
+    // Octo defaults
+    fn default() -> Self {
+        OctoOptions {
+            tickrate: 20,
+            fill_color: "#FFCC00".to_string(),
+            background_color: "#996600".to_string(),
+            shift_quirks: false,
+            load_store_quirks: false,
+            jump_quirks: false,
+            logic_quirks: false,
+            clip_quirks: false,
+            v_blank_quirks: false,
+            save_memory: String::new(),
+            other: Map::new(),
+        }
+    }
+}
+
+impl OctoOptions {
+    /// Options file kept next to the ROM: `Game.ch8` -> `Game.json`
+    pub fn path_for_rom(rom_file: &Path) -> PathBuf {
+        rom_file.with_extension("json")
+    }
+
+    /// Reads options for the ROM, `Ok(None)` if there is no options file.
+    pub fn load_for_rom(rom_file: &Path) -> io::Result<Option<Self>> {
+        let path = Self::path_for_rom(rom_file);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(path)?;
+        let options = serde_json::from_str(&text)?;
+        Ok(Some(options))
+    }
+
+    pub fn save_for_rom(&self, rom_file: &Path) -> io::Result<()> {
+        let text = serde_json::to_string_pretty(self)?;
+        fs::write(Self::path_for_rom(rom_file), text)
+    }
+
+    pub fn clock_hz(&self) -> f32 {
+        self.tickrate.max(1) as f32 * OCTO_FRAME_RATE
+    }
+
+    pub fn set_clock_hz(&mut self, hz: f32) {
+        self.tickrate = (hz / OCTO_FRAME_RATE).round().max(1.0) as u32;
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        Quirks {
+            shift: self.shift_quirks,
+            load_store: self.load_store_quirks,
+            jump: self.jump_quirks,
+            logic: self.logic_quirks,
+            clip: self.clip_quirks,
+            vblank: self.v_blank_quirks,
+        }
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.shift_quirks = quirks.shift;
+        self.load_store_quirks = quirks.load_store;
+        self.jump_quirks = quirks.jump;
+        self.logic_quirks = quirks.logic;
+        self.clip_quirks = quirks.clip;
+        self.v_blank_quirks = quirks.vblank;
+    }
+}
+
+/// Parses "#RRGGBB" into RGBA in 0..1 range
+pub fn parse_color(s: &str) -> Option<[f32; 4]> {
+    let hex = s.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let v = u32::from_str_radix(hex, 16).ok()?;
+    let channel = |shift: u32| ((v >> shift) & 0xFF) as f32 / 255.0;
+    Some([channel(16), channel(8), channel(0), 1.0])
+}
+
+/// Formats RGBA in 0..1 range as "#RRGGBB"
+pub fn format_color(color: [f32; 4]) -> String {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02X}{:02X}{:02X}",
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2])
+    )
+}
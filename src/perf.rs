@@ -0,0 +1,73 @@
+//! Frame timing graphs drawn over the UI from View > Performance graphs, for
+//! telling where stutter comes from and checking performance work.
+
+use crate::i18n::tr;
+use imgui::*;
+use std::collections::VecDeque;
+
+/// Frames kept in the graphs
+const HISTORY_LEN: usize = 300;
+
+// Picks one timing out of FrameTimes, used for the graphs
+type Timing = fn(&FrameTimes) -> f32;
+
+/// Timings of one UI frame in milliseconds
+#[derive(Clone, Copy, Default)]
+pub struct FrameTimes {
+    /// Since the previous frame started
+    pub frame: f32,
+    /// Emulator threads running their emulators since the previous frame, all instances added up
+    pub emulation: f32,
+    /// Converting new screens to pixels and uploading them to their textures
+    pub upload: f32,
+}
+
+#[derive(Default)]
+pub struct PerfGraphs {
+    history: VecDeque<FrameTimes>,
+}
+
+impl PerfGraphs {
+    pub fn push(&mut self, times: FrameTimes) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(times);
+    }
+
+    /// Graphs in the top right corner, below the menu bar
+    pub fn draw(&self, ui: &Ui) {
+        let graphs: [(&'static str, Timing); 3] = [
+            ("Frame time", |times| times.frame),
+            ("Emulation", |times| times.emulation),
+            ("Texture upload", |times| times.upload),
+        ];
+        let width = ui.io().display_size[0];
+        imgui::Window::new(im_str!("Performance"))
+            .position(
+                [width - 10.0, ui.frame_height_with_spacing() + 10.0],
+                Condition::Always,
+            )
+            .position_pivot([1.0, 0.0])
+            .no_decoration()
+            .always_auto_resize(true)
+            .movable(false)
+            .save_settings(false)
+            .bg_alpha(0.7)
+            .build(ui, || {
+                for (name, value) in graphs.iter() {
+                    let values: Vec<f32> = self.history.iter().map(value).collect();
+                    let last = values.last().copied().unwrap_or(0.0);
+                    let max = values.iter().copied().fold(0.0, f32::max);
+                    let overlay =
+                        ImString::new(format!("{} {:.2} ms, max {:.2}", tr(name), last, max));
+                    ui.plot_lines(&ImString::new(format!("##{}", name)), &values)
+                        .overlay_text(&overlay)
+                        .scale_min(0.0)
+                        .scale_max(max.max(1.0))
+                        .graph_size([HISTORY_LEN as f32, 50.0])
+                        .build();
+                }
+            });
+    }
+}
@@ -0,0 +1,257 @@
+//! Patches kept next to a ROM and applied every time it's loaded, so fixes and
+//! translations can be shared without the ROM itself. `Game.ch8` is patched by
+//! `Game.ips` (the usual IPS format, offsets from the start of the file) or by
+//! `Game.patch`, a text file of memory addresses and the bytes to put there:
+//!
+//! ```text
+//! # Skip the title screen
+//! 0x200: 12 4E
+//! 0x2A0: 00E0 A2F0   # Spaces are optional
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where the ROM starts in memory, `.patch` addresses are relative to memory
+const ROM_START: usize = 0x200;
+
+/// Largest ROM that fits in memory, the XO-CHIP 64KB less the interpreter area
+const MAX_ROM_SIZE: usize = 0x10000 - ROM_START;
+
+/// End of a write at `offset`, `None` if it goes past the largest ROM
+fn write_end(offset: usize, size: usize) -> Option<usize> {
+    offset.checked_add(size).filter(|&end| end <= MAX_ROM_SIZE)
+}
+
+/// Patch files looked for next to `rom_file`, in the order they are applied
+pub fn paths_for_rom(rom_file: &Path) -> [PathBuf; 2] {
+    [
+        rom_file.with_extension("ips"),
+        rom_file.with_extension("patch"),
+    ]
+}
+
+/// Applies the patch files that exist next to `rom_file` to `rom`, returns the
+/// ones applied. The ROM is left alone if any of them is broken.
+pub fn apply_for_rom(rom_file: &Path, rom: &mut Vec<u8>) -> io::Result<Vec<PathBuf>> {
+    let mut patched = rom.clone();
+    let mut applied = Vec::new();
+    for path in paths_for_rom(rom_file).iter() {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let result = if path.extension().is_some_and(|e| e == "ips") {
+            apply_ips(&data, &mut patched)
+        } else {
+            let text = String::from_utf8_lossy(&data);
+            parse_text(&text).map(|writes| apply_writes(&writes, &mut patched))
+        };
+        result.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })?;
+        applied.push(path.clone());
+    }
+    *rom = patched;
+    Ok(applied)
+}
+
+/// Writes ROM offset and bytes pairs, growing the ROM when they go past its end.
+/// The parsers make sure they end within `MAX_ROM_SIZE`.
+fn apply_writes(writes: &[(usize, Vec<u8>)], rom: &mut Vec<u8>) {
+    for (offset, bytes) in writes {
+        let end = offset + bytes.len();
+        if rom.len() < end {
+            rom.resize(end, 0);
+        }
+        rom[*offset..end].copy_from_slice(bytes);
+    }
+}
+
+/// Lines of `address: bytes` into ROM offset and bytes pairs
+fn parse_text(text: &str) -> Result<Vec<(usize, Vec<u8>)>, String> {
+    let mut writes = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: &str| format!("line {}: {}", number + 1, message);
+        let (address, bytes) = line
+            .split_once(':')
+            .ok_or_else(|| error("expected 'address: bytes'"))?;
+        let address = address.trim();
+        let address = address.strip_prefix("0x").unwrap_or(address);
+        let address = usize::from_str_radix(address, 16).map_err(|_| error("address isn't hex"))?;
+        if address < ROM_START {
+            return Err(error("address is before the ROM at 0x200"));
+        }
+        let digits: Vec<u8> = bytes.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(error("bytes aren't hex"));
+        }
+        if digits.is_empty() || !digits.len().is_multiple_of(2) {
+            return Err(error("bytes need two hex digits each"));
+        }
+        let bytes: Vec<u8> = digits
+            .chunks(2)
+            .map(|pair| (hex_digit(pair[0]) << 4) | hex_digit(pair[1]))
+            .collect();
+        let offset = address - ROM_START;
+        if write_end(offset, bytes.len()).is_none() {
+            return Err(error("address is past the end of memory"));
+        }
+        writes.push((offset, bytes));
+    }
+    Ok(writes)
+}
+
+fn hex_digit(digit: u8) -> u8 {
+    (digit as char).to_digit(16).unwrap_or(0) as u8
+}
+
+/// IPS: "PATCH", records of 3 byte offset and 2 byte size followed by the data,
+/// or size 0 then a 2 byte count and a byte to repeat, "EOF" and an optional
+/// 3 byte size to truncate to
+fn apply_ips(data: &[u8], rom: &mut Vec<u8>) -> Result<(), String> {
+    let mut data = data.strip_prefix(b"PATCH").ok_or("no IPS header")?;
+    let mut take = |count: usize| -> Result<&[u8], String> {
+        if data.len() < count {
+            return Err("IPS data ends early".to_string());
+        }
+        let (taken, rest) = data.split_at(count);
+        data = rest;
+        Ok(taken)
+    };
+    let number = |bytes: &[u8]| bytes.iter().fold(0, |n, &b| n << 8 | b as usize);
+    let mut writes = Vec::new();
+    loop {
+        let offset = take(3)?;
+        if offset == b"EOF" {
+            break;
+        }
+        let offset = number(offset);
+        let bytes = match number(take(2)?) {
+            0 => {
+                let count = number(take(2)?);
+                vec![take(1)?[0]; count]
+            }
+            size => take(size)?.to_vec(),
+        };
+        if write_end(offset, bytes.len()).is_none() {
+            return Err(format!(
+                "IPS record at 0x{:06X} is past the end of memory",
+                offset
+            ));
+        }
+        writes.push((offset, bytes));
+    }
+    apply_writes(&writes, rom);
+    if let Ok(size) = take(3) {
+        rom.truncate(number(size));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patched(rom: &[u8], writes: &[(usize, Vec<u8>)]) -> Vec<u8> {
+        let mut rom = rom.to_vec();
+        apply_writes(writes, &mut rom);
+        rom
+    }
+
+    #[test]
+    fn text_patch_writes_at_memory_addresses() {
+        let writes = parse_text("# Title\n0x200: 12 4E\n\n202: 00E0A2F0 # comment\n").unwrap();
+        assert_eq!(
+            writes,
+            vec![(0, vec![0x12, 0x4E]), (2, vec![0x00, 0xE0, 0xA2, 0xF0])]
+        );
+        assert_eq!(
+            patched(&[0; 2], &writes),
+            [0x12, 0x4E, 0x00, 0xE0, 0xA2, 0xF0]
+        );
+    }
+
+    #[test]
+    fn text_patch_errors_name_the_line() {
+        assert_eq!(
+            parse_text("0x200 12").unwrap_err(),
+            "line 1: expected 'address: bytes'"
+        );
+        assert_eq!(
+            parse_text("\n0x1FF: 12").unwrap_err(),
+            "line 2: address is before the ROM at 0x200"
+        );
+        assert_eq!(
+            parse_text("0x200: 1G").unwrap_err(),
+            "line 1: bytes aren't hex"
+        );
+        assert_eq!(
+            parse_text("0x200: 123").unwrap_err(),
+            "line 1: bytes need two hex digits each"
+        );
+    }
+
+    #[test]
+    fn text_patch_past_the_end_of_memory_is_rejected() {
+        assert!(parse_text("0xFFFF: 00").is_ok());
+        for line in [
+            "0xFFFF: 00 00",
+            "0xFFFFFFFFFF: 00",
+            "0xFFFFFFFFFFFFFFFF: 00",
+        ] {
+            assert_eq!(
+                parse_text(line).unwrap_err(),
+                "line 1: address is past the end of memory"
+            );
+        }
+    }
+
+    #[test]
+    fn ips_records_write_and_repeat() {
+        let mut ips = b"PATCH".to_vec();
+        ips.extend_from_slice(&[0, 0, 1, 0, 2, 0xAA, 0xBB]);
+        // RLE: size 0, then count 3 of 0xCC
+        ips.extend_from_slice(&[0, 0, 4, 0, 0, 0, 3, 0xCC]);
+        ips.extend_from_slice(b"EOF");
+        let mut rom = vec![0; 4];
+        apply_ips(&ips, &mut rom).unwrap();
+        assert_eq!(rom, [0, 0xAA, 0xBB, 0, 0xCC, 0xCC, 0xCC]);
+    }
+
+    #[test]
+    fn ips_truncates_after_eof() {
+        let mut ips = b"PATCHEOF".to_vec();
+        ips.extend_from_slice(&[0, 0, 2]);
+        let mut rom = vec![1, 2, 3, 4];
+        apply_ips(&ips, &mut rom).unwrap();
+        assert_eq!(rom, [1, 2]);
+    }
+
+    #[test]
+    fn broken_ips_is_rejected() {
+        let mut rom = vec![0; 4];
+        assert_eq!(apply_ips(b"PATC", &mut rom).unwrap_err(), "no IPS header");
+        assert_eq!(
+            apply_ips(b"PATCH\0\0\0\0\x04\xAA", &mut rom).unwrap_err(),
+            "IPS data ends early"
+        );
+        let mut ips = b"PATCH".to_vec();
+        ips.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0, 0, 0xFF, 0xFF, 0]);
+        ips.extend_from_slice(b"EOF");
+        assert_eq!(
+            apply_ips(&ips, &mut rom).unwrap_err(),
+            "IPS record at 0xFFFFFF is past the end of memory"
+        );
+        assert_eq!(rom, [0; 4]);
+    }
+}
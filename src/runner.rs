@@ -0,0 +1,252 @@
+use crate::crash;
+use chip8_core as chip8;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often the emulation thread runs the emulator
+const TICK_RATE: f32 = 240.0;
+
+/// Tick rate with low-latency input, keys are sampled before every tick
+const LOW_LATENCY_TICK_RATE: f32 = 1000.0;
+
+/// Longest time step emulated per tick, so a stalled thread doesn't try to catch up
+const MAX_TICK_TIME: f32 = 0.1;
+
+enum Command {
+    Keys([bool; 16]),
+    Speed(f32),
+    Pause(bool),
+    LowLatency(bool),
+    /// Run one 60Hz frame, even while paused
+    AdvanceFrame,
+    Quit,
+}
+
+/// Emulator output for the UI
+pub struct Frame {
+    /// Screen contents, `None` if it didn't change since the last frame
+    pub screen: Option<chip8::Screen>,
+    pub beep: bool,
+    /// Time spent running the emulator since the previous frame was picked up
+    pub emulation_time: Duration,
+}
+
+/// Output the UI hasn't picked up yet. Ticks merge into it instead of queueing,
+/// so however fast emulation runs only the latest screen gets uploaded per UI frame.
+type PendingFrame = Arc<Mutex<Option<Frame>>>;
+
+/// Runs an emulator on its own thread at a fixed rate, so emulation speed doesn't
+/// depend on the UI frame rate. Keys are sent to it over a channel and the latest
+/// screen is left in `pending`, debug views and settings access the emulator through `lock`.
+pub struct EmulatorThread {
+    emulator: Arc<Mutex<chip8::Emulator>>,
+    commands: Sender<Command>,
+    pending: PendingFrame,
+    thread: Option<JoinHandle<()>>,
+}
+
+// Keypad state received from the UI
+struct Keys([bool; 16]);
+
+impl chip8::Input for Keys {
+    fn is_key_down(&self, key: u8) -> bool {
+        self.0[key as usize]
+    }
+}
+
+// Screen changed during a tick
+#[derive(Default)]
+struct ScreenOutput(Option<chip8::Screen>);
+
+impl chip8::Display for ScreenOutput {
+    fn draw(&mut self, screen: &chip8::Screen) {
+        self.0 = Some(screen.clone());
+    }
+}
+
+// Sound state after a tick
+#[derive(Default)]
+struct BeepOutput(bool);
+
+impl chip8::Audio for BeepOutput {
+    fn set_beep(&mut self, on: bool) {
+        self.0 = on;
+    }
+}
+
+impl EmulatorThread {
+    pub fn spawn(emulator: chip8::Emulator, low_latency: bool) -> Self {
+        let emulator = Arc::new(Mutex::new(emulator));
+        let (commands, command_receiver) = mpsc::channel();
+        let pending = PendingFrame::default();
+
+        let thread_emulator = emulator.clone();
+        let thread_pending = pending.clone();
+        let thread = thread::Builder::new()
+            .name(crash::EMULATOR_THREAD.into())
+            .spawn(move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    run(thread_emulator, command_receiver, thread_pending)
+                }));
+                // The emulator is unlocked now, it can go into the crash dump
+                if result.is_err() {
+                    crash::write_pending();
+                }
+            })
+            .expect("Can't start emulator thread");
+
+        let thread = EmulatorThread {
+            emulator,
+            commands,
+            pending,
+            thread: Some(thread),
+        };
+        thread.set_low_latency(low_latency);
+        thread
+    }
+
+    /// Handle that doesn't keep the emulator alive, see `crash::register`
+    pub fn downgrade(&self) -> Weak<Mutex<chip8::Emulator>> {
+        Arc::downgrade(&self.emulator)
+    }
+
+    /// Locks the emulator for reading state or changing settings, emulation waits meanwhile.
+    pub fn lock(&self) -> MutexGuard<'_, chip8::Emulator> {
+        self.emulator.lock().unwrap()
+    }
+
+    pub fn set_keys(&self, keys: [bool; 16]) {
+        let _ = self.commands.send(Command::Keys(keys));
+    }
+
+    /// Scales emulated time, instructions and timers alike, 1.0 is real time
+    pub fn set_speed(&self, speed: f32) {
+        let _ = self.commands.send(Command::Speed(speed));
+    }
+
+    /// Stops running the emulator until unpaused, state stays accessible through `lock`
+    pub fn set_paused(&self, paused: bool) {
+        let _ = self.commands.send(Command::Pause(paused));
+    }
+
+    /// Low latency wakes the thread as soon as keys change and runs the emulator in
+    /// 1ms ticks instead of 4ms, so a press reaches the program within about a
+    /// millisecond. Key events are handled as they come either way, not per UI frame.
+    pub fn set_low_latency(&self, on: bool) {
+        let _ = self.commands.send(Command::LowLatency(on));
+    }
+
+    /// Runs one frame of a paused emulator, timers tick once
+    pub fn advance_frame(&self) {
+        let _ = self.commands.send(Command::AdvanceFrame);
+    }
+
+    /// Latest output since the previous call, `None` if the emulator hasn't run since
+    pub fn poll_frame(&self) -> Option<Frame> {
+        self.pending.lock().unwrap().take()
+    }
+}
+
+impl Drop for EmulatorThread {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Quit);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(emulator: Arc<Mutex<chip8::Emulator>>, commands: Receiver<Command>, pending: PendingFrame) {
+    let mut period = Duration::from_secs_f32(1.0 / TICK_RATE);
+    let mut low_latency = false;
+    // Command that ended the wait for the next tick early
+    let mut woken = None;
+    let mut keys = Keys([false; 16]);
+    let mut speed = 1.0;
+    let mut paused = false;
+    let mut advance = false;
+    let mut last_tick = Instant::now();
+    let mut next_tick = last_tick + period;
+    loop {
+        for command in woken.take().into_iter().chain(commands.try_iter()) {
+            match command {
+                Command::Keys(down) => {
+                    // Queued right away, so a tap shorter than a tick still reaches the program
+                    let mut emulator = emulator.lock().unwrap();
+                    let frame = emulator.frame();
+                    for (key, (&was_down, &down)) in keys.0.iter().zip(&down).enumerate() {
+                        if was_down != down {
+                            let key = key as u8;
+                            emulator.push_key_event(chip8::KeyEvent { key, down, frame });
+                        }
+                    }
+                    keys.0 = down;
+                }
+                Command::Speed(multiplier) => speed = multiplier,
+                Command::Pause(pause) => paused = pause,
+                Command::LowLatency(on) => {
+                    low_latency = on;
+                    let rate = if on { LOW_LATENCY_TICK_RATE } else { TICK_RATE };
+                    period = Duration::from_secs_f32(1.0 / rate);
+                }
+                Command::AdvanceFrame => advance = true,
+                Command::Quit => return,
+            }
+        }
+
+        let now = Instant::now();
+        let dt = (now - last_tick).as_secs_f32().min(MAX_TICK_TIME) * speed;
+        last_tick = now;
+
+        // While paused nothing runs, but changes made through `lock` (stepping,
+        // loading a state) still reach the screen
+        let dt = match (paused, advance) {
+            (true, true) => 1.0 / chip8::TIMER_HZ,
+            (true, false) => 0.0,
+            _ => dt,
+        };
+        advance = false;
+        let mut screen = ScreenOutput::default();
+        let mut beep = BeepOutput::default();
+        let mut locked = emulator.lock().unwrap();
+        let started = Instant::now();
+        locked.run_frame(dt, &keys, &mut screen, &mut beep);
+        let emulation_time = started.elapsed();
+        drop(locked);
+        // Screens the UI didn't get to are dropped, only the latest one matters
+        let mut pending = pending.lock().unwrap();
+        let (previous_screen, previous_time) = match pending.take() {
+            Some(frame) => (frame.screen, frame.emulation_time),
+            None => (None, Duration::ZERO),
+        };
+        *pending = Some(Frame {
+            screen: screen.0.or(previous_screen),
+            beep: beep.0,
+            emulation_time: previous_time + emulation_time,
+        });
+        drop(pending);
+
+        // Sleep until the next tick, skipping ticks if we fell behind. With low latency
+        // a command wakes the thread, so new keys apply before the next instruction runs.
+        let now = Instant::now();
+        if next_tick > now {
+            if low_latency {
+                match commands.recv_timeout(next_tick - now) {
+                    Ok(command) => woken = Some(command),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            } else {
+                thread::sleep(next_tick - now);
+            }
+            if woken.is_none() {
+                next_tick += period;
+            }
+        } else {
+            next_tick = now + period;
+        }
+    }
+}
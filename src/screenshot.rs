@@ -0,0 +1,155 @@
+use crate::config::Palette;
+use crate::logger;
+use chip8_core::{Screen, SpriteRef, SCREEN_SIZE};
+use image::{Delay, Frame, ImageResult, Rgba, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+fn to_rgba(c: [f32; 4]) -> Rgba<u8> {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    Rgba([channel(c[0]), channel(c[1]), channel(c[2]), channel(c[3])])
+}
+
+/// Renders the screen with palette colors, every chip8 pixel becomes a `scale` x `scale` square
+pub fn render(screen: &Screen, palette: &Palette, scale: u32) -> RgbaImage {
+    let scale = scale.max(1);
+    let (fg, bg) = (to_rgba(palette.fg), to_rgba(palette.bg));
+
+    RgbaImage::from_fn(
+        SCREEN_SIZE.0 as u32 * scale,
+        SCREEN_SIZE.1 as u32 * scale,
+        |x, y| {
+            if screen.get_pixel((x / scale) as usize, (y / scale) as usize) {
+                fg
+            } else {
+                bg
+            }
+        },
+    )
+}
+
+pub fn save(screen: &Screen, palette: &Palette, scale: u32, path: &Path) -> ImageResult<()> {
+    render(screen, palette, scale).save(path)
+}
+
+/// Sprites per row of a sprite sheet
+const SHEET_COLUMNS: usize = 8;
+
+/// Sprites from `memory` in a grid of 16x16 cells with a pixel of space around each,
+/// every chip8 pixel becomes a `scale` x `scale` square
+pub fn sprite_sheet(
+    memory: &[u8],
+    sprites: &[SpriteRef],
+    palette: &Palette,
+    scale: u32,
+) -> RgbaImage {
+    const CELL: usize = 17;
+    let scale = scale.max(1);
+    let (fg, bg) = (to_rgba(palette.fg), to_rgba(palette.bg));
+    let columns = sprites.len().clamp(1, SHEET_COLUMNS);
+    let rows = sprites.len().max(1).div_ceil(SHEET_COLUMNS);
+    let size = |cells: usize| (cells * CELL + 1) as u32 * scale;
+    let mut image = RgbaImage::from_pixel(size(columns), size(rows), Rgba([0, 0, 0, 0]));
+
+    for (i, sprite) in sprites.iter().enumerate() {
+        let left = (i % SHEET_COLUMNS * CELL + 1) as u32;
+        let top = (i / SHEET_COLUMNS * CELL + 1) as u32;
+        let start = sprite.address as usize;
+        let bytes = memory.get(start..start + sprite.size()).unwrap_or_default();
+        let bytes_per_row = sprite.width as usize / 8;
+        for y in 0..sprite.height as u32 {
+            for x in 0..sprite.width as u32 {
+                let byte = bytes
+                    .get(y as usize * bytes_per_row + x as usize / 8)
+                    .copied()
+                    .unwrap_or(0);
+                let color = if byte & (0x80 >> (x % 8)) != 0 {
+                    fg
+                } else {
+                    bg
+                };
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        image.put_pixel((left + x) * scale + dx, (top + y) * scale + dy, color);
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// Frames shorter than this are merged, most GIF viewers don't go below 20ms
+const MIN_FRAME_TIME: Duration = Duration::from_millis(20);
+
+/// About 5 minutes of a program redrawing at 60Hz, the rest isn't recorded
+const MAX_FRAMES: usize = 60 * 60 * 5;
+
+/// Screens collected while recording, written as an animated GIF by `finish`
+pub struct GifRecording {
+    path: PathBuf,
+    palette: Palette,
+    scale: u32,
+    frames: Vec<(Screen, Instant)>, // Screen and when it was shown
+}
+
+impl GifRecording {
+    pub fn new(path: PathBuf, palette: Palette, scale: u32) -> Self {
+        GifRecording {
+            path,
+            palette,
+            scale,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Adds a screen shown from now on until the next one
+    pub fn push(&mut self, screen: &Screen) {
+        let now = Instant::now();
+        let full = self.frames.len() >= MAX_FRAMES;
+        match self.frames.last_mut() {
+            Some((last, shown)) if now - *shown < MIN_FRAME_TIME => {
+                // Replacing keeps the start time, so the frame still lasts long enough
+                *last = screen.clone();
+            }
+            _ if full => {}
+            _ => self.frames.push((screen.clone(), now)),
+        }
+    }
+
+    /// Encodes the frames on a background thread, the result is logged
+    pub fn finish(self) -> JoinHandle<()> {
+        let end = Instant::now();
+        thread::spawn(move || match self.encode(end) {
+            Ok(()) => log::info!(
+                target: logger::NOTIFY,
+                "Saved recording '{}'",
+                self.path.display()
+            ),
+            Err(e) => log::error!(
+                "Can't save recording '{0}'. Error: {1}",
+                self.path.display(),
+                e
+            ),
+        })
+    }
+
+    fn encode(&self, end: Instant) -> ImageResult<()> {
+        let file = BufWriter::new(File::create(&self.path)?);
+        let mut encoder = image::gif::GifEncoder::new(file);
+        let ends = self.frames.iter().skip(1).map(|&(_, shown)| shown);
+        for ((screen, shown), next) in self.frames.iter().zip(ends.chain(Some(end))) {
+            let delay = Delay::from_saturating_duration(next - *shown);
+            let image = render(screen, &self.palette, self.scale);
+            encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use crate::cli::{Args, SmokeArgs};
+use crate::config::Config;
+use crate::loader;
+use crate::logger;
+use crate::octo::OctoOptions;
+use chip8_core::{Emulator, HaltReason, Instruction, StepEvent};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+/// How a ROM ended up after a smoke run
+enum Outcome {
+    /// Still running after all cycles
+    Running,
+    WaitingForKey,
+    /// Parked in a jump-to-self loop
+    IdleLoop(u16),
+    Halted(HaltReason),
+    /// Emulator panicked
+    Crashed(String),
+    LoadFailed(String),
+}
+
+impl Outcome {
+    fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Outcome::Halted(_) | Outcome::Crashed(_) | Outcome::LoadFailed(_)
+        )
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Outcome::Running => "ok".to_string(),
+            Outcome::WaitingForKey => "waiting for key".to_string(),
+            Outcome::IdleLoop(pc) => format!("idle loop at {:03X}", pc),
+            Outcome::Halted(HaltReason::PcOutOfBounds(pc)) => {
+                format!("halted: PC out of memory at {:04X}", pc)
+            }
+            Outcome::Halted(reason) => format!("halted: {:?}", reason),
+            Outcome::Crashed(message) => format!("crashed: {}", message),
+            Outcome::LoadFailed(message) => format!("can't load: {}", message),
+        }
+    }
+}
+
+struct Report {
+    outcome: Outcome,
+    /// Number of illegal opcodes executed and the first one as (pc, opcode)
+    illegal: u64,
+    first_illegal: Option<(u16, u16)>,
+}
+
+/// Runs every ROM in a directory headlessly and prints a compatibility table.
+/// Fails if any ROM crashed, halted or couldn't be loaded.
+pub fn run(smoke: &SmokeArgs, args: &Args) -> Result<(), String> {
+    let config = Config::load();
+    let dir = smoke.dir.clone().unwrap_or_else(|| config.rom_dir());
+    let mut roms: Vec<_> = loader::find_roms(&dir)
+        .map_err(|e| format!("Can't scan '{0}'. Error: {1}", dir.display(), e))?
+        .collect();
+    roms.sort();
+    if roms.is_empty() {
+        return Err(format!("No ROMs found in '{}'", dir.display()));
+    }
+
+    // Illegal opcodes are counted in the table, don't log each of them unless asked to
+    if std::env::var_os(logger::LOG_ENV).is_none() {
+        log::set_max_level(log::LevelFilter::Error);
+    }
+
+    // Panics are reported in the table, keep them off stderr
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let reports: Vec<_> = roms
+        .iter()
+        .map(|rom_file| smoke_rom(rom_file, &config, args, smoke.cycles))
+        .collect();
+    panic::set_hook(default_hook);
+
+    let names: Vec<_> = roms
+        .iter()
+        .map(|rom_file| {
+            rom_file
+                .strip_prefix(&dir)
+                .unwrap_or(rom_file)
+                .display()
+                .to_string()
+        })
+        .collect();
+    let illegal: Vec<_> = reports
+        .iter()
+        .map(|report| match report.first_illegal {
+            Some((pc, opcode)) => format!("{} ({:04X} at {:03X})", report.illegal, opcode, pc),
+            None => "0".to_string(),
+        })
+        .collect();
+    let name_width = names.iter().map(String::len).max().unwrap_or(0);
+    let illegal_width = illegal.iter().map(String::len).max().unwrap_or(0).max(7);
+    println!(
+        "{:<name_width$}  {:<illegal_width$}  RESULT",
+        "ROM", "ILLEGAL"
+    );
+    for ((name, illegal), report) in names.iter().zip(&illegal).zip(&reports) {
+        println!(
+            "{:<name_width$}  {:<illegal_width$}  {}",
+            name,
+            illegal,
+            report.outcome.describe()
+        );
+    }
+
+    let failed = reports.iter().filter(|r| r.outcome.is_failure()).count();
+    let with_illegal = reports.iter().filter(|r| r.illegal > 0).count();
+    println!(
+        "\n{} ROMs, {} failed, {} with illegal opcodes",
+        reports.len(),
+        failed,
+        with_illegal
+    );
+    if failed > 0 {
+        return Err(format!("{} of {} ROMs failed", failed, reports.len()));
+    }
+    Ok(())
+}
+
+fn smoke_rom(rom_file: &Path, config: &Config, args: &Args, cycles: u64) -> Report {
+    let mut report = Report {
+        outcome: Outcome::Running,
+        illegal: 0,
+        first_illegal: None,
+    };
+    let mut emulator = Emulator::builder()
+        .quirks(config.quirks)
+        .clock_hz(config.clock_hz)
+        .memory_size(config.memory_size())
+        .seed(0)
+        .build();
+    if let Ok(Some(options)) = OctoOptions::load_for_rom(rom_file) {
+        emulator.set_quirks(options.quirks());
+        emulator.set_clock_hz(options.clock_hz());
+    }
+    args.apply_overrides(&mut emulator);
+    let rom = match fs::read(rom_file) {
+        Ok(rom) => rom,
+        Err(e) => {
+            report.outcome = Outcome::LoadFailed(e.to_string());
+            return report;
+        }
+    };
+    if let Err(e) = emulator.load_rom_bytes(&rom) {
+        report.outcome = Outcome::LoadFailed(e.to_string());
+        return report;
+    }
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        for _ in 0..cycles {
+            let pc = emulator.pc();
+            let memory = emulator.memory();
+            if let Some(op) = memory.get(pc as usize..pc as usize + 2) {
+                if let Instruction::Jump { nnn } =
+                    Instruction::decode(u16::from_be_bytes([op[0], op[1]]))
+                {
+                    if nnn == pc {
+                        return Outcome::IdleLoop(pc);
+                    }
+                }
+            }
+            match emulator.step() {
+                StepEvent::WaitingForKey => return Outcome::WaitingForKey,
+                StepEvent::Halted(reason) => return Outcome::Halted(reason),
+                _ => {}
+            }
+        }
+        Outcome::Running
+    }));
+    report.outcome = result.unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Outcome::Crashed(message)
+    });
+    report.illegal = emulator.stats().illegal_opcodes;
+    report.first_illegal = emulator.stats().first_illegal_opcode;
+    report
+}
@@ -0,0 +1,120 @@
+use crate::cli::DiffStateArgs;
+use chip8_core::{SaveState, SCREEN_SIZE};
+use std::fs;
+use std::path::Path;
+
+/// What differs between two states, in words, values as "a / b"
+pub fn differences(a: &SaveState, b: &SaveState) -> Vec<String> {
+    let mut differences = Vec::new();
+    if a.pc != b.pc {
+        differences.push(format!("PC: 0x{:03X} / 0x{:03X}", a.pc, b.pc));
+    }
+    if a.index != b.index {
+        differences.push(format!("I: 0x{:03X} / 0x{:03X}", a.index, b.index));
+    }
+    for (i, (ra, rb)) in a.registers.iter().zip(&b.registers).enumerate() {
+        if ra != rb {
+            differences.push(format!("V{:X}: 0x{:02X} / 0x{:02X}", i, ra, rb));
+        }
+    }
+    if a.delay_timer != b.delay_timer {
+        differences.push(format!(
+            "Delay timer: {} / {}",
+            a.delay_timer, b.delay_timer
+        ));
+    }
+    if a.sound_timer != b.sound_timer {
+        differences.push(format!(
+            "Sound timer: {} / {}",
+            a.sound_timer, b.sound_timer
+        ));
+    }
+    if a.stack != b.stack {
+        differences.push(format!("Stack: {:03X?} / {:03X?}", a.stack, b.stack));
+    }
+    if a.memory.len() != b.memory.len() {
+        differences.push(format!(
+            "Memory size: {} / {} bytes",
+            a.memory.len(),
+            b.memory.len()
+        ));
+    }
+    for (address, (ma, mb)) in a.memory.iter().zip(&b.memory).enumerate() {
+        if ma != mb {
+            differences.push(format!(
+                "Memory 0x{:03X}: 0x{:02X} / 0x{:02X}",
+                address, ma, mb
+            ));
+        }
+    }
+    if let Some(summary) = screen_difference(&a.screen, &b.screen) {
+        differences.push(summary);
+    }
+    differences
+}
+
+/// Number of differing pixels and the area they are in, `None` if the screens are the same
+pub fn screen_difference(a: &[u64; SCREEN_SIZE.1], b: &[u64; SCREEN_SIZE.1]) -> Option<String> {
+    let rows: Vec<(usize, u64)> = a
+        .iter()
+        .zip(b)
+        .map(|(ra, rb)| ra ^ rb)
+        .enumerate()
+        .filter(|&(_, changed)| changed != 0)
+        .collect();
+    let (first, _) = *rows.first()?;
+    let (last, _) = *rows.last()?;
+    let pixels: u32 = rows.iter().map(|(_, changed)| changed.count_ones()).sum();
+    let columns = rows
+        .iter()
+        .fold(0, |columns, (_, changed)| columns | changed);
+    Some(format!(
+        "Screen: {} pixels differ within x {}-{}, y {}-{}",
+        pixels,
+        columns.leading_zeros(),
+        SCREEN_SIZE.0 as u32 - 1 - columns.trailing_zeros(),
+        first,
+        last
+    ))
+}
+
+/// Reads a save state file, the error names the file
+pub fn load(path: &Path) -> Result<SaveState, String> {
+    fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| SaveState::from_bytes(&data).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Can't load state '{0}'. Error: {1}", path.display(), e))
+}
+
+/// Prints how two save state files differ. Fails if they do, like `diff`.
+pub fn run(args: &DiffStateArgs) -> Result<(), String> {
+    let a = load(&args.a)?;
+    let b = load(&args.b)?;
+    let mut differences = differences(&a, &b);
+    // Settings and counters don't change what the program sees, listed after the rest
+    if a.quirks != b.quirks {
+        differences.push(format!("Quirks: {:?} / {:?}", a.quirks, b.quirks));
+    }
+    if a.clock_hz != b.clock_hz {
+        differences.push(format!("Clock: {} / {} Hz", a.clock_hz, b.clock_hz));
+    }
+    if a.instruction_count != b.instruction_count {
+        differences.push(format!(
+            "Instructions executed: {} / {}",
+            a.instruction_count, b.instruction_count
+        ));
+    }
+    if a.rom != b.rom {
+        differences.push("ROM: different programs".to_string());
+    }
+
+    if differences.is_empty() {
+        println!("States are identical");
+        return Ok(());
+    }
+    println!("a: {}\nb: {}", args.a.display(), args.b.display());
+    for difference in &differences {
+        println!("{}", difference);
+    }
+    Err(format!("States differ in {} places", differences.len()))
+}
@@ -0,0 +1,200 @@
+use crate::cli::{Args, MatrixFormat, TestAllArgs};
+use crate::compatibility::{self, Report};
+use crate::config::Config;
+use crate::loader::{self, RomData};
+use crate::logger;
+use chip8_core::{Emulator, Extension};
+use std::collections::BTreeSet;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+/// A ROM that gets this far without a fault has booted
+const BOOT_INSTRUCTIONS: u64 = 1000;
+
+/// Row of the compatibility matrix
+struct Row {
+    rom: String,
+    /// Where the quirks come from, like "options" or "suggested schip"
+    settings: String,
+    boots: bool,
+    /// Fault or panic that stopped the run
+    crash: Option<String>,
+    draws: bool,
+    extensions: BTreeSet<&'static str>,
+}
+
+/// Runs every ROM in a directory under its Octo options or the quirks the
+/// compatibility report suggests, and writes a matrix of how they did
+pub fn run(test_all: &TestAllArgs, args: &Args) -> Result<(), String> {
+    let config = Config::load();
+    let dir = test_all.dir.clone().unwrap_or_else(|| config.rom_dir());
+    let mut roms: Vec<_> = loader::find_roms(&dir)
+        .map_err(|e| format!("Can't scan '{0}'. Error: {1}", dir.display(), e))?
+        .collect();
+    roms.sort();
+    if roms.is_empty() {
+        return Err(format!("No ROMs found in '{}'", dir.display()));
+    }
+
+    // Faults end up in the matrix, don't log them unless asked to
+    if std::env::var_os(logger::LOG_ENV).is_none() {
+        log::set_max_level(log::LevelFilter::Error);
+    }
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let rows: Vec<_> = roms
+        .iter()
+        .map(|rom_file| {
+            let mut row = test_rom(rom_file, &config, args, test_all.cycles);
+            row.rom = rom_file
+                .strip_prefix(&dir)
+                .unwrap_or(rom_file)
+                .display()
+                .to_string();
+            row
+        })
+        .collect();
+    panic::set_hook(default_hook);
+
+    let text = match test_all.format {
+        MatrixFormat::Markdown => markdown(&rows),
+        MatrixFormat::Csv => csv(&rows),
+    };
+    match &test_all.output {
+        Some(path) => {
+            fs::write(path, text)
+                .map_err(|e| format!("Can't write '{0}'. Error: {1}", path.display(), e))?;
+            log::info!(target: logger::NOTIFY, "Saved matrix to '{}'", path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn test_rom(rom_file: &Path, config: &Config, args: &Args, cycles: u64) -> Row {
+    let mut row = Row {
+        rom: String::new(),
+        settings: "config".to_string(),
+        boots: false,
+        crash: None,
+        draws: false,
+        extensions: BTreeSet::new(),
+    };
+    let data = RomData::read(rom_file);
+    let rom = match data.rom {
+        Ok(rom) => rom,
+        Err(e) => {
+            row.crash = Some(format!("can't load: {}", e));
+            return row;
+        }
+    };
+
+    let (mut quirks, mut clock_hz) = (config.quirks, config.clock_hz);
+    if let Ok(Some(options)) = &data.options {
+        quirks = options.quirks();
+        clock_hz = options.clock_hz();
+        row.settings = "options".to_string();
+    } else if let Ok(report) = Report::run(&rom, clock_hz, config.memory_size(), cycles) {
+        if let Some(run) = report.suggestion(quirks) {
+            quirks = run.quirks;
+            row.settings = format!("suggested {}", run.preset);
+        }
+    }
+    if args.quirks.is_some() || args.speed.is_some() {
+        row.settings = "command line".to_string();
+    }
+
+    let mut emulator = Emulator::builder()
+        .quirks(quirks)
+        .clock_hz(clock_hz)
+        .memory_size(config.memory_size())
+        .seed(0)
+        .build();
+    args.apply_overrides(&mut emulator);
+    if let Err(e) = emulator.load_rom_bytes(&rom) {
+        row.crash = Some(format!("can't load: {}", e));
+        return row;
+    }
+
+    let extensions = &mut row.extensions;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        compatibility::run_until_fault(&mut emulator, cycles, &mut |opcode| {
+            if let Some(extension) = Extension::of(opcode) {
+                extensions.insert(extension.name());
+            }
+        })
+    }));
+    row.crash = result.unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Some(format!("panicked: {}", message))
+    });
+    row.boots = row.crash.is_none() || emulator.instruction_count() >= BOOT_INSTRUCTIONS;
+    row.draws = emulator.stats().draws > 0;
+    row
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn columns(row: &Row) -> [String; 6] {
+    let extensions: Vec<_> = row.extensions.iter().copied().collect();
+    [
+        row.rom.clone(),
+        row.settings.clone(),
+        yes_no(row.boots).to_string(),
+        row.crash.clone().unwrap_or_default(),
+        yes_no(row.draws).to_string(),
+        extensions.join(" "),
+    ]
+}
+
+const HEADER: [&str; 6] = ["ROM", "Settings", "Boots", "Crash", "Draws", "Extensions"];
+
+fn markdown(rows: &[Row]) -> String {
+    let mut text = format!("| {} |\n", HEADER.join(" | "));
+    text.push_str(&format!("|{}\n", "---|".repeat(HEADER.len())));
+    for row in rows {
+        let cells: Vec<_> = columns(row)
+            .iter()
+            .map(|cell| cell.replace('|', "\\|"))
+            .collect();
+        text.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    let booted = rows.iter().filter(|row| row.boots).count();
+    let crashed = rows.iter().filter(|row| row.crash.is_some()).count();
+    text.push_str(&format!(
+        "\n{} ROMs, {} boot, {} crash\n",
+        rows.len(),
+        booted,
+        crashed
+    ));
+    text
+}
+
+fn csv(rows: &[Row]) -> String {
+    let mut text = format!("{}\n", HEADER.join(","));
+    for row in rows {
+        let cells: Vec<_> = columns(row).iter().map(|cell| csv_field(cell)).collect();
+        text.push_str(&format!("{}\n", cells.join(",")));
+    }
+    text
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
@@ -0,0 +1,127 @@
+use crate::app::to_rgb01;
+use crate::config::Palette;
+use imgui::{Style, StyleColor};
+use serde::{Deserialize, Serialize};
+
+/// UI color scheme, each comes with a screen palette that suits it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    ClassicGreen,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::ClassicGreen];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::ClassicGreen => "Classic green",
+        }
+    }
+
+    /// Replaces style colors, sizes are set up once by the app
+    pub fn apply(self, style: &mut Style) {
+        match self {
+            Theme::Dark => {
+                style.use_dark_colors();
+                tint(
+                    style,
+                    [110, 110, 100],
+                    [110, 110, 110],
+                    [182, 182, 182],
+                    [70, 70, 70],
+                );
+                style[StyleColor::PopupBg] = to_rgb01([0, 0, 0, 230]);
+                style[StyleColor::TextSelectedBg] = to_rgb01([10, 23, 18, 180]);
+            }
+            Theme::Light => {
+                style.use_light_colors();
+            }
+            Theme::ClassicGreen => {
+                style.use_dark_colors();
+                tint(
+                    style,
+                    [40, 120, 40],
+                    [50, 140, 50],
+                    [60, 200, 60],
+                    [30, 90, 30],
+                );
+                style[StyleColor::Text] = to_rgb01([120, 255, 120, 255]);
+                style[StyleColor::TextDisabled] = to_rgb01([60, 140, 60, 255]);
+                style[StyleColor::WindowBg] = to_rgb01([5, 20, 5, 240]);
+                style[StyleColor::PopupBg] = to_rgb01([5, 20, 5, 240]);
+                style[StyleColor::Border] = to_rgb01([60, 200, 60, 80]);
+                style[StyleColor::CheckMark] = to_rgb01([120, 255, 120, 255]);
+                style[StyleColor::TextSelectedBg] = to_rgb01([60, 200, 60, 90]);
+            }
+        }
+    }
+
+    /// Color behind all windows
+    pub fn clear_color(self) -> wgpu::Color {
+        match self {
+            Theme::Dark => wgpu::Color {
+                r: 0.03,
+                g: 0.03,
+                b: 0.03,
+                a: 1.0,
+            },
+            Theme::Light => wgpu::Color {
+                r: 0.8,
+                g: 0.8,
+                b: 0.8,
+                a: 1.0,
+            },
+            Theme::ClassicGreen => wgpu::Color {
+                r: 0.0,
+                g: 0.02,
+                b: 0.0,
+                a: 1.0,
+            },
+        }
+    }
+
+    /// Default screen colors for ROMs that don't come with their own
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette::default(),
+            Theme::Light => Palette {
+                fg: to_rgb01([30, 30, 30, 255]),
+                bg: to_rgb01([230, 230, 220, 255]),
+            },
+            Theme::ClassicGreen => Palette {
+                fg: to_rgb01([51, 255, 51, 255]),
+                bg: to_rgb01([8, 24, 8, 255]),
+            },
+        }
+    }
+}
+
+// Translucent widget colors in shades of the given RGB values
+fn tint(style: &mut Style, title: [i32; 3], header: [i32; 3], button: [i32; 3], frame: [i32; 3]) {
+    let rgba = |[r, g, b]: [i32; 3], a| to_rgb01([r, g, b, a]);
+    style[StyleColor::TitleBg] = rgba(title, 62);
+    style[StyleColor::TitleBgCollapsed] = rgba(title, 52);
+    style[StyleColor::TitleBgActive] = rgba(title, 87);
+    style[StyleColor::Header] = rgba(header, 52);
+    style[StyleColor::HeaderHovered] = rgba(header, 92);
+    style[StyleColor::HeaderActive] = rgba(header, 72);
+    style[StyleColor::ScrollbarBg] = rgba(header, 12);
+    style[StyleColor::ScrollbarGrab] = rgba(header, 52);
+    style[StyleColor::ScrollbarGrabHovered] = rgba(header, 92);
+    style[StyleColor::ScrollbarGrabActive] = rgba(header, 72);
+    style[StyleColor::SliderGrab] = rgba(header, 52);
+    style[StyleColor::SliderGrabActive] = rgba(header, 72);
+    style[StyleColor::Button] = rgba(button, 60);
+    style[StyleColor::ButtonHovered] = rgba(button, 200);
+    style[StyleColor::ButtonActive] = rgba(button, 140);
+    style[StyleColor::FrameBg] = rgba(frame, 30);
+    style[StyleColor::FrameBgHovered] = rgba(frame, 70);
+    style[StyleColor::FrameBgActive] = rgba(frame, 50);
+    style[StyleColor::MenuBarBg] = rgba(frame, 30);
+}
@@ -0,0 +1,119 @@
+use crate::app::to_rgb01;
+use crate::logger;
+use imgui::{Condition, Ui};
+use log::Level;
+use std::time::{Duration, Instant};
+
+/// How long a toast stays up, errors stay longer so they can be read
+const INFO_TIME: Duration = Duration::from_secs(3);
+const ERROR_TIME: Duration = Duration::from_secs(8);
+
+/// Seconds a toast takes to fade out at the end of its time
+const FADE_TIME: f32 = 0.5;
+
+/// Toasts shown at once, older ones are dropped
+const MAX_VISIBLE: usize = 5;
+
+// Short message in the corner of the window
+struct Toast {
+    level: Level,
+    text: String,
+    count: usize, // Times the same message came in while it was shown
+    shown: Instant,
+}
+
+impl Toast {
+    fn time(&self) -> Duration {
+        if self.level <= Level::Warn {
+            ERROR_TIME
+        } else {
+            INFO_TIME
+        }
+    }
+}
+
+/// Corner notifications for warnings, errors and info logged with the `logger::NOTIFY` target
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    /// True while a toast is shown, it has to be redrawn to fade out
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    // Picks up new messages from the logger and drops expired toasts
+    fn update(&mut self) {
+        let new = logger::get().map_or_else(Vec::new, |logger| logger.take_toasts());
+        for (level, text) in new {
+            // A repeated message restarts its toast instead of stacking copies
+            match self.toasts.iter_mut().find(|toast| toast.text == text) {
+                Some(toast) => {
+                    toast.count += 1;
+                    toast.shown = Instant::now();
+                }
+                None => self.toasts.push(Toast {
+                    level,
+                    text,
+                    count: 1,
+                    shown: Instant::now(),
+                }),
+            }
+        }
+        self.toasts
+            .retain(|toast| toast.shown.elapsed() < toast.time());
+        let extra = self.toasts.len().saturating_sub(MAX_VISIBLE);
+        self.toasts.drain(..extra);
+    }
+
+    /// Draws toasts stacked up from the bottom right corner, `bottom` is space to leave below them
+    pub fn draw(&mut self, ui: &Ui, bottom: f32) {
+        self.update();
+        let [width, height] = ui.io().display_size;
+        let mut y = height - bottom - 10.0;
+        let mut dismissed = None;
+        for (i, toast) in self.toasts.iter().enumerate().rev() {
+            let left = toast.time().as_secs_f32() - toast.shown.elapsed().as_secs_f32();
+            let alpha = (left / FADE_TIME).min(1.0);
+            let color = match toast.level {
+                Level::Error => [255, 90, 90, 255],
+                Level::Warn => [255, 200, 0, 255],
+                _ => [255, 255, 255, 255],
+            };
+            let text = if toast.count > 1 {
+                format!("{} (x{})", toast.text, toast.count)
+            } else {
+                toast.text.clone()
+            };
+
+            let style = ui.push_style_var(imgui::StyleVar::Alpha(alpha));
+            let mut size = [0.0, 0.0];
+            imgui::Window::new(&imgui::ImString::new(format!("##toast{}", i)))
+                .position([width - 10.0, y], Condition::Always)
+                .position_pivot([1.0, 1.0])
+                .title_bar(false)
+                .resizable(false)
+                .movable(false)
+                .scroll_bar(false)
+                .always_auto_resize(true)
+                .save_settings(false)
+                .focus_on_appearing(false)
+                .build(ui, || {
+                    let wrap = ui.push_text_wrap_pos(400.0);
+                    ui.text_colored(to_rgb01(color), &text);
+                    wrap.pop(ui);
+                    if ui.is_window_hovered() && ui.is_mouse_clicked(imgui::MouseButton::Left) {
+                        dismissed = Some(i);
+                    }
+                    size = ui.window_size();
+                });
+            style.pop(ui);
+            y -= size[1] + 5.0;
+        }
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+    }
+}
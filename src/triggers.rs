@@ -0,0 +1,399 @@
+//! Triggers: conditions over memory and registers checked every frame, with an
+//! action when one becomes true, like achievements. Kept per ROM in a TOML file
+//! next to it (`Game.ch8` -> `Game.triggers.toml`):
+//!
+//! ```toml
+//! [[triggers]]
+//! name = "Ten points"
+//! condition = "address 0x3E0 >= 10"
+//! action = "toast"        # toast, pause, screenshot or command
+//!
+//! [[triggers]]
+//! name = "Game over"
+//! condition = "V5 == 0"
+//! action = "command"
+//! command = "notify-send 'Game over'"   # Run by the shell, CHIP8_ROM and CHIP8_TRIGGER are set
+//! once = false                           # Fire every time, not only the first
+//! ```
+//!
+//! Trigger files may come with downloaded ROMs, so a command is only run once
+//! the user has allowed it for that ROM, see `ApprovedCommands`.
+
+use crate::config::Config;
+use chip8_core::Emulator;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerAction {
+    Toast,
+    Pause,
+    Screenshot,
+    /// Runs the trigger's `command` with the system shell
+    Command,
+}
+
+impl TriggerAction {
+    pub const ALL: [TriggerAction; 4] = [
+        TriggerAction::Toast,
+        TriggerAction::Pause,
+        TriggerAction::Screenshot,
+        TriggerAction::Command,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TriggerAction::Toast => "toast",
+            TriggerAction::Pause => "pause",
+            TriggerAction::Screenshot => "screenshot",
+            TriggerAction::Command => "command",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Trigger {
+    pub name: String,
+    pub condition: String,
+    pub action: TriggerAction,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub command: String,
+    /// Fires the first time only, like an achievement being unlocked
+    #[serde(default = "default_once")]
+    pub once: bool,
+}
+
+fn default_once() -> bool {
+    true
+}
+
+/// What a condition looks at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Register(u8),
+    Index,
+    Pc,
+    Delay,
+    Sound,
+    /// Byte in memory
+    Address(u16),
+}
+
+impl Operand {
+    pub fn read(self, emulator: &Emulator) -> Option<u16> {
+        Some(match self {
+            Operand::Register(x) => emulator.registers()[x as usize] as u16,
+            Operand::Index => emulator.index(),
+            Operand::Pc => emulator.pc(),
+            Operand::Delay => emulator.delay_timer() as u16,
+            Operand::Sound => emulator.sound_timer() as u16,
+            Operand::Address(address) => *emulator.memory().get(address as usize)? as u16,
+        })
+    }
+}
+
+/// Parsed condition like "address 0x3E0 >= 10" or "VA != 0"
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Condition {
+    pub operand: Operand,
+    pub op: &'static str,
+    pub value: u16,
+}
+
+const OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+fn parse_number(text: &str) -> Option<u16> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+impl Condition {
+    /// Operands are `V0`-`VF`, `I`, `PC`, `delay`, `sound` and `address N`,
+    /// numbers are decimal or hex with `0x`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (position, op) = OPERATORS
+            .iter()
+            .filter_map(|op| text.find(op).map(|position| (position, *op)))
+            .min_by_key(|&(position, op)| (position, usize::MAX - op.len()))
+            .ok_or("no comparison (==, !=, <, <=, >, >=)")?;
+        let left = text[..position].trim().to_ascii_lowercase();
+        let right = text[position + op.len()..].trim().to_ascii_lowercase();
+        let value = parse_number(&right).ok_or_else(|| format!("'{}' isn't a number", right))?;
+
+        let mut words = left.split_whitespace();
+        let operand = match (words.next(), words.next(), words.next()) {
+            (Some("i"), None, _) => Operand::Index,
+            (Some("pc"), None, _) => Operand::Pc,
+            (Some("delay"), None, _) => Operand::Delay,
+            (Some("sound"), None, _) => Operand::Sound,
+            (Some("address"), Some(address), None) => Operand::Address(
+                parse_number(address).ok_or_else(|| format!("'{}' isn't an address", address))?,
+            ),
+            (Some(register), None, _) if register.len() == 2 && register.starts_with('v') => {
+                let x = u8::from_str_radix(&register[1..], 16)
+                    .map_err(|_| format!("'{}' isn't a register", register))?;
+                Operand::Register(x)
+            }
+            _ => return Err(format!("'{}' isn't a register or address", left)),
+        };
+        Ok(Condition { operand, op, value })
+    }
+
+    /// `None` when the address is outside of memory
+    pub fn is_met(&self, emulator: &Emulator) -> Option<bool> {
+        let current = self.operand.read(emulator)?;
+        Some(match self.op {
+            "==" => current == self.value,
+            "!=" => current != self.value,
+            "<=" => current <= self.value,
+            ">=" => current >= self.value,
+            "<" => current < self.value,
+            _ => current > self.value,
+        })
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operand::Register(x) => write!(f, "V{:X}", x),
+            Operand::Index => write!(f, "I"),
+            Operand::Pc => write!(f, "PC"),
+            Operand::Delay => write!(f, "delay"),
+            Operand::Sound => write!(f, "sound"),
+            Operand::Address(address) => write!(f, "address 0x{:03X}", address),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct TriggerFile {
+    triggers: Vec<Trigger>,
+}
+
+/// Triggers of the loaded ROM with what they did this session
+#[derive(Default)]
+pub struct Triggers {
+    pub triggers: Vec<Trigger>,
+    /// Parsed condition of each trigger, or why it doesn't parse
+    pub conditions: Vec<Result<Condition, String>>,
+    /// Condition was true at the last check, per trigger. Triggers fire when it turns true
+    met: Vec<bool>,
+    /// Times each trigger fired since the ROM was loaded
+    pub fired: Vec<u32>,
+    /// Commands the user didn't allow, not asked about again until the ROM is loaded again
+    pub declined: HashSet<String>,
+}
+
+impl Triggers {
+    /// `Game.ch8` -> `Game.triggers.toml`
+    pub fn path_for_rom(rom_file: &Path) -> PathBuf {
+        rom_file.with_extension("triggers.toml")
+    }
+
+    /// Reads triggers for the ROM, none if there is no triggers file
+    pub fn load_for_rom(rom_file: &Path) -> io::Result<Self> {
+        let path = Self::path_for_rom(rom_file);
+        if !path.exists() {
+            return Ok(Triggers::default());
+        }
+        let text = fs::read_to_string(path)?;
+        let file: TriggerFile = toml::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let mut triggers = Triggers::default();
+        for trigger in file.triggers {
+            triggers.push(trigger);
+        }
+        Ok(triggers)
+    }
+
+    pub fn save_for_rom(&self, rom_file: &Path) -> io::Result<()> {
+        let file = TriggerFile {
+            triggers: self.triggers.clone(),
+        };
+        let text = toml::to_string_pretty(&file)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        fs::write(Self::path_for_rom(rom_file), text)
+    }
+
+    pub fn push(&mut self, trigger: Trigger) {
+        self.conditions.push(Condition::parse(&trigger.condition));
+        self.triggers.push(trigger);
+        self.met.push(false);
+        self.fired.push(0);
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.triggers.remove(index);
+        let _ = self.conditions.remove(index);
+        self.met.remove(index);
+        self.fired.remove(index);
+    }
+
+    /// Checks every condition, returns the triggers that fire now.
+    /// Conditions that don't parse never fire, the Triggers window shows why.
+    pub fn check(&mut self, emulator: &Emulator) -> Vec<Trigger> {
+        let mut firing = Vec::new();
+        for (i, trigger) in self.triggers.iter().enumerate() {
+            let met = self.conditions[i]
+                .as_ref()
+                .ok()
+                .and_then(|condition| condition.is_met(emulator))
+                .unwrap_or(false);
+            let was_met = std::mem::replace(&mut self.met[i], met);
+            if met && !was_met && !(trigger.once && self.fired[i] > 0) {
+                self.fired[i] += 1;
+                firing.push(trigger.clone());
+            }
+        }
+        firing
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+struct ApprovedCommand {
+    /// SHA-1 of the ROM the trigger file is for
+    rom: String,
+    command: String,
+}
+
+/// Trigger commands the user allowed to run, per ROM. Kept in the config
+/// directory (`approved_commands.toml` next to `config.toml`), away from the
+/// trigger files that ROM packs could bring along.
+#[derive(Serialize, Deserialize, Default)]
+pub struct ApprovedCommands {
+    #[serde(default)]
+    approved: Vec<ApprovedCommand>,
+}
+
+impl ApprovedCommands {
+    fn path() -> Option<PathBuf> {
+        Some(Config::path()?.with_file_name("approved_commands.toml"))
+    }
+
+    /// Reads the approved commands, none if the file doesn't exist or can't be read
+    pub fn load() -> Self {
+        let text = match Self::path().map(fs::read_to_string) {
+            Some(Ok(text)) => text,
+            _ => return ApprovedCommands::default(),
+        };
+        toml::from_str(&text).unwrap_or_else(|e| {
+            log::warn!("Can't read approved commands. Error: {}", e);
+            ApprovedCommands::default()
+        })
+    }
+
+    fn entry(rom: &[u8], command: &str) -> ApprovedCommand {
+        ApprovedCommand {
+            rom: sha1_smol::Sha1::from(rom).digest().to_string(),
+            command: command.to_string(),
+        }
+    }
+
+    pub fn is_approved(&self, rom: &[u8], command: &str) -> bool {
+        self.approved.contains(&Self::entry(rom, command))
+    }
+
+    /// Allows `command` for `rom` from now on and writes the file
+    pub fn approve(&mut self, rom: &[u8], command: &str) -> io::Result<()> {
+        if self.is_approved(rom, command) {
+            return Ok(());
+        }
+        self.approved.push(Self::entry(rom, command));
+        let path = Self::path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<(Operand, &'static str, u16), String> {
+        Condition::parse(text).map(|condition| (condition.operand, condition.op, condition.value))
+    }
+
+    #[test]
+    fn operands() {
+        assert_eq!(parse("VF == 1"), Ok((Operand::Register(0xF), "==", 1)));
+        assert_eq!(parse("va != 0"), Ok((Operand::Register(0xA), "!=", 0)));
+        assert_eq!(parse("I == 0x300"), Ok((Operand::Index, "==", 0x300)));
+        assert_eq!(parse("PC == 0x2A4"), Ok((Operand::Pc, "==", 0x2A4)));
+        assert_eq!(parse("delay == 0"), Ok((Operand::Delay, "==", 0)));
+        assert_eq!(parse("sound > 0"), Ok((Operand::Sound, ">", 0)));
+        assert_eq!(
+            parse("address 0x3E0 >= 10"),
+            Ok((Operand::Address(0x3E0), ">=", 10))
+        );
+        assert_eq!(
+            parse("address 992 == 1"),
+            Ok((Operand::Address(992), "==", 1))
+        );
+    }
+
+    #[test]
+    fn longer_operator_wins_at_the_same_position() {
+        assert_eq!(parse("V0 <= 5"), Ok((Operand::Register(0), "<=", 5)));
+        assert_eq!(parse("V0 < 5"), Ok((Operand::Register(0), "<", 5)));
+        assert_eq!(parse("V0>=5"), Ok((Operand::Register(0), ">=", 5)));
+        assert_eq!(parse("V0 > 5"), Ok((Operand::Register(0), ">", 5)));
+    }
+
+    #[test]
+    fn broken_conditions_are_rejected() {
+        assert_eq!(
+            parse("V0 5"),
+            Err("no comparison (==, !=, <, <=, >, >=)".to_string())
+        );
+        assert_eq!(
+            parse("V10 == 1"),
+            Err("'v10' isn't a register or address".to_string())
+        );
+        assert_eq!(parse("VG == 1"), Err("'vg' isn't a register".to_string()));
+        assert_eq!(
+            parse("address == 1"),
+            Err("'address' isn't a register or address".to_string())
+        );
+        assert_eq!(
+            parse("address 0xZZ == 1"),
+            Err("'0xzz' isn't an address".to_string())
+        );
+        assert_eq!(parse("V0 == ten"), Err("'ten' isn't a number".to_string()));
+        assert_eq!(
+            parse("V0 == 0x10000"),
+            Err("'0x10000' isn't a number".to_string())
+        );
+    }
+
+    #[test]
+    fn conditions_are_parsed_once_per_trigger() {
+        let trigger = |condition: &str| Trigger {
+            name: String::new(),
+            condition: condition.to_string(),
+            action: TriggerAction::Toast,
+            command: String::new(),
+            once: true,
+        };
+        let mut triggers = Triggers::default();
+        triggers.push(trigger("V0 == 1"));
+        triggers.push(trigger("nonsense"));
+        assert!(triggers.conditions[0].is_ok());
+        assert!(triggers.conditions[1].is_err());
+        triggers.remove(0);
+        assert_eq!(triggers.conditions.len(), 1);
+        assert!(triggers.conditions[0].is_err());
+    }
+}
@@ -0,0 +1,99 @@
+use super::{Action, DebugView, ViewContext};
+use imgui::*;
+use std::collections::HashMap;
+
+// Window listing named addresses of the ROM, to jump to them in the Code or Memory window
+#[derive(Default)]
+pub struct BookmarksView {
+    /// New bookmark being written, per instance id
+    forms: HashMap<usize, BookmarkForm>,
+}
+
+struct BookmarkForm {
+    address: ImString,
+    name: ImString,
+}
+
+impl Default for BookmarkForm {
+    fn default() -> Self {
+        BookmarkForm {
+            address: ImString::with_capacity(8),
+            name: ImString::with_capacity(64),
+        }
+    }
+}
+
+impl DebugView for BookmarksView {
+    fn name(&self) -> &'static str {
+        "Bookmarks"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Bookmarks");
+        let window = imgui::Window::new(&title).opened(open);
+        let form = self.forms.entry(ctx.instance.id).or_default();
+        window
+            .size([320.0, 260.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(480.0, 280.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let bookmarks = &mut ctx.instance.bookmarks;
+                let mut remove = None;
+                for (&address, name) in bookmarks.iter() {
+                    let _id = ui.push_id(address as i32);
+                    if ui.small_button(im_str!("x")) {
+                        remove = Some(address);
+                    }
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text("Delete");
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Code")) {
+                        ctx.actions.push(Action::ShowCode(address));
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Memory")) {
+                        ctx.actions.push(Action::ShowMemory(address));
+                    }
+                    ui.same_line(0.0);
+                    ui.text(format!("{:03X} {}", address, name));
+                }
+                if let Some(address) = remove {
+                    bookmarks.remove(&address);
+                }
+                if bookmarks.is_empty() {
+                    ui.text_disabled("No bookmarks for this ROM yet");
+                }
+                ui.separator();
+
+                ui.set_next_item_width(50.0);
+                ui.input_text(im_str!("Address"), &mut form.address)
+                    .chars_hexadecimal(true)
+                    .chars_uppercase(true)
+                    .build();
+                ui.same_line(0.0);
+                ui.set_next_item_width(120.0);
+                let entered = ui
+                    .input_text(im_str!("Name"), &mut form.name)
+                    .enter_returns_true(true)
+                    .build();
+                ui.same_line(0.0);
+                if ui.button(im_str!("Add"), [0.0, 0.0]) || entered {
+                    match u16::from_str_radix(form.address.to_str(), 16) {
+                        Ok(address) => {
+                            let name = match form.name.to_str().trim() {
+                                "" => format!("{:03X}", address),
+                                name => name.to_string(),
+                            };
+                            bookmarks.insert(address, name);
+                            *form = BookmarkForm::default();
+                        }
+                        Err(_) => log::warn!("'{}' isn't an address", form.address.to_str()),
+                    }
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Bookmark lines from the Code window's right-click menu too");
+                }
+            });
+    }
+}
@@ -0,0 +1,140 @@
+use super::{Action, DebugView, ViewContext};
+use chip8_core::{FlowGraph, Subroutine};
+use imgui::*;
+use std::collections::{BTreeMap, HashMap};
+
+// Window with which subroutines call which, as a tree from the program entry
+#[derive(Default)]
+pub struct CallGraphView {
+    /// Call graph per instance id
+    states: HashMap<usize, CallGraphState>,
+}
+
+#[derive(Default)]
+struct CallGraphState {
+    code: Vec<u8>, // Bytes the graph was built from, a change means a new ROM
+    entry: u16,
+    subroutines: BTreeMap<u16, Subroutine>,
+    /// Subroutines calling each one
+    callers: BTreeMap<u16, Vec<u16>>,
+}
+
+impl CallGraphState {
+    fn rebuild(&mut self, code: &[u8], origin: u16) {
+        let graph = FlowGraph::build(code, origin, origin);
+        self.code = code.to_vec();
+        self.entry = graph.entry();
+        self.callers.clear();
+        self.subroutines.clear();
+        for subroutine in graph.subroutines() {
+            for &callee in &subroutine.calls {
+                self.callers
+                    .entry(callee)
+                    .or_default()
+                    .push(subroutine.entry);
+            }
+            self.subroutines.insert(subroutine.entry, subroutine);
+        }
+    }
+
+    // Node for `entry` with its callees below, `path` holds the callers up to the root
+    fn draw_node(
+        &self,
+        ui: &Ui,
+        entry: u16,
+        path: &mut Vec<u16>,
+        counts: Option<&BTreeMap<u16, u64>>,
+        actions: &mut Vec<Action>,
+    ) {
+        let recursive = path.contains(&entry);
+        let calls = self
+            .subroutines
+            .get(&entry)
+            .map_or(&[][..], |subroutine| &subroutine.calls[..]);
+        let mut label = format!("{:03X}", entry);
+        if entry == self.entry {
+            label.push_str(" entry");
+        } else if let Some(counts) = counts {
+            let count = counts.get(&entry).copied().unwrap_or(0);
+            label.push_str(&format!("  {} calls", count));
+        }
+        if recursive {
+            label.push_str("  (recursive)");
+        }
+        let id = ImString::new(format!("{:03X}", entry));
+        let label = ImString::new(label);
+        let node = TreeNode::new(&id)
+            .label(&label)
+            .leaf(recursive || calls.is_empty())
+            .open_on_arrow(true)
+            .default_open(path.is_empty())
+            .push(ui);
+        if ui.is_item_clicked(MouseButton::Left) {
+            actions.push(Action::ShowCode(entry));
+        }
+        if ui.is_item_hovered() {
+            match self.callers.get(&entry) {
+                Some(callers) => {
+                    let callers: Vec<_> = callers.iter().map(|a| format!("{:03X}", a)).collect();
+                    ui.tooltip_text(format!("Called from {}", callers.join(", ")));
+                }
+                None => ui.tooltip_text("Click to show in the Code window"),
+            }
+        }
+        if let Some(node) = node {
+            if !recursive {
+                path.push(entry);
+                for &callee in calls {
+                    self.draw_node(ui, callee, path, counts, actions);
+                }
+                path.pop();
+            }
+            node.pop(ui);
+        }
+    }
+}
+
+impl DebugView for CallGraphView {
+    fn name(&self) -> &'static str {
+        "Call graph"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Call graph");
+        let window = imgui::Window::new(&title).opened(open);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .size([300.0, 400.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(1200.0, 25.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let mut emulator = ctx.instance.emulator.lock();
+                let (start, end) = emulator.get_code_range();
+                let code = &emulator.memory()[start..end];
+                if state.code != code {
+                    state.rebuild(code, start as u16);
+                }
+                // Shares the switch with the Instructions window
+                let mut enabled = emulator.call_profile().is_some();
+                if ui.checkbox(im_str!("Count calls"), &mut enabled) {
+                    emulator.set_profiling(enabled);
+                }
+                let counts = emulator.call_profile().cloned();
+                drop(emulator);
+                ui.same_line(0.0);
+                ui.text_disabled(format!("{} subroutines", state.subroutines.len() - 1));
+                ui.separator();
+
+                ChildWindow::new(im_str!("tree")).build(ui, || {
+                    let mut path = Vec::new();
+                    state.draw_node(
+                        ui,
+                        state.entry,
+                        &mut path,
+                        counts.as_ref(),
+                        &mut ctx.actions,
+                    );
+                });
+            });
+    }
+}
@@ -0,0 +1,723 @@
+use super::list_clipper::ListClipper;
+use super::patch::{PatchAction, PatchInput};
+use super::radix::Radix;
+use super::regions::{RegionKind, Regions};
+use super::text_cache::CachedText;
+use super::{Action, DebugView, ViewContext};
+use crate::app::to_rgb01;
+use chip8_core::{ByteKind, Emulator, FlowGraph, Instruction, Quirks};
+use imgui::*;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+
+const LABEL_COLOR: [f32; 4] = [0.4, 0.8, 1.0, 1.0];
+
+// Window with program code
+#[derive(Default)]
+pub struct CodeView {
+    /// Browsing state per instance id
+    states: HashMap<usize, CodeState>,
+}
+
+// What an instance's Code window shows
+struct CodeState {
+    /// Disassembled lines, reformatted when the opcode or what it is changes
+    lines: Vec<CachedText<(u16, ByteKind, Radix)>>,
+    /// Base of the NN operands, addresses stay hex
+    radix: Radix,
+    /// Show ROM bytes static analysis didn't reach as data instead of instructions
+    mark_data: bool,
+    /// ROM the analysis ran on, a change means a new ROM
+    analyzed_rom: Vec<u8>,
+    /// What each ROM byte is, found by following the code from the entry
+    byte_kinds: Vec<ByteKind>,
+    /// Keep the PC line centered, cleared when jumping elsewhere
+    follow_pc: bool,
+    address: ImString,
+    /// Addresses jumped to, `history_pos` is the one shown
+    history: Vec<usize>,
+    history_pos: usize,
+    /// Line to bring into view on the next frame
+    scroll_to: Option<usize>,
+    /// Lines picked for copying, the clicked one and the one shift-clicked after it
+    selection: Option<(usize, usize)>,
+    /// Address and text of the comment being written
+    comment: Option<(u16, ImString)>,
+    /// Address and name of the label being written
+    label: Option<(u16, ImString)>,
+    /// Address and name of the bookmark being written
+    bookmark: Option<(u16, ImString)>,
+    /// Show the ROM's notes above the listing
+    show_notes: bool,
+    search: ImString,
+    /// Lines matching the search, highlighted in the listing
+    search_matches: Vec<usize>,
+    /// Line of the match shown
+    found: Option<usize>,
+    /// "3 of 5" or why nothing was found
+    search_status: String,
+    patch: Option<PatchInput>,
+}
+
+impl Default for CodeState {
+    fn default() -> Self {
+        CodeState {
+            lines: Vec::new(),
+            radix: Radix::Hex,
+            mark_data: true,
+            analyzed_rom: Vec::new(),
+            byte_kinds: Vec::new(),
+            follow_pc: true,
+            address: ImString::with_capacity(8),
+            history: Vec::new(),
+            history_pos: 0,
+            scroll_to: None,
+            selection: None,
+            comment: None,
+            label: None,
+            bookmark: None,
+            show_notes: false,
+            search: ImString::with_capacity(32),
+            search_matches: Vec::new(),
+            found: None,
+            search_status: String::new(),
+            patch: None,
+        }
+    }
+}
+
+impl CodeState {
+    // Jumps to `address`, dropping the forward history
+    fn jump(&mut self, address: usize, pc: usize) {
+        if self.history.is_empty() {
+            // Back from the first jump returns to where PC was
+            self.history.push(pc);
+        }
+        self.history.truncate(self.history_pos + 1);
+        self.history.push(address);
+        self.history_pos = self.history.len() - 1;
+        self.show(address);
+    }
+
+    // Runs static analysis again when another ROM was loaded
+    fn analyze(&mut self, emulator: &Emulator) {
+        let rom = emulator.rom();
+        if rom == &self.analyzed_rom[..] {
+            return;
+        }
+        let origin = emulator.get_code_range().0 as u16;
+        let graph = FlowGraph::build(rom, origin, origin);
+        self.byte_kinds = graph.byte_kinds(origin, rom.len());
+        self.analyzed_rom = rom.to_vec();
+    }
+
+    // What the line at `address` shows. Instructions that ran are code whatever
+    // the analysis found, memory outside of the ROM is shown as code too.
+    fn line_kind(&self, emulator: &Emulator, address: usize) -> ByteKind {
+        if !self.mark_data {
+            return ByteKind::Code;
+        }
+        let origin = emulator.get_code_range().0;
+        let kind = |address: usize| {
+            address
+                .checked_sub(origin)
+                .and_then(|i| self.byte_kinds.get(i))
+                .copied()
+                .unwrap_or(ByteKind::Code)
+        };
+        let executed = emulator
+            .memory_access()
+            .is_some_and(|access| access.executes[address] > 0);
+        if executed || kind(address) == ByteKind::Code || kind(address + 1) == ByteKind::Code {
+            ByteKind::Code
+        } else {
+            kind(address)
+        }
+    }
+
+    fn selected_lines(&self) -> Option<RangeInclusive<usize>> {
+        self.selection
+            .map(|(anchor, end)| anchor.min(end)..=anchor.max(end))
+    }
+
+    // Click picks a line, shift-click extends the selection to it
+    fn select(&mut self, ui: &Ui, line: usize) {
+        self.selection = match self.selection {
+            Some((anchor, _)) if ui.io().key_shift => Some((anchor, line)),
+            _ => Some((line, line)),
+        };
+    }
+
+    // Disassembly of the selected lines, as shown in the listing
+    fn selected_text(&self, emulator: &Emulator) -> String {
+        let memory = emulator.memory();
+        let mut text = String::new();
+        for line in self.selected_lines().into_iter().flatten() {
+            let address = line * 2;
+            let kind = self.line_kind(emulator, address);
+            text.push_str(&format_line(
+                address,
+                opcode_at(memory, address),
+                kind,
+                self.radix,
+            ));
+            text.push('\n');
+        }
+        text
+    }
+
+    // Moves to the next or previous line matching the search box
+    fn search(&mut self, emulator: &Emulator, forward: bool) {
+        let query = match SearchQuery::parse(self.search.to_str()) {
+            Some(query) => query,
+            None => {
+                self.search_matches.clear();
+                self.found = None;
+                self.search_status.clear();
+                return;
+            }
+        };
+        let memory = emulator.memory();
+        self.search_matches = (0..memory.len() / 2)
+            .filter(|&line| {
+                let address = line * 2;
+                let opcode = opcode_at(memory, address);
+                let kind = self.line_kind(emulator, address);
+                query.matches(opcode, &format_line(address, opcode, kind, self.radix))
+            })
+            .collect();
+        let matches = &self.search_matches;
+        // Starts from the match shown, the first search from the top
+        let next = match self.found {
+            Some(found) if forward => matches.iter().find(|&&line| line > found),
+            Some(found) => matches.iter().rev().find(|&&line| line < found),
+            None => None,
+        };
+        let wrapped = if forward {
+            matches.first()
+        } else {
+            matches.last()
+        };
+        match next.or(wrapped).copied() {
+            Some(line) => {
+                let number = matches.iter().position(|&l| l == line).unwrap_or(0) + 1;
+                self.search_status = format!("{:03X}, {} of {}", line * 2, number, matches.len());
+                self.found = Some(line);
+                self.show(line * 2);
+            }
+            None => {
+                self.search_status = "Not found".to_string();
+                self.found = None;
+            }
+        }
+    }
+
+    fn draw_search(&mut self, ui: &Ui, emulator: &Emulator) {
+        ui.set_next_item_width(150.0);
+        let entered = ui
+            .input_text(im_str!("##search"), &mut self.search)
+            .enter_returns_true(true)
+            .build();
+        if ui.is_item_hovered() {
+            ui.tooltip_text(
+                "Text in the listing like DRW or V3, or an opcode where ? matches \
+                 any digit: D??5",
+            );
+        }
+        ui.same_line(0.0);
+        let previous = ui.arrow_button(im_str!("previous"), Direction::Up);
+        ui.same_line(0.0);
+        let next = ui.arrow_button(im_str!("next"), Direction::Down);
+        if entered || previous || next {
+            self.search(emulator, !previous);
+        }
+        ui.same_line(0.0);
+        ui.text_disabled(&self.search_status);
+    }
+
+    fn show(&mut self, address: usize) {
+        self.follow_pc = false;
+        self.scroll_to = Some(address / 2);
+        self.address = ImString::new(format!("{:03X}", address));
+    }
+
+    // Returns true when the disassembly should be exported
+    fn draw_toolbar(&mut self, ui: &Ui, pc: usize, memory_len: usize) -> bool {
+        ui.checkbox(im_str!("Follow PC"), &mut self.follow_pc);
+        ui.same_line(0.0);
+        let can_go_back = self.history_pos > 0;
+        if ui.arrow_button(im_str!("back"), Direction::Left) && can_go_back {
+            self.history_pos -= 1;
+            self.show(self.history[self.history_pos]);
+        }
+        ui.same_line(0.0);
+        let can_go_forward = self.history_pos + 1 < self.history.len();
+        if ui.arrow_button(im_str!("forward"), Direction::Right) && can_go_forward {
+            self.history_pos += 1;
+            self.show(self.history[self.history_pos]);
+        }
+        ui.same_line(0.0);
+        ui.set_next_item_width(60.0);
+        let entered = ui
+            .input_text(im_str!("##address"), &mut self.address)
+            .chars_hexadecimal(true)
+            .enter_returns_true(true)
+            .build();
+        ui.same_line(0.0);
+        if ui.button(im_str!("Go"), [0.0, 0.0]) || entered {
+            match usize::from_str_radix(self.address.to_str(), 16) {
+                Ok(address) if address < memory_len => self.jump(address, pc),
+                _ => log::warn!("Address '{}' is outside of memory", self.address.to_str()),
+            }
+        }
+        ui.same_line(0.0);
+        ui.checkbox(im_str!("Data"), &mut self.mark_data);
+        if ui.is_item_hovered() {
+            ui.tooltip_text(
+                "Show ROM bytes the code never reaches as data, found by following \
+                 jumps and calls from the start",
+            );
+        }
+        ui.same_line(0.0);
+        self.radix.draw_toggle(ui);
+        ui.same_line(0.0);
+        ui.checkbox(im_str!("Notes"), &mut self.show_notes);
+        ui.same_line(0.0);
+        ui.button(im_str!("Export..."), [0.0, 0.0])
+    }
+
+    // Free-form notes on the ROM, saved with its comments and labels
+    fn draw_notes(&self, ui: &Ui, notes: &mut String) {
+        if !self.show_notes {
+            return;
+        }
+        let mut text = ImString::new(notes.as_str());
+        let changed = ui
+            .input_text_multiline(im_str!("##notes"), &mut text, [-1.0, 80.0])
+            .resize_buffer(true)
+            .build();
+        if changed {
+            *notes = text.to_string();
+        }
+    }
+}
+
+// Input for the comment or label being written, an empty text removes it
+fn draw_annotation_input(
+    ui: &Ui,
+    id: &ImStr,
+    editing: &mut Option<(u16, ImString)>,
+    annotations: &mut BTreeMap<u16, String>,
+) {
+    let (address, text) = match editing {
+        Some(annotation) => annotation,
+        None => return,
+    };
+    let _id = ui.push_id(id);
+    ui.text(format!("{:03X} {}", address, id));
+    ui.same_line(0.0);
+    ui.set_next_item_width(200.0);
+    let entered = ui
+        .input_text(im_str!("##text"), text)
+        .enter_returns_true(true)
+        .build();
+    ui.same_line(0.0);
+    if ui.button(im_str!("Save"), [0.0, 0.0]) || entered {
+        let text = text.to_str().trim();
+        if text.is_empty() {
+            annotations.remove(address);
+        } else {
+            annotations.insert(*address, text.to_string());
+        }
+        *editing = None;
+    } else if ui.button(im_str!("Cancel"), [0.0, 0.0]) {
+        *editing = None;
+    }
+}
+
+// What the Code window search looks for
+enum SearchQuery {
+    /// Opcode digits, `None` matches any digit
+    Opcode([Option<u8>; 4]),
+    /// Lowercase text anywhere in the line after the address
+    Text(String),
+}
+
+impl SearchQuery {
+    // Four hex digits with at least one '?' are an opcode pattern, the rest is text
+    fn parse(text: &str) -> Option<Self> {
+        let text: String = text.trim().to_lowercase();
+        if text.is_empty() {
+            return None;
+        }
+        let digits: Vec<char> = text.chars().collect();
+        let is_pattern = digits.len() == 4
+            && digits.contains(&'?')
+            && digits.iter().all(|&c| c == '?' || c.is_ascii_hexdigit());
+        if !is_pattern {
+            return Some(SearchQuery::Text(text));
+        }
+        let mut pattern = [None; 4];
+        for (digit, c) in pattern.iter_mut().zip(digits) {
+            *digit = c.to_digit(16).map(|d| d as u8);
+        }
+        Some(SearchQuery::Opcode(pattern))
+    }
+
+    fn matches(&self, opcode: u16, line: &str) -> bool {
+        match self {
+            SearchQuery::Opcode(pattern) => pattern.iter().enumerate().all(|(i, digit)| {
+                digit.is_none_or(|digit| (opcode >> (12 - i * 4)) as u8 & 0xF == digit)
+            }),
+            SearchQuery::Text(text) => {
+                let line = line.split_once(": ").map_or(line, |(_, rest)| rest);
+                line.to_lowercase().contains(text.as_str())
+            }
+        }
+    }
+}
+
+// Address a jump, call or I load refers to, to show its label
+fn target(instruction: Instruction) -> Option<u16> {
+    match instruction {
+        Instruction::Jump { nnn }
+        | Instruction::Call { nnn }
+        | Instruction::LoadIndex { nnn }
+        | Instruction::JumpOffset { nnn } => Some(nnn),
+        _ => None,
+    }
+}
+
+impl DebugView for CodeView {
+    fn name(&self) -> &'static str {
+        "Code"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Code");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([395.0, 600.0], Condition::FirstUseEver)
+            .position(
+                ctx.instance.position(1200.0, 240.0),
+                Condition::FirstUseEver,
+            )
+            .build(ui, || {
+                let emulator = ctx.instance.emulator.lock();
+                let memory = emulator.memory();
+                let pc = emulator.pc() as usize;
+                let quirks = emulator.quirks();
+                let regions = Regions::new(&emulator);
+                let state = self.states.entry(ctx.instance.id).or_default();
+                state.analyze(&emulator);
+                if let Some(address) = ctx.instance.show_code.take() {
+                    state.jump(address as usize, pc);
+                }
+                let export = state.draw_toolbar(ui, pc, memory.len());
+                state.draw_search(ui, &emulator);
+                draw_annotation_input(
+                    ui,
+                    im_str!("comment"),
+                    &mut state.comment,
+                    &mut ctx.instance.comments,
+                );
+                draw_annotation_input(
+                    ui,
+                    im_str!("label"),
+                    &mut state.label,
+                    &mut ctx.instance.labels,
+                );
+                draw_annotation_input(
+                    ui,
+                    im_str!("bookmark"),
+                    &mut state.bookmark,
+                    &mut ctx.instance.bookmarks,
+                );
+                state.draw_notes(ui, &mut ctx.instance.notes);
+                let patch_action = match &mut state.patch {
+                    Some(patch) => patch.draw(ui),
+                    None => PatchAction::None,
+                };
+                ui.separator();
+
+                // Lines start at even addresses, like code loaded at 0x200
+                let pc_line = pc / 2;
+                let line_count = memory.len() / 2;
+                let target_line = state.history.get(state.history_pos).map(|a| a / 2);
+                let selected = state.selected_lines();
+                state.lines.resize_with(line_count, CachedText::default);
+
+                let paused = ctx.instance.paused;
+                let comments = &ctx.instance.comments;
+                let labels = &ctx.instance.labels;
+                let bookmarks = &ctx.instance.bookmarks;
+                let actions = &mut ctx.actions;
+                ChildWindow::new(im_str!("listing")).build(ui, || {
+                    let line_height = ui.text_line_height_with_spacing();
+                    let center_on = if state.follow_pc {
+                        Some(pc_line)
+                    } else {
+                        state.scroll_to.take()
+                    };
+                    // Center even when the line is outside the clipped range
+                    if let Some(line) = center_on.filter(|&line| line < line_count) {
+                        let start_y = ui.cursor_pos()[1];
+                        let line_y = start_y + (line as f32 + 0.5) * line_height;
+                        ui.set_scroll_from_pos_y_with_ratio(line_y - ui.scroll_y(), 0.5);
+                    }
+
+                    let mut clipper = ListClipper::begin(line_count, line_height);
+                    while let Some(visible) = clipper.step() {
+                        for line in visible {
+                            let address = line * 2;
+                            let color = if line == pc_line {
+                                Some([0, 255, 0, 255])
+                            } else if Some(line) == target_line && !state.follow_pc {
+                                Some([255, 200, 0, 255])
+                            } else {
+                                None
+                            };
+                            let color_stack = color.map(|color| {
+                                ui.push_style_color(StyleColor::Text, to_rgb01(color))
+                            });
+                            let opcode = opcode_at(memory, address);
+                            let kind = state.line_kind(&emulator, address);
+                            let data_color =
+                                (color.is_none() && kind != ByteKind::Code).then(|| {
+                                    ui.push_style_color(
+                                        StyleColor::Text,
+                                        ui.style_color(StyleColor::TextDisabled),
+                                    )
+                                });
+                            let key = (opcode, kind, state.radix);
+                            ui.text(state.lines[line].get(key, |(opcode, kind, radix)| {
+                                format_line(address, opcode, kind, radix)
+                            }));
+                            if let Some(c) = data_color {
+                                c.pop(ui);
+                            }
+                            if let Some(c) = color_stack {
+                                c.pop(ui);
+                            }
+                            // Strip left of the line in the color of the region it's in
+                            let mut line_regions: Vec<_> = regions
+                                .all_at(address)
+                                .chain(regions.all_at(address + 1))
+                                .collect();
+                            line_regions.sort();
+                            line_regions.dedup();
+                            if let Some(kind) = line_regions.first() {
+                                let min = ui.item_rect_min();
+                                let max = ui.item_rect_max();
+                                ui.get_window_draw_list()
+                                    .add_rect(
+                                        [min[0] - 6.0, min[1]],
+                                        [min[0] - 3.0, max[1]],
+                                        kind.color(),
+                                    )
+                                    .filled(true)
+                                    .build();
+                            }
+                            let is_selected =
+                                selected.as_ref().is_some_and(|lines| lines.contains(&line));
+                            if is_selected {
+                                ui.get_window_draw_list()
+                                    .add_rect(
+                                        ui.item_rect_min(),
+                                        ui.item_rect_max(),
+                                        [0.3, 0.5, 1.0, 0.3],
+                                    )
+                                    .filled(true)
+                                    .build();
+                            }
+                            if state.search_matches.binary_search(&line).is_ok() {
+                                let alpha = if state.found == Some(line) {
+                                    0.35
+                                } else {
+                                    0.15
+                                };
+                                ui.get_window_draw_list()
+                                    .add_rect(
+                                        ui.item_rect_min(),
+                                        ui.item_rect_max(),
+                                        [1.0, 1.0, 1.0, alpha],
+                                    )
+                                    .filled(true)
+                                    .build();
+                            }
+                            if ui.is_item_clicked(MouseButton::Left) {
+                                state.select(ui, line);
+                            }
+                            if ui.is_item_clicked(MouseButton::Right) {
+                                if !is_selected {
+                                    state.selection = Some((line, line));
+                                }
+                                ui.open_popup(im_str!("line menu"));
+                            }
+                            if ui.is_item_hovered() && kind == ByteKind::Code {
+                                draw_tooltip(
+                                    ui,
+                                    Instruction::decode(opcode),
+                                    quirks,
+                                    &line_regions,
+                                );
+                            }
+                            if let Some(label) = labels.get(&(address as u16)) {
+                                ui.same_line(0.0);
+                                ui.text_colored(LABEL_COLOR, format!("{}:", label));
+                            }
+                            let target_label = (kind == ByteKind::Code)
+                                .then(|| target(Instruction::decode(opcode)))
+                                .flatten()
+                                .and_then(|target| labels.get(&target));
+                            if let Some(label) = target_label {
+                                ui.same_line(0.0);
+                                ui.text_colored(LABEL_COLOR, format!("-> {}", label));
+                            }
+                            if let Some(comment) = comments.get(&(address as u16)) {
+                                ui.same_line(0.0);
+                                ui.text_disabled(format!("; {}", comment));
+                            }
+                        }
+                    }
+                    ui.popup(im_str!("line menu"), || {
+                        let count = state.selected_lines().map_or(0, |lines| lines.count());
+                        let label = ImString::new(format!("Copy {} line(s)", count));
+                        if MenuItem::new(&label).build(ui) {
+                            ui.set_clipboard_text(&ImString::new(state.selected_text(&emulator)));
+                        }
+                        if MenuItem::new(im_str!("Patch...")).build(ui) {
+                            if let Some(lines) = state.selected_lines() {
+                                let address = (*lines.start() * 2) as u16;
+                                state.patch = Some(PatchInput::new(address, memory));
+                            }
+                        }
+                        if MenuItem::new(im_str!("Set PC here"))
+                            .enabled(paused)
+                            .build(ui)
+                        {
+                            if let Some(lines) = state.selected_lines() {
+                                actions.push(Action::SetPc((*lines.start() * 2) as u16));
+                                state.follow_pc = true;
+                            }
+                        }
+                        if MenuItem::new(im_str!("Comment...")).build(ui) {
+                            if let Some(lines) = state.selected_lines() {
+                                let address = (*lines.start() * 2) as u16;
+                                let text = comments.get(&address).cloned();
+                                let mut text = ImString::new(text.unwrap_or_default());
+                                text.reserve(128);
+                                state.comment = Some((address, text));
+                            }
+                        }
+                        if MenuItem::new(im_str!("Label...")).build(ui) {
+                            if let Some(lines) = state.selected_lines() {
+                                let address = (*lines.start() * 2) as u16;
+                                let text = labels.get(&address).cloned();
+                                let mut text = ImString::new(text.unwrap_or_default());
+                                text.reserve(64);
+                                state.label = Some((address, text));
+                            }
+                        }
+                        if MenuItem::new(im_str!("Bookmark...")).build(ui) {
+                            if let Some(lines) = state.selected_lines() {
+                                let address = (*lines.start() * 2) as u16;
+                                let text = bookmarks.get(&address).or_else(|| labels.get(&address));
+                                let mut text = ImString::new(text.cloned().unwrap_or_default());
+                                text.reserve(64);
+                                state.bookmark = Some((address, text));
+                            }
+                        }
+                        if MenuItem::new(im_str!("Clear selection")).build(ui) {
+                            state.selection = None;
+                        }
+                    });
+                });
+                drop(emulator);
+                match patch_action {
+                    PatchAction::Apply => {
+                        let patch = state.patch.as_ref().unwrap();
+                        if patch.apply(&mut ctx.instance.emulator.lock()) {
+                            state.patch = None;
+                        }
+                    }
+                    PatchAction::Cancel => state.patch = None,
+                    PatchAction::None => {}
+                }
+                if export {
+                    ctx.instance.export_disassembly();
+                }
+                ctx.track_focus(ui);
+            });
+    }
+}
+
+fn opcode_at(memory: &[u8], address: usize) -> u16 {
+    (memory[address] as u16) << 8 | memory[address + 1] as u16
+}
+
+// Instruction text with its NN operand in `radix`
+fn format_instruction(instruction: Instruction, radix: Radix) -> String {
+    use Instruction::*;
+    let text = instruction.to_string();
+    match instruction {
+        SkipIfEqual { nn, .. }
+        | SkipIfNotEqual { nn, .. }
+        | Load { nn, .. }
+        | Add { nn, .. }
+        | Random { nn, .. }
+            if radix != Radix::Hex =>
+        {
+            // NN is last, written as 0xNN
+            format!("{}{}", &text[..text.len() - 4], radix.format_u8(nn))
+        }
+        _ => text,
+    }
+}
+
+fn format_line(address: usize, opcode: u16, kind: ByteKind, radix: Radix) -> String {
+    match kind {
+        ByteKind::Code => format!(
+            "{:03X}: {:04X}  {}",
+            address,
+            opcode,
+            format_instruction(Instruction::decode(opcode), radix)
+        ),
+        // Sprite rows drawn as pixels
+        ByteKind::Sprite => {
+            let pixels: String = (0..16)
+                .rev()
+                .map(|bit| if opcode & (1 << bit) != 0 { '#' } else { '.' })
+                .collect();
+            format!(
+                "{:03X}: {:04X}  {} {}",
+                address,
+                opcode,
+                &pixels[..8],
+                &pixels[8..]
+            )
+        }
+        ByteKind::Data => format!("{:03X}: {:04X}  data", address, opcode),
+    }
+}
+
+// What the hovered instruction does with the quirks the emulator runs with
+fn draw_tooltip(ui: &Ui, instruction: Instruction, quirks: Quirks, regions: &[RegionKind]) {
+    let kind = instruction.kind();
+    ui.tooltip(|| {
+        let wrap = ui.push_text_wrap_pos(350.0);
+        ui.text(format!(
+            "{}  {}",
+            Instruction::KIND_PATTERNS[kind],
+            instruction
+        ));
+        ui.text(Instruction::KIND_DESCRIPTIONS[kind]);
+        for note in instruction.quirk_notes(quirks) {
+            ui.text_colored(to_rgb01([255, 200, 0, 255]), note);
+        }
+        for kind in regions {
+            ui.text_colored(kind.color(), kind.name());
+        }
+        wrap.pop(ui);
+    });
+}
@@ -0,0 +1,274 @@
+use super::{Action, DebugView, ViewContext};
+use crate::state_diff;
+use chip8_core::{Emulator, Instruction, Lockstep, Quirks, SCREEN_SIZE};
+use imgui::*;
+use std::collections::HashMap;
+
+const PIXEL_SIZE: f32 = 3.0;
+/// Steps per frame with "Fast" on
+const FAST_STEPS: usize = 50_000;
+/// Differences listed after a divergence, the rest are counted
+const MAX_DIFFERENCES: usize = 12;
+
+const NOTE_COLOR: [f32; 4] = [1.0, 0.78, 0.0, 1.0];
+const DIFF_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+
+// Window that runs the ROM twice with different quirks and stops where they part
+#[derive(Default)]
+pub struct CompareView {
+    /// Compare run per instance id
+    states: HashMap<usize, CompareState>,
+}
+
+struct CompareState {
+    /// Quirks of the second run, the first uses the instance's
+    quirks: Quirks,
+    lockstep: Option<Lockstep>,
+    running: bool,
+    fast: bool,
+}
+
+impl Default for CompareState {
+    fn default() -> Self {
+        CompareState {
+            quirks: Quirks::VIP,
+            lockstep: None,
+            running: false,
+            fast: true,
+        }
+    }
+}
+
+impl CompareState {
+    fn start(&mut self, emulator: &Emulator) {
+        let builder = Emulator::builder()
+            .clock_hz(emulator.clock_hz())
+            .memory_size(emulator.memory().len());
+        let quirks = [emulator.quirks(), self.quirks];
+        match Lockstep::new(builder, quirks, emulator.rom()) {
+            Ok(lockstep) => {
+                self.lockstep = Some(lockstep);
+                self.running = true;
+            }
+            Err(e) => log::error!("Can't start compare run. Error: {}", e),
+        }
+    }
+}
+
+// "vip" for presets, else the quirks that are on
+fn quirks_text(quirks: Quirks) -> String {
+    if let Some(name) = Quirks::PRESET_NAMES
+        .iter()
+        .find(|name| Quirks::preset(name) == Some(quirks))
+    {
+        return name.to_string();
+    }
+    let flags = [
+        ("shift", quirks.shift),
+        ("load/store", quirks.load_store),
+        ("jump", quirks.jump),
+        ("logic", quirks.logic),
+        ("clip", quirks.clip),
+        ("vblank", quirks.vblank),
+    ];
+    let on: Vec<_> = flags
+        .iter()
+        .filter(|(_, on)| *on)
+        .map(|(name, _)| *name)
+        .collect();
+    if on.is_empty() {
+        "no quirks".into()
+    } else {
+        on.join(", ")
+    }
+}
+
+// Screen of `emulator` with the pixels that differ from `other` in red
+fn draw_screen(ui: &Ui, emulator: &Emulator, other: &Emulator, fg: [f32; 4], bg: [f32; 4]) {
+    let origin = ui.cursor_screen_pos();
+    let size = [
+        SCREEN_SIZE.0 as f32 * PIXEL_SIZE,
+        SCREEN_SIZE.1 as f32 * PIXEL_SIZE,
+    ];
+    let draw_list = ui.get_window_draw_list();
+    draw_list
+        .add_rect(origin, [origin[0] + size[0], origin[1] + size[1]], bg)
+        .filled(true)
+        .build();
+    for y in 0..SCREEN_SIZE.1 {
+        for x in 0..SCREEN_SIZE.0 {
+            let on = emulator.screen().get_pixel(x, y);
+            let color = if on != other.screen().get_pixel(x, y) {
+                DIFF_COLOR
+            } else if on {
+                fg
+            } else {
+                continue;
+            };
+            let min = [
+                origin[0] + x as f32 * PIXEL_SIZE,
+                origin[1] + y as f32 * PIXEL_SIZE,
+            ];
+            draw_list
+                .add_rect(min, [min[0] + PIXEL_SIZE, min[1] + PIXEL_SIZE], color)
+                .filled(true)
+                .build();
+        }
+    }
+    drop(draw_list);
+    ui.dummy(size);
+}
+
+impl DebugView for CompareView {
+    fn name(&self) -> &'static str {
+        "Compare quirks"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Compare quirks");
+        let window = imgui::Window::new(&title).opened(open);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .size([440.0, 480.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(460.0, 160.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let emulator = ctx.instance.emulator.lock();
+                ui.text_wrapped(&ImString::new(format!(
+                    "Runs the ROM twice with the same keys, A with the instance's quirks ({}) \
+                     and B with these, and stops at the first instruction after which they differ.",
+                    quirks_text(emulator.quirks())
+                )));
+                for name in Quirks::PRESET_NAMES.iter() {
+                    if ui.small_button(&ImString::new(*name)) {
+                        state.quirks = Quirks::preset(name).unwrap();
+                    }
+                    ui.same_line(0.0);
+                }
+                ui.new_line();
+                let quirks = &mut state.quirks;
+                ui.checkbox(im_str!("shift"), &mut quirks.shift);
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("load/store"), &mut quirks.load_store);
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("jump"), &mut quirks.jump);
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("logic"), &mut quirks.logic);
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("clip"), &mut quirks.clip);
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("vblank"), &mut quirks.vblank);
+
+                if emulator.rom().is_empty() {
+                    ui.text_disabled("Load a ROM to compare");
+                    return;
+                }
+                let label = if state.lockstep.is_some() {
+                    im_str!("Restart")
+                } else {
+                    im_str!("Start")
+                };
+                if ui.button(label, [0.0, 0.0]) {
+                    state.start(&emulator);
+                }
+                drop(emulator);
+
+                let lockstep = match &mut state.lockstep {
+                    Some(lockstep) => lockstep,
+                    None => return,
+                };
+                let finished = lockstep.divergence().is_some() || lockstep.halt_reason().is_some();
+                if !finished {
+                    ui.same_line(0.0);
+                    let label = if state.running {
+                        im_str!("Pause")
+                    } else {
+                        im_str!("Run")
+                    };
+                    if ui.button(label, [0.0, 0.0]) {
+                        state.running = !state.running;
+                    }
+                    ui.same_line(0.0);
+                    if ui.button(im_str!("Step"), [0.0, 0.0]) {
+                        state.running = false;
+                        lockstep.run(1);
+                    }
+                    ui.same_line(0.0);
+                    ui.checkbox(im_str!("Fast"), &mut state.fast);
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(
+                            "Run as fast as possible instead of at the instance's clock speed",
+                        );
+                    }
+                }
+
+                // The instance's keypad drives both runs
+                for (key, &down) in ctx.instance.keys.iter().enumerate() {
+                    lockstep.set_key(key as u8, down);
+                }
+                if state.running && !finished {
+                    let steps = if state.fast {
+                        FAST_STEPS
+                    } else {
+                        let clock_hz = lockstep.emulators()[0].clock_hz();
+                        (clock_hz * ui.io().delta_time).ceil() as usize
+                    };
+                    if let Some(divergence) = lockstep.run(steps) {
+                        state.running = false;
+                        ctx.actions.push(Action::ShowCode(divergence.address));
+                    }
+                }
+                ui.separator();
+
+                ui.text(format!("{} instructions", lockstep.steps()));
+                let [a, b] = lockstep.emulators();
+                if let Some(divergence) = lockstep.divergence() {
+                    let instruction = Instruction::decode(divergence.opcode);
+                    ui.text_colored(
+                        DIFF_COLOR,
+                        format!(
+                            "Diverged after {:03X}  {:04X}  {}",
+                            divergence.address, divergence.opcode, instruction
+                        ),
+                    );
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Show code")) {
+                        ctx.actions.push(Action::ShowCode(divergence.address));
+                    }
+                    for (name, emulator) in [("A", a), ("B", b)].iter() {
+                        for note in instruction.quirk_notes(emulator.quirks()) {
+                            ui.text_colored(NOTE_COLOR, format!("{}: {}", name, note));
+                        }
+                    }
+                    let differences = state_diff::differences(&a.save_state(), &b.save_state());
+                    for difference in differences.iter().take(MAX_DIFFERENCES) {
+                        ui.text(difference);
+                    }
+                    if differences.len() > MAX_DIFFERENCES {
+                        ui.text_disabled(format!(
+                            "... and {} more",
+                            differences.len() - MAX_DIFFERENCES
+                        ));
+                    }
+                } else if let Some(reason) = lockstep.halt_reason() {
+                    ui.text_colored(
+                        NOTE_COLOR,
+                        format!("Both halted without differing: {:?}", reason),
+                    );
+                } else {
+                    ui.text_disabled("No difference yet");
+                }
+
+                let palette = &ctx.instance.palette;
+                let group = ui.begin_group();
+                ui.text(format!("A: {}", quirks_text(a.quirks())));
+                draw_screen(ui, a, b, palette.fg, palette.bg);
+                group.end(ui);
+                ui.same_line(0.0);
+                let group = ui.begin_group();
+                ui.text(format!("B: {}", quirks_text(b.quirks())));
+                draw_screen(ui, b, a, palette.fg, palette.bg);
+                group.end(ui);
+            });
+    }
+}
@@ -0,0 +1,119 @@
+use super::{Action, DebugView, ViewContext};
+use crate::compatibility::{self, Report};
+use imgui::*;
+use std::collections::HashMap;
+use std::thread::{self, JoinHandle};
+
+const OK_COLOR: [f32; 4] = [0.5, 0.9, 0.5, 1.0];
+const FAULT_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+
+// Window that runs the ROM under every quirk preset and suggests one
+#[derive(Default)]
+pub struct CompatibilityView {
+    /// Report per instance id
+    states: HashMap<usize, CompatibilityState>,
+}
+
+#[derive(Default)]
+struct CompatibilityState {
+    /// Runs on a background thread, they take a few seconds
+    job: Option<JoinHandle<Result<Report, String>>>,
+    report: Option<Report>,
+}
+
+impl CompatibilityState {
+    fn poll(&mut self) {
+        if !self.job.as_ref().is_some_and(|job| job.is_finished()) {
+            return;
+        }
+        let result = self.job.take().unwrap().join();
+        match result {
+            Ok(Ok(report)) => self.report = Some(report),
+            Ok(Err(e)) => log::error!("Can't check compatibility. Error: {}", e),
+            Err(_) => log::error!("Can't check compatibility. Error: the emulator crashed"),
+        }
+    }
+}
+
+impl DebugView for CompatibilityView {
+    fn name(&self) -> &'static str {
+        "Compatibility"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Compatibility");
+        let window = imgui::Window::new(&title).opened(open);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .size([420.0, 220.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(480.0, 200.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                state.poll();
+                let emulator = ctx.instance.emulator.lock();
+                if emulator.rom().is_empty() {
+                    ui.text_disabled("Load a ROM to check it");
+                    return;
+                }
+                ui.text_wrapped(&ImString::new(format!(
+                    "Runs the ROM for {} instructions under each quirk preset, pressing keys \
+                     when it waits for one, and stops at illegal opcodes, stack faults and \
+                     jumps out of memory.",
+                    compatibility::CYCLES
+                )));
+                if state.job.is_some() {
+                    ui.text_disabled("Checking...");
+                } else if ui.button(im_str!("Check presets"), [0.0, 0.0]) {
+                    let rom = emulator.rom().to_vec();
+                    let clock_hz = emulator.clock_hz();
+                    let memory_size = emulator.memory().len();
+                    state.job = Some(thread::spawn(move || {
+                        Report::run(&rom, clock_hz, memory_size, compatibility::CYCLES)
+                            .map_err(|e| e.to_string())
+                    }));
+                }
+                let current = emulator.quirks();
+                drop(emulator);
+
+                let report = match &state.report {
+                    Some(report) => report,
+                    None => return,
+                };
+                ui.separator();
+                for run in &report.runs {
+                    match &run.fault {
+                        Some(fault) => ui.text_colored(
+                            FAULT_COLOR,
+                            format!(
+                                "{}: {} after {} instructions",
+                                run.preset, fault, run.instructions
+                            ),
+                        ),
+                        None => ui.text_colored(
+                            OK_COLOR,
+                            format!("{}: ok, {} instructions", run.preset, run.instructions),
+                        ),
+                    }
+                }
+                if !report.quirks_matter() {
+                    ui.text_disabled(
+                        "Presets without faults ended the same, quirks don't matter so far",
+                    );
+                }
+                if let Some(best) = report.suggestion(current) {
+                    let verdict = if best.fault.is_some() {
+                        "Every preset faults, furthest"
+                    } else {
+                        "Suggested"
+                    };
+                    ui.text(format!("{}: {}", verdict, best.preset));
+                    if best.quirks != current {
+                        ui.same_line(0.0);
+                        if ui.small_button(im_str!("Use")) {
+                            ctx.actions.push(Action::SetQuirks(best.quirks));
+                        }
+                    }
+                }
+            });
+    }
+}
@@ -0,0 +1,260 @@
+use super::radix::Radix;
+use super::text_cache::CachedText;
+use super::{DebugView, ViewContext};
+use chip8_core::TIMER_HZ;
+use imgui::*;
+use std::collections::HashMap;
+
+/// UI frames of timer values kept for the graphs, 2 seconds while running
+const TIMER_HISTORY_LEN: usize = 120;
+
+// Window with CPU state
+#[derive(Default)]
+pub struct CpuView {
+    /// Formatted values per instance id
+    text: HashMap<usize, CpuText>,
+}
+
+#[derive(Default)]
+struct CpuText {
+    radix: Radix,
+    pc: CachedText<(u16, Radix)>,
+    index: CachedText<(u16, Radix)>,
+    registers: [CachedText<(u8, Radix)>; 16],
+    delay: CachedText<u8>,
+    sound: CachedText<(u8, bool)>,
+    delay_history: TimerHistory,
+    sound_history: TimerHistory,
+    rate: CachedText<(u32, bool)>,
+    stack_size: CachedText<usize>,
+    stack: Vec<CachedText<u16>>,
+    /// Stack entry being edited while paused and its new address
+    stack_edit: Option<(usize, ImString)>,
+}
+
+// Recent values of a timer, drawn as a bar of what's left and a graph
+struct TimerHistory {
+    samples: Vec<f32>, // Ring buffer, `next` is the oldest sample
+    next: usize,
+    peak: u8, // Value the timer was last set to
+    last: u8,
+}
+
+impl Default for TimerHistory {
+    fn default() -> Self {
+        TimerHistory {
+            samples: vec![0.0; TIMER_HISTORY_LEN],
+            next: 0,
+            peak: 0,
+            last: 0,
+        }
+    }
+}
+
+impl TimerHistory {
+    fn push(&mut self, value: u8) {
+        // Programs set timers and let them run down, a rise means a new start
+        if value > self.last {
+            self.peak = value;
+        }
+        self.last = value;
+        self.samples[self.next] = value as f32;
+        self.next = (self.next + 1) % self.samples.len();
+    }
+
+    fn draw(&self, ui: &Ui, id: &ImStr, text: &str) {
+        let fraction = if self.peak > 0 {
+            self.last as f32 / self.peak as f32
+        } else {
+            0.0
+        };
+        ProgressBar::new(fraction)
+            .overlay_text(im_str!(""))
+            .size([120.0, 0.0])
+            .build(ui);
+        ui.same_line(0.0);
+        ui.text(text);
+        let max = self.samples.iter().cloned().fold(1.0, f32::max);
+        ui.plot_lines(id, &self.samples)
+            .values_offset(self.next)
+            .scale_min(0.0)
+            .scale_max(max)
+            .graph_size([0.0, 30.0])
+            .build();
+    }
+}
+
+// CPU state as plain text, for pasting into bug reports and notes
+fn registers_text(emulator: &chip8_core::Emulator) -> String {
+    let mut text = format!("PC: {:03X}\nI: {:03X}\n", emulator.pc(), emulator.index());
+    for (i, v) in emulator.registers().iter().enumerate() {
+        text.push_str(&format!("V{:X}: {:02X}", i, v));
+        text.push(if (i + 1) % 4 == 0 { '\n' } else { ' ' });
+    }
+    text.push_str(&format!(
+        "delay: {}\nsound: {}\nstack:",
+        emulator.delay_timer(),
+        emulator.sound_timer()
+    ));
+    for address in emulator.stack() {
+        text.push_str(&format!(" {:03X}", address));
+    }
+    text.push('\n');
+    text
+}
+
+// Stack entries as buttons to edit them, with push and pop. Returns the changed stack
+fn edit_stack(ui: &Ui, text: &mut CpuText, stack: &[u16], pc: u16) -> Option<Vec<u16>> {
+    let mut changed = None;
+    for (i, &address) in stack.iter().enumerate() {
+        ui.same_line(0.0);
+        let _id = ui.push_id(i as i32);
+        if ui.small_button(&ImString::new(format!("{:X}", address))) {
+            let mut input = ImString::new(format!("{:X}", address));
+            input.reserve(8);
+            text.stack_edit = Some((i, input));
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Click to change the return address");
+        }
+    }
+    ui.same_line(0.0);
+    if ui.small_button(im_str!("Push")) {
+        // Returning to the next instruction, as a call from here would
+        let mut stack = stack.to_vec();
+        stack.push(pc.wrapping_add(2));
+        let mut input = ImString::new(format!("{:X}", pc.wrapping_add(2)));
+        input.reserve(8);
+        text.stack_edit = Some((stack.len() - 1, input));
+        changed = Some(stack);
+    }
+    ui.same_line(0.0);
+    if ui.small_button(im_str!("Pop")) && !stack.is_empty() {
+        text.stack_edit = None;
+        changed = Some(stack[..stack.len() - 1].to_vec());
+    }
+
+    if let Some((i, input)) = &mut text.stack_edit {
+        let i = *i;
+        ui.text(format!("Entry {}:", i));
+        ui.same_line(0.0);
+        ui.set_next_item_width(60.0);
+        let entered = ui
+            .input_text(im_str!("##stack entry"), input)
+            .chars_hexadecimal(true)
+            .enter_returns_true(true)
+            .build();
+        ui.same_line(0.0);
+        if ui.button(im_str!("Set"), [0.0, 0.0]) || entered {
+            match u16::from_str_radix(input.to_str(), 16) {
+                Ok(address) => {
+                    let mut stack = changed.unwrap_or_else(|| stack.to_vec());
+                    if let Some(entry) = stack.get_mut(i) {
+                        *entry = address;
+                    }
+                    changed = Some(stack);
+                    text.stack_edit = None;
+                }
+                Err(_) => log::warn!("'{}' isn't an address", input.to_str()),
+            }
+        }
+        ui.same_line(0.0);
+        if ui.button(im_str!("Cancel"), [0.0, 0.0]) {
+            text.stack_edit = None;
+        }
+    }
+    changed
+}
+
+impl DebugView for CpuView {
+    fn name(&self) -> &'static str {
+        "CPU"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("CPU");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([395.0, 300.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(1200.0, 25.0), Condition::FirstUseEver)
+            .build(ui, || {
+                let text = self.text.entry(ctx.instance.id).or_default();
+                let mut emulator = ctx.instance.emulator.lock();
+                if ui.small_button(im_str!("Copy")) {
+                    ui.set_clipboard_text(&ImString::new(registers_text(&emulator)));
+                }
+                ui.same_line(0.0);
+                text.radix.draw_toggle(ui);
+                let radix = text.radix;
+                let pc = (emulator.pc(), radix);
+                ui.text(
+                    text.pc
+                        .get(pc, |(pc, radix)| format!("PC: {}", radix.format_u16(pc))),
+                );
+                let index = (emulator.index(), radix);
+                ui.text(
+                    text.index
+                        .get(index, |(i, radix)| format!("I: {}", radix.format_u16(i))),
+                );
+                // Binary registers don't fit four to a line
+                let per_line = if radix == Radix::Binary { 2 } else { 4 };
+                for (i, &v) in emulator.registers().iter().enumerate() {
+                    ui.text(text.registers[i].get((v, radix), |(v, radix)| {
+                        format!("V{:X}: {} ", i, radix.format_u8(v))
+                    }));
+                    if (i + 1) % per_line != 0 {
+                        ui.same_line(0.0);
+                    }
+                }
+                // Timers tick at 60Hz of emulated time, so speed changes their real rate
+                let instance = &*ctx.instance;
+                let running = !instance.paused && !emulator.is_halted();
+                let speed = instance.fast_forward.unwrap_or(instance.speed);
+                let rate = (TIMER_HZ * speed).round() as u32;
+                ui.text(text.rate.get((rate, running), |(rate, running)| {
+                    if running {
+                        format!("timers count down {}/s", rate)
+                    } else {
+                        "timers stopped".to_string()
+                    }
+                }));
+                let delay = emulator.delay_timer();
+                text.delay_history.push(delay);
+                let delay_text = text.delay.get(delay, |t| {
+                    format!("delay: {} ({:.2}s)", t, t as f32 / TIMER_HZ)
+                });
+                text.delay_history.draw(ui, im_str!("##delay"), delay_text);
+                let sound = emulator.sound_timer();
+                text.sound_history.push(sound);
+                let sound_text = text.sound.get((sound, instance.beep), |(t, beep)| {
+                    format!(
+                        "sound: {} ({:.2}s){}",
+                        t,
+                        t as f32 / TIMER_HZ,
+                        if beep { " beep" } else { "" }
+                    )
+                });
+                text.sound_history.draw(ui, im_str!("##sound"), sound_text);
+
+                let stack = emulator.stack();
+                ui.text(
+                    text.stack_size
+                        .get(stack.len(), |len| format!("stack (size: {}):", len)),
+                );
+                if instance.paused {
+                    if let Some(stack) = edit_stack(ui, text, stack, emulator.pc()) {
+                        emulator.set_stack(&stack);
+                    }
+                } else {
+                    text.stack_edit = None;
+                    text.stack.resize_with(stack.len(), CachedText::default);
+                    for (cached, &v) in text.stack.iter_mut().zip(stack) {
+                        ui.same_line(0.0);
+                        ui.text(cached.get(v, |v| format!("{:X}", v)));
+                    }
+                }
+                drop(emulator);
+                ctx.track_focus(ui);
+            });
+    }
+}
@@ -0,0 +1,314 @@
+use super::{Action, DebugView, ViewContext};
+use crate::app::to_rgb01;
+use chip8_core::{EdgeKind, FlowGraph};
+use imgui::*;
+use std::collections::HashMap;
+
+const BOX_WIDTH: f32 = 150.0;
+const GAP: [f32; 2] = [30.0, 40.0];
+/// Longer blocks only show their first lines
+const MAX_LINES: usize = 8;
+const MIN_ZOOM: f32 = 0.2;
+const MAX_ZOOM: f32 = 2.0;
+
+// Window drawing the static control flow graph of the loaded ROM
+#[derive(Default)]
+pub struct FlowGraphView {
+    /// Graph and view per instance id
+    states: HashMap<usize, GraphState>,
+}
+
+struct GraphState {
+    code: Vec<u8>, // Bytes the graph was built from, a change means a new ROM
+    graph: FlowGraph,
+    /// Top left corner of each block, at zoom 1
+    positions: Vec<[f32; 2]>,
+    pan: [f32; 2],
+    zoom: f32,
+}
+
+impl Default for GraphState {
+    fn default() -> Self {
+        GraphState {
+            code: Vec::new(),
+            graph: FlowGraph::default(),
+            positions: Vec::new(),
+            pan: [10.0, 10.0],
+            zoom: 1.0,
+        }
+    }
+}
+
+fn box_height(lines: usize, line_height: f32) -> f32 {
+    (lines.min(MAX_LINES + 1) + 1) as f32 * line_height
+}
+
+fn edge_color(kind: EdgeKind) -> [f32; 4] {
+    to_rgb01(match kind {
+        EdgeKind::Next => [160, 160, 160, 255],
+        EdgeKind::Jump => [255, 200, 0, 255],
+        EdgeKind::Call => [100, 220, 100, 255],
+        EdgeKind::Skip => [255, 130, 60, 255],
+    })
+}
+
+impl GraphState {
+    fn rebuild(&mut self, code: &[u8], origin: u16, line_height: f32) {
+        self.code = code.to_vec();
+        self.graph = FlowGraph::build(code, origin, origin);
+        self.layout(line_height);
+    }
+
+    // Blocks go into rows by their distance from the entry, left to right by address
+    fn layout(&mut self, line_height: f32) {
+        let blocks = self.graph.blocks();
+        let index_of: HashMap<u16, usize> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (block.start, i))
+            .collect();
+        let mut depth = vec![usize::MAX; blocks.len()];
+        let mut queue = std::collections::VecDeque::new();
+        if !blocks.is_empty() {
+            depth[0] = 0;
+            queue.push_back(0);
+        }
+        while let Some(i) = queue.pop_front() {
+            for edge in &blocks[i].edges {
+                if let Some(&next) = index_of.get(&edge.target) {
+                    if depth[next] == usize::MAX {
+                        depth[next] = depth[i] + 1;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        // Blocks only reachable through code that starts mid-block end up at the bottom
+        let deepest = depth
+            .iter()
+            .filter(|&&d| d != usize::MAX)
+            .max()
+            .map_or(0, |d| d + 1);
+        for d in depth.iter_mut().filter(|d| **d == usize::MAX) {
+            *d = deepest;
+        }
+
+        let rows = depth.iter().max().map_or(0, |d| d + 1);
+        let mut row_heights = vec![0.0f32; rows];
+        let mut columns = vec![0usize; rows];
+        self.positions = vec![[0.0; 2]; blocks.len()];
+        for (i, block) in blocks.iter().enumerate() {
+            let row = depth[i];
+            self.positions[i][0] = columns[row] as f32 * (BOX_WIDTH + GAP[0]);
+            columns[row] += 1;
+            let height = box_height(block.instructions.len(), line_height);
+            row_heights[row] = row_heights[row].max(height);
+        }
+        let mut row_y = vec![0.0f32; rows];
+        for row in 1..rows {
+            row_y[row] = row_y[row - 1] + row_heights[row - 1] + GAP[1];
+        }
+        for (i, position) in self.positions.iter_mut().enumerate() {
+            position[1] = row_y[depth[i]];
+        }
+    }
+
+    // Screen rectangle of a block
+    fn rect(&self, i: usize, origin: [f32; 2], line_height: f32) -> ([f32; 2], [f32; 2]) {
+        let [x, y] = self.positions[i];
+        let lines = self.graph.blocks()[i].instructions.len();
+        let min = [
+            origin[0] + self.pan[0] + x * self.zoom,
+            origin[1] + self.pan[1] + y * self.zoom,
+        ];
+        let max = [
+            min[0] + BOX_WIDTH * self.zoom,
+            min[1] + box_height(lines, line_height) * self.zoom,
+        ];
+        (min, max)
+    }
+
+    // Drag pans, the wheel zooms around the mouse
+    fn handle_input(&mut self, ui: &Ui, origin: [f32; 2]) {
+        let io = ui.io();
+        if ui.is_item_active() && ui.is_mouse_dragging(MouseButton::Left) {
+            self.pan[0] += io.mouse_delta[0];
+            self.pan[1] += io.mouse_delta[1];
+        }
+        if ui.is_item_hovered() && io.mouse_wheel != 0.0 {
+            let zoom = (self.zoom * 1.1f32.powf(io.mouse_wheel)).clamp(MIN_ZOOM, MAX_ZOOM);
+            let mouse = [io.mouse_pos[0] - origin[0], io.mouse_pos[1] - origin[1]];
+            for (pan, mouse) in self.pan.iter_mut().zip(mouse.iter()) {
+                *pan = mouse - (mouse - *pan) * zoom / self.zoom;
+            }
+            self.zoom = zoom;
+        }
+    }
+
+    fn block_under(&self, point: [f32; 2], origin: [f32; 2], line_height: f32) -> Option<usize> {
+        (0..self.positions.len()).find(|&i| {
+            let (min, max) = self.rect(i, origin, line_height);
+            point[0] >= min[0] && point[0] < max[0] && point[1] >= min[1] && point[1] < max[1]
+        })
+    }
+
+    fn draw(&self, ui: &Ui, origin: [f32; 2], line_height: f32, pc: u16) {
+        let draw_list = ui.get_window_draw_list();
+        let blocks = self.graph.blocks();
+        let index_of: HashMap<u16, usize> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (block.start, i))
+            .collect();
+
+        for (i, block) in blocks.iter().enumerate() {
+            let (min, max) = self.rect(i, origin, line_height);
+            let from = [(min[0] + max[0]) / 2.0, max[1]];
+            for edge in &block.edges {
+                let target = match index_of.get(&edge.target) {
+                    Some(&target) => target,
+                    None => continue,
+                };
+                let (target_min, target_max) = self.rect(target, origin, line_height);
+                let to = [(target_min[0] + target_max[0]) / 2.0, target_min[1]];
+                let bend = 40.0 * self.zoom;
+                let color = edge_color(edge.kind);
+                draw_list
+                    .add_bezier_curve(
+                        from,
+                        [from[0], from[1] + bend],
+                        [to[0], to[1] - bend],
+                        to,
+                        color,
+                    )
+                    .thickness(1.5)
+                    .build();
+                let arrow = 5.0 * self.zoom;
+                draw_list
+                    .add_triangle(
+                        to,
+                        [to[0] - arrow, to[1] - arrow * 1.5],
+                        [to[0] + arrow, to[1] - arrow * 1.5],
+                        color,
+                    )
+                    .filled(true)
+                    .build();
+            }
+        }
+
+        for (i, block) in blocks.iter().enumerate() {
+            let (min, max) = self.rect(i, origin, line_height);
+            draw_list
+                .add_rect(min, max, [0.12, 0.12, 0.15, 1.0])
+                .filled(true)
+                .rounding(4.0)
+                .build();
+            let border = if block.contains(pc) {
+                to_rgb01([0, 255, 0, 255])
+            } else {
+                [0.5, 0.5, 0.5, 1.0]
+            };
+            draw_list.add_rect(min, max, border).rounding(4.0).build();
+
+            // Text is scaled with the window font scale, so it shrinks along with boxes
+            let step = line_height * self.zoom;
+            let text_x = min[0] + 4.0 * self.zoom;
+            draw_list.add_text(
+                [text_x, min[1] + step * 0.5],
+                [1.0, 1.0, 1.0, 1.0],
+                format!("{:03X}", block.start),
+            );
+            if self.zoom < 0.5 {
+                continue;
+            }
+            for (line, instruction) in block.instructions.iter().take(MAX_LINES).enumerate() {
+                draw_list.add_text(
+                    [text_x, min[1] + step * (line as f32 + 1.5)],
+                    [0.8, 0.8, 0.8, 1.0],
+                    instruction.to_string(),
+                );
+            }
+            if block.instructions.len() > MAX_LINES {
+                draw_list.add_text(
+                    [text_x, min[1] + step * (MAX_LINES as f32 + 1.5)],
+                    [0.6, 0.6, 0.6, 1.0],
+                    "...",
+                );
+            }
+        }
+    }
+}
+
+impl DebugView for FlowGraphView {
+    fn name(&self) -> &'static str {
+        "Flow graph"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Flow graph");
+        let window = imgui::Window::new(&title).opened(open);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .size([600.0, 500.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(420.0, 60.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let emulator = ctx.instance.emulator.lock();
+                let (start, end) = emulator.get_code_range();
+                let code = &emulator.memory()[start..end];
+                let pc = emulator.pc();
+                // Built at zoom 1, so the line height has to come before the font is scaled
+                let line_height = ui.text_line_height_with_spacing();
+                if state.code != code {
+                    state.rebuild(code, start as u16, line_height);
+                }
+                drop(emulator);
+
+                for kind in [
+                    EdgeKind::Next,
+                    EdgeKind::Jump,
+                    EdgeKind::Call,
+                    EdgeKind::Skip,
+                ] {
+                    ui.text_colored(edge_color(kind), format!("{:?}", kind).to_lowercase());
+                    ui.same_line(0.0);
+                }
+                ui.text_disabled(format!("{} blocks", state.graph.blocks().len()));
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Reset view")) {
+                    state.pan = [10.0, 10.0];
+                    state.zoom = 1.0;
+                }
+
+                ChildWindow::new(im_str!("canvas"))
+                    .border(true)
+                    .scroll_bar(false)
+                    .scrollable(false)
+                    .build(ui, || {
+                        let origin = ui.cursor_screen_pos();
+                        let size = ui.content_region_avail();
+                        ui.invisible_button(im_str!("graph"), size);
+                        state.handle_input(ui, origin);
+                        let clicked = ui.is_item_hovered()
+                            && ui.is_mouse_released(MouseButton::Left)
+                            && !ui.is_mouse_dragging(MouseButton::Left);
+                        let mouse = ui.io().mouse_pos;
+                        if clicked {
+                            if let Some(i) = state.block_under(mouse, origin, line_height) {
+                                let address = state.graph.blocks()[i].start;
+                                ctx.actions.push(Action::ShowCode(address));
+                            }
+                        }
+
+                        ui.set_window_font_scale(state.zoom);
+                        state.draw(ui, origin, line_height, pc);
+                        ui.set_window_font_scale(1.0);
+                        if ui.is_item_hovered()
+                            && state.block_under(mouse, origin, line_height).is_some()
+                        {
+                            ui.tooltip_text("Click to show in the Code window");
+                        }
+                    });
+            });
+    }
+}
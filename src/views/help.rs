@@ -0,0 +1,47 @@
+use super::{DebugView, ViewContext};
+use crate::config::{key_name, Hotkey, KEYPAD_LAYOUT};
+use crate::i18n::{self, tr};
+use imgui::*;
+
+// Help Window
+pub struct HelpView;
+
+impl DebugView for HelpView {
+    fn name(&self) -> &'static str {
+        "Help"
+    }
+
+    fn per_instance(&self) -> bool {
+        false
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = i18n::window_title("Help", "");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([395.0, 210.0], Condition::FirstUseEver)
+            .position([5.0, 680.0], Condition::FirstUseEver)
+            .build(ui, || {
+                // Everything below comes from the current bindings, so it stays right after rebinding
+                ui.text(tr("Select ROM file, to control use keys:"));
+                ui.columns(4, im_str!("keypad"), true);
+                for &key in KEYPAD_LAYOUT.iter() {
+                    ui.text(format!("{:X}: {}", key, key_name(ctx.config.keys[key])));
+                    ui.next_column();
+                }
+                ui.columns(1, im_str!("keypad"), false);
+                ui.separator();
+
+                ui.columns(2, im_str!("hotkeys"), false);
+                for &hotkey in Hotkey::ALL.iter() {
+                    ui.text(key_name(ctx.config.hotkeys.key(hotkey)));
+                    ui.next_column();
+                    ui.text(tr(hotkey.name()));
+                    ui.next_column();
+                }
+                ui.columns(1, im_str!("hotkeys"), false);
+                ui.separator();
+                ui.text(tr("Keys are changed in the Settings window.\n\nWith several instances, ROMs are loaded into\nand keys go to the last clicked one.\nClosed windows are reopened from the View menu,\nROMs can also be opened from the File menu.\n\nHave fun!"));
+            });
+    }
+}
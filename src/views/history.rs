@@ -0,0 +1,116 @@
+use super::{DebugView, ViewContext};
+use imgui::*;
+use std::collections::HashMap;
+
+/// UI frames of values kept for the plots, 5 seconds while running
+const HISTORY_LEN: usize = 300;
+
+/// V0-VF, I and stack depth
+const SIGNAL_COUNT: usize = 18;
+const INDEX: usize = 16;
+const STACK_DEPTH: usize = 17;
+
+// Window plotting registers, I and stack depth over the last few seconds
+#[derive(Default)]
+pub struct HistoryView {
+    /// Samples per instance id
+    histories: HashMap<usize, History>,
+}
+
+struct History {
+    samples: Vec<[f32; SIGNAL_COUNT]>, // Ring buffer, `next` is the oldest sample
+    next: usize,
+    last_count: Option<u64>, // Instruction count at the last sample
+    shown: [bool; SIGNAL_COUNT],
+}
+
+impl Default for History {
+    fn default() -> Self {
+        let mut shown = [false; SIGNAL_COUNT];
+        shown[INDEX] = true;
+        shown[STACK_DEPTH] = true;
+        History {
+            samples: vec![[0.0; SIGNAL_COUNT]; HISTORY_LEN],
+            next: 0,
+            last_count: None,
+            shown,
+        }
+    }
+}
+
+impl History {
+    fn push(&mut self, sample: [f32; SIGNAL_COUNT]) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % HISTORY_LEN;
+    }
+
+    fn values(&self, signal: usize) -> Vec<f32> {
+        self.samples.iter().map(|sample| sample[signal]).collect()
+    }
+}
+
+fn signal_name(signal: usize) -> String {
+    match signal {
+        INDEX => "I".to_string(),
+        STACK_DEPTH => "stack".to_string(),
+        v => format!("V{:X}", v),
+    }
+}
+
+impl DebugView for HistoryView {
+    fn name(&self) -> &'static str {
+        "History"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("History");
+        let window = imgui::Window::new(&title).opened(open);
+        let history = self.histories.entry(ctx.instance.id).or_default();
+        window
+            .size([395.0, 400.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(800.0, 440.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let emulator = ctx.instance.emulator.lock();
+                // Only sample while instructions run, so pauses don't flatten the plots
+                let count = emulator.instruction_count();
+                if history.last_count != Some(count) {
+                    history.last_count = Some(count);
+                    let mut sample = [0.0; SIGNAL_COUNT];
+                    for (value, &register) in sample.iter_mut().zip(emulator.registers()) {
+                        *value = register as f32;
+                    }
+                    sample[INDEX] = emulator.index() as f32;
+                    sample[STACK_DEPTH] = emulator.stack().len() as f32;
+                    history.push(sample);
+                }
+                drop(emulator);
+
+                for signal in 0..SIGNAL_COUNT {
+                    let label = ImString::new(signal_name(signal));
+                    ui.checkbox(&label, &mut history.shown[signal]);
+                    if signal % 6 != 5 && signal + 1 < SIGNAL_COUNT {
+                        ui.same_line(0.0);
+                    }
+                }
+                ui.separator();
+
+                let last = (history.next + HISTORY_LEN - 1) % HISTORY_LEN;
+                for signal in (0..SIGNAL_COUNT).filter(|&signal| history.shown[signal]) {
+                    let values = history.values(signal);
+                    let min = values.iter().cloned().fold(f32::MAX, f32::min);
+                    let max = values.iter().cloned().fold(f32::MIN, f32::max);
+                    let name = signal_name(signal);
+                    let current = history.samples[last][signal];
+                    let overlay = ImString::new(format!("{}: {:#X}", name, current as u32));
+                    ui.plot_lines(&ImString::new(format!("##{}", name)), &values)
+                        .values_offset(history.next)
+                        .overlay_text(&overlay)
+                        .scale_min(min)
+                        .scale_max(max.max(min + 1.0))
+                        .graph_size([0.0, 40.0])
+                        .build();
+                }
+            });
+    }
+}
@@ -0,0 +1,280 @@
+use super::{DebugView, ViewContext};
+use chip8_core::{Emulator, Instruction, Screen, StepEvent, SCREEN_SIZE};
+use imgui::*;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Changed memory bytes listed after a step, the rest are counted
+const MAX_MEMORY_CHANGES: usize = 8;
+
+const NOTE_COLOR: [f32; 4] = [1.0, 0.78, 0.0, 1.0];
+const CHANGE_COLOR: [f32; 4] = [0.5, 0.9, 0.5, 1.0];
+
+// Window that runs a program one instruction at a time and explains each one
+#[derive(Default)]
+pub struct LearnView {
+    /// Learn mode state per instance id
+    states: HashMap<usize, LearnState>,
+}
+
+struct LearnState {
+    /// Step on its own every `interval` seconds
+    auto: bool,
+    interval: f32,
+    last_step: Instant,
+    last: Option<LearnedStep>,
+}
+
+impl Default for LearnState {
+    fn default() -> Self {
+        LearnState {
+            auto: false,
+            interval: 2.0,
+            last_step: Instant::now(),
+            last: None,
+        }
+    }
+}
+
+// Instruction that was just executed with what it did
+struct LearnedStep {
+    address: u16,
+    opcode: u16,
+    changes: Vec<String>,
+    event: StepEvent,
+}
+
+// Machine state compared before and after a step
+struct Snapshot {
+    pc: u16,
+    index: u16,
+    registers: [u8; 16],
+    delay: u8,
+    sound: u8,
+    stack: Vec<u16>,
+    memory: Vec<u8>,
+    screen: Screen,
+}
+
+impl Snapshot {
+    fn take(emulator: &Emulator) -> Self {
+        Snapshot {
+            pc: emulator.pc(),
+            index: emulator.index(),
+            registers: *emulator.registers(),
+            delay: emulator.delay_timer(),
+            sound: emulator.sound_timer(),
+            stack: emulator.stack().to_vec(),
+            memory: emulator.memory().to_vec(),
+            screen: emulator.screen().clone(),
+        }
+    }
+
+    // What changed from `self` to `after`, in words
+    fn changes(&self, after: &Snapshot) -> Vec<String> {
+        let mut changes = Vec::new();
+        for (i, (&old, &new)) in self.registers.iter().zip(&after.registers).enumerate() {
+            if old != new {
+                changes.push(format!(
+                    "V{:X}: {} -> {}",
+                    i,
+                    byte_text(old),
+                    byte_text(new)
+                ));
+            }
+        }
+        if self.index != after.index {
+            changes.push(format!("I: 0x{:03X} -> 0x{:03X}", self.index, after.index));
+        }
+        if self.delay != after.delay {
+            changes.push(format!("Delay timer: {} -> {}", self.delay, after.delay));
+        }
+        if self.sound != after.sound {
+            changes.push(format!("Sound timer: {} -> {}", self.sound, after.sound));
+        }
+        if after.stack.len() > self.stack.len() {
+            if let Some(pushed) = after.stack.last() {
+                changes.push(format!("Stack: 0x{:03X} pushed", pushed));
+            }
+        } else if after.stack.len() < self.stack.len() {
+            if let Some(popped) = self.stack.last() {
+                changes.push(format!("Stack: 0x{:03X} popped", popped));
+            }
+        }
+
+        let written: Vec<_> = self
+            .memory
+            .iter()
+            .zip(&after.memory)
+            .enumerate()
+            .filter(|(_, (old, new))| old != new)
+            .collect();
+        for &(address, (&old, &new)) in written.iter().take(MAX_MEMORY_CHANGES) {
+            changes.push(format!(
+                "Memory 0x{:03X}: {} -> {}",
+                address,
+                byte_text(old),
+                byte_text(new)
+            ));
+        }
+        if written.len() > MAX_MEMORY_CHANGES {
+            changes.push(format!(
+                "... and {} more bytes",
+                written.len() - MAX_MEMORY_CHANGES
+            ));
+        }
+
+        let (mut on, mut off) = (0, 0);
+        for y in 0..SCREEN_SIZE.1 {
+            for x in 0..SCREEN_SIZE.0 {
+                match (self.screen.get_pixel(x, y), after.screen.get_pixel(x, y)) {
+                    (false, true) => on += 1,
+                    (true, false) => off += 1,
+                    _ => {}
+                }
+            }
+        }
+        if on + off > 0 {
+            changes.push(format!(
+                "Screen: {} pixels turned on, {} turned off",
+                on, off
+            ));
+        }
+
+        let next = self.pc.wrapping_add(2);
+        let flow = match after.pc {
+            pc if pc == self.pc => "stayed, the instruction runs again",
+            pc if pc == next => "moved on to the next instruction",
+            pc if pc == next.wrapping_add(2) => "skipped the next instruction",
+            _ => "jumped",
+        };
+        changes.push(format!(
+            "PC: 0x{:03X} -> 0x{:03X}, {}",
+            self.pc, after.pc, flow
+        ));
+        changes
+    }
+}
+
+fn byte_text(value: u8) -> String {
+    format!("0x{:02X} ({})", value, value)
+}
+
+fn opcode_at(memory: &[u8], address: u16) -> u16 {
+    let i = address as usize;
+    match memory.get(i..i + 2) {
+        Some(bytes) => u16::from_be_bytes([bytes[0], bytes[1]]),
+        None => 0,
+    }
+}
+
+impl LearnState {
+    // Executes one instruction and remembers what it changed
+    fn step(&mut self, emulator: &mut Emulator) {
+        let before = Snapshot::take(emulator);
+        let opcode = opcode_at(&before.memory, before.pc);
+        emulator.resume();
+        let event = emulator.step();
+        let after = Snapshot::take(emulator);
+        self.last = Some(LearnedStep {
+            address: before.pc,
+            opcode,
+            changes: before.changes(&after),
+            event,
+        });
+        self.last_step = Instant::now();
+    }
+}
+
+// Address, opcode and mnemonic on a line, then the explanation and quirk notes
+fn draw_instruction(ui: &Ui, address: u16, opcode: u16, emulator: &Emulator) {
+    let instruction = Instruction::decode(opcode);
+    ui.text(format!("{:03X}  {:04X}  {}", address, opcode, instruction));
+    let wrap = ui.push_text_wrap_pos(0.0);
+    ui.text(instruction.explain());
+    for note in instruction.quirk_notes(emulator.quirks()) {
+        ui.text_colored(NOTE_COLOR, note);
+    }
+    wrap.pop(ui);
+}
+
+impl DebugView for LearnView {
+    fn name(&self) -> &'static str {
+        "Learn"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Learn");
+        let window = imgui::Window::new(&title).opened(open);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .size([420.0, 420.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(500.0, 200.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                if !ctx.instance.paused {
+                    ui.text_wrapped(im_str!(
+                        "The program is running. Pause it to go through it one instruction at a time."
+                    ));
+                    if ui.button(im_str!("Pause and explain"), [0.0, 0.0]) {
+                        ctx.instance.set_paused(true);
+                    }
+                    return;
+                }
+
+                let mut step = ui.button(im_str!("Step"), [0.0, 0.0]);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Execute the next instruction and show what it changed");
+                }
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("Auto"), &mut state.auto);
+                if state.auto {
+                    ui.same_line(0.0);
+                    ui.set_next_item_width(120.0);
+                    Slider::new(im_str!("seconds"), 0.2..=5.0)
+                        .display_format(im_str!("%.1f"))
+                        .build(ui, &mut state.interval);
+                    let interval = Duration::from_secs_f32(state.interval);
+                    step |= state.last_step.elapsed() >= interval;
+                }
+                ui.same_line(0.0);
+                if ui.button(im_str!("Continue"), [0.0, 0.0]) {
+                    state.auto = false;
+                    ctx.instance.set_paused(false);
+                }
+
+                let mut emulator = ctx.instance.emulator.lock();
+                if step {
+                    state.step(&mut emulator);
+                }
+                ui.separator();
+
+                ui.text_disabled("Next instruction");
+                let pc = emulator.pc();
+                draw_instruction(ui, pc, opcode_at(emulator.memory(), pc), &emulator);
+
+                if let Some(last) = &state.last {
+                    ui.separator();
+                    ui.text_disabled("Last step");
+                    draw_instruction(ui, last.address, last.opcode, &emulator);
+                    match last.event {
+                        StepEvent::WaitingForKey => ui.text_colored(
+                            NOTE_COLOR,
+                            "Waiting for a key: hold one down and step again",
+                        ),
+                        StepEvent::WaitingForVBlank => ui.text_colored(
+                            NOTE_COLOR,
+                            "Waiting for the screen to refresh (vblank quirk), step again",
+                        ),
+                        StepEvent::Halted(reason) => {
+                            ui.text_colored(NOTE_COLOR, format!("Halted: {:?}", reason))
+                        }
+                        _ => {}
+                    }
+                    for change in &last.changes {
+                        ui.text_colored(CHANGE_COLOR, change);
+                    }
+                }
+            });
+    }
+}
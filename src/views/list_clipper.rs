@@ -0,0 +1,31 @@
+use imgui::sys;
+
+/// Thin wrapper over ImGuiListClipper, imgui-rs doesn't expose one yet.
+/// Yields the ranges of items that are actually visible, the space of the rest
+/// is skipped over.
+pub struct ListClipper {
+    raw: sys::ImGuiListClipper,
+}
+
+impl ListClipper {
+    pub fn begin(items_count: usize, items_height: f32) -> ListClipper {
+        let mut raw = sys::ImGuiListClipper {
+            DisplayStart: 0,
+            DisplayEnd: 0,
+            ItemsCount: 0,
+            StepNo: 0,
+            ItemsHeight: 0.0,
+            StartPosY: 0.0,
+        };
+        unsafe { sys::ImGuiListClipper_Begin(&mut raw, items_count as i32, items_height) };
+        ListClipper { raw }
+    }
+
+    pub fn step(&mut self) -> Option<std::ops::Range<usize>> {
+        if unsafe { sys::ImGuiListClipper_Step(&mut self.raw) } {
+            Some(self.raw.DisplayStart as usize..self.raw.DisplayEnd as usize)
+        } else {
+            None
+        }
+    }
+}
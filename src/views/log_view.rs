@@ -0,0 +1,48 @@
+use super::{DebugView, ViewContext};
+use crate::i18n;
+use crate::logger;
+use imgui::*;
+
+// Log window with recent messages
+pub struct LogView;
+
+impl DebugView for LogView {
+    fn name(&self) -> &'static str {
+        "Log"
+    }
+
+    fn per_instance(&self) -> bool {
+        false
+    }
+
+    fn draw(&mut self, ui: &Ui, _ctx: &mut ViewContext, open: &mut bool) {
+        let logger = match logger::get() {
+            Some(logger) => logger,
+            None => return,
+        };
+        let title = i18n::window_title("Log", "");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([600.0, 150.0], Condition::FirstUseEver)
+            .position([500.0, 740.0], Condition::FirstUseEver)
+            .build(ui, || {
+                if ui.small_button(im_str!("Clear")) {
+                    logger.clear();
+                }
+                ChildWindow::new(im_str!("lines")).build(ui, || {
+                    logger.for_each_line(|level, line| {
+                        let color = match level {
+                            log::Level::Error => [1.0, 0.3, 0.3, 1.0],
+                            log::Level::Warn => [1.0, 0.8, 0.3, 1.0],
+                            _ => [0.8, 0.8, 0.8, 1.0],
+                        };
+                        ui.text_colored(color, line);
+                    });
+                    // Keep following new lines unless scrolled up
+                    if ui.scroll_y() >= ui.scroll_max_y() {
+                        ui.set_scroll_here_y_with_ratio(1.0);
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,398 @@
+use super::list_clipper::ListClipper;
+use super::patch::{PatchAction, PatchInput};
+use super::radix::Radix;
+use super::regions::{RegionKind, Regions};
+use super::{DebugView, ViewContext};
+use crate::instance::Instance;
+use crate::logger;
+use chip8_core::LoadError;
+use imgui::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+const BYTES_PER_ROW: usize = 16;
+
+// Window with a hex listing of memory, dumps can be saved and loaded back
+#[derive(Default)]
+pub struct MemoryView {
+    /// Input state per instance id
+    states: HashMap<usize, MemoryState>,
+}
+
+struct MemoryState {
+    export_from: ImString,
+    export_to: ImString,
+    import_at: ImString,
+    /// Row to bring into view on the next frame
+    scroll_to: Option<usize>,
+    search: ImString,
+    search_text: bool, // Search for ASCII text instead of hex bytes
+    /// Address and length of the match shown
+    found: Option<(usize, usize)>,
+    /// "3 of 5" or why nothing was found
+    search_status: String,
+    patch: Option<PatchInput>,
+    radix: Radix,
+}
+
+impl Default for MemoryState {
+    fn default() -> Self {
+        MemoryState {
+            export_from: ImString::new("000"),
+            export_to: ImString::new("FFF"),
+            import_at: ImString::new("200"),
+            scroll_to: None,
+            search: ImString::with_capacity(64),
+            search_text: false,
+            found: None,
+            search_status: String::new(),
+            patch: None,
+            radix: Radix::Hex,
+        }
+    }
+}
+
+fn parse_address(text: &ImString) -> Option<usize> {
+    usize::from_str_radix(text.to_str(), 16).ok()
+}
+
+/// Bytes to look for, `None` matches any byte. Hex patterns are pairs of digits,
+/// "??" is a wildcard: "A2 ?? 60" or "A2??60".
+fn parse_pattern(text: &str, ascii: bool) -> Result<Vec<Option<u8>>, String> {
+    if ascii {
+        return Ok(text.bytes().map(Some).collect());
+    }
+    let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err("Hex bytes need two digits each".to_string());
+    }
+    digits
+        .chunks(2)
+        .map(|pair| match pair {
+            ['?', '?'] => Ok(None),
+            _ => {
+                let pair: String = pair.iter().collect();
+                u8::from_str_radix(&pair, 16)
+                    .map(Some)
+                    .map_err(|_| format!("'{}' isn't a hex byte", pair))
+            }
+        })
+        .collect()
+}
+
+fn matches_at(memory: &[u8], address: usize, pattern: &[Option<u8>]) -> bool {
+    memory.len() - address >= pattern.len()
+        && pattern
+            .iter()
+            .zip(&memory[address..])
+            .all(|(expected, &byte)| expected.is_none_or(|expected| expected == byte))
+}
+
+// First match after `from` going forward or backward, wrapping around the ends of memory
+fn find(memory: &[u8], pattern: &[Option<u8>], from: usize, forward: bool) -> Option<usize> {
+    let len = memory.len();
+    (1..=len)
+        .map(|offset| {
+            if forward {
+                (from + offset) % len
+            } else {
+                (from + len - offset) % len
+            }
+        })
+        .find(|&address| matches_at(memory, address, pattern))
+}
+
+fn hex_input(ui: &Ui, label: &ImStr, value: &mut ImString) {
+    ui.set_next_item_width(50.0);
+    ui.input_text(label, value)
+        .chars_hexadecimal(true)
+        .chars_uppercase(true)
+        .build();
+}
+
+// File next to the ROM for a dump of `from..=to`
+fn dump_path(instance: &Instance, from: usize, to: usize) -> String {
+    let name = format!("{:03X}-{:03X}.bin", from, to);
+    let path = match &instance.rom_file {
+        Some(rom_file) => {
+            let stem = rom_file.file_stem().unwrap_or_default().to_string_lossy();
+            rom_file.with_file_name(format!("{}-{}", stem, name))
+        }
+        None => PathBuf::from(name),
+    };
+    path.display().to_string()
+}
+
+impl MemoryState {
+    // Saves `from..=to` to a file picked by the user, blocks until the dialog is closed
+    fn export(&self, instance: &Instance) {
+        let memory = instance.emulator.lock().memory().to_vec();
+        let range = match (
+            parse_address(&self.export_from),
+            parse_address(&self.export_to),
+        ) {
+            (Some(from), Some(to)) if from <= to && to < memory.len() => from..=to,
+            _ => {
+                log::warn!(
+                    "Range '{}-{}' is outside of memory",
+                    self.export_from.to_str(),
+                    self.export_to.to_str()
+                );
+                return;
+            }
+        };
+        let default_path = dump_path(instance, *range.start(), *range.end());
+        let path = match tinyfiledialogs::save_file_dialog_with_filter(
+            "Export memory",
+            &default_path,
+            &["*.bin"],
+            "Memory dumps",
+        ) {
+            Some(path) => path,
+            None => return,
+        };
+        match fs::write(&path, &memory[range]) {
+            Ok(()) => log::info!(target: logger::NOTIFY, "Exported memory to '{}'", path),
+            Err(e) => log::error!("Can't export memory to '{0}'. Error: {1}", path, e),
+        }
+    }
+
+    // Loads a file picked by the user at the import address, blocks until the dialog is closed
+    fn import(&mut self, instance: &Instance) {
+        let address = match parse_address(&self.import_at) {
+            Some(address) => address,
+            None => {
+                log::warn!("Address '{}' isn't valid", self.import_at.to_str());
+                return;
+            }
+        };
+        let path = match tinyfiledialogs::open_file_dialog(
+            "Import memory",
+            "",
+            Some((&["*.bin"], "Memory dumps")),
+        ) {
+            Some(path) => path,
+            None => return,
+        };
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("Can't read '{0}'. Error: {1}", path, e);
+                return;
+            }
+        };
+        match instance.emulator.lock().load_memory_bytes(address, &data) {
+            Ok(()) => {
+                log::info!(
+                    target: logger::NOTIFY,
+                    "Imported {} bytes at {:03X}",
+                    data.len(),
+                    address
+                );
+                self.scroll_to = Some(address / BYTES_PER_ROW);
+            }
+            Err(LoadError::TooLarge { size, max }) => log::error!(
+                "Can't import '{0}', {1} bytes don't fit at {2:03X} (max {3})",
+                path,
+                size,
+                address,
+                max
+            ),
+            Err(e) => log::error!("Can't import '{0}'. Error: {1}", path, e),
+        }
+    }
+
+    // Moves to the next or previous match of the search box
+    fn search(&mut self, memory: &[u8], forward: bool) {
+        let pattern = match parse_pattern(self.search.to_str(), self.search_text) {
+            Ok(pattern) if !pattern.is_empty() => pattern,
+            Ok(_) => return,
+            Err(e) => {
+                self.search_status = e;
+                self.found = None;
+                return;
+            }
+        };
+        // Searching from the end makes the first forward search start at address 0
+        let from = match self.found {
+            Some((address, _)) => address,
+            None if forward => memory.len() - 1,
+            None => 0,
+        };
+        match find(memory, &pattern, from, forward) {
+            Some(address) => {
+                let matches: Vec<usize> = (0..memory.len())
+                    .filter(|&a| matches_at(memory, a, &pattern))
+                    .collect();
+                let number = matches.iter().position(|&a| a == address).unwrap_or(0) + 1;
+                self.search_status = format!("{:03X}, {} of {}", address, number, matches.len());
+                self.found = Some((address, pattern.len()));
+                self.scroll_to = Some(address / BYTES_PER_ROW);
+            }
+            None => {
+                self.search_status = "Not found".to_string();
+                self.found = None;
+            }
+        }
+    }
+
+    fn draw_search(&mut self, ui: &Ui, instance: &Instance) {
+        ui.set_next_item_width(150.0);
+        let entered = ui
+            .input_text(im_str!("##search"), &mut self.search)
+            .enter_returns_true(true)
+            .build();
+        if ui.is_item_hovered() {
+            ui.tooltip_text(if self.search_text {
+                "ASCII text"
+            } else {
+                "Hex bytes, ?? matches any byte: A2 ?? 60"
+            });
+        }
+        ui.same_line(0.0);
+        if ui.checkbox(im_str!("text"), &mut self.search_text) {
+            self.found = None;
+        }
+        ui.same_line(0.0);
+        let previous = ui.arrow_button(im_str!("previous"), Direction::Up);
+        ui.same_line(0.0);
+        let next = ui.arrow_button(im_str!("next"), Direction::Down);
+        if entered || previous || next {
+            let memory = instance.emulator.lock().memory().to_vec();
+            self.search(&memory, !previous);
+        }
+        ui.same_line(0.0);
+        ui.text_disabled(&self.search_status);
+    }
+
+    fn draw_toolbar(&mut self, ui: &Ui, instance: &Instance) {
+        hex_input(ui, im_str!("##export from"), &mut self.export_from);
+        ui.same_line(0.0);
+        ui.text("-");
+        ui.same_line(0.0);
+        hex_input(ui, im_str!("##export to"), &mut self.export_to);
+        ui.same_line(0.0);
+        if ui.button(im_str!("Export..."), [0.0, 0.0]) {
+            self.export(instance);
+        }
+        ui.same_line(0.0);
+        if ui.button(im_str!("All"), [0.0, 0.0]) {
+            let len = instance.emulator.lock().memory().len();
+            self.export_from = ImString::new("000");
+            self.export_to = ImString::new(format!("{:03X}", len - 1));
+        }
+
+        ui.text("at");
+        ui.same_line(0.0);
+        hex_input(ui, im_str!("##import at"), &mut self.import_at);
+        ui.same_line(0.0);
+        if ui.button(im_str!("Import..."), [0.0, 0.0]) {
+            self.import(instance);
+        }
+        self.draw_search(ui, instance);
+
+        let action = match &mut self.patch {
+            Some(patch) => patch.draw(ui),
+            None => PatchAction::None,
+        };
+        match action {
+            PatchAction::Apply => {
+                let patch = self.patch.as_ref().unwrap();
+                if patch.apply(&mut instance.emulator.lock()) {
+                    self.patch = None;
+                }
+            }
+            PatchAction::Cancel => self.patch = None,
+            PatchAction::None => {}
+        }
+    }
+}
+
+impl DebugView for MemoryView {
+    fn name(&self) -> &'static str {
+        "Memory"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Memory");
+        let window = imgui::Window::new(&title).opened(open);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .size([470.0, 400.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(420.0, 520.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                if let Some(address) = ctx.instance.show_memory.take() {
+                    state.scroll_to = Some(address as usize / BYTES_PER_ROW);
+                    state.found = Some((address as usize, 1));
+                }
+                state.draw_toolbar(ui, ctx.instance);
+                ui.separator();
+
+                let emulator = ctx.instance.emulator.lock();
+                let memory = emulator.memory();
+                let regions = Regions::new(&emulator);
+                for kind in RegionKind::ALL.iter() {
+                    ui.text_colored(kind.color(), kind.name());
+                    ui.same_line(0.0);
+                }
+                ui.text("  ");
+                ui.same_line(0.0);
+                state.radix.draw_toggle(ui);
+                let radix = state.radix;
+                let found = state
+                    .found
+                    .map_or(0..0, |(address, len)| address..address + len);
+                let rows = memory.len().div_ceil(BYTES_PER_ROW);
+                ChildWindow::new(im_str!("listing")).build(ui, || {
+                    let line_height = ui.text_line_height_with_spacing();
+                    if let Some(row) = state.scroll_to.take() {
+                        ui.set_scroll_y(row as f32 * line_height);
+                    }
+                    let mut clipper = ListClipper::begin(rows, line_height);
+                    while let Some(visible) = clipper.step() {
+                        for row in visible {
+                            let start = row * BYTES_PER_ROW;
+                            ui.text(format!("{:03X}:", start));
+                            for (i, byte) in memory[start..start + BYTES_PER_ROW].iter().enumerate()
+                            {
+                                let address = start + i;
+                                ui.same_line(0.0);
+                                let text = radix.format_cell(*byte);
+                                match regions.at(address) {
+                                    Some(kind) => ui.text_colored(kind.color(), text),
+                                    None => ui.text(text),
+                                }
+                                if found.contains(&address) {
+                                    ui.get_window_draw_list()
+                                        .add_rect(
+                                            ui.item_rect_min(),
+                                            ui.item_rect_max(),
+                                            [1.0, 1.0, 1.0, 0.25],
+                                        )
+                                        .filled(true)
+                                        .build();
+                                }
+                                if ui.is_item_clicked(MouseButton::Right) {
+                                    state.patch = Some(PatchInput::new(address as u16, memory));
+                                }
+                                if ui.is_item_hovered() {
+                                    let names: Vec<_> =
+                                        regions.all_at(address).map(RegionKind::name).collect();
+                                    ui.tooltip_text(format!(
+                                        "{:03X}: {:02X} = {} = {:08b} {}\nRight-click to patch",
+                                        address,
+                                        byte,
+                                        byte,
+                                        byte,
+                                        names.join(", ")
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                });
+            });
+    }
+}
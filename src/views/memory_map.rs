@@ -0,0 +1,219 @@
+use super::{DebugView, ViewContext};
+use chip8_core::MemoryAccess;
+use imgui::*;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+/// Cells drawn, bigger memories put several addresses in a cell
+const CELLS: usize = 64 * 64;
+const CELLS_PER_ROW: usize = 64;
+const CELL_SIZE: f32 = 5.0;
+
+/// Heat left after a frame, activity fades out in about a second
+const DECAY: f32 = 0.95;
+
+// Window showing memory as a bitmap lit up by recent reads, writes and executes
+#[derive(Default)]
+pub struct MemoryMapView {
+    /// Activity and the range to copy per instance id
+    states: HashMap<usize, MapState>,
+}
+
+struct MapState {
+    heat: Heat,
+    dump_from: ImString,
+    dump_to: ImString,
+}
+
+impl Default for MapState {
+    fn default() -> Self {
+        MapState {
+            heat: Heat::default(),
+            dump_from: ImString::with_capacity(8),
+            dump_to: ImString::with_capacity(8),
+        }
+    }
+}
+
+/// Classic hex dump of `memory[range]`, 16 bytes a line prefixed with the address
+fn hex_dump(memory: &[u8], range: RangeInclusive<usize>) -> String {
+    let mut text = String::new();
+    let start = *range.start();
+    for (i, chunk) in memory[range].chunks(16).enumerate() {
+        text.push_str(&format!("{:03X}:", start + i * 16));
+        for byte in chunk {
+            text.push_str(&format!(" {:02X}", byte));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+impl MapState {
+    // Range inputs and a button copying that part of memory
+    fn draw_dump_controls(&mut self, ui: &Ui, memory: &[u8]) {
+        for (label, value) in [
+            (im_str!("from"), &mut self.dump_from),
+            (im_str!("to"), &mut self.dump_to),
+        ] {
+            ui.set_next_item_width(50.0);
+            ui.input_text(label, value).chars_hexadecimal(true).build();
+            ui.same_line(0.0);
+        }
+        if ui.button(im_str!("Copy hex dump"), [0.0, 0.0]) {
+            let parse = |text: &ImString| usize::from_str_radix(text.to_str(), 16).ok();
+            match (parse(&self.dump_from), parse(&self.dump_to)) {
+                (Some(from), Some(to)) if from <= to && to < memory.len() => {
+                    ui.set_clipboard_text(&ImString::new(hex_dump(memory, from..=to)));
+                }
+                _ => log::warn!(
+                    "Range '{}-{}' is outside of memory",
+                    self.dump_from.to_str(),
+                    self.dump_to.to_str()
+                ),
+            }
+        }
+    }
+}
+
+// Recent activity per cell, 1 right after an access and fading to 0
+#[derive(Default)]
+struct Heat {
+    last: Option<MemoryAccess>, // Counters at the previous frame
+    reads: Vec<f32>,
+    writes: Vec<f32>,
+    executes: Vec<f32>,
+    touched: Vec<bool>, // Accessed at any time since tracking started
+}
+
+impl Heat {
+    fn update(&mut self, access: &MemoryAccess) {
+        let len = access.reads.len();
+        let per_cell = (len / CELLS).max(1);
+        let cells = len / per_cell;
+        let stale = match &self.last {
+            Some(last) => last.reads.len() != len,
+            None => true,
+        };
+        if stale {
+            *self = Heat {
+                last: Some(access.clone()),
+                reads: vec![0.0; cells],
+                writes: vec![0.0; cells],
+                executes: vec![0.0; cells],
+                touched: vec![false; cells],
+            };
+            return;
+        }
+
+        let last = self.last.as_mut().unwrap();
+        let channels = [
+            (&access.reads, &mut last.reads, &mut self.reads),
+            (&access.writes, &mut last.writes, &mut self.writes),
+            (&access.executes, &mut last.executes, &mut self.executes),
+        ];
+        for (counts, last_counts, heat) in channels {
+            for value in heat.iter_mut() {
+                *value *= DECAY;
+            }
+            for (address, (&count, last_count)) in
+                counts.iter().zip(last_counts.iter_mut()).enumerate()
+            {
+                if count != *last_count {
+                    heat[address / per_cell] = 1.0;
+                    self.touched[address / per_cell] = true;
+                    *last_count = count;
+                }
+            }
+        }
+    }
+
+    // Writes are red, reads green and executes blue, cells used before stay a little lit
+    fn color(&self, cell: usize) -> [f32; 4] {
+        let base = if self.touched[cell] { 0.2 } else { 0.05 };
+        [
+            base + self.writes[cell] * (1.0 - base),
+            base + self.reads[cell] * (1.0 - base),
+            base + self.executes[cell] * (1.0 - base),
+            1.0,
+        ]
+    }
+}
+
+impl DebugView for MemoryMapView {
+    fn name(&self) -> &'static str {
+        "Memory map"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Memory map");
+        let window = imgui::Window::new(&title)
+            .opened(open)
+            .always_auto_resize(true);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .position(ctx.instance.position(420.0, 460.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let mut emulator = ctx.instance.emulator.lock();
+                state.draw_dump_controls(ui, emulator.memory());
+                let heat = &mut state.heat;
+                let mut enabled = emulator.memory_access().is_some();
+                if ui.checkbox(im_str!("Track memory accesses"), &mut enabled) {
+                    emulator.set_access_tracking(enabled);
+                    *heat = Heat::default();
+                }
+                let access = match emulator.memory_access() {
+                    Some(access) => access,
+                    None => {
+                        ui.text_disabled("Tracking slows emulation down a little");
+                        return;
+                    }
+                };
+                heat.update(access);
+                let len = access.reads.len();
+                drop(emulator);
+
+                ui.text_colored([1.0, 0.3, 0.3, 1.0], "write");
+                ui.same_line(0.0);
+                ui.text_colored([0.3, 1.0, 0.3, 1.0], "read");
+                ui.same_line(0.0);
+                ui.text_colored([0.4, 0.4, 1.0, 1.0], "execute");
+
+                let cells = heat.reads.len();
+                let per_cell = len / cells;
+                let rows = cells.div_ceil(CELLS_PER_ROW);
+                let size = [CELLS_PER_ROW as f32 * CELL_SIZE, rows as f32 * CELL_SIZE];
+                let p0 = ui.cursor_screen_pos();
+                let draw_list = ui.get_window_draw_list();
+                for cell in 0..cells {
+                    let x = p0[0] + (cell % CELLS_PER_ROW) as f32 * CELL_SIZE;
+                    let y = p0[1] + (cell / CELLS_PER_ROW) as f32 * CELL_SIZE;
+                    draw_list
+                        .add_rect([x, y], [x + CELL_SIZE, y + CELL_SIZE], heat.color(cell))
+                        .filled(true)
+                        .build();
+                }
+                ui.invisible_button(im_str!("map"), size);
+                if ui.is_item_hovered() {
+                    let mouse = ui.io().mouse_pos;
+                    let column = ((mouse[0] - p0[0]) / CELL_SIZE) as usize;
+                    let row = ((mouse[1] - p0[1]) / CELL_SIZE) as usize;
+                    let cell = (row * CELLS_PER_ROW + column).min(cells - 1);
+                    let start = cell * per_cell;
+                    ui.tooltip_text(if per_cell == 1 {
+                        format!("{:03X}", start)
+                    } else {
+                        format!("{:04X}-{:04X}", start, start + per_cell - 1)
+                    });
+                    // Right click picks the hovered row for the hex dump
+                    if ui.is_mouse_clicked(MouseButton::Right) {
+                        let row_start = row * CELLS_PER_ROW * per_cell;
+                        let row_end = (row_start + CELLS_PER_ROW * per_cell).min(len) - 1;
+                        state.dump_from = ImString::new(format!("{:03X}", row_start));
+                        state.dump_to = ImString::new(format!("{:03X}", row_end));
+                    }
+                }
+            });
+    }
+}
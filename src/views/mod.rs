@@ -0,0 +1,165 @@
+//! Debugger and tool windows. Each window is a `DebugView` registered in `all()`,
+//! the app draws them and lists them in the View menu.
+
+mod bookmarks;
+mod call_graph;
+mod code;
+mod compare;
+mod compatibility;
+mod cpu;
+mod flow_graph;
+mod help;
+mod history;
+mod learn;
+mod list_clipper;
+mod log_view;
+mod memory;
+mod memory_map;
+mod options;
+mod patch;
+mod profile;
+mod radix;
+mod regions;
+mod rom_browser;
+mod screen;
+mod screen_diff;
+mod settings;
+mod sprite_editor;
+mod sprite_gallery;
+mod stats;
+mod test_roms;
+mod text_cache;
+mod triggers;
+
+use crate::config::{Config, Hotkey};
+use crate::instance::Instance;
+use crate::layouts::LayoutPreset;
+use crate::loader::RomList;
+use crate::theme::Theme;
+use chip8_core::Quirks;
+use imgui::{Ui, WindowFocusedFlags};
+use std::path::PathBuf;
+
+pub trait DebugView {
+    /// Window name, also shown in the View menu
+    fn name(&self) -> &'static str;
+
+    /// Per-instance views are drawn for every emulator instance,
+    /// others once for the instance that has keyboard focus
+    fn per_instance(&self) -> bool {
+        true
+    }
+
+    /// Shown on startup, the rest is opened from the View menu
+    fn open_by_default(&self) -> bool {
+        false
+    }
+
+    /// Builds the window, `open` is cleared when the user closes it
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool);
+}
+
+/// State available to views while drawing
+pub struct ViewContext<'a> {
+    pub instance: &'a mut Instance,
+    pub config: &'a Config,
+    /// `instance` receives keyboard input
+    pub has_keyboard: bool,
+    /// There are other instances, so this one can be closed
+    pub can_close: bool,
+    /// ROMs in the ROM directory, still coming in while it is scanned
+    pub roms: &'a RomList,
+    /// Requests to the app, handled after all views are drawn
+    pub actions: Vec<Action>,
+}
+
+pub enum Action {
+    /// Load ROM into the focused instance
+    LoadRom(PathBuf),
+    /// Pick a ROM file with the system file dialog and load it
+    OpenRomDialog,
+    NewInstance,
+    /// Pause or unpause the view's instance
+    TogglePause,
+    SetSpeed(f32),
+    SetQuirks(Quirks),
+    /// Same as pressing the hotkey, acts on the focused instance
+    Hotkey(Hotkey),
+    /// Switch UI colors and screen palettes
+    SetTheme(Theme),
+    /// Open the windows of a preset and move them where it has them
+    ApplyLayout(LayoutPreset),
+    /// Ask for a name and keep the current layout as a preset
+    SaveLayout,
+    /// Forget the saved preset at this index in `Config::layouts`
+    DeleteLayout(usize),
+    /// Use edited settings from now on
+    ApplySettings(Box<Config>),
+    /// Apply settings and write them to the config file
+    SaveSettings(Box<Config>),
+    /// Exit the app, same as closing its window
+    Quit,
+    /// Restart the ROM of the view's instance
+    Reset,
+    /// Read the ROM of the view's instance from disk again
+    Reload,
+    /// Reload the ROM of the view's instance whenever its file is saved
+    SetWatch(bool),
+    /// Continue at the same label after an Octo source is reloaded
+    SetResumeAtLabel(bool),
+    /// Give keyboard focus to the instance of the view
+    Focus,
+    /// Close the instance of the view
+    Close,
+    /// Save a zip for reproducing the state of the view's instance
+    ExportBugReport,
+    /// Save the ROM region of the view's instance memory as a new ROM
+    ExportPatchedRom,
+    /// Open the Code window of the view's instance at an address
+    ShowCode(u16),
+    /// Open the Memory window of the view's instance at an address
+    ShowMemory(u16),
+    /// Move PC of the view's instance past the next instruction without running it
+    SkipInstruction,
+    /// Continue at an address in the view's instance
+    SetPc(u16),
+}
+
+impl ViewContext<'_> {
+    /// Asks for keyboard focus if the current window or one of its children is focused
+    pub fn track_focus(&mut self, ui: &Ui) {
+        if ui.is_window_focused_with_flags(WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS) {
+            self.actions.push(Action::Focus);
+        }
+    }
+}
+
+/// All views in the order they are drawn and listed in the View menu
+pub fn all() -> Vec<Box<dyn DebugView>> {
+    vec![
+        Box::new(rom_browser::RomBrowser::default()),
+        Box::new(options::OptionsView),
+        Box::new(cpu::CpuView::default()),
+        Box::new(code::CodeView::default()),
+        Box::new(flow_graph::FlowGraphView::default()),
+        Box::new(call_graph::CallGraphView::default()),
+        Box::new(profile::ProfileView::default()),
+        Box::new(history::HistoryView::default()),
+        Box::new(memory::MemoryView::default()),
+        Box::new(sprite_editor::SpriteEditorView::default()),
+        Box::new(sprite_gallery::SpriteGalleryView::default()),
+        Box::new(memory_map::MemoryMapView::default()),
+        Box::new(stats::StatsView),
+        Box::new(screen::ScreenView),
+        Box::new(settings::SettingsView::default()),
+        Box::new(help::HelpView),
+        Box::new(learn::LearnView::default()),
+        Box::new(log_view::LogView),
+        Box::new(test_roms::TestRomsView::default()),
+        Box::new(compare::CompareView::default()),
+        Box::new(screen_diff::ScreenDiffView::default()),
+        Box::new(compatibility::CompatibilityView::default()),
+        Box::new(triggers::TriggersView::default()),
+        Box::new(bookmarks::BookmarksView::default()),
+    ]
+}
@@ -0,0 +1,214 @@
+use super::{Action, DebugView, ViewContext};
+use crate::app::to_rgb01;
+use crate::config::{key_name, Hotkey};
+use crate::logger;
+use chip8_core::{Quirks, MEMORY_SIZE, XO_CHIP_MEMORY_SIZE};
+use imgui::*;
+
+type QuirkField = fn(&mut Quirks) -> &mut bool;
+
+// Quirk checkboxes with what they change
+const QUIRKS: [(&str, &str, QuirkField); 6] = [
+    (
+        "shift",
+        "8XY6/8XYE shift VX in place instead of storing shifted VY into VX",
+        |q| &mut q.shift,
+    ),
+    (
+        "load/store",
+        "FX55/FX65 leave I unchanged instead of incrementing it",
+        |q| &mut q.load_store,
+    ),
+    ("jump", "BNNN jumps to NNN + VX instead of NNN + V0", |q| {
+        &mut q.jump
+    }),
+    ("logic", "8XY1/8XY2/8XY3 reset VF to 0", |q| &mut q.logic),
+    (
+        "clip",
+        "Sprites are clipped at the screen edges instead of wrapping around",
+        |q| &mut q.clip,
+    ),
+    (
+        "vblank",
+        "DXYN waits for the next 60Hz tick, at most one sprite per frame",
+        |q| &mut q.vblank,
+    ),
+];
+
+// Window with emulation options, saved in Octo format next to the ROM
+pub struct OptionsView;
+
+impl DebugView for OptionsView {
+    fn name(&self) -> &'static str {
+        "Options"
+    }
+
+    fn open_by_default(&self) -> bool {
+        true
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let mut save_options = false;
+        let title = ctx.instance.title("Options");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([600.0, 150.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(500.0, 580.0), Condition::FirstUseEver)
+            .build(ui, || {
+                let mut emulator = ctx.instance.emulator.lock();
+                let mut tickrate = (emulator.clock_hz() / 60.0).round() as i32;
+                if ui
+                    .input_int(im_str!("Instructions per frame"), &mut tickrate)
+                    .build()
+                {
+                    emulator.set_clock_hz(tickrate.max(1) as f32 * 60.0);
+                }
+                ui.same_line(0.0);
+                let mut vip_timing = emulator.vip_timing();
+                if ui.checkbox(im_str!("VIP timing"), &mut vip_timing) {
+                    emulator.set_vip_timing(vip_timing);
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(
+                        "Every instruction takes as long as on the COSMAC VIP and draws wait for the display.\n\
+                         Instructions per frame are ignored",
+                    );
+                }
+                ui.same_line(0.0);
+                let mut halt_on_fault = emulator.halt_on_fault();
+                if ui.checkbox(im_str!("Stop on faults"), &mut halt_on_fault) {
+                    emulator.set_halt_on_fault(halt_on_fault);
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(
+                        "Illegal opcodes, stack errors and I past the end of memory stop the game.\n\
+                         Off, the game runs on like an interpreter that doesn't check",
+                    );
+                }
+
+                // Power makes the low end of the range usable on a linear slider
+                if Slider::new(im_str!("Speed"), 0.1..=100.0)
+                    .display_format(im_str!("%.1fx"))
+                    .power(3.0)
+                    .build(ui, &mut ctx.instance.speed)
+                {
+                    ctx.instance.apply_speed();
+                }
+                if let Some(speed) = ctx.instance.fast_forward {
+                    ui.same_line(0.0);
+                    ui.text_colored(
+                        to_rgb01([255, 200, 0, 255]),
+                        format!("Fast-forward {}x", speed),
+                    );
+                }
+
+                // Quirks apply from the next instruction, a running ROM keeps its state
+                let mut quirks = emulator.quirks();
+                for (i, (name, description, field)) in QUIRKS.iter().enumerate() {
+                    if i > 0 {
+                        ui.same_line(0.0);
+                    }
+                    let value = field(&mut quirks);
+                    if ui.checkbox(&ImString::new(*name), value) {
+                        if emulator.instruction_count() > 0 {
+                            log::info!(
+                                target: logger::NOTIFY,
+                                "Quirk {} {} from the next instruction",
+                                name,
+                                if *value { "on" } else { "off" }
+                            );
+                        }
+                        emulator.set_quirks(quirks);
+                    }
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(description);
+                    }
+                }
+                let mut start = ctx.instance.start_quirks;
+                let changed: Vec<&str> = QUIRKS
+                    .iter()
+                    .filter(|(_, _, field)| *field(&mut quirks) != *field(&mut start))
+                    .map(|(name, _, _)| *name)
+                    .collect();
+                if !changed.is_empty() && emulator.instruction_count() > 0 {
+                    ui.text_colored(
+                        to_rgb01([255, 200, 0, 255]),
+                        format!(
+                            "Changed while running: {}. Earlier instructions ran with the old setting",
+                            changed.join(", ")
+                        ),
+                    );
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Revert")) {
+                        emulator.set_quirks(start);
+                    }
+                    ui.same_line(0.0);
+                    if ui.small_button(im_str!("Restart with them")) {
+                        ctx.actions.push(Action::Reset);
+                    }
+                }
+
+                let mut xo_chip = emulator.memory().len() == XO_CHIP_MEMORY_SIZE;
+                if ui.checkbox(im_str!("64KB memory"), &mut xo_chip) {
+                    let size = if xo_chip {
+                        XO_CHIP_MEMORY_SIZE
+                    } else {
+                        MEMORY_SIZE
+                    };
+                    if let Err(e) = emulator.set_memory_size(size) {
+                        log::warn!("Can't change memory size. Error: {}", e);
+                    }
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("XO-CHIP memory instead of the standard 4KB, restarts the ROM");
+                }
+                drop(emulator);
+
+                if ctx.instance.rom_file.is_some() {
+                    let hotkeys = &ctx.config.hotkeys;
+                    let reset = format!("Reset ({})", key_name(hotkeys.key(Hotkey::Reset)));
+                    if ui.button(&ImString::new(reset), [0.0, 0.0]) {
+                        ctx.actions.push(Action::Reset);
+                    }
+                    ui.same_line(0.0);
+                    let reload = format!("Reload ({})", key_name(hotkeys.key(Hotkey::Reload)));
+                    if ui.button(&ImString::new(reload), [0.0, 0.0]) {
+                        ctx.actions.push(Action::Reload);
+                    }
+                    ui.same_line(0.0);
+                    save_options = ui.button(im_str!("Save options"), [0.0, 0.0]);
+                    ui.same_line(0.0);
+                    let mut range = ImString::with_capacity(16);
+                    range.push_str(&ctx.instance.options.save_memory);
+                    ui.set_next_item_width(90.0);
+                    if ui
+                        .input_text(im_str!("Save memory"), &mut range)
+                        .chars_uppercase(true)
+                        .build()
+                    {
+                        ctx.instance.options.save_memory = range.to_string();
+                    }
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(
+                            "Memory range like 3F0-3FF kept between sessions, for high scores.\n\
+                             Stored with the options when they are saved",
+                        );
+                    }
+                    ui.same_line(0.0);
+                }
+                if ctx.can_close {
+                    if ui.button(im_str!("Close instance"), [0.0, 0.0]) {
+                        ctx.actions.push(Action::Close);
+                    }
+                    ui.same_line(0.0);
+                }
+                if ctx.has_keyboard {
+                    ui.text_colored(to_rgb01([0, 255, 0, 255]), im_str!("Keyboard input"));
+                }
+                ctx.track_focus(ui);
+            });
+        if save_options {
+            ctx.instance.save_options();
+        }
+    }
+}
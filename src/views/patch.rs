@@ -0,0 +1,84 @@
+use crate::logger;
+use chip8_core::{assemble, Emulator, Instruction};
+use imgui::*;
+
+/// What the user did with the patch input this frame
+#[derive(PartialEq, Eq)]
+pub enum PatchAction {
+    None,
+    Apply,
+    Cancel,
+}
+
+/// Octo statement typed in to replace the code at an address, like `jump 0x230`
+pub struct PatchInput {
+    address: u16,
+    text: ImString,
+}
+
+impl PatchInput {
+    /// Starts with the instruction at `address` in Octo syntax
+    pub fn new(address: u16, memory: &[u8]) -> Self {
+        let i = address as usize;
+        let current = memory
+            .get(i..i + 2)
+            .and_then(|bytes| {
+                Instruction::decode(u16::from_be_bytes([bytes[0], bytes[1]])).to_octo()
+            })
+            .unwrap_or_default();
+        let mut text = ImString::new(current);
+        text.reserve(64);
+        PatchInput { address, text }
+    }
+
+    pub fn draw(&mut self, ui: &Ui) -> PatchAction {
+        ui.text(format!("Patch {:03X}", self.address));
+        ui.same_line(0.0);
+        ui.set_next_item_width(160.0);
+        let entered = ui
+            .input_text(im_str!("##patch"), &mut self.text)
+            .enter_returns_true(true)
+            .build();
+        if ui.is_item_hovered() {
+            ui.tooltip_text("Octo statement: jump 0x230, vA += 3, i := hex v0...");
+        }
+        ui.same_line(0.0);
+        if ui.button(im_str!("Apply"), [0.0, 0.0]) || entered {
+            return PatchAction::Apply;
+        }
+        ui.same_line(0.0);
+        if ui.button(im_str!("Cancel"), [0.0, 0.0]) {
+            return PatchAction::Cancel;
+        }
+        PatchAction::None
+    }
+
+    /// Assembles the statement into memory, returns false if it doesn't assemble or fit
+    pub fn apply(&self, emulator: &mut Emulator) -> bool {
+        let text = self.text.to_str();
+        let bytes = match assemble(text) {
+            Ok(bytes) if !bytes.is_empty() => bytes,
+            Ok(_) => return false,
+            Err(e) => {
+                log::warn!("Can't assemble '{0}'. Error: {1}", text, e.message);
+                return false;
+            }
+        };
+        match emulator.load_memory_bytes(self.address as usize, &bytes) {
+            Ok(()) => {
+                log::info!(
+                    target: logger::NOTIFY,
+                    "Patched {:03X} with '{}' ({} bytes)",
+                    self.address,
+                    text,
+                    bytes.len()
+                );
+                true
+            }
+            Err(e) => {
+                log::error!("Can't patch {0:03X}. Error: {1}", self.address, e);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use super::{DebugView, ViewContext};
+use crate::app::to_rgb01;
+use chip8_core::Instruction;
+use imgui::*;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Seconds between updates of the per second rates
+const RATE_PERIOD: f32 = 1.0;
+
+// Window with how often each kind of instruction executes
+#[derive(Default)]
+pub struct ProfileView {
+    /// Measurements per instance id
+    rates: HashMap<usize, Rates>,
+    cumulative: bool, // Show totals instead of per second rates
+}
+
+// Per second rates computed from the difference of two profiles
+struct Rates {
+    last_profile: [u64; Instruction::KIND_COUNT],
+    last_time: Instant,
+    per_second: [f32; Instruction::KIND_COUNT],
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Rates {
+            last_profile: [0; Instruction::KIND_COUNT],
+            last_time: Instant::now(),
+            per_second: [0.0; Instruction::KIND_COUNT],
+        }
+    }
+}
+
+impl Rates {
+    fn update(&mut self, profile: &[u64; Instruction::KIND_COUNT]) {
+        let elapsed = self.last_time.elapsed().as_secs_f32();
+        if elapsed < RATE_PERIOD {
+            return;
+        }
+        for (kind, &count) in profile.iter().enumerate() {
+            // Profiling was restarted if a count went down
+            let executed = count.checked_sub(self.last_profile[kind]).unwrap_or(count);
+            self.per_second[kind] = executed as f32 / elapsed;
+        }
+        self.last_profile = *profile;
+        self.last_time = Instant::now();
+    }
+}
+
+impl DebugView for ProfileView {
+    fn name(&self) -> &'static str {
+        "Instructions"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Instructions");
+        let window = imgui::Window::new(&title).opened(open);
+        let rates = self.rates.entry(ctx.instance.id).or_default();
+        let cumulative = &mut self.cumulative;
+        window
+            .size([395.0, 400.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(800.0, 25.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let mut emulator = ctx.instance.emulator.lock();
+                let mut enabled = emulator.instruction_profile().is_some();
+                if ui.checkbox(im_str!("Count instructions"), &mut enabled) {
+                    emulator.set_profiling(enabled);
+                    *rates = Rates::default();
+                }
+                ui.same_line(0.0);
+                ui.checkbox(im_str!("Totals"), cumulative);
+
+                let profile = match emulator.instruction_profile() {
+                    Some(profile) => *profile,
+                    None => {
+                        ui.text_disabled("Counting slows emulation down a little");
+                        return;
+                    }
+                };
+                drop(emulator);
+                rates.update(&profile);
+
+                let values: Vec<f32> = if *cumulative {
+                    profile.iter().map(|&count| count as f32).collect()
+                } else {
+                    rates.per_second.to_vec()
+                };
+                ui.plot_histogram(im_str!("##kinds"), &values)
+                    .graph_size([0.0, 80.0])
+                    .build();
+
+                let unknown = Instruction::decode(0xFFFF).kind();
+                let system = Instruction::decode(0x0000).kind();
+                if profile[unknown] > 0 || profile[system] > 0 {
+                    // SCHIP and XO-CHIP extend chip8 with opcodes that decode as these
+                    ui.text_colored(
+                        to_rgb01([255, 200, 0, 255]),
+                        "0NNN or unknown opcodes ran, the ROM may need SCHIP/XO-CHIP",
+                    );
+                }
+
+                let total: f32 = values.iter().sum();
+                let mut kinds: Vec<usize> = (0..Instruction::KIND_COUNT)
+                    .filter(|&kind| profile[kind] > 0)
+                    .collect();
+                kinds.sort_by(|&a, &b| values[b].partial_cmp(&values[a]).unwrap());
+                ui.columns(3, im_str!("kinds"), false);
+                ui.text("Opcode");
+                ui.next_column();
+                ui.text(if *cumulative { "Total" } else { "Per second" });
+                ui.next_column();
+                ui.text("Share");
+                ui.next_column();
+                for kind in kinds {
+                    ui.text(Instruction::KIND_PATTERNS[kind]);
+                    ui.next_column();
+                    ui.text(format!("{:.0}", values[kind]));
+                    ui.next_column();
+                    ui.text(format!("{:.1}%", values[kind] * 100.0 / total.max(1.0)));
+                    ui.next_column();
+                }
+                ui.columns(1, im_str!("kinds"), false);
+            });
+    }
+}
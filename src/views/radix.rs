@@ -0,0 +1,48 @@
+use imgui::{im_str, Ui};
+
+/// Base debug windows show values in, picked per window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Radix {
+    #[default]
+    Hex,
+    Decimal,
+    Binary,
+}
+
+impl Radix {
+    /// "0x2A", "42" or "0b00101010"
+    pub fn format_u8(self, value: u8) -> String {
+        match self {
+            Radix::Hex => format!("{:#04X}", value),
+            Radix::Decimal => value.to_string(),
+            Radix::Binary => format!("{:#010b}", value),
+        }
+    }
+
+    /// Like `format_u8`, binary shows all 16 bits
+    pub fn format_u16(self, value: u16) -> String {
+        match self {
+            Radix::Hex => format!("{:#X}", value),
+            Radix::Decimal => value.to_string(),
+            Radix::Binary => format!("{:#018b}", value),
+        }
+    }
+
+    /// Same width for every byte, without prefix, for tables of bytes
+    pub fn format_cell(self, value: u8) -> String {
+        match self {
+            Radix::Hex => format!("{:02X}", value),
+            Radix::Decimal => format!("{:3}", value),
+            Radix::Binary => format!("{:08b}", value),
+        }
+    }
+
+    /// Hex, Dec and Bin radio buttons on one line
+    pub fn draw_toggle(&mut self, ui: &Ui) {
+        ui.radio_button(im_str!("Hex"), self, Radix::Hex);
+        ui.same_line(0.0);
+        ui.radio_button(im_str!("Dec"), self, Radix::Decimal);
+        ui.same_line(0.0);
+        ui.radio_button(im_str!("Bin"), self, Radix::Binary);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::app::to_rgb01;
+use chip8_core::{Emulator, Instruction};
+use std::ops::Range;
+
+/// Kinds of memory areas the Memory and Code windows mark, most specific first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegionKind {
+    /// Bytes the instruction at PC reads or writes at I
+    NextAccess,
+    /// Byte I points to
+    Index,
+    /// Registers saved by FX55
+    SavedRegisters,
+    Rom,
+    Font,
+}
+
+impl RegionKind {
+    pub const ALL: [RegionKind; 5] = [
+        RegionKind::NextAccess,
+        RegionKind::Index,
+        RegionKind::SavedRegisters,
+        RegionKind::Rom,
+        RegionKind::Font,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RegionKind::NextAccess => "next access",
+            RegionKind::Index => "I",
+            RegionKind::SavedRegisters => "FX55 registers",
+            RegionKind::Rom => "ROM",
+            RegionKind::Font => "font",
+        }
+    }
+
+    pub fn color(self) -> [f32; 4] {
+        to_rgb01(match self {
+            RegionKind::NextAccess => [255, 90, 90, 255],
+            RegionKind::Index => [255, 200, 0, 255],
+            RegionKind::SavedRegisters => [200, 120, 255, 255],
+            RegionKind::Rom => [120, 220, 120, 255],
+            RegionKind::Font => [100, 180, 255, 255],
+        })
+    }
+}
+
+/// Marked areas of an emulator's memory at the moment
+pub struct Regions(Vec<(RegionKind, Range<usize>)>);
+
+impl Regions {
+    pub fn new(emulator: &Emulator) -> Self {
+        let mut regions = Vec::new();
+        let memory = emulator.memory();
+        let pc = emulator.pc() as usize;
+        let index = emulator.index() as usize;
+        if pc + 1 < memory.len() {
+            let opcode = u16::from_be_bytes([memory[pc], memory[pc + 1]]);
+            let len = Instruction::decode(opcode).index_bytes();
+            if len > 0 {
+                regions.push((RegionKind::NextAccess, index..index + len));
+            }
+        }
+        regions.push((RegionKind::Index, index..index + 1));
+        for (address, len) in emulator.register_stores() {
+            let address = address as usize;
+            regions.push((RegionKind::SavedRegisters, address..address + len));
+        }
+        let (start, end) = emulator.get_code_range();
+        regions.push((RegionKind::Rom, start..end));
+        let (start, end) = emulator.get_font_range();
+        regions.push((RegionKind::Font, start..end));
+        Regions(regions)
+    }
+
+    /// Most specific region `address` is in
+    pub fn at(&self, address: usize) -> Option<RegionKind> {
+        self.all_at(address).next()
+    }
+
+    /// All regions `address` is in, most specific first
+    pub fn all_at(&self, address: usize) -> impl Iterator<Item = RegionKind> + '_ {
+        RegionKind::ALL.iter().copied().filter(move |&kind| {
+            self.0
+                .iter()
+                .any(|(k, range)| *k == kind && range.contains(&address))
+        })
+    }
+}
@@ -0,0 +1,85 @@
+use super::{Action, DebugView, ViewContext};
+use crate::i18n::{self, im, tr, trf};
+use imgui::*;
+use std::collections::HashSet;
+
+// Window with list of ROMs
+#[derive(Default)]
+pub struct RomBrowser {
+    hide_copies: bool, // Only the first of the files with the same contents is listed
+}
+
+impl DebugView for RomBrowser {
+    fn name(&self) -> &'static str {
+        "ROMs"
+    }
+
+    fn open_by_default(&self) -> bool {
+        true
+    }
+
+    fn per_instance(&self) -> bool {
+        false
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = i18n::window_title("ROMs", "");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([400.0, 600.0], Condition::FirstUseEver)
+            .position([5.0, 25.0], Condition::FirstUseEver)
+            .build(ui, || {
+                if ui.button(im_str!("New instance"), [0.0, 0.0]) {
+                    ctx.actions.push(Action::NewInstance);
+                }
+                ui.separator();
+                if let Some((checked, total)) = ctx.roms.progress() {
+                    let fraction = if total > 0 {
+                        checked as f32 / total as f32
+                    } else {
+                        0.0
+                    };
+                    let text = format!("{} {}/{}", tr("Scanning..."), checked, total);
+                    ProgressBar::new(fraction)
+                        .overlay_text(&ImString::new(text))
+                        .size([-1.0, 0.0])
+                        .build(ui);
+                }
+                let duplicates = ctx.roms.duplicate_count();
+                if duplicates > 0 {
+                    ui.text_disabled(trf("{} ROMs are copies of others", duplicates));
+                    ui.same_line(0.0);
+                    ui.checkbox(&im("Hide copies"), &mut self.hide_copies);
+                }
+
+                let rom_dir = ctx.config.rom_dir();
+                let mut listed = HashSet::new();
+                for rom in &ctx.roms.files {
+                    if !listed.insert(rom.hash) && self.hide_copies {
+                        continue;
+                    }
+                    // Files of the same name in different directories get their own IDs
+                    let filename = rom.path.file_name().unwrap().to_string_lossy();
+                    let label = ImString::new(format!("{}##{}", filename, rom.path.display()));
+                    if ui.button(&label, [0 as f32, 0 as f32]) {
+                        ctx.actions.push(Action::LoadRom(rom.path.clone()));
+                    }
+                    let copies = ctx.roms.copies(rom);
+                    if copies < 2 {
+                        continue;
+                    }
+                    ui.same_line(0.0);
+                    ui.text_disabled(trf("({} copies)", copies));
+                    if ui.is_item_hovered() {
+                        ui.tooltip(|| {
+                            let same = ctx.roms.files.iter().filter(|other| other.hash == rom.hash);
+                            for other in same {
+                                let path = other.path.strip_prefix(&rom_dir).unwrap_or(&other.path);
+                                ui.text(path.to_string_lossy());
+                            }
+                        });
+                    }
+                }
+            });
+    }
+}
@@ -0,0 +1,231 @@
+use super::{Action, DebugView, ViewContext};
+use crate::config::{key_name, Hotkey};
+use crate::i18n::{im, tr, trf};
+use crate::instance::Instance;
+use crate::theme::Theme;
+use chip8_core::{Fault, HaltReason, Instruction};
+use imgui::*;
+
+/// Smallest panel shown for a fault, screens at small scales are too small for it
+const FAULT_PANEL_SIZE: [f32; 2] = [360.0, 170.0];
+
+// Screen window showing emulator screen texture
+pub struct ScreenView;
+
+impl ScreenView {
+    // Right-click menu with the things worth changing without leaving the game
+    fn draw_context_menu(ui: &Ui, ctx: &mut ViewContext) {
+        let hotkeys = &ctx.config.hotkeys;
+        let instance = &mut *ctx.instance;
+        let has_rom = instance.rom_file.is_some();
+        if MenuItem::new(&im("Screenshot"))
+            .shortcut(&ImString::new(key_name(hotkeys.key(Hotkey::Screenshot))))
+            .enabled(has_rom)
+            .build(ui)
+        {
+            instance.screenshot();
+        }
+        let recording_label = if instance.recording.is_some() {
+            im("Stop GIF recording")
+        } else {
+            im("Start GIF recording")
+        };
+        if MenuItem::new(&recording_label).enabled(has_rom).build(ui) {
+            instance.toggle_recording();
+        }
+        ui.separator();
+        ui.menu(&im("Palette"), true, || {
+            for &theme in Theme::ALL.iter() {
+                if MenuItem::new(&im(theme.name())).build(ui) {
+                    instance.palette = theme.palette();
+                }
+            }
+            if MenuItem::new(&im("Swap colors")).build(ui) {
+                let palette = &mut instance.palette;
+                std::mem::swap(&mut palette.fg, &mut palette.bg);
+            }
+        });
+        ui.menu(&im("Scale"), true, || {
+            for &scale in &[2.0, 4.0, 6.0, 8.0, 10.0, 12.0, 16.0] {
+                let label = ImString::new(format!("{}x", scale));
+                if MenuItem::new(&label)
+                    .selected(instance.screen.ui_scale == scale)
+                    .build(ui)
+                {
+                    instance.screen.ui_scale = scale;
+                }
+            }
+        });
+        MenuItem::new(&im("Pixel grid")).build_with_ref(ui, &mut instance.screen.grid);
+    }
+
+    // Panel in place of the screen saying what went wrong, with ways to go on from there
+    fn draw_fault(ui: &Ui, ctx: &mut ViewContext, reason: HaltReason, size: [f32; 2]) {
+        let (pc, opcode, fault) = match reason {
+            HaltReason::Fault { pc, opcode, fault } => (pc, Some(opcode), Some(fault)),
+            HaltReason::PcOutOfBounds(pc) => (pc, None, None),
+            _ => return,
+        };
+        let size = [
+            size[0].max(FAULT_PANEL_SIZE[0]),
+            size[1].max(FAULT_PANEL_SIZE[1]),
+        ];
+        ChildWindow::new(im_str!("fault"))
+            .size(size)
+            .border(true)
+            .build(ui, || {
+                let description = fault.map_or("PC ran past the end of memory", Fault::description);
+                ui.text_colored([1.0, 0.4, 0.4, 1.0], tr(description));
+                ui.text(trf("PC: {}", format_args!("{:03X}", pc)));
+                if let Some(opcode) = opcode {
+                    let instruction = Instruction::decode(opcode);
+                    ui.text(trf(
+                        "Opcode: {}",
+                        format_args!("{:04X} ({})", opcode, instruction),
+                    ));
+                }
+                if let Some(Fault::MemoryOutOfBounds(address)) = fault {
+                    ui.text(trf("Address: {}", format_args!("{:04X}", address)));
+                }
+                ui.separator();
+
+                let instance = &mut *ctx.instance;
+                if ui.button(&im("Reset"), [0.0, 0.0]) {
+                    ctx.actions.push(Action::Reset);
+                }
+                ui.same_line(0.0);
+                if instance.has_state() {
+                    if ui.button(&im("Load state"), [0.0, 0.0]) {
+                        instance.load_state();
+                    }
+                } else {
+                    ui.text_disabled(tr("No saved state"));
+                }
+                // Running on from outside of memory goes nowhere
+                if fault.is_some() {
+                    ui.same_line(0.0);
+                    if ui.button(&im("Ignore and continue"), [0.0, 0.0]) {
+                        instance.emulator.lock().ignore_fault();
+                    }
+                }
+                ui.same_line(0.0);
+                if ui.button(&im("Show code"), [0.0, 0.0]) {
+                    ctx.actions.push(Action::ShowCode(pc));
+                }
+                let mut emulator = instance.emulator.lock();
+                let mut halt_on_fault = emulator.halt_on_fault();
+                if ui.checkbox(&im("Stop on faults"), &mut halt_on_fault) {
+                    emulator.set_halt_on_fault(halt_on_fault);
+                }
+            });
+    }
+
+    // Lines between chip8 pixels, only drawn when pixels are big enough to tell apart
+    fn draw_grid(ui: &Ui, instance: &Instance, p0: [f32; 2], size: [f32; 2]) {
+        let scale = instance.screen.ui_scale;
+        if scale < 3.0 {
+            return;
+        }
+        let fg = instance.palette.fg;
+        let color = [fg[0], fg[1], fg[2], 0.15];
+        let draw_list = ui.get_window_draw_list();
+        for x in 1..instance.screen.size.0 {
+            let x = p0[0] + x as f32 * scale;
+            draw_list
+                .add_line([x, p0[1]], [x, p0[1] + size[1]], color)
+                .build();
+        }
+        for y in 1..instance.screen.size.1 {
+            let y = p0[1] + y as f32 * scale;
+            draw_list
+                .add_line([p0[0], y], [p0[0] + size[0], y], color)
+                .build();
+        }
+    }
+}
+
+impl DebugView for ScreenView {
+    fn name(&self) -> &'static str {
+        "Screen"
+    }
+
+    fn open_by_default(&self) -> bool {
+        true
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Screen");
+        let window = imgui::Window::new(&title)
+            .opened(open)
+            .always_auto_resize(true)
+            .focused(std::mem::take(&mut ctx.instance.screen.take_focus));
+        window
+            .position(ctx.instance.position(500.0, 220.0), Condition::FirstUseEver)
+            .build(ui, || {
+                let instance = &mut *ctx.instance;
+                instance.screen.focused =
+                    ui.is_window_focused_with_flags(WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS);
+                let screen = &mut instance.screen;
+                let palette = &mut instance.palette;
+                let size = [
+                    (screen.size.0 as f32) * screen.ui_scale,
+                    (screen.size.1 as f32) * screen.ui_scale,
+                ];
+                let halt = instance.emulator.lock().halt_reason();
+                if let Some(reason @ (HaltReason::Fault { .. } | HaltReason::PcOutOfBounds(_))) =
+                    halt
+                {
+                    Self::draw_fault(ui, ctx, reason, size);
+                    ctx.track_focus(ui);
+                    return;
+                }
+                // "Off" pixels are transparent, so background shows through them
+                let p0 = ui.cursor_screen_pos();
+                ui.get_window_draw_list()
+                    .add_rect(p0, [p0[0] + size[0], p0[1] + size[1]], palette.bg)
+                    .filled(true)
+                    .build();
+                match screen.texture_id {
+                    Some(texture_id) => Image::new(texture_id, size).tint_col(palette.fg).build(ui),
+                    None => ui.dummy(size),
+                }
+                if ui.is_item_hovered() && ui.is_mouse_clicked(MouseButton::Right) {
+                    ui.open_popup(im_str!("screen menu"));
+                }
+                if screen.grid {
+                    Self::draw_grid(ui, instance, p0, size);
+                }
+                if instance.beep {
+                    ctx.config
+                        .beep_indicator
+                        .draw(ui, p0, size, instance.palette.fg);
+                }
+                if instance.recording.is_some() {
+                    // Red dot in the corner while recording
+                    ui.get_window_draw_list()
+                        .add_circle(
+                            [p0[0] + size[0] - 12.0, p0[1] + 12.0],
+                            6.0,
+                            [1.0, 0.0, 0.0, 1.0],
+                        )
+                        .filled(true)
+                        .build();
+                }
+
+                let screen = &mut instance.screen;
+                let palette = &mut instance.palette;
+                ui.drag_float(im_str!("Scale"), &mut screen.ui_scale)
+                    .build();
+                ui.same_line(0.0);
+                imgui::ColorEdit::new(im_str!("Color"), &mut palette.fg)
+                    .inputs(false)
+                    .build(ui);
+                ui.same_line(0.0);
+                imgui::ColorEdit::new(im_str!("Background"), &mut palette.bg)
+                    .inputs(false)
+                    .build(ui);
+                ui.popup(im_str!("screen menu"), || Self::draw_context_menu(ui, ctx));
+                ctx.track_focus(ui);
+            });
+    }
+}
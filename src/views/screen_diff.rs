@@ -0,0 +1,237 @@
+use super::{DebugView, ViewContext};
+use crate::instance::Instance;
+use crate::state_diff;
+use chip8_core::SCREEN_SIZE;
+use imgui::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const PIXEL_SIZE: f32 = 3.0;
+/// The diff is drawn larger, single changed pixels are easy to miss
+const DIFF_PIXEL_SIZE: f32 = 6.0;
+
+/// Pixels on only in A
+const ONLY_A_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+/// Pixels on only in B
+const ONLY_B_COLOR: [f32; 4] = [0.3, 0.9, 0.3, 1.0];
+/// Pixels that are the same, dimmed so the changes stand out
+const SAME_ALPHA: f32 = 0.35;
+
+type Rows = [u64; SCREEN_SIZE.1];
+
+// Window that compares the screens of two save states, or a state and the live screen
+#[derive(Default)]
+pub struct ScreenDiffView {
+    /// Screens compared per instance id
+    states: HashMap<usize, DiffState>,
+}
+
+enum Source {
+    /// Screen of the view's instance as it runs
+    Live,
+    /// Screen of a save state file, read when picked
+    State(PathBuf, Box<Rows>),
+}
+
+impl Source {
+    // Logs why the state can't be used
+    fn load(path: &Path) -> Option<Source> {
+        match state_diff::load(path) {
+            Ok(state) => Some(Source::State(path.to_path_buf(), Box::new(state.screen))),
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Source::Live => "live screen".to_string(),
+            Source::State(path, _) => path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+        }
+    }
+
+    fn rows(&self, instance: &Instance) -> Rows {
+        match self {
+            Source::Live => *instance.emulator.lock().screen().rows(),
+            Source::State(_, rows) => **rows,
+        }
+    }
+}
+
+struct DiffState {
+    a: Option<Source>,
+    b: Option<Source>,
+}
+
+impl DiffState {
+    // The quick save slot of the ROM against the live screen, to see where a replay went another way
+    fn new(instance: &Instance) -> Self {
+        let a = instance
+            .rom_file
+            .as_ref()
+            .map(|rom_file| Instance::state_path(rom_file))
+            .filter(|path| path.exists())
+            .and_then(|path| Source::load(&path));
+        DiffState {
+            a,
+            b: Some(Source::Live),
+        }
+    }
+}
+
+// Picks a state file, blocks until the dialog is closed
+fn open_state(name: &str) -> Option<Source> {
+    let path = tinyfiledialogs::open_file_dialog(
+        &format!("Open state {}", name),
+        "",
+        Some((&["*.state"], "Save states")),
+    )?;
+    Source::load(Path::new(&path))
+}
+
+// Buttons that pick where screen `name` comes from
+fn draw_source(ui: &Ui, name: &str, source: &mut Option<Source>) {
+    let _id = ui.push_id(name);
+    match source {
+        Some(current) => ui.text(format!("{}: {}", name, current.name())),
+        None => ui.text_disabled(format!("{}: pick a state", name)),
+    }
+    if let Some(Source::State(path, _)) = source {
+        if ui.is_item_hovered() {
+            ui.tooltip_text(path.display().to_string());
+        }
+    }
+    if ui.small_button(im_str!("Open state...")) {
+        if let Some(picked) = open_state(name) {
+            *source = Some(picked);
+        }
+    }
+    ui.same_line(0.0);
+    if ui.small_button(im_str!("Live screen")) {
+        *source = Some(Source::Live);
+    }
+    if let Some(Source::State(path, _)) = source {
+        ui.same_line(0.0);
+        if ui.small_button(im_str!("Reload")) {
+            let path = path.clone();
+            if let Some(reloaded) = Source::load(&path) {
+                *source = Some(reloaded);
+            }
+        }
+    }
+}
+
+// Draws a screen pixel by pixel, `color` gives each pixel's color or `None` for the background
+fn draw_pixels(
+    ui: &Ui,
+    pixel_size: f32,
+    bg: [f32; 4],
+    color: impl Fn(usize, usize) -> Option<[f32; 4]>,
+) {
+    let origin = ui.cursor_screen_pos();
+    let size = [
+        SCREEN_SIZE.0 as f32 * pixel_size,
+        SCREEN_SIZE.1 as f32 * pixel_size,
+    ];
+    let draw_list = ui.get_window_draw_list();
+    draw_list
+        .add_rect(origin, [origin[0] + size[0], origin[1] + size[1]], bg)
+        .filled(true)
+        .build();
+    for y in 0..SCREEN_SIZE.1 {
+        for x in 0..SCREEN_SIZE.0 {
+            let color = match color(x, y) {
+                Some(color) => color,
+                None => continue,
+            };
+            let min = [
+                origin[0] + x as f32 * pixel_size,
+                origin[1] + y as f32 * pixel_size,
+            ];
+            draw_list
+                .add_rect(min, [min[0] + pixel_size, min[1] + pixel_size], color)
+                .filled(true)
+                .build();
+        }
+    }
+    drop(draw_list);
+    ui.dummy(size);
+}
+
+fn pixel(rows: &Rows, x: usize, y: usize) -> bool {
+    rows[y] & (1 << (SCREEN_SIZE.0 - 1 - x)) != 0
+}
+
+impl DebugView for ScreenDiffView {
+    fn name(&self) -> &'static str {
+        "Screen diff"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Screen diff");
+        let window = imgui::Window::new(&title).opened(open);
+        let instance = &*ctx.instance;
+        let state = self
+            .states
+            .entry(instance.id)
+            .or_insert_with(|| DiffState::new(instance));
+        window
+            .size([420.0, 560.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(480.0, 120.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                ui.text_wrapped(im_str!(
+                    "Compares the screens of two save states, or of a state and the running \
+                     instance, to check that runs are deterministic or to find where they \
+                     started drawing differently."
+                ));
+                draw_source(ui, "A", &mut state.a);
+                draw_source(ui, "B", &mut state.b);
+                ui.separator();
+
+                let (a, b) = match (&state.a, &state.b) {
+                    (Some(a), Some(b)) => (a.rows(ctx.instance), b.rows(ctx.instance)),
+                    _ => {
+                        ui.text_disabled("Pick two screens to compare");
+                        return;
+                    }
+                };
+                match state_diff::screen_difference(&a, &b) {
+                    Some(summary) => ui.text(summary),
+                    None => ui.text_disabled("Screens are identical"),
+                }
+
+                let palette = &ctx.instance.palette;
+                let (fg, bg) = (palette.fg, palette.bg);
+                for (name, rows) in [("A", &a), ("B", &b)].iter() {
+                    let group = ui.begin_group();
+                    ui.text(*name);
+                    draw_pixels(ui, PIXEL_SIZE, bg, |x, y| pixel(rows, x, y).then_some(fg));
+                    group.end(ui);
+                    ui.same_line(0.0);
+                }
+                ui.new_line();
+
+                ui.text("Diff");
+                ui.same_line(0.0);
+                ui.text_colored(ONLY_A_COLOR, "only in A");
+                ui.same_line(0.0);
+                ui.text_colored(ONLY_B_COLOR, "only in B");
+                let same = [fg[0], fg[1], fg[2], fg[3] * SAME_ALPHA];
+                draw_pixels(ui, DIFF_PIXEL_SIZE, bg, |x, y| {
+                    match (pixel(&a, x, y), pixel(&b, x, y)) {
+                        (true, true) => Some(same),
+                        (true, false) => Some(ONLY_A_COLOR),
+                        (false, true) => Some(ONLY_B_COLOR),
+                        (false, false) => None,
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,460 @@
+use super::{Action, DebugView, ViewContext};
+use crate::config::{
+    key_name, BeepIndicator, Config, GpuPower, Hotkey, AUDIO_BUFFER_SIZES, AUDIO_SAMPLE_RATE,
+    KEYPAD_LAYOUT,
+};
+use crate::gpu;
+use crate::i18n::{self, im, tr, trf, Language};
+use crate::theme::Theme;
+use chip8_core::Quirks;
+use imgui::*;
+use winit::event::VirtualKeyCode;
+
+// Keys offered for binding. Escape is left out, so the default quit key can't be lost by accident
+const BINDABLE_KEYS: [VirtualKeyCode; 68] = {
+    use VirtualKeyCode::*;
+    [
+        Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Numpad0, Numpad1, Numpad2, Numpad3,
+        Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, Up, Down, Left, Right, F1, F2, F3,
+        F4, F5, F6, F7, F8, F9, F10, F11, F12, Tab, Space, Return, Back, Pause, Grave,
+    ]
+};
+
+// Key waiting to be rebound
+#[derive(Clone, Copy, PartialEq)]
+enum Binding {
+    Keypad(usize),
+    Hotkey(Hotkey),
+}
+
+// Window editing a copy of the app config, changes take effect on Apply and are written on Save
+#[derive(Default)]
+pub struct SettingsView {
+    draft: Option<Config>,
+    rom_dir: ImString,
+    font: ImString,
+    binding: Option<Binding>,  // Waiting for a keyboard key to be pressed
+    gpus: Option<Vec<String>>, // Adapter names, looked up when the settings are first drawn
+}
+
+impl SettingsView {
+    fn draw_video(&mut self, ui: &Ui, draft: &mut Config) {
+        let languages: Vec<ImString> = Language::ALL
+            .iter()
+            .map(|language| ImString::new(language.name()))
+            .collect();
+        let language_refs: Vec<&ImStr> = languages.iter().map(|name| name.as_ref()).collect();
+        let mut current = Language::ALL
+            .iter()
+            .position(|&language| language == draft.language)
+            .unwrap_or(0);
+        if ComboBox::new(&im("Language")).build_simple_string(ui, &mut current, &language_refs) {
+            draft.language = Language::ALL[current];
+        }
+        let names: Vec<ImString> = Theme::ALL.iter().map(|theme| im(theme.name())).collect();
+        let name_refs: Vec<&ImStr> = names.iter().map(|name| name.as_ref()).collect();
+        let mut current = Theme::ALL
+            .iter()
+            .position(|&theme| theme == draft.theme)
+            .unwrap_or(0);
+        if ComboBox::new(&im("Theme")).build_simple_string(ui, &mut current, &name_refs) {
+            draft.theme = Theme::ALL[current];
+            draft.palette = draft.theme.palette();
+        }
+        ui.drag_float(&im("Screen scale"), &mut draft.window.screen_scale)
+            .min(1.0)
+            .max(32.0)
+            .build();
+        imgui::ColorEdit::new(&im("Color"), &mut draft.palette.fg)
+            .inputs(false)
+            .build(ui);
+        ui.same_line(0.0);
+        imgui::ColorEdit::new(&im("Background"), &mut draft.palette.bg)
+            .inputs(false)
+            .build(ui);
+
+        Slider::new(&im("UI scale"), 0.5..=4.0)
+            .display_format(im_str!("%.2f"))
+            .build(ui, &mut draft.window.ui_scale);
+        if ui
+            .input_text(&im("Font"), &mut self.font)
+            .resize_buffer(true)
+            .build()
+        {
+            draft.window.font = match self.font.to_str() {
+                "" => None,
+                file => Some(file.into()),
+            };
+        }
+        if ui.button(
+            &ImString::new(format!("{}##font", tr("Browse..."))),
+            [0.0, 0.0],
+        ) {
+            let picked = tinyfiledialogs::open_file_dialog(
+                "UI font",
+                "",
+                Some((&["*.ttf", "*.otf"], "Fonts")),
+            );
+            if let Some(file) = picked {
+                self.font = ImString::new(&file);
+                draft.window.font = Some(file.into());
+            }
+        }
+        ui.same_line(0.0);
+        ui.text_disabled(tr("Empty uses the built-in font"));
+        ui.drag_float(&im("Font size"), &mut draft.window.font_size)
+            .min(6.0)
+            .max(48.0)
+            .build();
+        self.draw_gpu(ui, draft);
+    }
+
+    fn draw_gpu(&mut self, ui: &Ui, draft: &mut Config) {
+        let gpus = self.gpus.get_or_insert_with(gpu::adapter_names);
+        // A configured adapter that is gone stays in the list until another one is picked
+        if let Some(name) = &draft.gpu.adapter {
+            if !gpus.contains(name) {
+                gpus.push(name.clone());
+            }
+        }
+        let mut names = vec![im("Auto")];
+        names.extend(gpus.iter().map(ImString::new));
+        let name_refs: Vec<&ImStr> = names.iter().map(|name| name.as_ref()).collect();
+        let mut current = draft
+            .gpu
+            .adapter
+            .as_ref()
+            .and_then(|adapter| gpus.iter().position(|name| name == adapter))
+            .map_or(0, |index| index + 1);
+        if ComboBox::new(&im("GPU")).build_simple_string(ui, &mut current, &name_refs) {
+            draft.gpu.adapter = current.checked_sub(1).map(|index| gpus[index].clone());
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr(
+                "Falls back to another GPU, or to drawing in software, if this one can't be used",
+            ));
+        }
+
+        let names: Vec<ImString> = GpuPower::ALL.iter().map(|power| im(power.name())).collect();
+        let name_refs: Vec<&ImStr> = names.iter().map(|name| name.as_ref()).collect();
+        let mut current = GpuPower::ALL
+            .iter()
+            .position(|&power| power == draft.gpu.power)
+            .unwrap_or(0);
+        if ComboBox::new(&im("GPU preference")).build_simple_string(ui, &mut current, &name_refs) {
+            draft.gpu.power = GpuPower::ALL[current];
+        }
+        ui.text_disabled(tr("GPU changes apply after a restart"));
+    }
+
+    fn draw_input(&mut self, ui: &Ui, draft: &mut Config) {
+        if let Some(binding) = self.binding {
+            let io = ui.io();
+            if let Some(&code) = BINDABLE_KEYS
+                .iter()
+                .find(|&&code| io.keys_down[code as usize])
+            {
+                Self::bind(draft, binding, code);
+                self.binding = None;
+            }
+        }
+
+        for (i, &key) in KEYPAD_LAYOUT.iter().enumerate() {
+            let name = format!("{:X}: {}##key{}", key, key_name(draft.keys[key]), key);
+            self.binding_button(ui, &name, Binding::Keypad(key), [90.0, 0.0]);
+            if i % 4 != 3 {
+                ui.same_line(0.0);
+            }
+        }
+
+        for &hotkey in Hotkey::ALL.iter() {
+            let name = format!("{}##{}", key_name(draft.hotkeys.key(hotkey)), hotkey.name());
+            self.binding_button(ui, &name, Binding::Hotkey(hotkey), [90.0, 0.0]);
+            ui.same_line(0.0);
+            ui.text(tr(hotkey.name()));
+        }
+        if self.binding.is_some() {
+            ui.text(tr("Press a key, click the button again to cancel"));
+        }
+        let conflicts: Vec<String> = Hotkey::ALL
+            .iter()
+            .filter(|&&hotkey| draft.keypad_index(draft.hotkeys.key(hotkey)).is_some())
+            .map(|hotkey| tr(hotkey.name()).to_string())
+            .collect();
+        if !conflicts.is_empty() {
+            ui.text_colored(
+                [1.0, 0.8, 0.0, 1.0],
+                trf("Keypad keys taken by hotkeys: {}", conflicts.join(", ")),
+            );
+        }
+
+        ui.input_float(&im("Fast-forward speed"), &mut draft.fast_forward_speed)
+            .build();
+        draft.fast_forward_speed = draft.fast_forward_speed.max(1.0);
+        ui.checkbox(&im("Low-latency input"), &mut draft.low_latency_input);
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr(
+                "Key changes reach the game right away and it runs in 1ms steps instead of 4ms, for fast games. Uses more CPU",
+            ));
+        }
+    }
+
+    // Button showing the current key, clicking it starts or cancels rebinding
+    fn binding_button(&mut self, ui: &Ui, name: &str, binding: Binding, size: [f32; 2]) {
+        let label = if self.binding == Some(binding) {
+            // Keep the ID after ## so the button stays the same widget
+            let id = name.find("##").map_or("", |i| &name[i..]);
+            ImString::new(format!("...{}", id))
+        } else {
+            ImString::new(name)
+        };
+        if ui.button(&label, size) {
+            self.binding = if self.binding == Some(binding) {
+                None
+            } else {
+                Some(binding)
+            };
+        }
+    }
+
+    // A keyboard key drives a single chip8 key or hotkey, the old owner gets the replaced key
+    fn bind(draft: &mut Config, binding: Binding, code: VirtualKeyCode) {
+        match binding {
+            Binding::Keypad(key) => {
+                if let Some(other) = draft.keypad_index(code) {
+                    draft.keys[other] = draft.keys[key];
+                }
+                draft.keys[key] = code;
+            }
+            Binding::Hotkey(hotkey) => {
+                if let Some(other) = draft.hotkeys.find(code) {
+                    *draft.hotkeys.key_mut(other) = draft.hotkeys.key(hotkey);
+                }
+                *draft.hotkeys.key_mut(hotkey) = code;
+            }
+        }
+    }
+
+    fn draw_emulation(ui: &Ui, draft: &mut Config) {
+        ui.text(tr("Defaults for ROMs without their own options"));
+        let mut tickrate = (draft.clock_hz / 60.0).round() as i32;
+        if ui
+            .input_int(&im("Instructions per frame"), &mut tickrate)
+            .build()
+        {
+            draft.clock_hz = tickrate.max(1) as f32 * 60.0;
+        }
+
+        let presets: Vec<ImString> = Quirks::PRESET_NAMES
+            .iter()
+            .map(|&name| ImString::new(name))
+            .collect();
+        let preset_refs: Vec<&ImStr> = presets.iter().map(|name| name.as_ref()).collect();
+        let mut preset = Quirks::PRESET_NAMES
+            .iter()
+            .position(|name| Quirks::preset(name) == Some(draft.quirks))
+            .unwrap_or(usize::MAX);
+        if ComboBox::new(&im("Quirks preset")).build_simple_string(ui, &mut preset, &preset_refs) {
+            draft.quirks = Quirks::preset(Quirks::PRESET_NAMES[preset]).unwrap();
+        }
+        let quirks = &mut draft.quirks;
+        ui.checkbox(im_str!("shift"), &mut quirks.shift);
+        ui.same_line(0.0);
+        ui.checkbox(im_str!("load/store"), &mut quirks.load_store);
+        ui.same_line(0.0);
+        ui.checkbox(im_str!("jump"), &mut quirks.jump);
+        ui.same_line(0.0);
+        ui.checkbox(im_str!("logic"), &mut quirks.logic);
+        ui.same_line(0.0);
+        ui.checkbox(im_str!("clip"), &mut quirks.clip);
+        ui.same_line(0.0);
+        ui.checkbox(im_str!("vblank"), &mut quirks.vblank);
+        ui.checkbox(&im("64KB memory (XO-CHIP)"), &mut draft.xo_chip_memory);
+        ui.checkbox(&im("COSMAC VIP timing"), &mut draft.vip_timing);
+
+        ui.checkbox(
+            &im("Ask before replacing a running game"),
+            &mut draft.confirm_rom_switch,
+        );
+        ui.checkbox(
+            &im("Save a state before replacing a running game"),
+            &mut draft.save_state_on_switch,
+        );
+        ui.checkbox(
+            &im("Offer a profile for SUPER-CHIP and XO-CHIP ROMs"),
+            &mut draft.suggest_profile,
+        );
+        ui.checkbox(
+            &im("Pause when the window loses focus"),
+            &mut draft.pause_when_unfocused,
+        );
+        ui.checkbox(
+            &im("Pause when the window is minimized"),
+            &mut draft.pause_when_minimized,
+        );
+    }
+
+    fn draw_audio(ui: &Ui, draft: &mut Config) {
+        let names: Vec<ImString> = BeepIndicator::ALL
+            .iter()
+            .map(|indicator| im(indicator.name()))
+            .collect();
+        let name_refs: Vec<&ImStr> = names.iter().map(|name| name.as_ref()).collect();
+        let mut current = BeepIndicator::ALL
+            .iter()
+            .position(|&indicator| indicator == draft.beep_indicator)
+            .unwrap_or(0);
+        if ComboBox::new(&im("Show beeps")).build_simple_string(ui, &mut current, &name_refs) {
+            draft.beep_indicator = BeepIndicator::ALL[current];
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr("Shown on the screen while the sound timer runs"));
+        }
+
+        // "Auto" first, then the sizes with the delay they add to a beep
+        let mut names = vec![im("Auto")];
+        names.extend(AUDIO_BUFFER_SIZES.iter().map(|&samples| {
+            let millis = samples as f32 * 1000.0 / AUDIO_SAMPLE_RATE as f32;
+            ImString::new(trf("{} samples", samples) + &format!(" ({:.0} ms)", millis))
+        }));
+        let name_refs: Vec<&ImStr> = names.iter().map(|name| name.as_ref()).collect();
+        let mut current = draft
+            .audio_buffer
+            .and_then(|samples| AUDIO_BUFFER_SIZES.iter().position(|&size| size == samples))
+            .map_or(0, |index| index + 1);
+        if ComboBox::new(&im("Buffer size")).build_simple_string(ui, &mut current, &name_refs) {
+            draft.audio_buffer = current
+                .checked_sub(1)
+                .map(|index| AUDIO_BUFFER_SIZES[index]);
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tr(
+                "Used by the SDL frontend. Bigger buffers crackle less, smaller ones make beeps more responsive",
+            ));
+        }
+    }
+
+    fn draw_paths(&mut self, ui: &Ui, draft: &mut Config) {
+        if ui
+            .input_text(&im("ROM directory"), &mut self.rom_dir)
+            .resize_buffer(true)
+            .build()
+        {
+            draft.rom_dir = match self.rom_dir.to_str() {
+                "" => None,
+                dir => Some(dir.into()),
+            };
+        }
+        if ui.button(&im("Browse..."), [0.0, 0.0]) {
+            let current = draft.rom_dir();
+            let picked =
+                tinyfiledialogs::select_folder_dialog("ROM directory", &current.to_string_lossy());
+            if let Some(dir) = picked {
+                self.rom_dir = ImString::new(&dir);
+                draft.rom_dir = Some(dir.into());
+            }
+        }
+        ui.same_line(0.0);
+        ui.text_disabled(tr("Empty uses roms next to the executable"));
+        if let Some(path) = Config::path() {
+            ui.text(trf("Config file: {}", path.display()));
+        }
+    }
+}
+
+impl DebugView for SettingsView {
+    fn name(&self) -> &'static str {
+        "Settings"
+    }
+
+    fn per_instance(&self) -> bool {
+        false
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let mut draft = match self.draft.take() {
+            Some(draft) => draft,
+            None => {
+                self.rom_dir = rom_dir_text(ctx.config);
+                self.font = font_text(ctx.config);
+                ctx.config.clone()
+            }
+        };
+
+        let title = i18n::window_title("Settings", "");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([520.0, 520.0], Condition::FirstUseEver)
+            .position([400.0, 100.0], Condition::FirstUseEver)
+            .build(ui, || {
+                if CollapsingHeader::new(&im("Video"))
+                    .default_open(true)
+                    .build(ui)
+                {
+                    self.draw_video(ui, &mut draft);
+                }
+                if CollapsingHeader::new(&im("Audio"))
+                    .default_open(true)
+                    .build(ui)
+                {
+                    ui.text_disabled(tr("There is no audio output yet"));
+                    Self::draw_audio(ui, &mut draft);
+                }
+                if CollapsingHeader::new(&im("Input"))
+                    .default_open(true)
+                    .build(ui)
+                {
+                    self.draw_input(ui, &mut draft);
+                }
+                if CollapsingHeader::new(&im("Emulation"))
+                    .default_open(true)
+                    .build(ui)
+                {
+                    Self::draw_emulation(ui, &mut draft);
+                }
+                if CollapsingHeader::new(&im("Paths"))
+                    .default_open(true)
+                    .build(ui)
+                {
+                    self.draw_paths(ui, &mut draft);
+                }
+
+                ui.separator();
+                if ui.button(&im("Apply"), [0.0, 0.0]) {
+                    ctx.actions
+                        .push(Action::ApplySettings(Box::new(draft.clone())));
+                }
+                ui.same_line(0.0);
+                if ui.button(&im("Save"), [0.0, 0.0]) {
+                    ctx.actions
+                        .push(Action::SaveSettings(Box::new(draft.clone())));
+                }
+                ui.same_line(0.0);
+                if ui.button(&im("Revert"), [0.0, 0.0]) {
+                    draft = ctx.config.clone();
+                    self.rom_dir = rom_dir_text(&draft);
+                    self.font = font_text(&draft);
+                    self.binding = None;
+                }
+            });
+        // Start from the current config next time the window is opened
+        if *open {
+            self.draft = Some(draft);
+        }
+    }
+}
+
+fn rom_dir_text(config: &Config) -> ImString {
+    match &config.rom_dir {
+        Some(dir) => ImString::new(dir.to_string_lossy()),
+        None => ImString::default(),
+    }
+}
+
+fn font_text(config: &Config) -> ImString {
+    match &config.window.font {
+        Some(file) => ImString::new(file.to_string_lossy()),
+        None => ImString::default(),
+    }
+}
@@ -0,0 +1,229 @@
+use super::{DebugView, ViewContext};
+use crate::instance::Instance;
+use crate::logger;
+use imgui::*;
+use std::collections::HashMap;
+
+const CELL_SIZE: f32 = 18.0;
+const MAX_SIZE: usize = 16;
+
+// Window to draw sprites on a grid and write them into memory
+#[derive(Default)]
+pub struct SpriteEditorView {
+    /// Sprite per instance id
+    states: HashMap<usize, SpriteState>,
+}
+
+struct SpriteState {
+    /// 16x16 sprites (SCHIP DXY0 format, two bytes a row) instead of 8xN
+    wide: bool,
+    height: i32, // Rows of an 8xN sprite, 16x16 sprites always have 16
+    pixels: [[bool; MAX_SIZE]; MAX_SIZE],
+    address: ImString,
+    /// Value being painted while the mouse is held, set by the first cell clicked
+    painting: Option<bool>,
+}
+
+impl Default for SpriteState {
+    fn default() -> Self {
+        SpriteState {
+            wide: false,
+            height: 8,
+            pixels: [[false; MAX_SIZE]; MAX_SIZE],
+            address: ImString::new("300"),
+            painting: None,
+        }
+    }
+}
+
+impl SpriteState {
+    fn size(&self) -> (usize, usize) {
+        if self.wide {
+            (16, 16)
+        } else {
+            (8, self.height as usize)
+        }
+    }
+
+    /// Sprite bytes as DXYN reads them, rows top to bottom, leftmost pixel in the top bit
+    fn bytes(&self) -> Vec<u8> {
+        let (width, height) = self.size();
+        let mut bytes = Vec::new();
+        for row in &self.pixels[..height] {
+            for byte in row[..width].chunks(8) {
+                bytes.push(byte.iter().fold(0, |acc, &on| acc << 1 | on as u8));
+            }
+        }
+        bytes
+    }
+
+    fn set_bytes(&mut self, bytes: &[u8]) {
+        let (width, _) = self.size();
+        let bytes_per_row = width / 8;
+        for (y, row) in bytes.chunks(bytes_per_row).enumerate() {
+            for (x, pixel) in self.pixels[y][..width].iter_mut().enumerate() {
+                *pixel = row[x / 8] & (0x80 >> (x % 8)) != 0;
+            }
+        }
+    }
+
+    fn parse_address(&self) -> Option<usize> {
+        usize::from_str_radix(self.address.to_str(), 16).ok()
+    }
+
+    fn read(&mut self, instance: &Instance) {
+        let emulator = instance.emulator.lock();
+        let memory = emulator.memory();
+        let len = self.bytes().len();
+        match self.parse_address() {
+            Some(address) if address + len <= memory.len() => {
+                let bytes = memory[address..address + len].to_vec();
+                drop(emulator);
+                self.set_bytes(&bytes);
+            }
+            _ => log::warn!("Address '{}' is outside of memory", self.address.to_str()),
+        }
+    }
+
+    fn write(&self, instance: &Instance) {
+        let address = match self.parse_address() {
+            Some(address) => address,
+            None => {
+                log::warn!("Address '{}' isn't valid", self.address.to_str());
+                return;
+            }
+        };
+        let bytes = self.bytes();
+        match instance.emulator.lock().load_memory_bytes(address, &bytes) {
+            Ok(()) => log::info!(
+                target: logger::NOTIFY,
+                "Wrote {} sprite bytes at {:03X}",
+                bytes.len(),
+                address
+            ),
+            Err(e) => log::error!("Can't write sprite at {0:03X}. Error: {1}", address, e),
+        }
+    }
+
+    // Grid of cells, left button paints, right button erases
+    fn draw_grid(&mut self, ui: &Ui) {
+        let (width, height) = self.size();
+        let origin = ui.cursor_screen_pos();
+        let draw_list = ui.get_window_draw_list();
+        for (y, row) in self.pixels[..height].iter().enumerate() {
+            for (x, &on) in row[..width].iter().enumerate() {
+                let min = [
+                    origin[0] + x as f32 * CELL_SIZE,
+                    origin[1] + y as f32 * CELL_SIZE,
+                ];
+                let max = [min[0] + CELL_SIZE - 1.0, min[1] + CELL_SIZE - 1.0];
+                let color = if on {
+                    [0.9, 0.9, 0.9, 1.0]
+                } else {
+                    [0.15, 0.15, 0.15, 1.0]
+                };
+                draw_list.add_rect(min, max, color).filled(true).build();
+            }
+        }
+        // Byte boundaries of 16 pixel wide rows
+        if width > 8 {
+            let x = origin[0] + 8.0 * CELL_SIZE - 1.0;
+            draw_list
+                .add_line(
+                    [x, origin[1]],
+                    [x, origin[1] + height as f32 * CELL_SIZE],
+                    [1.0, 0.6, 0.0, 1.0],
+                )
+                .build();
+        }
+        drop(draw_list);
+
+        let size = [width as f32 * CELL_SIZE, height as f32 * CELL_SIZE];
+        ui.invisible_button(im_str!("grid"), size);
+        let left = ui.is_mouse_down(MouseButton::Left);
+        let right = ui.is_mouse_down(MouseButton::Right);
+        if !ui.is_item_hovered() || !(left || right) {
+            self.painting = None;
+            return;
+        }
+        let mouse = ui.io().mouse_pos;
+        let x = ((mouse[0] - origin[0]) / CELL_SIZE) as usize;
+        let y = ((mouse[1] - origin[1]) / CELL_SIZE) as usize;
+        if x < width && y < height {
+            let value = *self.painting.get_or_insert(left);
+            self.pixels[y][x] = value;
+        }
+    }
+}
+
+// "0xF0 0x90 0x90" as written in Octo sources
+fn format_bytes(bytes: &[u8]) -> String {
+    let bytes: Vec<_> = bytes.iter().map(|b| format!("0x{:02X}", b)).collect();
+    bytes.join(" ")
+}
+
+impl DebugView for SpriteEditorView {
+    fn name(&self) -> &'static str {
+        "Sprite editor"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Sprite editor");
+        let window = imgui::Window::new(&title)
+            .opened(open)
+            .always_auto_resize(true);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .position(ctx.instance.position(420.0, 100.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                ui.checkbox(im_str!("16x16"), &mut state.wide);
+                if !state.wide {
+                    ui.same_line(0.0);
+                    ui.set_next_item_width(100.0);
+                    Slider::new(im_str!("rows"), 1..=15).build(ui, &mut state.height);
+                }
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Clear")) {
+                    state.pixels = [[false; MAX_SIZE]; MAX_SIZE];
+                }
+                ui.same_line(0.0);
+                if ui.small_button(im_str!("Invert")) {
+                    let (width, height) = state.size();
+                    for row in &mut state.pixels[..height] {
+                        for pixel in &mut row[..width] {
+                            *pixel = !*pixel;
+                        }
+                    }
+                }
+
+                state.draw_grid(ui);
+
+                ui.set_next_item_width(50.0);
+                ui.input_text(im_str!("address"), &mut state.address)
+                    .chars_hexadecimal(true)
+                    .chars_uppercase(true)
+                    .build();
+                ui.same_line(0.0);
+                if ui.button(im_str!("Read"), [0.0, 0.0]) {
+                    state.read(ctx.instance);
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Load the sprite from memory at the address");
+                }
+                ui.same_line(0.0);
+                if ui.button(im_str!("Write"), [0.0, 0.0]) {
+                    state.write(ctx.instance);
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Store the sprite in memory at the address");
+                }
+
+                let text = format_bytes(&state.bytes());
+                ui.text_wrapped(&ImString::new(&text));
+                if ui.small_button(im_str!("Copy bytes")) {
+                    ui.set_clipboard_text(&ImString::new(text));
+                }
+            });
+    }
+}
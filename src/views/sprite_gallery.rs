@@ -0,0 +1,202 @@
+use super::{Action, DebugView, ViewContext};
+use crate::instance::Instance;
+use crate::{logger, screenshot};
+use chip8_core::{FlowGraph, SpriteRef};
+use imgui::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const PIXEL_SIZE: f32 = 4.0;
+/// Room for the largest sprite, 16x16, so the gallery lines up
+const CELL_SIZE: f32 = 16.0 * PIXEL_SIZE + 8.0;
+
+// Window with every sprite found in the ROM
+#[derive(Default)]
+pub struct SpriteGalleryView {
+    /// Sprites per instance id
+    states: HashMap<usize, GalleryState>,
+}
+
+struct GalleryState {
+    rom: Vec<u8>, // ROM the sprites were found in, a change means a new ROM
+    sprites: Vec<SpriteRef>,
+    show_guessed: bool,
+}
+
+impl Default for GalleryState {
+    fn default() -> Self {
+        GalleryState {
+            rom: Vec::new(),
+            sprites: Vec::new(),
+            show_guessed: true,
+        }
+    }
+}
+
+impl GalleryState {
+    fn rescan(&mut self, rom: &[u8], origin: u16) {
+        let graph = FlowGraph::build(rom, origin, origin);
+        self.rom = rom.to_vec();
+        self.sprites = graph.sprites(origin + rom.len() as u16);
+    }
+
+    fn visible(&self) -> Vec<SpriteRef> {
+        self.sprites
+            .iter()
+            .filter(|sprite| self.show_guessed || !sprite.guessed)
+            .copied()
+            .collect()
+    }
+
+    // Saves the visible sprites as a PNG sheet picked with the system dialog
+    fn export(&self, instance: &Instance) {
+        let rom_file = match &instance.rom_file {
+            Some(rom_file) => rom_file,
+            None => return,
+        };
+        let stem = rom_file.file_stem().unwrap_or_default().to_string_lossy();
+        let default_path = rom_file.with_file_name(format!("{}-sprites.png", stem));
+        let path = match tinyfiledialogs::save_file_dialog_with_filter(
+            "Export sprites",
+            &default_path.display().to_string(),
+            &["*.png"],
+            "PNG image",
+        ) {
+            Some(path) => PathBuf::from(path),
+            None => return,
+        };
+        let memory = instance.emulator.lock().memory().to_vec();
+        let sheet = screenshot::sprite_sheet(&memory, &self.visible(), &instance.palette, 4);
+        match sheet.save(&path) {
+            Ok(()) => log::info!(
+                target: logger::NOTIFY,
+                "Exported sprites to '{}'",
+                path.display()
+            ),
+            Err(e) => log::error!(
+                "Can't export sprites to '{0}'. Error: {1}",
+                path.display(),
+                e
+            ),
+        }
+    }
+}
+
+// Draws one sprite from memory in a cell, returns true when it's clicked
+fn draw_sprite(ui: &Ui, sprite: &SpriteRef, memory: &[u8], fg: [f32; 4], bg: [f32; 4]) -> bool {
+    let origin = ui.cursor_screen_pos();
+    let draw_list = ui.get_window_draw_list();
+    let border = if sprite.guessed {
+        [0.5, 0.5, 0.5, 1.0]
+    } else {
+        [1.0, 0.6, 0.0, 1.0]
+    };
+    let width = sprite.width as f32 * PIXEL_SIZE;
+    let height = sprite.height as f32 * PIXEL_SIZE;
+    let min = [origin[0] + 3.0, origin[1] + 3.0];
+    draw_list
+        .add_rect(
+            [min[0] - 1.0, min[1] - 1.0],
+            [min[0] + width + 1.0, min[1] + height + 1.0],
+            border,
+        )
+        .build();
+    draw_list
+        .add_rect(min, [min[0] + width, min[1] + height], bg)
+        .filled(true)
+        .build();
+    let start = sprite.address as usize;
+    let bytes = memory.get(start..start + sprite.size()).unwrap_or_default();
+    let bytes_per_row = sprite.width as usize / 8;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let y = (i / bytes_per_row) as f32;
+        for bit in 0..8 {
+            if byte & (0x80 >> bit) == 0 {
+                continue;
+            }
+            let x = ((i % bytes_per_row) * 8 + bit) as f32;
+            let pixel = [min[0] + x * PIXEL_SIZE, min[1] + y * PIXEL_SIZE];
+            draw_list
+                .add_rect(pixel, [pixel[0] + PIXEL_SIZE, pixel[1] + PIXEL_SIZE], fg)
+                .filled(true)
+                .build();
+        }
+    }
+    drop(draw_list);
+
+    let id = ImString::new(format!("##{:03X}", sprite.address));
+    let clicked = ui.invisible_button(&id, [CELL_SIZE, CELL_SIZE]);
+    if ui.is_item_hovered() {
+        let mut text = format!(
+            "{:03X}: {}x{}, {} bytes",
+            sprite.address,
+            sprite.width,
+            sprite.height,
+            sprite.size()
+        );
+        if sprite.guessed {
+            text.push_str("\nSize is a guess, no draw uses it right after loading I");
+        }
+        text.push_str("\nClick to show in the Code window");
+        ui.tooltip_text(text);
+    }
+    ui.text(format!("{:03X}", sprite.address));
+    clicked
+}
+
+impl DebugView for SpriteGalleryView {
+    fn name(&self) -> &'static str {
+        "Sprite gallery"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Sprite gallery");
+        let window = imgui::Window::new(&title).opened(open);
+        let state = self.states.entry(ctx.instance.id).or_default();
+        window
+            .size([420.0, 360.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(420.0, 420.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let emulator = ctx.instance.emulator.lock();
+                let origin = emulator.get_code_range().0 as u16;
+                if state.rom != emulator.rom() {
+                    state.rescan(emulator.rom(), origin);
+                }
+                // Current memory, so sprites the program changed show as they are now
+                let memory = emulator.memory().to_vec();
+                drop(emulator);
+
+                ui.checkbox(im_str!("Guesses"), &mut state.show_guessed);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Show data loaded into I that isn't drawn right after");
+                }
+                ui.same_line(0.0);
+                if ui.button(im_str!("Export PNG..."), [0.0, 0.0]) {
+                    state.export(ctx.instance);
+                }
+                let sprites = state.visible();
+                ui.same_line(0.0);
+                ui.text_disabled(format!("{} sprites", sprites.len()));
+                ui.separator();
+
+                let palette = &ctx.instance.palette;
+                let (fg, bg) = (palette.fg, palette.bg);
+                ChildWindow::new(im_str!("sprites")).build(ui, || {
+                    let spacing = ui.clone_style().item_spacing[0];
+                    let per_row = ((ui.content_region_avail()[0] + spacing) / (CELL_SIZE + spacing))
+                        .max(1.0) as usize;
+                    for (i, sprite) in sprites.iter().enumerate() {
+                        if i % per_row != 0 {
+                            ui.same_line(0.0);
+                        }
+                        let group = ui.begin_group();
+                        if draw_sprite(ui, sprite, &memory, fg, bg) {
+                            ctx.actions.push(Action::ShowCode(sprite.address));
+                        }
+                        group.end(ui);
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,111 @@
+use super::{DebugView, ViewContext};
+use crate::config::{key_name, KEYPAD_LAYOUT};
+use chip8_core::EmulatorStats;
+use imgui::*;
+
+const KEY_CELL_SIZE: [f32; 2] = [70.0, 38.0];
+
+// Window with counters of what the running program did
+pub struct StatsView;
+
+// "1:05.3" for 65.3 seconds
+fn format_time(seconds: f64) -> String {
+    let minutes = (seconds / 60.0) as u64;
+    format!("{}:{:04.1}", minutes, seconds - minutes as f64 * 60.0)
+}
+
+// "2 (first illegal opcode 5001 at 200)"
+fn format_faults(stats: &EmulatorStats) -> String {
+    match stats.first_illegal_opcode {
+        Some((pc, opcode)) => format!(
+            "{} (first illegal opcode {:04X} at {:03X})",
+            stats.faults, opcode, pc
+        ),
+        None => stats.faults.to_string(),
+    }
+}
+
+impl StatsView {
+    // Keypad laid out like the real one, lit up by how often the game checks each key.
+    // Keys that are checked but never pressed stand out as controls worth trying.
+    fn draw_keypad(ui: &Ui, ctx: &ViewContext, stats: &EmulatorStats) {
+        let max_queries = stats.key_queries.iter().copied().max().unwrap_or(0).max(1);
+        let p0 = ui.cursor_screen_pos();
+        let draw_list = ui.get_window_draw_list();
+        for (i, &key) in KEYPAD_LAYOUT.iter().enumerate() {
+            let x = p0[0] + (i % 4) as f32 * KEY_CELL_SIZE[0];
+            let y = p0[1] + (i / 4) as f32 * KEY_CELL_SIZE[1];
+            let heat = stats.key_queries[key] as f32 / max_queries as f32;
+            let fill = [0.15 + heat * 0.85, 0.15 + heat * 0.35, 0.15, 1.0];
+            let max = [x + KEY_CELL_SIZE[0] - 2.0, y + KEY_CELL_SIZE[1] - 2.0];
+            draw_list.add_rect([x, y], max, fill).filled(true).build();
+            if stats.key_presses[key] > 0 {
+                draw_list
+                    .add_rect([x, y], max, [0.3, 1.0, 0.3, 1.0])
+                    .thickness(2.0)
+                    .build();
+            }
+            let label = format!("{:X} ({})", key, key_name(ctx.config.keys[key]));
+            draw_list.add_text([x + 4.0, y + 2.0], [1.0, 1.0, 1.0, 1.0], label);
+            let counts = format!("{} / {}", stats.key_presses[key], stats.key_queries[key]);
+            draw_list.add_text([x + 4.0, y + 18.0], [0.8, 0.8, 0.8, 1.0], counts);
+        }
+        ui.invisible_button(
+            im_str!("keypad"),
+            [KEY_CELL_SIZE[0] * 4.0, KEY_CELL_SIZE[1] * 4.0],
+        );
+        if ui.is_item_hovered() {
+            ui.tooltip_text(
+                "Presses / checks by EX9E and EXA1\nGreen border: pressed at least once",
+            );
+        }
+    }
+}
+
+impl DebugView for StatsView {
+    fn name(&self) -> &'static str {
+        "Statistics"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Statistics");
+        let window = imgui::Window::new(&title)
+            .opened(open)
+            .always_auto_resize(true);
+        window
+            .position(ctx.instance.position(20.0, 460.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let mut emulator = ctx.instance.emulator.lock();
+                let stats = *emulator.stats();
+                let rows = [
+                    ("Instructions", stats.instructions.to_string()),
+                    ("Draws", stats.draws.to_string()),
+                    ("Collisions", stats.collisions.to_string()),
+                    ("Key waits", stats.key_waits.to_string()),
+                    ("Subroutine calls", stats.calls.to_string()),
+                    ("Random numbers", stats.randoms.to_string()),
+                    ("Faults", format_faults(&stats)),
+                    ("Emulated time", format_time(stats.emulated_time)),
+                ];
+                ui.columns(2, im_str!("stats"), false);
+                ui.set_column_width(0, 130.0);
+                for (name, value) in rows.iter() {
+                    ui.text(name);
+                    ui.next_column();
+                    ui.text(value);
+                    ui.next_column();
+                }
+                ui.columns(1, im_str!("stats"), false);
+                ui.separator();
+                ui.text("Keypad usage");
+                Self::draw_keypad(ui, ctx, &stats);
+                ui.separator();
+                if ui.button(im_str!("Reset"), [0.0, 0.0]) {
+                    emulator.reset_stats();
+                }
+                ui.same_line(0.0);
+                ui.text_disabled("Also reset when a ROM is loaded");
+            });
+    }
+}
@@ -0,0 +1,83 @@
+use super::{DebugView, ViewContext};
+use crate::i18n;
+use chip8_core::{TestReport, TEST_ROMS};
+use imgui::*;
+use std::fs;
+
+// Runs known test ROMs found in the ROM directory and shows their results
+#[derive(Default)]
+pub struct TestRomsView {
+    // Result for each entry of TEST_ROMS, None if the ROM wasn't found
+    results: Option<Vec<Option<TestReport>>>,
+}
+
+impl DebugView for TestRomsView {
+    fn name(&self) -> &'static str {
+        "Test ROMs"
+    }
+
+    fn per_instance(&self) -> bool {
+        false
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = i18n::window_title("Test ROMs", "");
+        let window = imgui::Window::new(&title).opened(open);
+        window
+            .size([300.0, 120.0], Condition::FirstUseEver)
+            .position([500.0, 615.0], Condition::FirstUseEver)
+            .build(ui, || {
+                if ui.small_button(im_str!("Run")) {
+                    let results = TEST_ROMS.iter().map(|test| {
+                        let rom_file =
+                            ctx.roms.files.iter().map(|rom| &rom.path).find(|path| {
+                                path.file_name().is_some_and(|name| name == test.file)
+                            })?;
+                        match fs::read(rom_file) {
+                            Ok(rom) => Some(test.run(&rom)),
+                            Err(e) => {
+                                log::error!(
+                                    "Can't read file: '{0}'. Error: {1}",
+                                    rom_file.display(),
+                                    e
+                                );
+                                None
+                            }
+                        }
+                    });
+                    self.results = Some(results.collect());
+                }
+                let results = match &self.results {
+                    Some(results) => results,
+                    None => return,
+                };
+                for (test, result) in TEST_ROMS.iter().zip(results) {
+                    match result {
+                        None => ui.text_disabled(ImString::new(format!(
+                            "{}: {} not found",
+                            test.name, test.file
+                        ))),
+                        Some(report) => {
+                            let color = if report.is_success() {
+                                [0.3, 1.0, 0.3, 1.0]
+                            } else {
+                                [1.0, 0.3, 0.3, 1.0]
+                            };
+                            let status = if report.finished {
+                                ""
+                            } else {
+                                ", didn't finish"
+                            };
+                            ui.text_colored(
+                                color,
+                                format!(
+                                    "{}: {} passed, {} failed{}",
+                                    test.name, report.passed, report.failed, status
+                                ),
+                            );
+                        }
+                    }
+                }
+            });
+    }
+}
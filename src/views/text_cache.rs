@@ -0,0 +1,25 @@
+/// Formatted text that is only rebuilt when the value it shows changes,
+/// so debug windows don't allocate a string per line every frame
+pub struct CachedText<T> {
+    value: Option<T>,
+    text: String,
+}
+
+impl<T> Default for CachedText<T> {
+    fn default() -> Self {
+        CachedText {
+            value: None,
+            text: String::new(),
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> CachedText<T> {
+    pub fn get(&mut self, value: T, format: impl FnOnce(T) -> String) -> &str {
+        if self.value != Some(value) {
+            self.text = format(value);
+            self.value = Some(value);
+        }
+        &self.text
+    }
+}
@@ -0,0 +1,206 @@
+use super::{DebugView, ViewContext};
+use crate::logger;
+use crate::triggers::{
+    ApprovedCommands, Condition as TriggerCondition, Trigger, TriggerAction, Triggers,
+};
+use imgui::*;
+use std::collections::HashMap;
+
+const ERROR_COLOR: [f32; 4] = [1.0, 0.3, 0.3, 1.0];
+const FIRED_COLOR: [f32; 4] = [0.5, 0.9, 0.5, 1.0];
+
+// Window listing the ROM's triggers with a form to add more
+#[derive(Default)]
+pub struct TriggersView {
+    /// New trigger being written, per instance id
+    forms: HashMap<usize, TriggerForm>,
+}
+
+struct TriggerForm {
+    name: ImString,
+    condition: ImString,
+    action: usize, // Index in `TriggerAction::ALL`
+    command: ImString,
+    once: bool,
+}
+
+impl Default for TriggerForm {
+    fn default() -> Self {
+        TriggerForm {
+            name: ImString::with_capacity(64),
+            condition: ImString::with_capacity(64),
+            action: 0,
+            command: ImString::with_capacity(256),
+            once: true,
+        }
+    }
+}
+
+impl TriggerForm {
+    fn trigger(&self) -> Trigger {
+        let condition = self.condition.to_str().trim().to_string();
+        let name = match self.name.to_str().trim() {
+            "" => condition.clone(),
+            name => name.to_string(),
+        };
+        Trigger {
+            name,
+            condition,
+            action: TriggerAction::ALL[self.action],
+            command: self.command.to_string(),
+            once: self.once,
+        }
+    }
+}
+
+impl DebugView for TriggersView {
+    fn name(&self) -> &'static str {
+        "Triggers"
+    }
+
+    fn draw(&mut self, ui: &Ui, ctx: &mut ViewContext, open: &mut bool) {
+        let title = ctx.instance.title("Triggers");
+        let window = imgui::Window::new(&title).opened(open);
+        let form = self.forms.entry(ctx.instance.id).or_default();
+        window
+            .size([460.0, 320.0], Condition::FirstUseEver)
+            .position(ctx.instance.position(480.0, 240.0), Condition::FirstUseEver)
+            .build(ui, || {
+                ctx.track_focus(ui);
+                let rom_file = match &ctx.instance.rom_file {
+                    Some(rom_file) => rom_file.clone(),
+                    None => {
+                        ui.text_disabled("Load a ROM to set triggers for it");
+                        return;
+                    }
+                };
+
+                let emulator = ctx.instance.emulator.lock();
+                let triggers = &mut ctx.instance.triggers;
+                let mut remove = None;
+                for (i, trigger) in triggers.triggers.iter().enumerate() {
+                    let _id = ui.push_id(i as i32);
+                    if ui.small_button(im_str!("x")) {
+                        remove = Some(i);
+                    }
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text("Delete");
+                    }
+                    ui.same_line(0.0);
+                    let fired = triggers.fired[i];
+                    if fired > 0 {
+                        ui.text_colored(FIRED_COLOR, format!("[{}x]", fired));
+                        ui.same_line(0.0);
+                    }
+                    ui.text(format!(
+                        "{}: {} -> {}",
+                        trigger.name,
+                        trigger.condition,
+                        trigger.action.name()
+                    ));
+                    match &triggers.conditions[i] {
+                        Ok(condition) => {
+                            if ui.is_item_hovered() {
+                                let value = condition.operand.read(&emulator);
+                                let mut text = match value {
+                                    Some(value) => {
+                                        format!("{} is {} now", condition.operand, value)
+                                    }
+                                    None => format!("{} is outside of memory", condition.operand),
+                                };
+                                if trigger.action == TriggerAction::Command {
+                                    text.push_str(&format!("\nRuns: {}", trigger.command));
+                                }
+                                if trigger.once {
+                                    text.push_str("\nFires only the first time");
+                                }
+                                ui.tooltip_text(text);
+                            }
+                        }
+                        Err(e) => ui.text_colored(ERROR_COLOR, format!("  {}", e)),
+                    }
+                }
+                drop(emulator);
+                if let Some(i) = remove {
+                    triggers.remove(i);
+                }
+                if triggers.triggers.is_empty() {
+                    ui.text_disabled("No triggers for this ROM yet");
+                }
+                ui.separator();
+
+                ui.input_text(im_str!("Name"), &mut form.name).build();
+                ui.input_text(im_str!("Condition"), &mut form.condition)
+                    .build();
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(
+                        "Like 'address 0x3E0 >= 10' or 'VA == 0'.\n\
+                         V0-VF, I, PC, delay, sound or address N compared with ==, !=, <, <=, >, >=",
+                    );
+                }
+                let names: Vec<_> = TriggerAction::ALL
+                    .iter()
+                    .map(|action| ImString::new(action.name()))
+                    .collect();
+                let name_refs: Vec<_> = names.iter().collect();
+                ComboBox::new(im_str!("Action")).build_simple_string(
+                    ui,
+                    &mut form.action,
+                    &name_refs,
+                );
+                if TriggerAction::ALL[form.action] == TriggerAction::Command {
+                    ui.input_text(im_str!("Command"), &mut form.command).build();
+                }
+                ui.checkbox(im_str!("Only once"), &mut form.once);
+
+                let parsed = TriggerCondition::parse(form.condition.to_str());
+                ui.same_line(0.0);
+                if ui.button(im_str!("Add"), [0.0, 0.0]) {
+                    match &parsed {
+                        Ok(_) => {
+                            let trigger = form.trigger();
+                            // Commands typed in here are the user's own, don't ask about them
+                            if trigger.action == TriggerAction::Command {
+                                approve(ctx.instance.emulator.lock().rom(), &trigger);
+                            }
+                            triggers.push(trigger);
+                            *form = TriggerForm::default();
+                        }
+                        Err(e) => log::warn!("Condition '{0}' isn't valid: {1}", form.condition, e),
+                    }
+                }
+                ui.same_line(0.0);
+                if ui.button(im_str!("Save"), [0.0, 0.0]) {
+                    save(triggers, &rom_file);
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(format!(
+                        "Write the triggers to '{}'",
+                        Triggers::path_for_rom(&rom_file).display()
+                    ));
+                }
+            });
+    }
+}
+
+fn save(triggers: &Triggers, rom_file: &std::path::Path) {
+    let path = Triggers::path_for_rom(rom_file);
+    match triggers.save_for_rom(rom_file) {
+        Ok(()) => log::info!(target: logger::NOTIFY, "Saved triggers to '{}'", path.display()),
+        Err(e) => log::error!(
+            "Can't save triggers to '{0}'. Error: {1}",
+            path.display(),
+            e
+        ),
+    }
+}
+
+fn approve(rom: &[u8], trigger: &Trigger) {
+    if let Err(e) = ApprovedCommands::load().approve(rom, &trigger.command) {
+        log::error!(
+            "Can't save approved command '{0}'. Error: {1}",
+            trigger.command,
+            e
+        );
+    }
+}
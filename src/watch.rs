@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Files are looked at this often, often enough to feel instant after saving
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Notices a file being saved again by its modification time, polled every frame
+pub struct FileWatch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl FileWatch {
+    pub fn new(path: &Path) -> Self {
+        FileWatch {
+            path: path.to_path_buf(),
+            modified: modified(path),
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// True once for every change, a file that is missing for a moment isn't a change
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        match modified(&self.path) {
+            Some(time) if Some(time) != self.modified => {
+                self.modified = Some(time);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}